@@ -0,0 +1,236 @@
+//! 리소스 묶음 파일 (에셋 팩).
+//! 리소스 폴더의 파일들을 목차와 함께 파일 하나로 묶어서, 클라이언트를 실행파일과 팩 파일 하나로 배포할 수 있게 함.
+//!
+//! 형식 (정수는 모두 little endian):
+//! `MAGIC` | 버전 `u32` | 파일 수 `u32` | 파일마다 (이름 길이 `u16` | 이름 (UTF-8) | 위치 `u64` | 크기 `u64`) | 내용
+//! 위치는 팩 파일 처음부터의 바이트 수.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    ops::Range,
+    path::Path,
+};
+
+
+pub const MAGIC: &[u8; 4] = b"GSPK";
+pub const VERSION: u32 = 1;
+/// 실행파일 옆에서 찾는 팩 파일 이름
+pub const DEFAULT_PACK_FILE: &str = "assets.pack";
+
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(std::io::Error),
+    /// `MAGIC`으로 시작하지 않음
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    /// 목차나 내용이 파일 끝을 넘어감
+    Truncated,
+    InvalidName,
+    DuplicateName(String),
+}
+
+impl std::fmt::Display for PackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackError::Io(e) => write!(f, "failed to read asset pack: {}", e),
+            PackError::InvalidMagic => write!(f, "not an asset pack"),
+            PackError::UnsupportedVersion(version) => {
+                write!(f, "unsupported asset pack version {} (expected {})", version, VERSION)
+            }
+            PackError::Truncated => write!(f, "asset pack is truncated"),
+            PackError::InvalidName => write!(f, "asset pack has an invalid file name"),
+            PackError::DuplicateName(name) => write!(f, "asset pack has {:?} twice", name),
+        }
+    }
+}
+
+impl std::error::Error for PackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PackError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PackError {
+    fn from(e: std::io::Error) -> Self {
+        PackError::Io(e)
+    }
+}
+
+
+/// 메모리에 올린 팩. 파일 내용은 복사하지 않고 목차의 범위로 꺼냄
+#[derive(Debug, Clone, Default)]
+pub struct AssetPack {
+    data: Vec<u8>,
+    index: HashMap<String, Range<usize>>,
+}
+
+impl AssetPack {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PackError> {
+        Self::parse(std::fs::read(path)?)
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Self, PackError> {
+        let mut reader = Reader { data: &data, position: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(PackError::InvalidMagic);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(PackError::UnsupportedVersion(version));
+        }
+
+        let count = reader.u32()?;
+        let mut index = HashMap::new();
+        for _ in 0..count {
+            let len = reader.u16()? as usize;
+            let name = std::str::from_utf8(reader.take(len)?)
+                .map_err(|_| PackError::InvalidName)?
+                .to_string();
+            let offset = usize::try_from(reader.u64()?).map_err(|_| PackError::Truncated)?;
+            let size = usize::try_from(reader.u64()?).map_err(|_| PackError::Truncated)?;
+            let end = offset.checked_add(size).filter(|end| *end <= data.len()).ok_or(PackError::Truncated)?;
+            if index.insert(name.clone(), offset..end).is_some() {
+                return Err(PackError::DuplicateName(name));
+            }
+        }
+
+        Ok(Self { data, index })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.index.get(name).map(|range| &self.data[range.clone()])
+    }
+
+    /// 이름 순서
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.index.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PackError> {
+        let bytes = self.data.get(self.position..self.position + len).ok_or(PackError::Truncated)?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, PackError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, PackError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, PackError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+
+/// `files`를 이름 순서로 묶어서 씀
+pub fn write_pack(files: &[(String, Vec<u8>)], out: &mut impl Write) -> Result<(), PackError> {
+    let mut files = files.iter().collect::<Vec<_>>();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(pair) = files.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(PackError::DuplicateName(pair[0].0.clone()));
+    }
+
+    let mut header = Vec::new();
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&(files.len() as u32).to_le_bytes());
+
+    let index_len = files.iter().map(|(name, _)| 2 + name.len() + 8 + 8).sum::<usize>();
+    let mut offset = (header.len() + index_len) as u64;
+    for (name, data) in &files {
+        let len = u16::try_from(name.len()).map_err(|_| PackError::InvalidName)?;
+        header.extend_from_slice(&len.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        header.extend_from_slice(&offset.to_le_bytes());
+        header.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        offset += data.len() as u64;
+    }
+
+    out.write_all(&header)?;
+    for (_, data) in files {
+        out.write_all(data)?;
+    }
+    Ok(())
+}
+
+/// 폴더 안 파일 (하위 폴더 제외). 이름은 폴더 기준의 파일 이름
+pub fn collect_dir(dir: impl AsRef<Path>) -> Result<Vec<(String, Vec<u8>)>, PackError> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let name = entry.file_name().into_string().map_err(|_| PackError::InvalidName)?;
+        files.push((name, std::fs::read(entry.path())?));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packed(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let files = files.iter()
+            .map(|(name, data)| (name.to_string(), data.to_vec()))
+            .collect::<Vec<_>>();
+        let mut out = Vec::new();
+        write_pack(&files, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = packed(&[("pawn.obj", b"v 0 0 0"), ("empty.txt", b""), ("cube.mtl", b"newmtl")]);
+        let pack = AssetPack::parse(data).unwrap();
+        assert_eq!(pack.names(), ["cube.mtl", "empty.txt", "pawn.obj"]);
+        assert_eq!(pack.get("pawn.obj"), Some(&b"v 0 0 0"[..]));
+        assert_eq!(pack.get("empty.txt"), Some(&b""[..]));
+        assert_eq!(pack.get("missing.obj"), None);
+    }
+
+    #[test]
+    fn test_invalid_packs() {
+        let data = packed(&[("pawn.obj", b"v 0 0 0")]);
+        assert!(matches!(AssetPack::parse(b"PK\x03\x04".to_vec()), Err(PackError::InvalidMagic)));
+        assert!(matches!(AssetPack::parse(data[..data.len() - 1].to_vec()), Err(PackError::Truncated)));
+
+        let mut future = data.clone();
+        future[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(AssetPack::parse(future), Err(PackError::UnsupportedVersion(2))));
+
+        let files = [("a".to_string(), vec![1]), ("a".to_string(), vec![2])];
+        assert!(matches!(write_pack(&files, &mut Vec::new()), Err(PackError::DuplicateName(name)) if name == "a"));
+    }
+}
@@ -0,0 +1,57 @@
+//! 리소스 폴더를 팩 파일로 묶는 도구.
+//!
+//! `asset_pack [RESOURCE_DIR] [OUT_FILE]` (기본값 `resource`, `assets.pack`)
+//! `asset_pack --list PACK_FILE`
+//!
+//! 만든 팩 파일을 클라이언트 실행파일 옆에 두면 리소스 폴더 없이 실행됨.
+
+use std::path::PathBuf;
+
+use asset_pack::{collect_dir, write_pack, AssetPack, PackError, DEFAULT_PACK_FILE};
+
+
+const USAGE: &str = "Usage: asset_pack [RESOURCE_DIR] [OUT_FILE]\n       asset_pack --list PACK_FILE";
+
+
+fn build(dir: PathBuf, out: PathBuf) -> Result<(), PackError> {
+    let files = collect_dir(&dir)?;
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&out)?);
+    write_pack(&files, &mut file)?;
+    std::io::Write::flush(&mut file)?;
+
+    let size = files.iter().map(|(_, data)| data.len()).sum::<usize>();
+    println!("packed {} files ({} bytes) from {} into {}", files.len(), size, dir.display(), out.display());
+    Ok(())
+}
+
+fn list(path: PathBuf) -> Result<(), PackError> {
+    let pack = AssetPack::open(&path)?;
+    for name in pack.names() {
+        println!("{:>10}  {}", pack.get(name).map_or(0, <[u8]>::len), name);
+    }
+    Ok(())
+}
+
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["--list", pack] => list(pack.into()),
+        [arg, ..] if arg.starts_with("--") => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+        [] => build("resource".into(), DEFAULT_PACK_FILE.into()),
+        [dir] => build(dir.into(), DEFAULT_PACK_FILE.into()),
+        [dir, out] => build(dir.into(), out.into()),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::MissedTickBehavior,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use network::{ClientMessage, PacketParser, ServerMessage, PROTOCOL_VERSION};
+
+use crate::{stats::Stats, Config};
+
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// 접속한 뒤 이 시간 안에 `init`이 와야 함
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+
+/// `index`번째 봇. `config.duration`이 지나거나 연결이 끊기면 기록을 반환함
+pub async fn run(index: usize, config: &Config) -> Stats {
+    let mut stats = Stats::default();
+    let started = Instant::now();
+    let stream = match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&config.addr)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => {
+            stats.error(format!("failed to connect: {}", e.kind()));
+            return stats;
+        }
+        Err(_) => {
+            stats.error("connect timed out");
+            return stats;
+        }
+    };
+    let _ = stream.set_nodelay(true);
+
+    let mut bot = Bot {
+        name: format!("bot{}", index),
+        stream,
+        packet_parser: PacketParser::new(),
+        stats,
+        started,
+        id: None,
+        moves_sent: 0,
+        unacked: VecDeque::new(),
+        last_ack: 0,
+        pings_sent: 0,
+        pings: HashMap::new(),
+        rng: StdRng::from_entropy(),
+        running: true,
+    };
+    bot.run(config).await;
+    bot.stats
+}
+
+
+struct Bot {
+    name: String,
+    stream: TcpStream,
+    packet_parser: PacketParser,
+    stats: Stats,
+    /// 접속을 시작한 시각
+    started: Instant,
+    /// `init`에서 받은 플레이어 id
+    id: Option<u32>,
+    moves_sent: u32,
+    /// 보낸 이동 중 아직 `ack`를 받지 못한 것. (그 이동까지 적용했을 때 서버가 보낼 `ack`, 보낸 시각)
+    unacked: VecDeque<(u32, Instant)>,
+    last_ack: u32,
+    pings_sent: u64,
+    /// `ping`의 stamp와 보낸 시각
+    pings: HashMap<u64, Instant>,
+    rng: StdRng,
+    /// 연결이 끊겼거나 서버가 종료함
+    running: bool,
+}
+
+impl Bot {
+    async fn run(&mut self, config: &Config) {
+        let deadline = tokio::time::sleep(config.duration);
+        tokio::pin!(deadline);
+        let handshake = tokio::time::sleep(HANDSHAKE_TIMEOUT);
+        tokio::pin!(handshake);
+
+        // 초당 0개이면 이동을 보내지 않음
+        let move_interval = (config.move_rate > 0.0).then(|| Duration::from_secs_f64(1.0 / config.move_rate));
+        let mut moves = tokio::time::interval(move_interval.unwrap_or(config.duration));
+        moves.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut pings = tokio::time::interval(PING_INTERVAL);
+        pings.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut buf = [0; 4096];
+        while self.running {
+            let connected = self.id.is_some();
+            tokio::select! {
+                _ = &mut deadline => break,
+                _ = &mut handshake, if !connected => {
+                    self.stats.error("no init from server");
+                    return;
+                },
+                _ = moves.tick(), if connected && move_interval.is_some() => self.send_move().await,
+                _ = pings.tick(), if connected => self.send_ping().await,
+                read = self.stream.read(&mut buf) => match read {
+                    Ok(0) => {
+                        self.stats.error("connection closed by server");
+                        return;
+                    },
+                    Ok(n) => {
+                        self.stats.bytes_received += n as u64;
+                        self.packet_parser.push(&buf[..n]);
+                        self.process_messages().await;
+                    },
+                    Err(e) => {
+                        self.stats.error(format!("failed to read: {}", e.kind()));
+                        return;
+                    },
+                },
+            }
+        }
+
+        if let Some(id) = self.id.filter(|_| self.running) {
+            self.send(&ClientMessage::Disconnect { id }).await;
+            let _ = self.stream.shutdown().await;
+        }
+    }
+
+    /// 보내지 못하면 연결이 끊긴 것으로 처리
+    async fn send(&mut self, message: &ClientMessage) {
+        if let Err(e) = self.stream.write_all(message.encode().as_bytes()).await {
+            self.stats.error(format!("failed to write: {}", e.kind()));
+            self.running = false;
+        }
+    }
+
+    /// 상하좌우 중 한 칸
+    async fn send_move(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+
+        let (x, y) = match self.rng.gen_range(0..4) {
+            0 => (1.0, 0.0),
+            1 => (-1.0, 0.0),
+            2 => (0.0, 1.0),
+            _ => (0.0, -1.0),
+        };
+        self.moves_sent = self.moves_sent.wrapping_add(1);
+        self.unacked.push_back((self.moves_sent, Instant::now()));
+        self.stats.moves_sent += 1;
+        self.send(&ClientMessage::Move { id, x, y }).await;
+    }
+
+    async fn send_ping(&mut self) {
+        self.pings_sent += 1;
+        self.pings.insert(self.pings_sent, Instant::now());
+        self.send(&ClientMessage::Ping { stamp: Some(self.pings_sent) }).await;
+    }
+
+    async fn process_messages(&mut self) {
+        while let Some(msg) = self.packet_parser.pop() {
+            self.stats.messages_received += 1;
+            let msg = String::from_utf8_lossy(&msg).into_owned();
+            match ServerMessage::decode(&msg) {
+                Ok(message) => self.process_message(message).await,
+                Err(_) => self.stats.error("malformed message"),
+            }
+        }
+    }
+
+    /// 서버의 응답이 보낸 메세지와 맞는지 확인
+    async fn process_message(&mut self, message: ServerMessage) {
+        match message {
+            ServerMessage::Init { id, version, .. } => {
+                if self.id.is_some() {
+                    self.stats.error("init received twice");
+                    return;
+                }
+                if version != Some(PROTOCOL_VERSION) {
+                    self.stats.error(format!("server protocol version {:?}, expected {}", version, PROTOCOL_VERSION));
+                }
+                self.id = Some(id);
+                self.stats.connected += 1;
+                self.stats.handshake.push(self.started.elapsed());
+                self.send(&ClientMessage::Name { name: self.name.clone(), key: None }).await;
+            },
+
+            // ack는 다음에 받을 이동의 일련번호. 서버가 버린 이동에는 ack가 오지 않음
+            ServerMessage::Ack { seq } => {
+                if seq < self.last_ack || seq > self.moves_sent {
+                    self.stats.error("ack for a move that was not sent");
+                    return;
+                }
+                self.last_ack = seq;
+                while let Some(&(expected, sent)) = self.unacked.front() {
+                    if expected > seq {
+                        break;
+                    }
+                    self.unacked.pop_front();
+                    if expected == seq {
+                        self.stats.moves_acked += 1;
+                        self.stats.ack.push(sent.elapsed());
+                    }
+                }
+            },
+
+            ServerMessage::Pong { stamp } => match stamp.and_then(|stamp| self.pings.remove(&stamp)) {
+                Some(sent) => self.stats.ping.push(sent.elapsed()),
+                None => self.stats.error("pong for a ping that was not sent"),
+            },
+
+            ServerMessage::Closing => {
+                self.stats.error("server closing");
+                self.running = false;
+            },
+
+            _ => {},
+        }
+    }
+}
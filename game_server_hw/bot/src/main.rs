@@ -0,0 +1,128 @@
+//! 부하 테스트용 봇.
+//! 그래픽 없이 프로토콜만 사용하는 클라이언트를 여러 개 접속시켜 무작위로 움직이고,
+//! 서버의 응답을 확인하면서 지연 시간을 잰 뒤 결과를 출력함.
+//!
+//! `bot <ip:port> [--clients N] [--rate MOVES_PER_SEC] [--duration SECS]`
+
+use std::time::{Duration, Instant};
+use futures::future::join_all;
+
+mod bot;
+mod stats;
+
+use stats::Stats;
+
+
+const USAGE: &str = "Usage: bot <ip:port> [--clients N] [--rate MOVES_PER_SEC] [--duration SECS]";
+
+pub const DEFAULT_CLIENTS: usize = 10;
+/// 서버의 기본 제한 (`GAMESERVER_MOVE_RATE`)보다 낮음
+pub const DEFAULT_MOVE_RATE: f64 = 10.0;
+pub const DEFAULT_DURATION: Duration = Duration::from_secs(30);
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub addr: String,
+    pub clients: usize,
+    /// 봇마다 초당 보내는 이동 수. 0이면 이동하지 않음
+    pub move_rate: f64,
+    pub duration: Duration,
+}
+
+impl Config {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut addr = None;
+        let mut config = Config {
+            addr: String::new(),
+            clients: DEFAULT_CLIENTS,
+            move_rate: DEFAULT_MOVE_RATE,
+            duration: DEFAULT_DURATION,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("{} requires a value\n{}", name, USAGE));
+            match arg.as_str() {
+                "--clients" => {
+                    config.clients = value("--clients")?.parse()
+                        .map_err(|e| format!("invalid --clients: {}", e))?;
+                },
+                "--rate" => {
+                    config.move_rate = value("--rate")?.parse::<f64>().ok()
+                        .filter(|rate| rate.is_finite() && *rate >= 0.0)
+                        .ok_or_else(|| "invalid --rate: expected moves per second".to_string())?;
+                },
+                "--duration" => {
+                    config.duration = value("--duration")?.parse::<f64>().ok()
+                        .filter(|secs| secs.is_finite() && *secs > 0.0)
+                        .map(Duration::from_secs_f64)
+                        .ok_or_else(|| "invalid --duration: expected seconds".to_string())?;
+                },
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}\n{}", arg, USAGE)),
+                _ if addr.is_none() => addr = Some(arg),
+                _ => return Err(USAGE.to_string()),
+            }
+        }
+
+        config.addr = addr.ok_or_else(|| USAGE.to_string())?;
+        Ok(config)
+    }
+}
+
+
+#[tokio::main]
+async fn main() {
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    println!(
+        "{} bots -> {}, {} moves/s each for {:?}",
+        config.clients,
+        config.addr,
+        config.move_rate,
+        config.duration
+    );
+    let started = Instant::now();
+    let results = join_all((0..config.clients).map(|index| bot::run(index, &config))).await;
+
+    let mut stats = Stats::default();
+    for result in results {
+        stats.merge(result);
+    }
+    println!("{}", stats.report(config.clients, started.elapsed()));
+
+    if !stats.errors.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_args() {
+        let args = ["localhost:7878", "--clients", "100", "--rate", "20", "--duration", "1.5"].map(String::from);
+        assert_eq!(Config::from_args(args).unwrap(), Config {
+            addr: "localhost:7878".to_string(),
+            clients: 100,
+            move_rate: 20.0,
+            duration: Duration::from_millis(1500),
+        });
+
+        let config = Config::from_args(["localhost:7878".to_string()]).unwrap();
+        assert_eq!((config.clients, config.duration), (DEFAULT_CLIENTS, DEFAULT_DURATION));
+
+        for invalid in [&[][..], &["localhost:7878", "--rate"], &["localhost:7878", "--duration", "0"], &["a:1", "b:2"], &["a:1", "--fast"]] {
+            assert!(Config::from_args(invalid.iter().map(|arg| arg.to_string())).is_err(), "{:?}", invalid);
+        }
+    }
+}
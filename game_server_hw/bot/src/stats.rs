@@ -0,0 +1,162 @@
+use std::{
+    collections::BTreeMap,
+    time::Duration,
+};
+
+
+/// 봇 하나가 모은 기록. 끝나면 모든 봇의 기록을 `merge`해서 보고함
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub connected: usize,
+    pub moves_sent: u64,
+    pub moves_acked: u64,
+    pub messages_received: u64,
+    pub bytes_received: u64,
+    /// 접속부터 `init`까지
+    pub handshake: Vec<Duration>,
+    /// `move`를 보낸 뒤 그 이동을 포함한 `ack`까지
+    pub ack: Vec<Duration>,
+    /// `ping` 왕복
+    pub ping: Vec<Duration>,
+    /// 종류별 횟수
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl Stats {
+    pub fn error(&mut self, kind: impl Into<String>) {
+        *self.errors.entry(kind.into()).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: Stats) {
+        self.connected += other.connected;
+        self.moves_sent += other.moves_sent;
+        self.moves_acked += other.moves_acked;
+        self.messages_received += other.messages_received;
+        self.bytes_received += other.bytes_received;
+        self.handshake.extend(other.handshake);
+        self.ack.extend(other.ack);
+        self.ping.extend(other.ping);
+        for (kind, count) in other.errors {
+            *self.errors.entry(kind).or_default() += count;
+        }
+    }
+
+    /// `clients`개의 봇이 `elapsed` 동안 모은 기록
+    pub fn report(&self, clients: usize, elapsed: Duration) -> String {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        let mut lines = vec![
+            format!("clients: {}/{} connected, {:.1}s", self.connected, clients, elapsed.as_secs_f64()),
+            format!(
+                "moves: {} sent, {} acked ({:.1}/s)",
+                self.moves_sent,
+                self.moves_acked,
+                self.moves_sent as f64 / secs
+            ),
+            format!(
+                "received: {} messages ({:.1}/s), {} bytes ({:.1} KiB/s)",
+                self.messages_received,
+                self.messages_received as f64 / secs,
+                self.bytes_received,
+                self.bytes_received as f64 / secs / 1024.0
+            ),
+            format!("handshake: {}", Latency::of(&self.handshake)),
+            format!("ack: {}", Latency::of(&self.ack)),
+            format!("ping: {}", Latency::of(&self.ping)),
+        ];
+        match self.errors.is_empty() {
+            true => lines.push("errors: none".to_string()),
+            false => lines.extend(self.errors.iter().map(|(kind, count)| format!("error: {} x{}", kind, count))),
+        }
+        lines.join("\n")
+    }
+}
+
+
+/// 지연 시간의 백분위수
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Latency {
+    pub samples: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Latency {
+    /// 샘플이 없으면 모두 0
+    pub fn of(samples: &[Duration]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        Self {
+            samples: sorted.len(),
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for Latency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.samples == 0 {
+            return write!(f, "no samples");
+        }
+
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "p50 {:.2}ms, p90 {:.2}ms, p99 {:.2}ms, max {:.2}ms ({} samples)",
+            ms(self.p50),
+            ms(self.p90),
+            ms(self.p99),
+            ms(self.max),
+            self.samples
+        )
+    }
+}
+
+/// 정렬된 `sorted`에서 nearest-rank 방식의 `p`백분위수
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency() {
+        let samples = (1..=100).rev().map(Duration::from_millis).collect::<Vec<_>>();
+        let latency = Latency::of(&samples);
+        assert_eq!(latency.p50, Duration::from_millis(50));
+        assert_eq!(latency.p90, Duration::from_millis(90));
+        assert_eq!(latency.p99, Duration::from_millis(99));
+        assert_eq!(latency.max, Duration::from_millis(100));
+
+        assert_eq!(Latency::of(&[Duration::from_millis(7)]).p50, Duration::from_millis(7));
+        assert_eq!(Latency::of(&[]).to_string(), "no samples");
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut stats = Stats { connected: 1, moves_sent: 3, ..Default::default() };
+        stats.error("malformed message");
+
+        let mut other = Stats { connected: 1, moves_sent: 2, ack: vec![Duration::from_millis(1)], ..Default::default() };
+        other.error("malformed message");
+        other.error("no init");
+        stats.merge(other);
+
+        assert_eq!((stats.connected, stats.moves_sent, stats.ack.len()), (2, 5, 1));
+        assert_eq!(stats.errors["malformed message"], 2);
+        assert_eq!(stats.errors["no init"], 1);
+    }
+}
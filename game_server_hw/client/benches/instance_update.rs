@@ -0,0 +1,74 @@
+//! 1000개 오브젝트 중 1%만 움직이는 프레임에서
+//! 전체 인스턴스 재작성과 바뀐 슬롯만 고르는 방식의 비용 비교.  
+//! `cargo bench -p client --bench instance_update`
+
+use std::time::Instant;
+
+use client::framework::{
+    model::changed_instance_ranges,
+    object::{Instance, InstanceRaw, Renderable},
+    transform::Transform,
+};
+
+
+const OBJECTS: usize = 1000;
+const FRAMES: usize = 1000;
+
+
+fn bench(name: &str, mut frame: impl FnMut(usize) -> usize) {
+    let mut written = 0;
+    let start = Instant::now();
+    for i in 0..FRAMES {
+        written += frame(i);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<12} {:>10.2?}/frame, {:>6} slots written/frame",
+        name,
+        elapsed / FRAMES as u32,
+        written / FRAMES
+    );
+}
+
+fn move_one_percent(transforms: &mut [Transform], frame: usize) {
+    for transform in transforms.iter_mut().skip(frame % 100).step_by(100) {
+        transform.position.x += 1.0;
+    }
+}
+
+fn instance_data(transforms: &[Transform], renderable: &Renderable) -> Vec<InstanceRaw> {
+    transforms.iter()
+        .map(|transform| Instance::new(transform, renderable, None).raw)
+        .collect()
+}
+
+fn main() {
+    let mut transforms = vec![Transform::default(); OBJECTS];
+    let renderable = Renderable::new("pawn");
+    let mut buffer = vec![0u8; OBJECTS * std::mem::size_of::<InstanceRaw>()];
+
+    // 매 프레임 전체 데이터를 버퍼로 복사
+    bench("full", |frame| {
+        move_one_percent(&mut transforms, frame);
+        let data = instance_data(&transforms, &renderable);
+        buffer.copy_from_slice(bytemuck::cast_slice(&data));
+        data.len()
+    });
+
+    // 바뀐 슬롯만 복사
+    let mut uploaded = Vec::new();
+    bench("dirty", |frame| {
+        move_one_percent(&mut transforms, frame);
+        let data = instance_data(&transforms, &renderable);
+        let ranges = changed_instance_ranges(&mut uploaded, &data);
+        let size = std::mem::size_of::<InstanceRaw>();
+        for range in ranges.iter() {
+            buffer[range.start * size..range.end * size]
+                .copy_from_slice(bytemuck::cast_slice(&data[range.clone()]));
+        }
+        ranges.iter().map(|range| range.len()).sum()
+    });
+
+    std::hint::black_box(&buffer);
+}
@@ -0,0 +1,281 @@
+//! 키프레임 애니메이션.
+//! 리소스 폴더의 애니메이션 파일 (`pawn.anim`)에서 이름별 클립을 읽고, 오브젝트의 `Animator`가 시간에 따라
+//! 클립의 자세 (모델 원점 기준의 이동, 회전, 크기)를 계산함. 자세는 인스턴스 행렬에 곱해지므로 셰이더는 그대로.
+
+use std::{
+    collections::HashMap,
+    rc::Rc,
+    str::FromStr,
+    time::Duration,
+};
+use cgmath::{Deg, Euler, InnerSpace, Matrix4, Quaternion, Vector3, VectorSpace};
+
+use super::resources;
+
+
+/// 폰 모델의 애니메이션 파일
+pub const PAWN_ANIMATION_FILE: &str = "pawn.anim";
+/// 다른 클립이 끝나면 돌아가는 클립
+pub const IDLE: &str = "idle";
+/// 한 칸을 움직일 때 한 번 재생하는 클립
+pub const WALK: &str = "walk";
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    /// 클립 시작부터의 시간 (초)
+    pub time: f32,
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: f32,
+}
+
+impl Keyframe {
+    fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_scale(self.scale)
+    }
+}
+
+
+/// 시간 순서의 키프레임. 마지막 키프레임의 시간이 길이
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClip {
+    pub looping: bool,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl AnimationClip {
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// `time`초의 자세. 반복하면 길이로 나눈 나머지, 아니면 마지막 키프레임에서 멈춤
+    pub fn sample(&self, time: f32) -> Matrix4<f32> {
+        let duration = self.duration();
+        let time = match self.looping && duration > 0.0 {
+            true => time.rem_euclid(duration),
+            false => time.clamp(0.0, duration),
+        };
+
+        let next = self.keyframes.iter().position(|keyframe| keyframe.time > time);
+        let (a, b) = match next {
+            Some(0) | None if self.keyframes.is_empty() => return Matrix4::from_scale(1.0),
+            Some(0) => return self.keyframes[0].matrix(),
+            None => return self.keyframes[self.keyframes.len() - 1].matrix(),
+            Some(next) => (&self.keyframes[next - 1], &self.keyframes[next]),
+        };
+
+        let t = (time - a.time) / (b.time - a.time);
+        Keyframe {
+            time,
+            translation: a.translation.lerp(b.translation, t),
+            rotation: a.rotation.nlerp(b.rotation, t),
+            scale: a.scale + (b.scale - a.scale) * t,
+        }.matrix()
+    }
+}
+
+
+/// 이름별 클립
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnimationSet {
+    clips: HashMap<String, AnimationClip>,
+}
+
+impl FromStr for AnimationSet {
+    type Err = anyhow::Error;
+
+    /// `[이름]` 표마다 `loop = true|false`와 한 줄에 키프레임 하나
+    /// (`시간 x y z 회전x 회전y 회전z 크기`, 회전은 도). `#` 뒤는 주석
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut clips: HashMap<String, AnimationClip> = HashMap::new();
+        let mut current: Option<String> = None;
+
+        for (n, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                let name = name.trim().to_string();
+                if clips.contains_key(&name) {
+                    anyhow::bail!("line {}: duplicate clip [{}]", n + 1, name);
+                }
+                clips.insert(name.clone(), AnimationClip { looping: true, keyframes: Vec::new() });
+                current = Some(name);
+                continue;
+            }
+            let Some(clip) = current.as_ref().and_then(|name| clips.get_mut(name)) else {
+                anyhow::bail!("line {}: keyframe must be in a clip", n + 1);
+            };
+
+            if let Some((key, value)) = line.split_once('=') {
+                match (key.trim(), value.trim().parse::<bool>()) {
+                    ("loop", Ok(looping)) => clip.looping = looping,
+                    ("loop", Err(_)) => anyhow::bail!("line {}: invalid boolean {:?} for `loop`", n + 1, value.trim()),
+                    (key, _) => anyhow::bail!("line {}: unknown key `{}`", n + 1, key),
+                }
+                continue;
+            }
+
+            let values = line.split_whitespace()
+                .map(str::parse::<f32>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| anyhow::anyhow!("line {}: invalid keyframe {:?}", n + 1, line))?;
+            let &[time, x, y, z, rx, ry, rz, scale] = values.as_slice() else {
+                anyhow::bail!("line {}: keyframe needs 8 numbers (time x y z rx ry rz scale), found {}", n + 1, values.len());
+            };
+            if clip.keyframes.last().is_some_and(|last| time <= last.time) || time < 0.0 {
+                anyhow::bail!("line {}: keyframe times must increase", n + 1);
+            }
+            clip.keyframes.push(Keyframe {
+                time,
+                translation: Vector3::new(x, y, z),
+                rotation: Quaternion::from(Euler::new(Deg(rx), Deg(ry), Deg(rz))).normalize(),
+                scale,
+            });
+        }
+
+        if let Some((name, _)) = clips.iter().find(|(_, clip)| clip.keyframes.is_empty()) {
+            anyhow::bail!("clip [{}] has no keyframes", name);
+        }
+        Ok(Self { clips })
+    }
+}
+
+impl AnimationSet {
+    /// 리소스 폴더의 `file_name`
+    pub async fn load(file_name: &str) -> anyhow::Result<Self> {
+        let text = resources::load_string(file_name).await?;
+        text.parse().map_err(|e| anyhow::anyhow!("invalid {}: {}", file_name, e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+}
+
+
+/// 오브젝트가 재생하는 클립. `IDLE`을 반복하다가 다른 클립이 끝나면 돌아감
+#[derive(Debug, Clone)]
+pub struct Animator {
+    clips: Rc<AnimationSet>,
+    current: String,
+    /// `current`를 시작한 뒤 흐른 시간 (초)
+    time: f32,
+}
+
+impl Animator {
+    pub fn new(clips: Rc<AnimationSet>) -> Self {
+        Self { clips, current: IDLE.to_string(), time: 0.0 }
+    }
+
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// `name`을 처음부터 재생. 이미 재생 중이면 그대로 이어가고, 없는 클립이면 무시
+    pub fn play(&mut self, name: &str) {
+        if self.current == name || self.clips.get(name).is_none() {
+            return;
+        }
+        self.current = name.to_string();
+        self.time = 0.0;
+    }
+
+    pub fn advance(&mut self, dt: Duration) {
+        self.time += dt.as_secs_f32();
+        let Some(clip) = self.clips.get(&self.current) else {
+            return;
+        };
+        if !clip.looping && self.time >= clip.duration() && self.current != IDLE {
+            self.current = IDLE.to_string();
+            self.time = 0.0;
+        }
+    }
+
+    /// 모델 원점 기준의 자세. 오브젝트의 `Transform` 안쪽에 곱함
+    pub fn pose(&self) -> Matrix4<f32> {
+        self.clips.get(&self.current)
+            .map_or_else(|| Matrix4::from_scale(1.0), |clip| clip.sample(self.time))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{SquareMatrix, Vector4};
+
+    const CLIPS: &str = "\
+[idle]
+0   0 0 0   0 0 0   1
+1   0 1 0   0 0 0   1 # 위로
+2   0 0 0   0 0 0   1
+
+[walk]
+loop = false
+0   0 0 0   0 0 0   1
+0.5 0 0 0   0 90 0  2
+";
+
+    fn translation(matrix: Matrix4<f32>) -> Vector3<f32> {
+        (matrix * Vector4::new(0.0, 0.0, 0.0, 1.0)).truncate()
+    }
+
+    #[test]
+    fn test_parse_and_sample() {
+        let clips = CLIPS.parse::<AnimationSet>().unwrap();
+        let idle = clips.get(IDLE).unwrap();
+        assert!(idle.looping);
+        assert_eq!(idle.duration(), 2.0);
+        assert_eq!(translation(idle.sample(0.5)), Vector3::new(0.0, 0.5, 0.0));
+        // 반복
+        assert_eq!(translation(idle.sample(2.5)), Vector3::new(0.0, 0.5, 0.0));
+
+        // 끝나면 마지막 자세에서 멈춤
+        let walk = clips.get(WALK).unwrap();
+        assert!(!walk.looping);
+        let end = walk.sample(3.0) * Vector4::new(1.0, 0.0, 0.0, 0.0);
+        assert!((end - Vector4::new(0.0, 0.0, -2.0, 0.0)).magnitude() < 1e-5, "{:?}", end);
+
+        for invalid in [
+            "0 0 0 0 0 0 0 1",
+            "[idle]\nloop = maybe",
+            "[idle]\n0 0 0 0",
+            "[idle]\n1 0 0 0 0 0 0 1\n0 0 0 0 0 0 0 1",
+            "[idle]",
+            "[idle]\n0 0 0 0 0 0 0 1\n[idle]\n0 0 0 0 0 0 0 1",
+        ] {
+            assert!(invalid.parse::<AnimationSet>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_animator() {
+        let mut animator = Animator::new(Rc::new(CLIPS.parse().unwrap()));
+        animator.advance(Duration::from_millis(500));
+        assert_eq!(translation(animator.pose()), Vector3::new(0.0, 0.5, 0.0));
+
+        // 없는 클립은 무시하고, 한 번 재생하는 클립이 끝나면 `idle`로
+        animator.play("jump");
+        assert_eq!(animator.current(), IDLE);
+        animator.play(WALK);
+        assert_eq!(animator.pose(), Matrix4::identity());
+        animator.advance(Duration::from_millis(300));
+        animator.play(WALK);
+        assert_eq!(animator.current(), WALK);
+        animator.advance(Duration::from_millis(300));
+        assert_eq!(animator.current(), IDLE);
+    }
+
+    #[test]
+    fn test_load_pawn_animations() {
+        let clips = futures::executor::block_on(AnimationSet::load(PAWN_ANIMATION_FILE)).unwrap();
+        assert!(clips.get(IDLE).is_some_and(|clip| clip.looping));
+        assert!(clips.get(WALK).is_some_and(|clip| !clip.looping));
+    }
+}
@@ -0,0 +1,175 @@
+//! 씬이 이름으로 찾는 모델과 그 GPU 데이터.
+//! 모델마다 (파일, 크기, 색)이 같은 `ModelGeometry`는 한 번만 GPU에 올려서 공유하고,
+//! 인스턴스 버퍼는 이름별 `Model`이 따로 가짐. 파일은 `MeshCache`가 한 번만 읽음.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+};
+
+use super::{
+    color::Color,
+    model::{MeshCache, Model, ModelGeometry},
+};
+
+
+/// 모델 파일을 GPU에 올릴 때 메쉬에 곱하는 크기와 정점 색
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelParams {
+    pub scale: f32,
+    pub color: Color,
+}
+
+impl ModelParams {
+    pub const fn new(scale: f32, color: Color) -> Self {
+        Self { scale, color }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GeometryKey {
+    file_name: String,
+    /// `f32`의 비트. 같은 값으로 적은 크기와 색이면 같은 키
+    scale: u32,
+    color: [u32; 4],
+}
+
+impl GeometryKey {
+    fn new(file_name: &str, params: ModelParams) -> Self {
+        let color = params.color;
+        Self {
+            file_name: file_name.to_string(),
+            scale: params.scale.to_bits(),
+            color: [color.r(), color.g(), color.b(), color.a()].map(f32::to_bits),
+        }
+    }
+}
+
+
+#[derive(Default)]
+pub struct AssetCache {
+    meshes: MeshCache,
+    geometry: HashMap<GeometryKey, Rc<ModelGeometry>>,
+    /// 추가한 순서 (그리는 순서)
+    models: Vec<(&'static str, Rc<RefCell<Model>>)>,
+}
+
+impl AssetCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mesh_cache(&self) -> &MeshCache {
+        &self.meshes
+    }
+
+    pub fn mesh_cache_mut(&mut self) -> &mut MeshCache {
+        &mut self.meshes
+    }
+
+    /// GPU에 올린 서로 다른 (파일, 크기, 색) 수
+    pub fn geometry_count(&self) -> usize {
+        self.geometry.len()
+    }
+
+    /// `file_name`을 `params`로 올린 데이터. 이미 올렸으면 공유
+    pub async fn geometry(
+        &mut self,
+        file_name: &str,
+        params: ModelParams,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Rc<ModelGeometry>> {
+        let key = GeometryKey::new(file_name, params);
+        if let Some(geometry) = self.geometry.get(&key) {
+            return Ok(geometry.clone());
+        }
+
+        let geometry = ModelGeometry::load(file_name, device, queue, params.scale, params.color, &mut self.meshes).await?;
+        let geometry = Rc::new(geometry);
+        self.geometry.insert(key, geometry.clone());
+        Ok(geometry)
+    }
+
+    /// `file_name`을 `params`로 그리는 모델을 `name`으로 추가
+    pub async fn load_model(
+        &mut self,
+        name: &'static str,
+        file_name: &str,
+        params: ModelParams,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<Rc<RefCell<Model>>> {
+        let geometry = self.geometry(file_name, params, device, queue).await?;
+        Ok(self.insert_model(name, Model::from_geometry(device, geometry)))
+    }
+
+    /// 파일에서 읽지 않는 모델 (`Model::ring` 등)을 `name`으로 추가. 같은 이름이 있으면 바꿈
+    pub fn insert_model(&mut self, name: &'static str, model: Model) -> Rc<RefCell<Model>> {
+        let model = Rc::new(RefCell::new(model));
+        match self.models.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, existing)) => *existing = model.clone(),
+            None => self.models.push((name, model.clone())),
+        }
+        model
+    }
+
+    pub fn model(&self, name: &str) -> Option<&Rc<RefCell<Model>>> {
+        self.models.iter()
+            .find(|(existing, _)| *existing == name)
+            .map(|(_, model)| model)
+    }
+
+    /// 이름과 모델 (추가한 순서)
+    pub fn named_models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        self.models.iter().map(|(name, model)| (*name, model))
+    }
+
+    pub fn models(&self) -> impl Iterator<Item = &Rc<RefCell<Model>>> {
+        self.models.iter().map(|(_, model)| model)
+    }
+
+    pub fn model_count(&self) -> usize {
+        self.models.len()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_device;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_shared_geometry() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut assets = AssetCache::new();
+        let tile = ModelParams::new(0.5, Color::LIGHT_GRAY);
+
+        let a = block_on(assets.load_model("tile", "cube.obj", tile, &device, &queue)).unwrap();
+        let b = block_on(assets.load_model("name_tag", "cube.obj", tile, &device, &queue)).unwrap();
+        let c = block_on(assets.load_model("wall", "cube.obj", ModelParams::new(0.5, Color::DARK_GRAY), &device, &queue)).unwrap();
+        assert!(Rc::ptr_eq(&a.borrow().geometry, &b.borrow().geometry));
+        assert!(!Rc::ptr_eq(&a.borrow().geometry, &c.borrow().geometry));
+        assert!(!Rc::ptr_eq(&a, &b));
+        assert_eq!(assets.geometry_count(), 2);
+        assert_eq!(assets.mesh_cache().misses(), 1);
+
+        // 이름으로 찾고, 같은 이름은 바꿈
+        assert!(Rc::ptr_eq(assets.model("name_tag").unwrap(), &b));
+        assert!(assets.model("missing").is_none());
+        let ring = assets.insert_model("tile", Model::ring(&device, &queue, 0.1, 0.2, Color::WHITE));
+        assert!(Rc::ptr_eq(assets.model("tile").unwrap(), &ring));
+        assert_eq!(assets.named_models().map(|(name, _)| name).collect::<Vec<_>>(), ["tile", "name_tag", "wall"]);
+
+        // 읽지 못한 파일은 저장하지 않음
+        assert!(block_on(assets.load_model("missing", "missing.obj", tile, &device, &queue)).is_err());
+        assert_eq!((assets.model_count(), assets.geometry_count()), (3, 2));
+    }
+}
@@ -0,0 +1,117 @@
+use cgmath::{Vector2, Vector3};
+
+
+/// 서버가 `map`을 보내지 않을 때 보드 한 변의 칸 수 (이전 서버의 보드)
+pub const BOARD_SIZE: i32 = 8;
+
+
+/// 보드 칸(서버 좌표)과 월드 좌표 사이의 변환.  
+/// 칸 `(x, z)`의 중심이 `origin + (x * spacing_x, 0, z * spacing_z)`에 놓임.
+/// 보드 타일과 서버 오브젝트가 모두 이 변환을 사용함.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardLayout {
+    /// 가로(`x`) 칸 수
+    pub width: i32,
+    /// 세로(`z`) 칸 수
+    pub height: i32,
+    pub spacing_x: f32,
+    pub spacing_z: f32,
+    /// 칸 `(0, 0)` 중심의 월드 좌표
+    pub origin: Vector3<f32>,
+}
+
+impl Default for BoardLayout {
+    fn default() -> Self {
+        Self::uniform(1.0)
+    }
+}
+
+impl BoardLayout {
+    pub fn uniform(spacing: f32) -> Self {
+        Self { 
+            width: BOARD_SIZE,
+            height: BOARD_SIZE,
+            spacing_x: spacing, 
+            spacing_z: spacing, 
+            origin: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn tile_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// 칸 중심의 월드 좌표 (`y`는 `origin.y`)
+    pub fn cell_to_world(&self, x: i32, z: i32) -> Vector3<f32> {
+        self.origin + Vector3::new(x as f32 * self.spacing_x, 0.0, z as f32 * self.spacing_z)
+    }
+
+    /// 월드 좌표가 속한 칸. 보드 밖이면 `None`
+    pub fn world_to_cell(&self, x: f32, z: f32) -> Option<(i32, i32)> {
+        let cell_x = ((x - self.origin.x) / self.spacing_x).round();
+        let cell_z = ((z - self.origin.z) / self.spacing_z).round();
+        if !(0.0..self.width as f32).contains(&cell_x) || !(0.0..self.height as f32).contains(&cell_z) {
+            return None;
+        }
+
+        Some((cell_x as i32, cell_z as i32))
+    }
+
+    /// 보드 타일(`GameScene::objects`)의 인덱스
+    pub fn tile_index(&self, x: i32, z: i32) -> usize {
+        (z * self.width + x) as usize
+    }
+
+    /// `tile_index`의 반대
+    pub fn tile_cell(&self, idx: usize) -> (i32, i32) {
+        let idx = idx as i32;
+        (idx % self.width, idx / self.width)
+    }
+
+    /// 서버와 같은 규칙으로 한 칸 이동 (보드 밖으로 나가지 않음)
+    pub fn step(&self, cell: Vector2<i32>, delta: Vector2<i32>) -> Vector2<i32> {
+        Vector2::new(
+            (cell.x + delta.x).clamp(0, self.width - 1),
+            (cell.y + delta.y).clamp(0, self.height - 1),
+        )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_to_cell() {
+        let layout = BoardLayout { spacing_x: 2.0, spacing_z: 0.5, ..Default::default() };
+        assert_eq!(layout.cell_to_world(3, 4), Vector3::new(6.0, 0.0, 2.0));
+        assert_eq!(layout.world_to_cell(6.4, 1.8), Some((3, 4)));
+        assert_eq!(layout.world_to_cell(-2.0, 0.0), None);
+        assert_eq!(layout.world_to_cell(16.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_origin_and_size() {
+        let layout = BoardLayout { width: 4, height: 4, origin: Vector3::new(-1.5, 0.0, -1.5), ..Default::default() };
+        assert_eq!(layout.cell_to_world(0, 3), Vector3::new(-1.5, 0.0, 1.5));
+        assert_eq!(layout.world_to_cell(1.4, -1.6), Some((3, 0)));
+        assert_eq!(layout.world_to_cell(2.5, 0.0), None);
+
+        assert_eq!(layout.tile_count(), 16);
+        assert_eq!(layout.tile_cell(layout.tile_index(1, 2)), (1, 2));
+        assert_eq!(layout.step(Vector2::new(3, 0), Vector2::new(1, -1)), Vector2::new(3, 0));
+    }
+
+    #[test]
+    fn test_rectangular() {
+        let layout = BoardLayout { width: 5, height: 2, ..Default::default() };
+        assert_eq!(layout.tile_count(), 10);
+        assert_eq!(layout.tile_index(4, 1), 9);
+        assert_eq!(layout.tile_cell(7), (2, 1));
+        assert_eq!(layout.world_to_cell(4.0, 1.0), Some((4, 1)));
+        assert_eq!(layout.world_to_cell(1.0, 2.0), None);
+        assert_eq!(layout.step(Vector2::new(4, 1), Vector2::new(1, 1)), Vector2::new(4, 1));
+    }
+}
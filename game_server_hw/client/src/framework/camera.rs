@@ -68,14 +68,23 @@ impl From<CameraComponent> for DefaultCamera {
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
+    /// 정반사광 계산에 쓰는 카메라 위치. w는 사용하지 않음
+    view_position: [f32; 4],
     view_proj: [[f32; 4]; 4],
 }
 
+impl Default for CameraUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CameraUniform {
     pub fn new() -> Self {
         use cgmath::SquareMatrix;
 
         Self {
+            view_position: [0.0; 4],
             view_proj: cgmath::Matrix4::identity().into()
         }
     }
@@ -83,4 +92,8 @@ impl CameraUniform {
     pub fn update_view_proj(&mut self, camera_view_proj: cgmath::Matrix4<f32>) {
         self.view_proj = camera_view_proj.into();
     }
+
+    pub fn update_view_position(&mut self, eye: Point3<f32>) {
+        self.view_position = eye.to_homogeneous().into();
+    }
 }
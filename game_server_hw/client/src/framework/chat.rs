@@ -0,0 +1,121 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+
+/// 보관하는 채팅 메세지 최대 개수
+pub const CHAT_HISTORY_CAPACITY: usize = 100;
+
+/// 화면에 보여주는 최근 채팅 줄 수
+pub const CHAT_OVERLAY_LINES: usize = 3;
+/// 받은 뒤 이 시간이 지난 채팅은 화면에서 사라짐. 입력창을 열면 다시 보임
+pub const CHAT_OVERLAY_DURATION: Duration = Duration::from_secs(10);
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub sender: u32,
+    /// 서버가 이름을 보내지 않으면 `None`
+    pub name: Option<String>,
+    /// 클라이언트가 메세지를 받은 시간
+    pub timestamp: Instant,
+    pub text: String,
+}
+
+impl ChatMessage {
+    /// `이름: 내용`. 이름이 없으면 보낸 플레이어 id로 표시
+    pub fn line(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}: {}", name, self.text),
+            None => format!("{}: {}", self.sender, self.text),
+        }
+    }
+}
+
+
+/// 최근 채팅 메세지. 가득 차면 가장 오래된 메세지부터 버림
+pub struct ChatHistory {
+    messages: VecDeque<ChatMessage>,
+    capacity: usize,
+}
+
+impl Default for ChatHistory {
+    fn default() -> Self {
+        Self::new(CHAT_HISTORY_CAPACITY)
+    }
+}
+
+impl ChatHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { messages: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.messages.len() == self.capacity {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    /// 마지막 `n`개를 오래된 순서로
+    pub fn recent(&self, n: usize) -> impl Iterator<Item = &ChatMessage> {
+        self.messages.iter().skip(self.messages.len().saturating_sub(n))
+    }
+
+    /// 마지막 `n`개 중 `now`까지 `duration`이 지나지 않은 메세지 (오래된 순서)
+    pub fn visible(&self, n: usize, now: Instant, duration: Duration) -> impl Iterator<Item = &ChatMessage> {
+        self.recent(n).filter(move |message| now.saturating_duration_since(message.timestamp) < duration)
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_after_overflow() {
+        let mut history = ChatHistory::new(3);
+        for i in 0..5 {
+            history.push(ChatMessage {
+                sender: i,
+                name: None,
+                timestamp: Instant::now(),
+                text: format!("msg {}", i),
+            });
+        }
+
+        let senders = history.recent(2).map(|m| m.sender).collect::<Vec<_>>();
+        assert_eq!(senders, [3, 4]);
+        let senders = history.recent(10).map(|m| m.sender).collect::<Vec<_>>();
+        assert_eq!(senders, [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_visible() {
+        let start = Instant::now();
+        let mut history = ChatHistory::default();
+        for i in 0..3 {
+            history.push(ChatMessage {
+                sender: i,
+                name: (i == 2).then(|| "bob".to_string()),
+                timestamp: start + Duration::from_secs(i as u64 * 5),
+                text: "hi".to_string(),
+            });
+        }
+
+        let now = start + Duration::from_secs(12);
+        let lines = history.visible(10, now, Duration::from_secs(10)).map(ChatMessage::line).collect::<Vec<_>>();
+        assert_eq!(lines, ["1: hi", "bob: hi"]);
+        assert_eq!(history.visible(1, now, Duration::from_secs(10)).count(), 1);
+    }
+}
@@ -1,5 +1,5 @@
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Color(f32, f32, f32, f32);
 
 impl Color {
@@ -15,7 +15,7 @@ impl Color {
     pub const LIGHT_GRAY: Color = Color(0.75, 0.75, 0.75, 1.0);
     pub const DARK_GRAY: Color  = Color(0.25, 0.25, 0.25, 1.0);
 
-    pub fn from_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+    pub const fn from_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
         let r = r.clamp(0.0, 1.0);
         let g = g.clamp(0.0, 1.0);
         let b = b.clamp(0.0, 1.0);
@@ -24,10 +24,38 @@ impl Color {
         Self(r, g, b, a)
     }
 
-    pub fn from_rgb(r: f32, g: f32, b: f32) -> Self {
+    pub const fn from_rgb(r: f32, g: f32, b: f32) -> Self {
         Self::from_rgba(r, g, b, 1.0)
     }
 
+    /// `"#RRGGBB"`, `"RRGGBB"`, `"#RRGGBBAA"`, `"RRGGBBAA"` 형식의 문자열에서 색상 생성
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return None;
+        }
+
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .ok()
+                .map(|v| v as f32 / 255.0)
+        };
+
+        let a = if hex.len() == 8 { channel(6)? } else { 1.0 };
+        Some(Self::from_rgba(channel(0)?, channel(2)?, channel(4)?, a))
+    }
+
+    /// `self`에서 `other`로 `t`(0..=1)만큼 보간한 색상
+    pub fn lerp(&self, other: &Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self::from_rgba(
+            self.0 + (other.0 - self.0) * t,
+            self.1 + (other.1 - self.1) * t,
+            self.2 + (other.2 - self.2) * t,
+            self.3 + (other.3 - self.3) * t,
+        )
+    }
+
     pub fn r(&self) -> f32 {
         self.0
     }
@@ -57,19 +85,52 @@ impl From<[f32; 3]> for Color {
     }
 }
 
-impl Into<[f32; 4]> for Color {
-    fn into(self) -> [f32; 4] {
-        [self.0, self.1, self.2, self.3]
+impl From<Color> for [f32; 4] {
+    fn from(color: Color) -> Self {
+        [color.0, color.1, color.2, color.3]
     }
 }
 
-impl Into<wgpu::Color> for Color {
-    fn into(self) -> wgpu::Color {
+impl From<Color> for wgpu::Color {
+    fn from(color: Color) -> Self {
         wgpu::Color {
-            r: self.0 as f64,
-            g: self.1 as f64,
-            b: self.2 as f64,
-            a: self.3 as f64,
+            r: color.0 as f64,
+            g: color.1 as f64,
+            b: color.2 as f64,
+            a: color.3 as f64,
         }
     }
-}
\ No newline at end of file
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constructors() {
+        assert_eq!(Color::from_rgb(0.2, 0.4, 0.6).a(), 1.0);
+        assert_eq!(Color::from_rgba(0.2, 0.4, 0.6, 0.5).a(), 0.5);
+        assert_eq!(Color::from_rgba(2.0, -1.0, 0.5, 1.5), Color::from_rgb(1.0, 0.0, 0.5));
+        assert_eq!(Color::from([0.0, 1.0, 1.0, 1.0]), Color::CYAN);
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let color = Color::from_hex("#ff0080").unwrap();
+        assert_eq!((color.r(), color.g(), color.b(), color.a()), (1.0, 0.0, 128.0 / 255.0, 1.0));
+
+        let color = Color::from_hex("00ff0000").unwrap();
+        assert_eq!((color.r(), color.g(), color.b(), color.a()), (0.0, 1.0, 0.0, 0.0));
+
+        assert!(Color::from_hex("#fff").is_none());
+        assert!(Color::from_hex("gg0000").is_none());
+    }
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(Color::BLACK.lerp(&Color::WHITE, 0.5), Color::GRAY);
+        assert_eq!(Color::BLACK.lerp(&Color::WHITE, 2.0), Color::WHITE);
+    }
+}
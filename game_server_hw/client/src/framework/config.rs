@@ -0,0 +1,1043 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    time::Duration,
+};
+use winit::keyboard::KeyCode;
+
+use super::{
+    input::{Action, InputMap},
+    logging,
+    resources::{self, ResourceError},
+    skybox::DEFAULT_SKYBOX,
+    SCREEN_WIDTH,
+    SCREEN_HEIGHT,
+};
+
+
+/// 접속할 서버 주소
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerConfig {
+    pub ip: String,
+    pub port: u16,
+}
+
+impl FromStr for ServerConfig {
+    type Err = anyhow::Error;
+
+    /// `ip:port`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((ip, port)) = s.rsplit_once(':') else {
+            anyhow::bail!("invalid server address {:?}: expected ip:port", s);
+        };
+        if ip.is_empty() {
+            anyhow::bail!("invalid server address {:?}: missing ip", s);
+        }
+        let port = port.parse::<u16>()
+            .map_err(|e| anyhow::anyhow!("invalid server port {:?}: {}", port, e))?;
+
+        Ok(Self { ip: ip.to_string(), port })
+    }
+}
+
+impl std::fmt::Display for ServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.ip, self.port)
+    }
+}
+
+impl ServerConfig {
+    /// 명령줄의 `--server ip:port`. 없으면 `None`
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<Self>> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--server" {
+                let Some(addr) = args.next() else {
+                    anyhow::bail!("--server requires an address (ip:port)");
+                };
+                return addr.parse().map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+
+/// 명령줄의 `--log <filter>` (`RUST_LOG`와 같은 형식, 예: `gameserver::net=debug`). 있으면 `RUST_LOG` 대신 사용
+pub fn log_filter_from_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<String>> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--log" {
+            let Some(filter) = args.next().filter(|filter| !filter.trim().is_empty()) else {
+                anyhow::bail!("--log requires a filter (e.g. gameserver::net=debug)");
+            };
+            return Ok(Some(filter));
+        }
+    }
+
+    Ok(None)
+}
+
+/// 명령줄의 `--replay <file>`. 있으면 서버에 접속하지 않고 기록된 매치를 재생함
+pub fn replay_from_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<PathBuf>> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--replay" {
+            let Some(path) = args.next().filter(|path| !path.trim().is_empty()) else {
+                anyhow::bail!("--replay requires a replay file");
+            };
+            return Ok(Some(PathBuf::from(path)));
+        }
+    }
+
+    Ok(None)
+}
+
+
+/// MSAA 샘플 수의 기본값과 설정할 수 있는 값
+pub const DEFAULT_MSAA_SAMPLES: u32 = 4;
+pub const MSAA_SAMPLES: [u32; 4] = [1, 2, 4, 8];
+
+/// 렌더링 방식에 관한 설정
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderConfig {
+    /// 반투명 인스턴스를 카메라에서 먼 순서로 정렬해서 불투명 인스턴스 다음에 그림.
+    /// 끄면 정렬 비용은 없지만 반투명 오브젝트가 겹칠 때 색이 잘못 섞일 수 있음.
+    pub depth_sort: bool,
+    /// 빛에서 본 깊이를 그림자 맵에 그려서 오브젝트가 보드에 그림자를 드리움.
+    /// 끄면 그림자 패스를 건너뜀
+    pub shadows: bool,
+    /// 카메라 (그림자를 켰으면 빛도) 범위 밖의 인스턴스를 인스턴스 버퍼에 올리지 않음
+    pub frustum_culling: bool,
+    /// 픽셀마다 샘플 수 (MSAA). 1이면 끔. 장치가 지원하지 않으면 더 작은 값을 씀
+    pub msaa: u32,
+    /// 씬이 스카이박스를 쓸 때 그리는 큐브맵 (`skybox::face_files`).
+    /// 설정 파일에서 `skybox = ""`로 끄면 배경색만 칠함
+    pub skybox: Option<String>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self { 
+            depth_sort: true, 
+            shadows: true, 
+            frustum_culling: true, 
+            msaa: DEFAULT_MSAA_SAMPLES,
+            skybox: Some(DEFAULT_SKYBOX.to_string()),
+        }
+    }
+}
+
+
+/// 카메라 설정 파일. 리소스 폴더에서 찾음
+pub const CAMERA_CONFIG_FILE: &str = "camera.toml";
+
+/// 플레이어를 따라가는 카메라 설정
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraConfig {
+    /// 따라가는 대상에서 카메라까지의 위치
+    pub offset: cgmath::Vector3<f32>,
+    /// 세로 시야각 (도)
+    pub fovy: f32,
+    pub znear: f32,
+    pub zfar: f32,
+    /// 대상을 따라가는 속도 (초당 남은 거리의 비율). `None`(`follow_speed = 0`)이면 매 프레임 바로 붙음
+    pub follow_speed: Option<f32>,
+    /// 대상이 움직인 방향으로 이만큼 앞을 바라봄. 0이면 끔
+    pub look_ahead: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            offset: cgmath::Vector3::new(0.0, 2.0, 4.0),
+            fovy: 60.0,
+            znear: 0.1,
+            zfar: 100.0,
+            follow_speed: Some(10.0),
+            look_ahead: 0.0,
+        }
+    }
+}
+
+impl FromStr for CameraConfig {
+    type Err = anyhow::Error;
+
+    /// TOML 문서. 키는 맨 위나 `[camera]` 표에 있음. 없는 값은 기본값을 사용
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let document = parse_document(s)?;
+        let mut config = Self::default();
+
+        for (key, item, line) in entries(s, document.as_table()) {
+            match item.as_table() {
+                Some(camera) if key == "camera" => config.apply(s, camera)?,
+                _ => config.apply_entry(s, key, item, line)?,
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl CameraConfig {
+    /// `table`의 값으로 덮어씀. 검사는 하지 않음 (`validate`). `source`는 오류의 줄 번호를 찾는 원래 문서
+    fn apply(&mut self, source: &str, table: &toml_edit::Table) -> anyhow::Result<()> {
+        for (key, item, line) in entries(source, table) {
+            self.apply_entry(source, key, item, line)?;
+        }
+        Ok(())
+    }
+
+    fn apply_entry(&mut self, source: &str, key: &str, item: &toml_edit::Item, line: usize) -> anyhow::Result<()> {
+        let float = |item: &toml_edit::Item| item.as_value().and_then(number)
+            .ok_or_else(|| anyhow::anyhow!("line {}: invalid number {} for `{}`", line, raw(source, item), key));
+
+        match key {
+            "offset" => {
+                let values = item.as_array()
+                    .map(|values| values.iter()
+                        .map(number)
+                        .collect::<Option<Vec<_>>>());
+                let Some(&[x, y, z]) = values.flatten().as_deref() else {
+                    anyhow::bail!("line {}: `offset` must be [x, y, z], found {}", line, raw(source, item));
+                };
+                self.offset = cgmath::Vector3::new(x, y, z);
+            }
+            "fovy" => self.fovy = float(item)?,
+            "znear" => self.znear = float(item)?,
+            "zfar" => self.zfar = float(item)?,
+            "follow_speed" => self.follow_speed = Some(float(item)?).filter(|speed| *speed != 0.0),
+            "look_ahead" => self.look_ahead = float(item)?,
+            _ => anyhow::bail!("line {}: unknown key `{}`", line, key),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let offset = [self.offset.x, self.offset.y, self.offset.z];
+        if !offset.iter().all(|v| v.is_finite()) {
+            anyhow::bail!("camera offset {:?} must be finite", offset);
+        }
+        if !(self.fovy > 0.0 && self.fovy < 180.0) {
+            anyhow::bail!("fovy {} must be between 0 and 180 degrees", self.fovy);
+        }
+        if !(self.znear > 0.0 && self.znear < self.zfar && self.zfar.is_finite()) {
+            anyhow::bail!("znear {} and zfar {} must satisfy 0 < znear < zfar", self.znear, self.zfar);
+        }
+        if self.follow_speed.is_some_and(|speed| !(speed > 0.0 && speed.is_finite())) {
+            anyhow::bail!("follow_speed {:?} must be positive", self.follow_speed);
+        }
+        if !(self.look_ahead >= 0.0 && self.look_ahead.is_finite()) {
+            anyhow::bail!("look_ahead {} must not be negative", self.look_ahead);
+        }
+
+        Ok(())
+    }
+
+    /// `CAMERA_CONFIG_FILE`을 읽음. 파일이 없으면 기본값, 잘못되었으면 경고 후 기본값
+    pub async fn load() -> Self {
+        let text = match resources::load_string(CAMERA_CONFIG_FILE).await {
+            Ok(text) => text,
+            Err(ResourceError::NotFound { .. }) => return Self::default(),
+            Err(e) => {
+                log::warn!(target: logging::RENDER, "Failed to read {}: {}, using defaults", CAMERA_CONFIG_FILE, e);
+                return Self::default();
+            }
+        };
+
+        text.parse().unwrap_or_else(|e| {
+            log::warn!(target: logging::RENDER, "Invalid {}: {}, using defaults", CAMERA_CONFIG_FILE, e);
+            Self::default()
+        })
+    }
+}
+
+
+/// 서버가 보낸 오브젝트 수가 `max_remote_objects`를 넘었을 때의 처리 방식
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// 새로 등장한 오브젝트를 무시
+    RejectNew,
+    /// 카메라에서 가장 먼 오브젝트를 제거하고 새 오브젝트를 추가
+    EvictFurthest,
+}
+
+
+/// 서버와의 통신 방식에 관한 설정
+#[derive(Clone, Debug)]
+pub struct NetworkConfig {
+    /// 키를 누르고 있는 동안 현재 이동방향을 다시 보내는 주기.
+    /// `None`이면 다시 보내지 않음.
+    pub resend_interval: Option<Duration>,
+    /// 이동키를 누르고 있는 동안 `move`를 보내는 최소 간격
+    pub move_interval: Duration,
+    /// 연결 후 이 시간 안에 서버에서 `init`을 받지 못하면 다시 연결
+    pub handshake_timeout: Duration,
+    /// 서버에 `ping`을 보내는 간격
+    pub heartbeat_interval: Duration,
+    /// 이 시간 동안 `pong`이 오지 않으면 연결이 끊긴 것으로 봄 (`GameScene::is_stale`)
+    pub stale_timeout: Duration,
+    /// 서버에 `update`를 요청하는 최소 간격
+    pub update_request_interval: Duration,
+    /// 요청한 `update`의 응답이 이 시간 안에 오지 않으면 다시 요청
+    pub update_request_timeout: Duration,
+    /// 켜면 `init`을 받은 뒤 `update`를 UDP로 요청하고 받음. 나머지 메세지는 계속 TCP 사용
+    pub udp_updates: bool,
+    /// 서버에서 받은 오브젝트를 최대 몇 개까지 유지할지
+    pub max_remote_objects: usize,
+    /// 이동을 바로 보내지 않고 이 tick 수 (`timestep::FIXED_TIMESTEP`)만큼 모아서 한 메세지(`moves`)로 보냄.
+    /// `None`이면 이동마다 바로 보냄.
+    pub move_batch_ticks: Option<u32>,
+    pub overflow_policy: OverflowPolicy,
+    /// `init`을 받은 뒤 서버에 등록할 이름. 서버가 바꿀 수 있음
+    pub player_name: Option<String>,
+    /// 플레이어 없이 관전함 (`SPECTATOR_VERSION` 이상인 서버만)
+    pub spectator: bool,
+    /// 다른 오브젝트를 이 시간만큼 늦게, 받은 스냅샷 사이로 보간해서 그림. 0이면 받은 위치를 바로 따라감
+    pub interpolation_delay: Duration,
+    /// 켜면 `init`을 받은 뒤 `compress`를 보내서 서버가 긴 메세지를 압축해서 보내게 함
+    pub compression: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            resend_interval: None,
+            move_interval: Duration::from_millis(50),
+            handshake_timeout: Duration::from_secs(5),
+            heartbeat_interval: Duration::from_secs(1),
+            stale_timeout: Duration::from_secs(5),
+            update_request_interval: Duration::from_millis(16),
+            update_request_timeout: Duration::from_secs(1),
+            udp_updates: false,
+            max_remote_objects: 128,
+            move_batch_ticks: None,
+            overflow_policy: OverflowPolicy::RejectNew,
+            player_name: None,
+            spectator: false,
+            interpolation_delay: Duration::from_millis(100),
+            compression: true,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// 명령줄의 `--name <이름>`을 `player_name`으로, `--spectate`를 `spectator`로,
+    /// `--move-rate <초당 횟수>`를 이동키를 누르고 있을 때의 `move_interval`로,
+    /// `--interp-delay <ms>`를 `interpolation_delay`로, `--no-compression`을 `compression`으로
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--name" {
+                let Some(name) = args.next().filter(|name| !name.trim().is_empty()) else {
+                    anyhow::bail!("--name requires a name");
+                };
+                self.player_name = Some(name);
+            } else if arg == "--spectate" {
+                self.spectator = true;
+            } else if arg == "--move-rate" {
+                let rate = args.next().and_then(|rate| rate.parse::<f64>().ok());
+                let Some(rate) = rate.filter(|rate| rate.is_finite() && *rate > 0.0) else {
+                    anyhow::bail!("--move-rate requires a positive number of moves per second");
+                };
+                self.move_interval = Duration::from_secs_f64(1.0 / rate);
+            } else if arg == "--interp-delay" {
+                let Some(delay) = args.next().and_then(|delay| delay.parse::<u64>().ok()) else {
+                    anyhow::bail!("--interp-delay requires a delay in milliseconds");
+                };
+                self.interpolation_delay = Duration::from_millis(delay);
+            } else if arg == "--no-compression" {
+                self.compression = false;
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+
+/// 클라이언트 설정 파일. 리소스 폴더에서 찾고, `--config <path>`로 다른 파일을 지정할 수 있음
+pub const CLIENT_CONFIG_FILE: &str = "config.toml";
+
+/// 설정 파일과 `--config`에서 쓰는 키 이름
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::KeyA),
+    ("B", KeyCode::KeyB),
+    ("C", KeyCode::KeyC),
+    ("D", KeyCode::KeyD),
+    ("E", KeyCode::KeyE),
+    ("F", KeyCode::KeyF),
+    ("G", KeyCode::KeyG),
+    ("H", KeyCode::KeyH),
+    ("I", KeyCode::KeyI),
+    ("J", KeyCode::KeyJ),
+    ("K", KeyCode::KeyK),
+    ("L", KeyCode::KeyL),
+    ("M", KeyCode::KeyM),
+    ("N", KeyCode::KeyN),
+    ("O", KeyCode::KeyO),
+    ("P", KeyCode::KeyP),
+    ("Q", KeyCode::KeyQ),
+    ("R", KeyCode::KeyR),
+    ("S", KeyCode::KeyS),
+    ("T", KeyCode::KeyT),
+    ("U", KeyCode::KeyU),
+    ("V", KeyCode::KeyV),
+    ("W", KeyCode::KeyW),
+    ("X", KeyCode::KeyX),
+    ("Y", KeyCode::KeyY),
+    ("Z", KeyCode::KeyZ),
+    ("0", KeyCode::Digit0),
+    ("1", KeyCode::Digit1),
+    ("2", KeyCode::Digit2),
+    ("3", KeyCode::Digit3),
+    ("4", KeyCode::Digit4),
+    ("5", KeyCode::Digit5),
+    ("6", KeyCode::Digit6),
+    ("7", KeyCode::Digit7),
+    ("8", KeyCode::Digit8),
+    ("9", KeyCode::Digit9),
+    ("Up", KeyCode::ArrowUp),
+    ("Down", KeyCode::ArrowDown),
+    ("Left", KeyCode::ArrowLeft),
+    ("Right", KeyCode::ArrowRight),
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Escape", KeyCode::Escape),
+    ("Backspace", KeyCode::Backspace),
+    ("LShift", KeyCode::ShiftLeft),
+    ("RShift", KeyCode::ShiftRight),
+    ("LCtrl", KeyCode::ControlLeft),
+    ("RCtrl", KeyCode::ControlRight),
+    ("LAlt", KeyCode::AltLeft),
+    ("RAlt", KeyCode::AltRight),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+];
+
+/// `W`, `Up`, `Space` 등. 대소문자 구분 없음
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, keycode)| *keycode)
+}
+
+/// `parse_key`의 반대. 이름이 없는 키는 `None`
+pub fn key_name(keycode: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter()
+        .find(|(_, key)| *key == keycode)
+        .map(|(name, _)| *name)
+}
+
+
+/// TOML 문서로 읽음. 문법 오류도 줄 번호와 함께 알려줌
+fn parse_document(s: &str) -> anyhow::Result<toml_edit::Document<&str>> {
+    toml_edit::Document::parse(s).map_err(|e| {
+        let line = e.span().map_or(1, |span| line_at(s, span.start));
+        anyhow::anyhow!("line {}: {}", line, e.message())
+    })
+}
+
+/// `table`의 키와 값, 그 키가 있는 줄
+fn entries<'a>(source: &'a str, table: &'a toml_edit::Table) -> impl Iterator<Item = (&'a str, &'a toml_edit::Item, usize)> {
+    table.iter().map(move |(key, item)| {
+        let span = table.key(key).and_then(toml_edit::Key::span);
+        (key, item, span.map_or(1, |span| line_at(source, span.start)))
+    })
+}
+
+/// `source`의 `offset` 바이트가 있는 줄 (1부터)
+fn line_at(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// 오류 메세지에 쓰는 값의 원래 글자
+fn raw<'a>(source: &'a str, item: &toml_edit::Item) -> &'a str {
+    item.span().and_then(|span| source.get(span)).unwrap_or_default().trim()
+}
+
+/// 정수도 받음
+fn number(value: &toml_edit::Value) -> Option<f32> {
+    value.as_float().or_else(|| value.as_integer().map(|value| value as f64)).map(|value| value as f32)
+}
+
+/// `MSAA_SAMPLES` 중 하나
+fn msaa(value: &str) -> Option<u32> {
+    value.parse().ok().filter(|samples| MSAA_SAMPLES.contains(samples))
+}
+
+/// 장치가 지원하는 샘플 수 `supported` 중에서 `samples`를 넘지 않는 가장 큰 값. 없으면 1 (끔)
+pub fn clamp_msaa(samples: u32, supported: &[u32]) -> u32 {
+    supported.iter().copied().filter(|&n| n <= samples).max().unwrap_or(1)
+}
+
+/// `supported` 중에서 `samples` 다음으로 큰 값. 가장 크면 처음으로 돌아감
+pub fn next_msaa(samples: u32, supported: &[u32]) -> u32 {
+    supported.iter().copied().filter(|&n| n > samples).min()
+        .or_else(|| supported.iter().copied().min())
+        .unwrap_or(1)
+}
+
+
+/// 미니맵 크기의 기본값 (픽셀)
+pub const DEFAULT_MINIMAP_SIZE: u32 = 160;
+
+/// 3D 장면 위에 그리는 HUD 설정
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HudConfig {
+    /// 처음부터 화면 오른쪽 아래에 미니맵을 보여줌. 실행 중에는 `Action::ToggleMinimap`으로 바꿈
+    pub minimap: bool,
+    /// 미니맵의 긴 변 (픽셀). 짧은 변은 보드 비율을 따름
+    pub minimap_size: u32,
+}
+
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self { minimap: true, minimap_size: DEFAULT_MINIMAP_SIZE }
+    }
+}
+
+
+/// 창 설정
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowConfig {
+    /// 처음 창 크기 (픽셀)
+    pub width: u32,
+    pub height: u32,
+    /// 테두리 없는 전체 화면
+    pub fullscreen: bool,
+    /// 화면에 내보내는 방식. `vsync = false`는 `Immediate`
+    pub present_mode: PresentMode,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self { width: SCREEN_WIDTH, height: SCREEN_HEIGHT, fullscreen: false, present_mode: PresentMode::Vsync }
+    }
+}
+
+
+/// 그린 화면을 내보내는 방식 (`present_mode = "vsync"`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentMode {
+    /// 화면 주사율에 맞춤. 찢어짐이 없고 모든 환경에서 지원됨
+    Vsync,
+    /// 주사율을 기다리지 않고 그리지만 가장 최근 화면만 내보내서 찢어짐이 없음
+    Mailbox,
+    /// 그리는 대로 바로 내보냄. 지연이 가장 짧지만 화면이 찢어질 수 있음
+    Immediate,
+}
+
+impl PresentMode {
+    pub const ALL: [PresentMode; 3] = [PresentMode::Vsync, PresentMode::Mailbox, PresentMode::Immediate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PresentMode::Vsync => "vsync",
+            PresentMode::Mailbox => "mailbox",
+            PresentMode::Immediate => "immediate",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name().eq_ignore_ascii_case(name))
+    }
+
+    /// `ALL`에서 다음 방식
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// 표면이 지원하는 `supported` 중에서 고름. 지원하지 않으면 찢어짐 없이 기다리지 않는 방식부터 차례로
+    pub fn to_wgpu(self, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Vsync => wgpu::PresentMode::AutoVsync,
+            PresentMode::Mailbox if supported.contains(&wgpu::PresentMode::Mailbox) => wgpu::PresentMode::Mailbox,
+            PresentMode::Mailbox | PresentMode::Immediate => wgpu::PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+
+thread_local! {
+    /// 창을 만든 스레드의 씬들이 같이 쓰는 설정
+    static CURRENT: RefCell<Rc<ClientConfig>> = RefCell::new(Rc::new(ClientConfig::default()));
+}
+
+/// 설정 파일 (`CLIENT_CONFIG_FILE`) 전체. 시작할 때 읽고 명령줄 옵션으로 덮어쓴 뒤 `install`하면
+/// 씬들이 `ClientConfig::current`로 같이 씀.
+///
+/// TOML 문서 (`toml_edit`으로 읽음). `[server]`, `[window]`, `[render]`, `[hud]`, `[camera]`, `[keys]` 표만 있고,
+/// 문자열 값은 따옴표로 감쌈.
+/// `[camera]`는 `camera.toml`과 같은 형식이며, 없으면 `camera.toml`을 읽음.
+/// `[keys]`는 동작 이름 (`Action::name`)에 키 하나나 목록 (`open_chat = ["Enter", "T"]`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClientConfig {
+    /// 첫 화면의 입력창에 채워둘 서버 (`address = "ip:port"`)
+    pub server: Option<ServerConfig>,
+    pub window: WindowConfig,
+    pub render: RenderConfig,
+    pub hud: HudConfig,
+    pub camera: CameraConfig,
+    pub keys: InputMap,
+}
+
+impl FromStr for ClientConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).map(|(config, _)| config)
+    }
+}
+
+impl ClientConfig {
+    pub fn current() -> Rc<Self> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    /// 이 스레드에서 앞으로 만드는 씬이 `config`를 씀
+    pub fn install(config: Self) {
+        CURRENT.with(|current| *current.borrow_mut() = Rc::new(config));
+    }
+
+    /// 설정과 `[camera]` 표가 있었는지
+    fn parse(s: &str) -> anyhow::Result<(Self, bool)> {
+        let document = parse_document(s)?;
+        let mut config = Self::default();
+        let mut has_camera = false;
+
+        for (section, item, line) in entries(s, document.as_table()) {
+            let Some(table) = item.as_table() else {
+                anyhow::bail!("line {}: `{}` must be in a table", line, section);
+            };
+            match section {
+                "camera" => {
+                    config.camera.apply(s, table)?;
+                    has_camera = true;
+                }
+                "server" | "window" | "render" | "hud" | "keys" => {
+                    for (key, item, line) in entries(s, table) {
+                        config.apply_entry(s, section, key, item, line)?;
+                    }
+                }
+                section => anyhow::bail!("line {}: unknown table [{}]", line, section),
+            }
+        }
+
+        if has_camera {
+            config.camera.validate()?;
+        }
+        Ok((config, has_camera))
+    }
+
+    fn apply_entry(&mut self, source: &str, section: &str, key: &str, item: &toml_edit::Item, line: usize) -> anyhow::Result<()> {
+        let invalid = |expected: &str| anyhow::anyhow!("line {}: invalid {} {} for `{}`", line, expected, raw(source, item), key);
+        let size = || item.as_integer()
+            .and_then(|size| u32::try_from(size).ok())
+            .filter(|size| *size > 0)
+            .ok_or_else(|| invalid("size"));
+        let flag = || item.as_bool().ok_or_else(|| invalid("boolean"));
+        let text = || item.as_str().ok_or_else(|| invalid("string"));
+        let keycode = |value: &toml_edit::Value| value.as_str().and_then(parse_key).ok_or_else(|| invalid("key"));
+
+        match (section, key) {
+            ("server", "address") => {
+                self.server = Some(text()?.parse().map_err(|e| anyhow::anyhow!("line {}: {}", line, e))?);
+            }
+            ("window", "width") => self.window.width = size()?,
+            ("window", "height") => self.window.height = size()?,
+            ("window", "fullscreen") => self.window.fullscreen = flag()?,
+            ("window", "vsync") => {
+                self.window.present_mode = if flag()? { PresentMode::Vsync } else { PresentMode::Immediate };
+            }
+            ("window", "present_mode") => {
+                self.window.present_mode = PresentMode::from_name(text()?).ok_or_else(|| invalid("present mode"))?;
+            }
+            ("render", "depth_sort") => self.render.depth_sort = flag()?,
+            ("render", "shadows") => self.render.shadows = flag()?,
+            ("render", "frustum_culling") => self.render.frustum_culling = flag()?,
+            ("render", "msaa") => {
+                self.render.msaa = item.as_integer()
+                    .and_then(|samples| u32::try_from(samples).ok())
+                    .filter(|samples| MSAA_SAMPLES.contains(samples))
+                    .ok_or_else(|| invalid("sample count"))?;
+            }
+            ("render", "skybox") => self.render.skybox = Some(text()?.to_string()).filter(|name| !name.is_empty()),
+            ("hud", "minimap") => self.hud.minimap = flag()?,
+            ("hud", "minimap_size") => self.hud.minimap_size = size()?,
+            // `action = "Key"` 또는 `action = ["Key", "Key"]`
+            ("keys", name) if Action::from_name(name).is_some() => {
+                let keys = match (item.as_array(), item.as_value()) {
+                    (Some(list), _) => list.iter().map(keycode).collect::<anyhow::Result<Vec<_>>>()?,
+                    (None, Some(value)) => vec![keycode(value)?],
+                    (None, None) => return Err(invalid("key")),
+                };
+                self.keys.rebind(Action::from_name(name).unwrap(), &keys);
+            }
+            (section, key) => anyhow::bail!("line {}: unknown key `{}` in [{}]", line, key, section),
+        }
+        Ok(())
+    }
+
+    /// `path`가 없으면 리소스 폴더의 `CLIENT_CONFIG_FILE`. 그 파일이 없거나 잘못되었으면 경고 후 기본값이지만,
+    /// 직접 지정한 `path`를 읽지 못하거나 잘못되었으면 오류
+    pub async fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let parsed = match path {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path.display(), e))?;
+                Some(Self::parse(&text).map_err(|e| anyhow::anyhow!("invalid {}: {}", path.display(), e))?)
+            }
+            None => match resources::load_string(CLIENT_CONFIG_FILE).await {
+                Ok(text) => Self::parse(&text)
+                    .inspect_err(|e| log::warn!(target: logging::SCENE, "Invalid {}: {}, using defaults", CLIENT_CONFIG_FILE, e))
+                    .ok(),
+                Err(ResourceError::NotFound { .. }) => None,
+                Err(e) => {
+                    log::warn!(target: logging::SCENE, "Failed to read {}: {}, using defaults", CLIENT_CONFIG_FILE, e);
+                    None
+                }
+            },
+        };
+
+        let (mut config, has_camera) = parsed.unwrap_or_default();
+        if !has_camera {
+            config.camera = CameraConfig::load().await;
+        }
+        Ok(config)
+    }
+
+    /// 명령줄 옵션으로 설정 파일의 값을 덮어씀.
+    /// `--width <px>`, `--height <px>`, `--fullscreen`, `--windowed`, `--vsync`, `--no-vsync`,
+    /// `--present-mode <vsync|mailbox|immediate>`, `--msaa <샘플 수>`, `--no-shadows`, `--no-skybox`,
+    /// `--fov <도>`, `--minimap-size <px>`, `--no-minimap`
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| anyhow::anyhow!("{} requires a value", name));
+            match arg.as_str() {
+                "--width" | "--height" => {
+                    let size = value(&arg)?.parse::<u32>().ok()
+                        .filter(|size| *size > 0)
+                        .ok_or_else(|| anyhow::anyhow!("{} requires a positive size in pixels", arg))?;
+                    match arg.as_str() {
+                        "--width" => self.window.width = size,
+                        _ => self.window.height = size,
+                    }
+                }
+                "--fullscreen" => self.window.fullscreen = true,
+                "--windowed" => self.window.fullscreen = false,
+                "--vsync" => self.window.present_mode = PresentMode::Vsync,
+                "--no-vsync" => self.window.present_mode = PresentMode::Immediate,
+                "--present-mode" => {
+                    self.window.present_mode = PresentMode::from_name(&value("--present-mode")?)
+                        .ok_or_else(|| anyhow::anyhow!("--present-mode requires vsync, mailbox or immediate"))?;
+                }
+                "--msaa" => {
+                    self.render.msaa = msaa(&value("--msaa")?)
+                        .ok_or_else(|| anyhow::anyhow!("--msaa requires one of {:?}", MSAA_SAMPLES))?;
+                }
+                "--no-shadows" => self.render.shadows = false,
+                "--no-skybox" => self.render.skybox = None,
+                "--minimap-size" => {
+                    self.hud.minimap_size = value("--minimap-size")?.parse::<u32>().ok()
+                        .filter(|size| *size > 0)
+                        .ok_or_else(|| anyhow::anyhow!("--minimap-size requires a positive size in pixels"))?;
+                }
+                "--no-minimap" => self.hud.minimap = false,
+                "--fov" => {
+                    self.camera.fovy = value("--fov")?.parse()
+                        .map_err(|_| anyhow::anyhow!("--fov requires an angle in degrees"))?;
+                    self.camera.validate()?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// 명령줄의 `--config <file>`. 있으면 리소스 폴더의 `CLIENT_CONFIG_FILE` 대신 읽음
+pub fn config_path_from_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<PathBuf>> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            let Some(path) = args.next().filter(|path| !path.trim().is_empty()) else {
+                anyhow::bail!("--config requires a config file");
+            };
+            return Ok(Some(PathBuf::from(path)));
+        }
+    }
+
+    Ok(None)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_from_args() {
+        let args = ["client", "--server", "127.0.0.1:7878"].map(String::from);
+        assert_eq!(
+            ServerConfig::from_args(args).unwrap(),
+            Some(ServerConfig { ip: "127.0.0.1".to_string(), port: 7878 })
+        );
+
+        assert_eq!(ServerConfig::from_args(["client".to_string()]).unwrap(), None);
+        assert!(ServerConfig::from_args(["--server", "localhost"].map(String::from)).is_err());
+        assert!(ServerConfig::from_args(["--server".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_name_from_args() {
+        let args = ["client", "--server", "127.0.0.1:7878", "--name", "alice"].map(String::from);
+        assert_eq!(NetworkConfig::default().with_args(args).unwrap().player_name.as_deref(), Some("alice"));
+
+        assert_eq!(NetworkConfig::default().with_args(["client".to_string()]).unwrap().player_name, None);
+        assert!(NetworkConfig::default().with_args(["--name".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_spectate_from_args() {
+        let args = ["client", "--spectate"].map(String::from);
+        assert!(NetworkConfig::default().with_args(args).unwrap().spectator);
+        assert!(!NetworkConfig::default().with_args(["client".to_string()]).unwrap().spectator);
+    }
+
+    #[test]
+    fn test_move_rate_from_args() {
+        let args = ["client", "--move-rate", "4"].map(String::from);
+        assert_eq!(NetworkConfig::default().with_args(args).unwrap().move_interval, Duration::from_millis(250));
+        for invalid in [&["--move-rate"][..], &["--move-rate", "0"], &["--move-rate", "fast"]] {
+            assert!(NetworkConfig::default().with_args(invalid.iter().map(|arg| arg.to_string())).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_interp_delay_from_args() {
+        let args = ["client", "--interp-delay", "0"].map(String::from);
+        assert_eq!(NetworkConfig::default().with_args(args).unwrap().interpolation_delay, Duration::ZERO);
+        assert!(NetworkConfig::default().with_args(["--interp-delay", "-5"].map(String::from)).is_err());
+    }
+
+    #[test]
+    fn test_no_compression_from_args() {
+        assert!(NetworkConfig::default().compression);
+        assert!(!NetworkConfig::default().with_args(["--no-compression".to_string()]).unwrap().compression);
+    }
+
+    #[test]
+    fn test_log_filter_from_args() {
+        let args = ["client", "--log", "gameserver::net=debug"].map(String::from);
+        assert_eq!(log_filter_from_args(args).unwrap().as_deref(), Some("gameserver::net=debug"));
+        assert_eq!(log_filter_from_args(["client".to_string()]).unwrap(), None);
+        assert!(log_filter_from_args(["--log".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_replay_from_args() {
+        let args = ["client", "--replay", "replays/match-1.replay"].map(String::from);
+        assert_eq!(replay_from_args(args).unwrap(), Some(PathBuf::from("replays/match-1.replay")));
+        assert_eq!(replay_from_args(["client".to_string()]).unwrap(), None);
+        assert!(replay_from_args(["--replay".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_camera_config() {
+        let config = "[camera]\n# 더 멀리서\noffset = [0, 3.5, 6]\nfovy = 45 # 좁게\nzfar = 200\nfollow_speed = 0\nlook_ahead = 1.5\n"
+            .parse::<CameraConfig>()
+            .unwrap();
+        assert_eq!(config, CameraConfig {
+            offset: cgmath::Vector3::new(0.0, 3.5, 6.0),
+            fovy: 45.0,
+            znear: 0.1,
+            zfar: 200.0,
+            follow_speed: None,
+            look_ahead: 1.5,
+        });
+        assert_eq!("".parse::<CameraConfig>().unwrap(), CameraConfig::default());
+
+        for invalid in ["fovy = 180", "fovy = wide", "znear = 10\nzfar = 5", "offset = [1, 2]", "speed = 3", "fovy", "follow_speed = -1", "look_ahead = -2"] {
+            assert!(invalid.parse::<CameraConfig>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_camera_config_fallback() {
+        // 리소스 폴더에 camera.toml이 없음
+        assert_eq!(futures::executor::block_on(CameraConfig::load()), CameraConfig::default());
+    }
+
+    #[test]
+    fn test_client_config() {
+        let text = "\
+[server]
+address = \"127.0.0.1:7878\"
+
+[window]
+width = 1280
+height = 720 # 16:9
+vsync = false
+
+[render]
+shadows = false
+msaa = 8
+frustum_culling = false
+skybox = \"night\"
+
+[hud]
+minimap = false
+minimap_size = 240
+
+[camera]
+fovy = 70
+
+[keys]
+move_up = \"Up\"
+move_left = \"left\"
+open_chat = [\"Y\", \"Enter\"]
+";
+        let config = text.parse::<ClientConfig>().unwrap();
+        assert_eq!(config.server, Some(ServerConfig { ip: "127.0.0.1".to_string(), port: 7878 }));
+        assert_eq!(config.window, WindowConfig { width: 1280, height: 720, fullscreen: false, present_mode: PresentMode::Immediate });
+        assert_eq!(config.render, RenderConfig {
+            depth_sort: true,
+            shadows: false,
+            frustum_culling: false,
+            msaa: 8,
+            skybox: Some("night".to_string()),
+        });
+        assert_eq!(config.hud, HudConfig { minimap: false, minimap_size: 240 });
+        assert_eq!("[render]\nskybox = \"\"".parse::<ClientConfig>().unwrap().render.skybox, None);
+        let window = "[window]\npresent_mode = \"Mailbox\"".parse::<ClientConfig>().unwrap().window;
+        assert_eq!(window.present_mode, PresentMode::Mailbox);
+        assert_eq!(config.camera.fovy, 70.0);
+        assert_eq!(config.keys.action(KeyCode::ArrowUp), Some(Action::MoveUp));
+        assert_eq!(config.keys.action(KeyCode::KeyW), None);
+        assert_eq!(config.keys.action(KeyCode::ArrowLeft), Some(Action::MoveLeft));
+        assert_eq!(config.keys.action(KeyCode::KeyS), Some(Action::MoveDown));
+        assert_eq!(config.keys.keys(Action::OpenChat).collect::<Vec<_>>(), [KeyCode::KeyY, KeyCode::Enter]);
+        assert_eq!("".parse::<ClientConfig>().unwrap(), ClientConfig::default());
+
+        for invalid in [
+            "width = 100",
+            "[video]",
+            "[window]\nwidth = 0",
+            "[window]\nfullscreen = yes",
+            "[render]\nshadows = 1",
+            "[render]\nmsaa = 3",
+            "[window]\npresent_mode = fast",
+            "[hud]\nminimap_size = 0",
+            "[keys]\nmove_up = Hyper",
+            "[keys]\nopen_chat = [T, Hyper]",
+            "[keys]\njump = Space",
+            "[server]\naddress = localhost",
+            "[camera]\nfovy = 0",
+        ] {
+            assert!(invalid.parse::<ClientConfig>().is_err(), "{:?}", invalid);
+        }
+
+        // `[camera]`의 오류도 파일 전체의 줄 번호로 알려줌
+        let error = "[window]\nvsync = true\n[camera]\nfovy = wide".parse::<ClientConfig>().unwrap_err();
+        assert!(error.to_string().starts_with("line 4:"), "{}", error);
+    }
+
+    #[test]
+    fn test_client_config_toml() {
+        // 따옴표 안의 `#`은 주석이 아니고, 목록은 여러 줄에 걸칠 수 있음
+        let text = "\
+[render]
+skybox = \"night#2\" # 주석
+
+[keys]
+open_chat = [
+    \"Y\",
+    \"Enter\", # 줄 끝 쉼표
+]
+";
+        let config = text.parse::<ClientConfig>().unwrap();
+        assert_eq!(config.render.skybox.as_deref(), Some("night#2"));
+        assert_eq!(config.keys.keys(Action::OpenChat).collect::<Vec<_>>(), [KeyCode::KeyY, KeyCode::Enter]);
+
+        let error = "[window]\nwidth = 1280\nheight = \"tall\"".parse::<ClientConfig>().unwrap_err();
+        assert_eq!(error.to_string(), "line 3: invalid size \"tall\" for `height`");
+    }
+
+    #[test]
+    fn test_client_config_args() {
+        let args = ["client", "--width", "1024", "--fullscreen", "--no-vsync", "--no-shadows", "--no-skybox", "--fov", "75"].map(String::from);
+        let config = ClientConfig::default().with_args(args).unwrap();
+        assert_eq!(config.window, WindowConfig { width: 1024, height: SCREEN_HEIGHT, fullscreen: true, present_mode: PresentMode::Immediate });
+        assert!(!config.render.shadows);
+        assert_eq!(config.render.skybox, None);
+        assert_eq!(config.camera.fovy, 75.0);
+
+        let args = ["client", "--present-mode", "mailbox", "--msaa", "1"].map(String::from);
+        let config = ClientConfig::default().with_args(args).unwrap();
+        assert_eq!(config.window.present_mode, PresentMode::Mailbox);
+        assert_eq!(config.render.msaa, 1);
+
+        let args = ["client", "--minimap-size", "200", "--no-minimap"].map(String::from);
+        assert_eq!(ClientConfig::default().with_args(args).unwrap().hud, HudConfig { minimap: false, minimap_size: 200 });
+
+        for invalid in [&["--width"][..], &["--height", "0"], &["--fov", "200"], &["--fov", "wide"], &["--msaa", "3"], &["--present-mode", "fast"], &["--minimap-size", "0"]] {
+            assert!(ClientConfig::default().with_args(invalid.iter().map(|arg| arg.to_string())).is_err(), "{:?}", invalid);
+        }
+
+        let args = ["client", "--config", "configs/lan.toml"].map(String::from);
+        assert_eq!(config_path_from_args(args).unwrap(), Some(PathBuf::from("configs/lan.toml")));
+        assert_eq!(config_path_from_args(["client".to_string()]).unwrap(), None);
+        assert!(config_path_from_args(["--config".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_client_config_load() {
+        // 리소스 폴더에 config.toml이 없음
+        let config = futures::executor::block_on(ClientConfig::load(None)).unwrap();
+        assert_eq!(config, ClientConfig::default());
+        assert!(futures::executor::block_on(ClientConfig::load(Some(Path::new("missing.toml")))).is_err());
+    }
+
+    #[test]
+    fn test_key_names() {
+        assert_eq!(parse_key("w"), Some(KeyCode::KeyW));
+        assert_eq!(parse_key("SPACE"), Some(KeyCode::Space));
+        assert_eq!(parse_key("f3"), Some(KeyCode::F3));
+        assert_eq!(parse_key("F13"), None);
+        assert_eq!(key_name(KeyCode::ArrowDown), Some("Down"));
+    }
+
+    #[test]
+    fn test_msaa_and_present_mode() {
+        assert_eq!(clamp_msaa(8, &[1, 4]), 4);
+        assert_eq!(clamp_msaa(2, &[1, 4]), 1);
+        assert_eq!(clamp_msaa(4, &[]), 1);
+        assert_eq!(next_msaa(1, &[1, 2, 4]), 2);
+        assert_eq!(next_msaa(4, &[1, 2, 4]), 1);
+
+        let only_fifo = [wgpu::PresentMode::Fifo];
+        assert_eq!(PresentMode::Mailbox.to_wgpu(&only_fifo), wgpu::PresentMode::AutoNoVsync);
+        assert_eq!(PresentMode::Mailbox.to_wgpu(&[wgpu::PresentMode::Mailbox]), wgpu::PresentMode::Mailbox);
+        assert_eq!(PresentMode::Vsync.to_wgpu(&only_fifo), wgpu::PresentMode::AutoVsync);
+        assert_eq!(PresentMode::Immediate.next(), PresentMode::Vsync);
+    }
+}
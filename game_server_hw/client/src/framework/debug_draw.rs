@@ -0,0 +1,374 @@
+//! 디버그 렌더 모드 (F5)의 선.
+//! 씬이 매 프레임 `DebugLines`에 선을 모으면 `DebugRenderer`가 주 렌더 패스의 마지막에 다른 오브젝트 위로 그림.
+
+use cgmath::{Matrix4, SquareMatrix, Vector3, Vector4};
+use wgpu::util::DeviceExt;
+
+use super::{
+    board::BoardLayout,
+    color::Color,
+    model::Vertex,
+    texture::Texture,
+};
+
+
+/// 정점 버퍼의 처음 크기 (정점 수). 부족하면 두 배씩 늘림
+const INITIAL_CAPACITY: usize = 1024;
+
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl Vertex for LineVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::offset_of!(LineVertex, color) as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ]
+        }
+    }
+}
+
+
+/// 한 프레임에 그릴 선분 (정점 두 개씩)
+#[derive(Debug, Clone, Default)]
+pub struct DebugLines {
+    vertices: Vec<LineVertex>,
+}
+
+impl DebugLines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// 선분 수
+    pub fn len(&self) -> usize {
+        self.vertices.len() / 2
+    }
+
+    pub fn vertices(&self) -> &[LineVertex] {
+        &self.vertices
+    }
+
+    pub fn line(&mut self, from: Vector3<f32>, to: Vector3<f32>, color: Color) {
+        let color = color.into();
+        self.vertices.push(LineVertex { position: from.into(), color });
+        self.vertices.push(LineVertex { position: to.into(), color });
+    }
+
+    /// `center`에서 세 축으로 `size`만큼 뻗은 십자
+    pub fn cross(&mut self, center: Vector3<f32>, size: f32, color: Color) {
+        for axis in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()] {
+            self.line(center - axis * size, center + axis * size, color);
+        }
+    }
+
+    /// 보드 칸의 경계. 칸 윗면 (`origin.y`)보다 `lift`만큼 위에 그림
+    pub fn board_grid(&mut self, board: &BoardLayout, lift: f32, color: Color) {
+        let offset = Vector3::new(-board.spacing_x * 0.5, lift, -board.spacing_z * 0.5);
+        let corner = |x: i32, z: i32| board.cell_to_world(x, z) + offset;
+        for x in 0..=board.width {
+            self.line(corner(x, 0), corner(x, board.height), color);
+        }
+        for z in 0..=board.height {
+            self.line(corner(0, z), corner(board.width, z), color);
+        }
+    }
+
+    /// `view_proj`로 보이는 범위의 모서리 열두 개. wgpu 클립 공간 (깊이 0..=1) 기준
+    pub fn frustum(&mut self, view_proj: Matrix4<f32>, color: Color) {
+        let Some(inverse) = view_proj.invert() else {
+            return;
+        };
+        let corner = |x: f32, y: f32, z: f32| {
+            let world = inverse * Vector4::new(x, y, z, 1.0);
+            world.truncate() / world.w
+        };
+        let near = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| corner(x, y, 0.0));
+        let far = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| corner(x, y, 1.0));
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.line(near[i], near[next], color);
+            self.line(far[i], far[next], color);
+            self.line(near[i], far[i], color);
+        }
+    }
+}
+
+
+pub struct DebugRenderer {
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    /// `vertex_buffer`에 들어가는 정점 수
+    capacity: usize,
+    /// 마지막 `update`에서 쓴 정점 수
+    count: u32,
+}
+
+impl DebugRenderer {
+    /// `format`은 그릴 화면의 텍스처 형식, `sample_count`는 주 렌더 패스의 MSAA 샘플 수
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug Line Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../debug_lines.wgsl").into()),
+        });
+
+        let view_proj: [[f32; 4]; 4] = Matrix4::identity().into();
+        let uniform_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Debug Line Buffer"),
+                contents: bytemuck::cast_slice(&[view_proj]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("debug_line_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ]
+            }
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("debug_line_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Line Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = create_pipeline(device, &pipeline_layout, &shader, format, sample_count);
+        let capacity = INITIAL_CAPACITY;
+        let vertex_buffer = create_vertex_buffer(device, capacity);
+
+        Self {
+            shader,
+            pipeline_layout,
+            format,
+            sample_count,
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            vertex_buffer,
+            capacity,
+            count: 0,
+        }
+    }
+
+    /// 주 렌더 패스의 MSAA 샘플 수가 바뀌면 파이프라인을 다시 만듦
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        if sample_count != self.sample_count {
+            self.sample_count = sample_count;
+            self.pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, sample_count);
+        }
+    }
+
+    /// 렌더 패스를 시작하기 전에 호출. `lines`가 `None`이면 그리지 않음
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lines: Option<&DebugLines>,
+        view_proj: Matrix4<f32>,
+    ) {
+        let vertices = lines.map_or(&[][..], DebugLines::vertices);
+        self.count = vertices.len() as u32;
+        if vertices.is_empty() {
+            return;
+        }
+
+        if vertices.len() > self.capacity {
+            self.capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = create_vertex_buffer(device, self.capacity);
+        }
+        let view_proj: [[f32; 4]; 4] = view_proj.into();
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[view_proj]));
+    }
+
+    /// 주 렌더 패스의 마지막에 호출. 파이프라인을 바꾸므로 다음에 다시 설정해야 함
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if self.count == 0 {
+            return;
+        }
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        rpass.draw(0..self.count, 0..1);
+    }
+}
+
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Debug Line Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[LineVertex::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::LineList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        // 가려진 오브젝트의 서버 위치도 보이도록 깊이와 상관없이 위에 그림
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(
+        &wgpu::BufferDescriptor {
+            label: Some("Debug Line Vertex Buffer"),
+            mapped_at_creation: false,
+            size: (std::mem::size_of::<LineVertex>() * capacity) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        }
+    )
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Point3};
+
+    #[test]
+    fn test_board_grid() {
+        let mut lines = DebugLines::new();
+        let board = BoardLayout { width: 3, height: 2, ..BoardLayout::uniform(2.0) };
+        lines.board_grid(&board, 0.0, Color::WHITE);
+        // 세로선 4개, 가로선 3개
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines.vertices()[0].position, [-1.0, 0.0, -1.0]);
+        assert_eq!(lines.vertices()[1].position, [-1.0, 0.0, 3.0]);
+    }
+
+    #[test]
+    fn test_frustum_corners() {
+        let view = Matrix4::look_at_rh(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), Vector3::unit_y());
+        #[rustfmt::skip]
+        let to_wgpu = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.5, 0.0,
+            0.0, 0.0, 0.5, 1.0,
+        );
+        let proj = to_wgpu * cgmath::perspective(Deg(90.0), 1.0, 1.0, 10.0);
+
+        let mut lines = DebugLines::new();
+        lines.frustum(proj * view, Color::WHITE);
+        assert_eq!(lines.len(), 12);
+        let depths = lines.vertices().iter().map(|vertex| vertex.position[2]).collect::<Vec<_>>();
+        assert!(depths.iter().all(|z| (z + 1.0).abs() < 1e-4 || (z + 10.0).abs() < 1e-3), "{:?}", depths);
+
+        // 뒤집을 수 없는 행렬은 그리지 않음
+        lines.clear();
+        lines.frustum(Matrix4::from_scale(0.0), Color::WHITE);
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn test_renderer_capacity() {
+        let Some((device, queue)) = super::super::test_device() else {
+            return;
+        };
+
+        let mut renderer = DebugRenderer::new(&device, wgpu::TextureFormat::Rgba8UnormSrgb, 1);
+        let mut lines = DebugLines::new();
+        while lines.vertices().len() <= INITIAL_CAPACITY {
+            lines.cross(Vector3::new(0.0, 0.0, 0.0), 1.0, Color::RED);
+        }
+        renderer.update(&device, &queue, Some(&lines), Matrix4::identity());
+        assert_eq!(renderer.count as usize, lines.vertices().len());
+        assert!(renderer.capacity >= lines.vertices().len());
+
+        renderer.set_sample_count(&device, 4);
+        renderer.update(&device, &queue, None, Matrix4::identity());
+        assert_eq!(renderer.count, 0);
+    }
+}
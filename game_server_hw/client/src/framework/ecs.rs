@@ -0,0 +1,333 @@
+//! 씬 오브젝트의 엔티티와 컴포넌트 (작은 ECS).
+//! 엔티티는 번호와 세대만 가진 핸들이고, 데이터는 컴포넌트 종류별 저장소 (`Components`)에 모여 있음.
+//! 렌더러는 `World::instances`로 `Renderable`과 `Transform`이 있는 엔티티를 모델별로 모아서 그림.
+
+use std::collections::HashMap;
+use cgmath::{Quaternion, Vector3};
+
+use super::{
+    animation::Animator,
+    object::{Instance, Renderable},
+    snapshot::SnapshotBuffer,
+    transform::Transform,
+};
+
+
+/// `World`의 엔티티. 삭제된 엔티티의 번호를 다시 쓰면 세대가 바뀌어서 이전 핸들로는 찾을 수 없음
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+/// 서버가 정한 오브젝트 id. `World::set_network_id`로만 바꿈
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NetworkId(pub u32);
+
+/// 보간 중인 목표. 도착한 값은 `None`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Interpolated {
+    pub target: Option<Vector3<f32>>,
+    pub target_rotation: Option<Quaternion<f32>>,
+}
+
+
+/// 한 종류의 컴포넌트. 값은 빈틈없이 모아두고 (sparse set), 삭제하면 마지막 값을 그 자리로 옮김
+#[derive(Debug, Clone)]
+pub struct Components<T> {
+    /// 엔티티 번호별 `values` 위치
+    sparse: Vec<Option<usize>>,
+    entities: Vec<Entity>,
+    values: Vec<T>,
+}
+
+impl<T> Default for Components<T> {
+    fn default() -> Self {
+        Self { sparse: Vec::new(), entities: Vec::new(), values: Vec::new() }
+    }
+}
+
+impl<T> Components<T> {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn slot(&self, entity: Entity) -> Option<usize> {
+        let slot = (*self.sparse.get(entity.index as usize)?)?;
+        (self.entities[slot] == entity).then_some(slot)
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.slot(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.slot(entity).map(|slot| &self.values[slot])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.slot(entity).map(|slot| &mut self.values[slot])
+    }
+
+    /// 이미 있으면 바꾸고 이전 값을 반환
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(slot) = self.slot(entity) {
+            return Some(std::mem::replace(&mut self.values[slot], value));
+        }
+
+        let index = entity.index as usize;
+        if self.sparse.len() <= index {
+            self.sparse.resize(index + 1, None);
+        }
+        self.sparse[index] = Some(self.values.len());
+        self.entities.push(entity);
+        self.values.push(value);
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slot(entity)?;
+        self.sparse[entity.index as usize] = None;
+        self.entities.swap_remove(slot);
+        let value = self.values.swap_remove(slot);
+        if let Some(moved) = self.entities.get(slot) {
+            self.sparse[moved.index as usize] = Some(slot);
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.values.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(self.values.iter_mut())
+    }
+}
+
+
+/// 엔티티와 종류별 컴포넌트. 저장소가 필드로 나뉘어 있어서 서로 다른 컴포넌트는 동시에 빌려 쓸 수 있음
+#[derive(Debug, Default)]
+pub struct World {
+    /// 엔티티 번호별 현재 세대
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    /// 다시 쓸 수 있는 번호
+    free: Vec<u32>,
+
+    pub transforms: Components<Transform>,
+    pub renderables: Components<Renderable>,
+    pub interpolated: Components<Interpolated>,
+    pub animators: Components<Animator>,
+    /// 지연 보간 (`interpolation_delay`)으로 움직이는 오브젝트가 받은 위치
+    pub snapshots: Components<SnapshotBuffer>,
+    network_ids: Components<NetworkId>,
+    /// `network_ids`의 역방향
+    by_network_id: HashMap<u32, Entity>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 컴포넌트가 없는 새 엔티티
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity { index, generation: self.generations[index as usize] }
+            }
+            None => {
+                let index = self.generations.len() as u32;
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity { index, generation: 0 }
+            }
+        }
+    }
+
+    /// `Transform`과 `Renderable`만 있는 엔티티
+    pub fn spawn_renderable(&mut self, renderable: Renderable, transform: Transform) -> Entity {
+        let entity = self.spawn();
+        self.renderables.insert(entity, renderable);
+        self.transforms.insert(entity, transform);
+        entity
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index).copied().unwrap_or(false) && self.generations[index] == entity.generation
+    }
+
+    /// 엔티티와 모든 컴포넌트를 삭제. 이미 없으면 `false`
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        self.transforms.remove(entity);
+        self.renderables.remove(entity);
+        self.interpolated.remove(entity);
+        self.animators.remove(entity);
+        self.snapshots.remove(entity);
+        self.remove_network_id(entity);
+
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        true
+    }
+
+    /// 살아 있는 엔티티 수
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 다른 엔티티가 `id`를 쓰고 있었으면 그 엔티티에서는 뗌
+    pub fn set_network_id(&mut self, entity: Entity, id: u32) {
+        self.remove_network_id(entity);
+        if let Some(previous) = self.by_network_id.insert(id, entity) {
+            self.network_ids.remove(previous);
+        }
+        self.network_ids.insert(entity, NetworkId(id));
+    }
+
+    /// 서버 오브젝트 목록에서만 빼고 엔티티는 남김 (사라지는 효과 등)
+    pub fn remove_network_id(&mut self, entity: Entity) -> Option<u32> {
+        let NetworkId(id) = self.network_ids.remove(entity)?;
+        self.by_network_id.remove(&id);
+        Some(id)
+    }
+
+    pub fn network_id(&self, entity: Entity) -> Option<u32> {
+        self.network_ids.get(entity).map(|NetworkId(id)| *id)
+    }
+
+    pub fn network_entity(&self, id: u32) -> Option<Entity> {
+        self.by_network_id.get(&id).copied()
+    }
+
+    /// 서버 id와 엔티티. 순서는 정해져 있지 않음
+    pub fn network_entities(&self) -> impl Iterator<Item = (u32, Entity)> + '_ {
+        self.network_ids.iter().map(|(entity, NetworkId(id))| (*id, entity))
+    }
+
+    pub fn network_count(&self) -> usize {
+        self.network_ids.len()
+    }
+
+    /// 서버 오브젝트 `id`의 현재 위치
+    pub fn network_position(&self, id: u32) -> Option<Vector3<f32>> {
+        let entity = self.network_entity(id)?;
+        self.transforms.get(entity).map(|transform| transform.position)
+    }
+
+    /// `Renderable`과 `Transform`이 있는 엔티티의 인스턴스를 모델 이름별로 모음 (엔티티를 추가한 순서)
+    pub fn instances(&self) -> HashMap<&'static str, Vec<Instance>> {
+        let mut instances: HashMap<&'static str, Vec<Instance>> = HashMap::new();
+        for (entity, renderable) in self.renderables.iter() {
+            let Some(transform) = self.transforms.get(entity) else {
+                continue;
+            };
+            instances.entry(renderable.model)
+                .or_default()
+                .push(Instance::new(transform, renderable, self.animators.get(entity)));
+        }
+        instances
+    }
+
+    /// `model`로 그리는 엔티티 수
+    pub fn count_model(&self, model: &str) -> usize {
+        self.renderables.iter()
+            .filter(|(_, renderable)| renderable.model == model)
+            .count()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_components() {
+        let mut world = World::new();
+        let [a, b, c] = [(); 3].map(|_| world.spawn());
+        let mut numbers = Components::default();
+        numbers.insert(a, 1);
+        numbers.insert(b, 2);
+        numbers.insert(c, 3);
+        assert_eq!(numbers.insert(b, 20), Some(2));
+
+        // 가운데를 지워도 나머지는 그대로 찾음
+        assert_eq!(numbers.remove(a), Some(1));
+        assert_eq!(numbers.remove(a), None);
+        assert_eq!((numbers.get(b), numbers.get(c)), (Some(&20), Some(&3)));
+        *numbers.get_mut(c).unwrap() += 1;
+        let mut values = numbers.iter().map(|(_, value)| *value).collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, [4, 20]);
+    }
+
+    #[test]
+    fn test_despawn_reuses_index() {
+        let mut world = World::new();
+        let old = world.spawn_renderable(Renderable::new("pawn"), Transform::default());
+        world.set_network_id(old, 7);
+        assert!(world.despawn(old));
+        assert!(!world.despawn(old));
+        assert_eq!((world.len(), world.network_count(), world.network_entity(7)), (0, 0, None));
+
+        // 같은 번호를 다시 써도 이전 핸들의 컴포넌트로 보이지 않음
+        let new = world.spawn();
+        assert!(world.is_alive(new) && !world.is_alive(old));
+        assert_eq!(new.index, old.index);
+        world.transforms.insert(new, Transform::default());
+        assert!(world.transforms.get(old).is_none());
+    }
+
+    #[test]
+    fn test_network_ids() {
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.set_network_id(a, 1);
+        world.set_network_id(b, 2);
+        assert_eq!(world.network_entity(2), Some(b));
+
+        // 같은 id를 다른 엔티티에 주면 이전 엔티티에서 뗌
+        world.set_network_id(b, 1);
+        assert_eq!(world.network_entity(1), Some(b));
+        assert_eq!((world.network_id(a), world.network_entity(2)), (None, None));
+
+        assert_eq!(world.remove_network_id(b), Some(1));
+        assert!(world.is_alive(b));
+        assert_eq!(world.network_count(), 0);
+    }
+
+    #[test]
+    fn test_instances_by_model() {
+        let mut world = World::new();
+        for model in ["tile", "pawn", "tile"] {
+            world.spawn_renderable(Renderable::new(model), Transform::default());
+        }
+        // 위치가 없으면 그리지 않음
+        let hidden = world.spawn();
+        world.renderables.insert(hidden, Renderable::new("pawn"));
+
+        let instances = world.instances();
+        assert_eq!(instances["tile"].len(), 2);
+        assert_eq!(instances["pawn"].len(), 1);
+        assert_eq!(world.count_model("pawn"), 2);
+    }
+}
@@ -0,0 +1,125 @@
+//! 프레임 시간 통계.  
+//! 최근 `capacity` 프레임의 시간으로 최소/평균/최대 프레임 시간과 FPS를 계산함.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+
+/// 기본으로 기억하는 프레임 수
+pub const FRAME_STATS_CAPACITY: usize = 120;
+/// 통계를 로그로 남기는 간격
+pub const FRAME_STATS_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSummary {
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+    /// 평균 프레임 시간으로 계산한 초당 프레임 수
+    pub fps: f32,
+}
+
+impl std::fmt::Display for FrameSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f, 
+            "{:.1} fps (frame min {:.2}ms / avg {:.2}ms / max {:.2}ms)", 
+            self.fps, 
+            self.min.as_secs_f32() * 1000.0, 
+            self.avg.as_secs_f32() * 1000.0, 
+            self.max.as_secs_f32() * 1000.0,
+        )
+    }
+}
+
+
+pub struct FrameStats {
+    durations: VecDeque<Duration>,
+    capacity: usize,
+    last_report: Option<Instant>,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new(FRAME_STATS_CAPACITY)
+    }
+}
+
+impl FrameStats {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self { durations: VecDeque::with_capacity(capacity), capacity, last_report: None }
+    }
+
+    /// 가득 차면 가장 오래된 프레임을 버림
+    pub fn push(&mut self, frame_time: Duration) {
+        if self.durations.len() == self.capacity {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(frame_time);
+    }
+
+    /// 기록된 프레임이 없으면 `None`
+    pub fn summary(&self) -> Option<FrameSummary> {
+        let min = *self.durations.iter().min()?;
+        let max = *self.durations.iter().max()?;
+        let avg = self.durations.iter().sum::<Duration>() / self.durations.len() as u32;
+        let fps = if avg.is_zero() { 0.0 } else { 1.0 / avg.as_secs_f32() };
+
+        Some(FrameSummary { min, avg, max, fps })
+    }
+
+    /// 마지막으로 보고한 뒤 `FRAME_STATS_REPORT_INTERVAL`이 지났으면 통계를 반환
+    pub fn report(&mut self, now: Instant) -> Option<FrameSummary> {
+        if self.last_report.is_some_and(|last| now.saturating_duration_since(last) < FRAME_STATS_REPORT_INTERVAL) {
+            return None;
+        }
+
+        let summary = self.summary()?;
+        self.last_report = Some(now);
+        Some(summary)
+    }
+
+    pub fn clear(&mut self) {
+        self.durations.clear();
+        self.last_report = None;
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut stats = FrameStats::new(3);
+        assert_eq!(stats.summary(), None);
+
+        for ms in [100, 10, 20, 30] {
+            stats.push(Duration::from_millis(ms));
+        }
+        // 가장 오래된 100ms는 버려짐
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.min, Duration::from_millis(10));
+        assert_eq!(summary.avg, Duration::from_millis(20));
+        assert_eq!(summary.max, Duration::from_millis(30));
+        assert!((summary.fps - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_report_interval() {
+        let mut stats = FrameStats::default();
+        let start = Instant::now();
+        assert_eq!(stats.report(start), None);
+
+        stats.push(Duration::from_millis(16));
+        assert!(stats.report(start).is_some());
+        assert!(stats.report(start + FRAME_STATS_REPORT_INTERVAL / 2).is_none());
+        assert!(stats.report(start + FRAME_STATS_REPORT_INTERVAL).is_some());
+    }
+}
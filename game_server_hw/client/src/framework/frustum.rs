@@ -0,0 +1,66 @@
+//! 보이는 범위 (절두체).
+//! view-projection 행렬에서 여섯 평면을 뽑아서, 화면에 들어오지 않는 인스턴스를 그리기 전에 걸러냄.
+
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector3, Vector4};
+
+
+/// 안쪽을 향하는 여섯 평면 (`xyz`는 단위 법선, `w`는 원점에서의 거리)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// wgpu의 클립 공간 (x, y는 -1..=1, 깊이는 0..=1) 기준.
+    /// 원근 투영이든 직교 투영 (`DirectionalLight::view_proj`)이든 같음
+    pub fn from_view_proj(view_proj: Matrix4<f32>) -> Self {
+        let [r0, r1, r2, r3] = [0, 1, 2, 3].map(|i| view_proj.row(i));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2]
+            .map(|plane| {
+                let length = plane.truncate().magnitude();
+                if length.is_normal() { plane / length } else { plane }
+            });
+
+        Self { planes }
+    }
+
+    /// 중심이 `center`이고 반지름이 `radius`인 구가 조금이라도 안에 있으면 `true`
+    pub fn intersects_sphere(&self, center: Vector3<f32>, radius: f32) -> bool {
+        self.planes.iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Point3};
+
+    #[test]
+    fn test_intersects_sphere() {
+        // 원점에서 -z를 봄. near 1, far 10
+        let view = Matrix4::look_at_rh(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), Vector3::unit_y());
+        // OpenGL의 깊이 -1..=1을 0..=1로
+        #[rustfmt::skip]
+        let to_wgpu = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.5, 0.0,
+            0.0, 0.0, 0.5, 1.0,
+        );
+        let proj = to_wgpu * cgmath::perspective(Deg(90.0), 1.0, 1.0, 10.0);
+        let frustum = Frustum::from_view_proj(proj * view);
+
+        assert!(frustum.intersects_sphere(Vector3::new(0.0, 0.0, -5.0), 0.1));
+        // 뒤, 너무 멂, 옆
+        assert!(!frustum.intersects_sphere(Vector3::new(0.0, 0.0, 5.0), 0.1));
+        assert!(!frustum.intersects_sphere(Vector3::new(0.0, 0.0, -12.0), 1.0));
+        assert!(!frustum.intersects_sphere(Vector3::new(8.0, 0.0, -5.0), 1.0));
+
+        // 경계에 걸치면 보임
+        assert!(frustum.intersects_sphere(Vector3::new(5.5, 0.0, -5.0), 1.0));
+        assert!(frustum.intersects_sphere(Vector3::new(0.0, 0.0, -10.5), 1.0));
+    }
+}
@@ -0,0 +1,169 @@
+use cgmath::{InnerSpace, Vector2};
+#[cfg(feature = "gamepad")]
+use super::logging;
+use super::input::Action;
+
+
+/// 이 값보다 작은 스틱 기울기는 무시
+pub const STICK_DEADZONE: f32 = 0.2;
+
+
+/// 게임패드 버튼에 대응되는 동작
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadAction {
+    Confirm,
+    Cancel,
+    Select,
+}
+
+
+/// 한 번의 poll에서 얻은 게임패드 입력
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GamepadInput {
+    /// 왼쪽 스틱이나 방향 버튼의 이동 방향 (크기 포함). 둘 다 중립이면 `None`
+    pub direction: Option<Vector2<f32>>,
+    /// deadzone을 적용하기 전의 왼쪽 스틱 기울기 (x, y). 위가 +y
+    pub stick: (f32, f32),
+    pub actions: Vec<GamepadAction>,
+}
+
+
+/// 왼쪽 스틱 기울기를 이동 방향으로 변환.
+/// 스틱은 위가 +y 이지만, 서버 좌표계는 앞(W)이 -y 이므로 y축을 뒤집음.
+/// 크기는 deadzone을 뺀 뒤 0..=1로 다시 맞춤.
+pub fn stick_to_direction(x: f32, y: f32) -> Option<Vector2<f32>> {
+    let stick = Vector2::new(x, -y);
+    let magnitude = stick.magnitude();
+    if !magnitude.is_finite() || magnitude < STICK_DEADZONE {
+        return None;
+    }
+
+    let scaled = ((magnitude - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0);
+    Some(stick / magnitude * scaled)
+}
+
+
+/// 누르고 있는 방향 버튼을 키보드와 같은 이동 동작으로 보고 합친 방향. 크기는 1
+pub fn dpad_to_direction(held: &[Action]) -> Option<Vector2<f32>> {
+    let sum = held.iter()
+        .filter_map(|action| action.direction())
+        .fold(Vector2::new(0, 0), |sum, direction| sum + direction);
+    if sum == Vector2::new(0, 0) {
+        return None;
+    }
+    Some(sum.cast::<f32>()?.normalize())
+}
+
+
+#[cfg(feature = "gamepad")]
+pub use gilrs_backend::Gamepad;
+
+#[cfg(feature = "gamepad")]
+mod gilrs_backend {
+    use gilrs::{Axis, Button, EventType, Gilrs};
+    use super::*;
+
+    pub struct Gamepad {
+        gilrs: Gilrs,
+        stick: (f32, f32),
+        /// 누르고 있는 방향 버튼
+        dpad: Vec<Action>,
+    }
+
+    /// 방향 버튼은 WASD와 같은 이동 동작
+    fn dpad_action(button: Button) -> Option<Action> {
+        match button {
+            Button::DPadUp => Some(Action::MoveUp),
+            Button::DPadDown => Some(Action::MoveDown),
+            Button::DPadLeft => Some(Action::MoveLeft),
+            Button::DPadRight => Some(Action::MoveRight),
+            _ => None,
+        }
+    }
+
+    impl Gamepad {
+        /// 게임패드 백엔드를 사용할 수 없으면 `None`
+        pub fn new() -> Option<Self> {
+            match Gilrs::new() {
+                Ok(gilrs) => Some(Self { gilrs, stick: (0.0, 0.0), dpad: Vec::new() }),
+                Err(e) => {
+                    log::warn!(target: logging::INPUT, "Gamepad unavailable: {}", e);
+                    None
+                }
+            }
+        }
+
+        /// 쌓인 게임패드 이벤트를 모두 처리
+        pub fn poll(&mut self) -> GamepadInput {
+            let mut actions = Vec::new();
+
+            while let Some(event) = self.gilrs.next_event() {
+                match event.event {
+                    EventType::AxisChanged(Axis::LeftStickX, value, _) => self.stick.0 = value,
+                    EventType::AxisChanged(Axis::LeftStickY, value, _) => self.stick.1 = value,
+                    EventType::ButtonPressed(button, _) => match button {
+                        Button::South => actions.push(GamepadAction::Confirm),
+                        Button::East => actions.push(GamepadAction::Cancel),
+                        Button::Select => actions.push(GamepadAction::Select),
+                        _ => if let Some(action) = dpad_action(button) {
+                            if !self.dpad.contains(&action) {
+                                self.dpad.push(action);
+                            }
+                        },
+                    },
+                    EventType::ButtonReleased(button, _) => {
+                        if let Some(action) = dpad_action(button) {
+                            self.dpad.retain(|held| *held != action);
+                        }
+                    },
+                    EventType::Disconnected => {
+                        self.stick = (0.0, 0.0);
+                        self.dpad.clear();
+                    },
+                    _ => {}
+                }
+            }
+
+            // 스틱을 기울이고 있으면 방향 버튼보다 스틱을 따름
+            let direction = stick_to_direction(self.stick.0, self.stick.1)
+                .or_else(|| dpad_to_direction(&self.dpad));
+
+            GamepadInput {
+                direction,
+                stick: self.stick,
+                actions,
+            }
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stick_to_direction() {
+        assert_eq!(stick_to_direction(0.1, -0.1), None);
+
+        // 스틱 위로 끝까지 -> 앞(-y)으로 최대 크기
+        let direction = stick_to_direction(0.0, 1.0).unwrap();
+        assert!((direction - Vector2::new(0.0, -1.0)).magnitude() < 1e-6);
+
+        // 오른쪽 절반 -> 크기는 deadzone만큼 줄어듦
+        let direction = stick_to_direction(0.6, 0.0).unwrap();
+        assert!((direction - Vector2::new(0.5, 0.0)).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn test_dpad_to_direction() {
+        assert_eq!(dpad_to_direction(&[]), None);
+        assert_eq!(dpad_to_direction(&[Action::MoveUp, Action::MoveDown]), None);
+        assert_eq!(dpad_to_direction(&[Action::MoveUp]), Some(Vector2::new(0.0, -1.0)));
+
+        // 대각선도 크기는 1
+        let direction = dpad_to_direction(&[Action::MoveRight, Action::MoveDown]).unwrap();
+        assert!((direction - Vector2::new(1.0, 1.0).normalize()).magnitude() < 1e-6);
+    }
+}
@@ -0,0 +1,138 @@
+//! 개발 빌드의 리소스 다시 읽기.
+//! 리소스 폴더와 셰이더 소스 폴더의 파일 수정 시각을 주기적으로 비교해서 바뀐 파일을 알려주고,
+//! `State`가 종류 (`ResourceKind`)에 따라 셰이더, 모델, 텍스처를 다시 만듦.
+//! 빌드할 때 복사한 리소스 대신 원본을 고치려면 `GAMESERVER_RES_DIR`을 `resource` 폴더로 지정.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::resources;
+
+
+/// 파일 수정 시각을 다시 확인하는 간격
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// `.wgsl`
+    Shader,
+    /// `.obj`, `.mtl`과 애니메이션 (`.anim`)
+    Model,
+    /// `.png`, `.jpg`
+    Texture,
+}
+
+impl ResourceKind {
+    /// 확장자로 구분. 다시 읽지 않는 파일은 `None`
+    pub fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "wgsl" => Some(ResourceKind::Shader),
+            "obj" | "mtl" | "anim" => Some(ResourceKind::Model),
+            "png" | "jpg" | "jpeg" => Some(ResourceKind::Texture),
+            _ => None,
+        }
+    }
+}
+
+
+/// 리소스 폴더와 (개발 빌드에서 `include_str!`로 넣는) 셰이더 소스 폴더
+pub fn watched_dirs() -> Vec<PathBuf> {
+    let mut dirs = resources::resource_dirs();
+    dirs.push(shader_dir());
+    dirs
+}
+
+pub fn shader_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src")
+}
+
+
+/// 폴더 안 파일 (하위 폴더 제외)의 수정 시각을 `POLL_INTERVAL`마다 비교
+#[derive(Debug)]
+pub struct ResourceWatcher {
+    dirs: Vec<PathBuf>,
+    modified: HashMap<PathBuf, SystemTime>,
+    last_poll: Instant,
+}
+
+impl ResourceWatcher {
+    /// 지금 있는 파일은 바뀌지 않은 것으로 봄
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        let mut watcher = Self { dirs, modified: HashMap::new(), last_poll: Instant::now() };
+        watcher.modified = watcher.scan();
+        watcher
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        self.dirs.iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+                Some((entry.path(), metadata.modified().ok()?))
+            })
+            .collect()
+    }
+
+    /// 지난 확인 뒤에 새로 생기거나 바뀐 파일. `POLL_INTERVAL`이 지나지 않았으면 비어 있음
+    pub fn poll(&mut self, now: Instant) -> Vec<PathBuf> {
+        if now.saturating_duration_since(self.last_poll) < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = now;
+
+        let current = self.scan();
+        let mut changed = current.iter()
+            .filter(|(path, modified)| self.modified.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        changed.sort();
+        self.modified = current;
+        changed
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_kind() {
+        assert_eq!(ResourceKind::of(Path::new("src/shader.wgsl")), Some(ResourceKind::Shader));
+        assert_eq!(ResourceKind::of(Path::new("pawn.OBJ")), Some(ResourceKind::Model));
+        assert_eq!(ResourceKind::of(Path::new("pawn.anim")), Some(ResourceKind::Model));
+        assert_eq!(ResourceKind::of(Path::new("cube-diffuse.jpg")), Some(ResourceKind::Texture));
+        assert_eq!(ResourceKind::of(Path::new("client.toml")), None);
+        assert_eq!(ResourceKind::of(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_poll() {
+        let dir = std::env::temp_dir().join(format!("gameserver-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("old.obj"), "o").unwrap();
+
+        let mut watcher = ResourceWatcher::new(vec![dir.clone(), dir.join("missing")]);
+        let start = Instant::now();
+        assert!(watcher.poll(start + POLL_INTERVAL).is_empty());
+
+        // 새 파일과 수정 시각이 바뀐 파일. 확인 간격 전에는 알려주지 않음
+        std::fs::write(dir.join("new.wgsl"), "n").unwrap();
+        let file = std::fs::File::options().write(true).open(dir.join("old.obj")).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        drop(file);
+        assert!(watcher.poll(start + POLL_INTERVAL).is_empty());
+        assert_eq!(watcher.poll(start + POLL_INTERVAL * 2), [dir.join("new.wgsl"), dir.join("old.obj")]);
+        assert!(watcher.poll(start + POLL_INTERVAL * 3).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+//! 화면 위에 그리는 2D 글자 (HUD).
+//! 씬이 `Scene::hud`에서 `Hud::draw_text`와 `Hud::fill_rect`로 글자와 사각형을 쌓아두면,
+//! 주 렌더 패스 뒤에 `HudRenderer`가 픽셀마다 사각형 두 개로 한 번에 그림.
+//! 글꼴은 이름표와 같은 3x5 글꼴 (`name_tag::glyph_pixels`)
+
+use super::{
+    color::Color,
+    model::Vertex,
+    name_tag::{self, GLYPH_HEIGHT},
+};
+
+
+/// 기본 글자 높이 (화면 픽셀). 글꼴 한 픽셀이 3x3
+pub const DEFAULT_TEXT_SIZE: f32 = 15.0;
+/// 정점 버퍼의 처음 크기 (정점 수). 부족하면 두 배씩 늘림
+const INITIAL_VERTEX_CAPACITY: usize = 1024;
+
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct HudVertex {
+    /// 클립 좌표 (-1..=1, 위가 +)
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl Vertex for HudVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<HudVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::offset_of!(HudVertex, color) as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ]
+        }
+    }
+}
+
+
+/// 화면 픽셀 단위의 사각형 하나. `(x, y)`는 왼쪽 위
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Quad {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    color: Color,
+}
+
+
+/// 한 프레임 동안 그릴 글자를 모아둠. 매 프레임 `clear` 후 다시 채움
+#[derive(Debug, Default)]
+pub struct Hud {
+    quads: Vec<Quad>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.quads.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    /// `pos`는 글자 왼쪽 위 (화면 픽셀, 아래로 +y), `size`는 글자 높이 (화면 픽셀)
+    pub fn draw_text(&mut self, pos: (f32, f32), size: f32, color: Color, text: &str) {
+        let pixel = size / GLYPH_HEIGHT as f32;
+        if !(pixel.is_finite() && pixel > 0.0) {
+            return;
+        }
+
+        self.quads.extend(name_tag::glyph_pixels(text).map(|(col, row)| Quad {
+            x: pos.0 + col as f32 * pixel,
+            y: pos.1 + row as f32 * pixel,
+            width: pixel,
+            height: pixel,
+            color,
+        }));
+    }
+
+    /// 왼쪽 위가 `pos`이고 크기가 `size`인 사각형 (화면 픽셀). 진행 막대 등에 사용
+    pub fn fill_rect(&mut self, pos: (f32, f32), size: (f32, f32), color: Color) {
+        if !(size.0 > 0.0 && size.1 > 0.0) {
+            return;
+        }
+        self.quads.push(Quad { x: pos.0, y: pos.1, width: size.0, height: size.1, color });
+    }
+
+    /// `draw_text`로 그린 `text`의 가로 길이 (화면 픽셀)
+    pub fn text_width(size: f32, text: &str) -> f32 {
+        name_tag::text_width(text) as f32 * size / GLYPH_HEIGHT as f32
+    }
+
+    /// 크기가 `width` x `height`인 화면에 그릴 삼각형 목록 (사각형마다 정점 6개)
+    pub fn vertices(&self, width: u32, height: u32) -> Vec<HudVertex> {
+        let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+        let to_clip = |x: f32, y: f32| [x / width * 2.0 - 1.0, 1.0 - y / height * 2.0];
+
+        self.quads.iter()
+            .flat_map(|quad| {
+                let color = [quad.color.r(), quad.color.g(), quad.color.b(), quad.color.a()];
+                let (left, top) = (quad.x, quad.y);
+                let (right, bottom) = (quad.x + quad.width, quad.y + quad.height);
+                [
+                    (left, top), (left, bottom), (right, bottom),
+                    (left, top), (right, bottom), (right, top),
+                ].map(|(x, y)| HudVertex { position: to_clip(x, y), color })
+            })
+            .collect()
+    }
+}
+
+
+fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(
+        &wgpu::BufferDescriptor {
+            label: Some("HUD Vertex Buffer"),
+            mapped_at_creation: false,
+            size: (std::mem::size_of::<HudVertex>() * capacity) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        }
+    )
+}
+
+
+pub struct HudRenderer {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    /// `vertex_buffer`에 들어가는 정점 수
+    capacity: usize,
+}
+
+impl HudRenderer {
+    /// `format`은 그릴 화면의 텍스처 형식
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("HUD Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../hud.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("HUD Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("HUD Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[HudVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            // 항상 3D 장면 위에 그림
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let capacity = INITIAL_VERTEX_CAPACITY;
+        let vertex_buffer = create_vertex_buffer(device, capacity);
+
+        Self { pipeline, vertex_buffer, capacity }
+    }
+
+    /// `view`에 이미 그려진 장면 위에 `hud`를 그림. 크기는 `view`의 크기 (픽셀)
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        hud: &Hud,
+        (width, height): (u32, u32),
+    ) {
+        if hud.is_empty() {
+            return;
+        }
+
+        let vertices = hud.vertices(width, height);
+        if vertices.len() > self.capacity {
+            self.capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = create_vertex_buffer(device, self.capacity);
+        }
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("HUD Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..vertices.len() as u32, 0..1);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_text() {
+        let mut hud = Hud::new();
+        hud.draw_text((10.0, 20.0), 10.0, Color::RED, "-");
+        assert_eq!(hud.quads.len(), 3);
+        // 가운데 줄: 글꼴 한 픽셀이 2x2
+        assert_eq!(hud.quads[0], Quad { x: 10.0, y: 24.0, width: 2.0, height: 2.0, color: Color::RED });
+        assert_eq!(Hud::text_width(10.0, "--"), 14.0);
+
+        hud.draw_text((0.0, 0.0), 0.0, Color::RED, "-");
+        hud.draw_text((0.0, 0.0), 10.0, Color::RED, " ");
+        assert_eq!(hud.quads.len(), 3);
+
+        hud.clear();
+        assert!(hud.is_empty());
+    }
+
+    #[test]
+    fn test_vertices() {
+        let mut hud = Hud::new();
+        hud.draw_text((0.0, 0.0), 50.0, Color::WHITE, ".");
+        // `.`은 가운데 열의 맨 아래 픽셀 (10..20, 40..50)
+        let vertices = hud.vertices(100, 100);
+        assert_eq!(vertices.len(), 6);
+        let near = |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).abs() < 1e-6 && (a[1] - b[1]).abs() < 1e-6;
+        assert!(near(vertices[0].position, [-0.8, 0.2]));
+        assert!(near(vertices[2].position, [-0.6, 0.0]));
+        assert_eq!(vertices[0].color, [1.0; 4]);
+
+        hud.clear();
+        hud.fill_rect((0.0, 50.0), (50.0, 0.0), Color::WHITE);
+        hud.fill_rect((0.0, 50.0), (50.0, 25.0), Color::WHITE);
+        let vertices = hud.vertices(100, 100);
+        assert_eq!(vertices.len(), 6);
+        assert!(near(vertices[0].position, [-1.0, 0.0]));
+        assert!(near(vertices[2].position, [0.0, -0.5]));
+    }
+}
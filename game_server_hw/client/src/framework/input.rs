@@ -0,0 +1,209 @@
+use cgmath::Vector2;
+use winit::keyboard::KeyCode;
+
+use super::gamepad::GamepadAction;
+
+
+/// 입력 이벤트에서 만들어진 동작.  
+/// `handle_event`에서 쌓이고 `update`에서 한번에 처리(서버 메세지로 변환 등)됨.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputAction {
+    /// 이동 방향 (키보드는 각 축 ±1, 게임패드는 크기 포함)
+    Move(Vector2<f32>),
+    CycleSpectateTarget,
+    SubmitText(String),
+}
+
+
+/// 키에 연결하는 게임 동작. 씬은 키 대신 `InputMap`이 바꾼 동작을 처리함
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    OpenChat,
+    ToggleScoreboard,
+    ToggleFreeCamera,
+    CycleSpectateTarget,
+    /// 돌리거나 당긴 카메라를 설정의 기본 위치로 되돌림
+    ResetCamera,
+    /// 카메라, 오브젝트 수, 네트워크 상태를 보여주는 디버그 패널
+    ToggleDebugPanel,
+    /// 화면 구석의 보드 지도
+    ToggleMinimap,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::OpenChat,
+        Action::ToggleScoreboard,
+        Action::ToggleFreeCamera,
+        Action::CycleSpectateTarget,
+        Action::ResetCamera,
+        Action::ToggleDebugPanel,
+        Action::ToggleMinimap,
+        Action::Pause,
+    ];
+
+    /// 설정 파일의 `[keys]`에서 쓰는 이름
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::OpenChat => "open_chat",
+            Action::ToggleScoreboard => "toggle_scoreboard",
+            Action::ToggleFreeCamera => "toggle_free_camera",
+            Action::CycleSpectateTarget => "cycle_spectate_target",
+            Action::ResetCamera => "reset_camera",
+            Action::ToggleDebugPanel => "toggle_debug_panel",
+            Action::ToggleMinimap => "toggle_minimap",
+            Action::Pause => "pause",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// 이동 동작의 카메라 기준 방향 (`MoveUp`이 `(0, -1)`)
+    pub fn direction(self) -> Option<Vector2<i32>> {
+        match self {
+            Action::MoveUp => Some(Vector2::new(0, -1)),
+            Action::MoveDown => Some(Vector2::new(0, 1)),
+            Action::MoveLeft => Some(Vector2::new(-1, 0)),
+            Action::MoveRight => Some(Vector2::new(1, 0)),
+            _ => None,
+        }
+    }
+}
+
+
+/// 물리 키를 동작으로 바꾸는 표. 한 동작에 여러 키를 연결할 수 있지만 한 키는 한 동작에만 연결됨.
+/// 설정 파일의 `[keys]`로 바꾸거나 실행 중에 `bind`/`rebind`로 바꿀 수 있음.
+/// 게임패드 버튼도 같은 방식으로 동작에 연결함 (이동은 스틱과 방향 버튼이 함)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMap {
+    /// 연결한 순서
+    bindings: Vec<(KeyCode, Action)>,
+    buttons: Vec<(GamepadAction, Action)>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (KeyCode::KeyW, Action::MoveUp),
+                (KeyCode::KeyS, Action::MoveDown),
+                (KeyCode::KeyA, Action::MoveLeft),
+                (KeyCode::KeyD, Action::MoveRight),
+                (KeyCode::Enter, Action::OpenChat),
+                (KeyCode::NumpadEnter, Action::OpenChat),
+                (KeyCode::KeyT, Action::OpenChat),
+                (KeyCode::Tab, Action::ToggleScoreboard),
+                (KeyCode::KeyF, Action::ToggleFreeCamera),
+                (KeyCode::KeyC, Action::CycleSpectateTarget),
+                (KeyCode::KeyR, Action::ResetCamera),
+                (KeyCode::F3, Action::ToggleDebugPanel),
+                (KeyCode::KeyM, Action::ToggleMinimap),
+                (KeyCode::Escape, Action::Pause),
+            ],
+            buttons: vec![
+                (GamepadAction::Confirm, Action::OpenChat),
+                (GamepadAction::Cancel, Action::Pause),
+                (GamepadAction::Select, Action::CycleSpectateTarget),
+            ],
+        }
+    }
+}
+
+impl InputMap {
+    pub fn action(&self, keycode: KeyCode) -> Option<Action> {
+        self.bindings.iter()
+            .find(|(key, _)| *key == keycode)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn button_action(&self, button: GamepadAction) -> Option<Action> {
+        self.buttons.iter()
+            .find(|(bound, _)| *bound == button)
+            .map(|(_, action)| *action)
+    }
+
+    /// `button`을 `action`에 연결함. `button`이 하던 동작은 연결이 끊어짐
+    pub fn bind_button(&mut self, button: GamepadAction, action: Action) {
+        self.buttons.retain(|(bound, _)| *bound != button);
+        self.buttons.push((button, action));
+    }
+
+    /// `action`에 연결된 키
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = KeyCode> + '_ {
+        self.bindings.iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+    }
+
+    /// `keycode`를 `action`에 연결함. `keycode`가 하던 동작은 연결이 끊어짐
+    pub fn bind(&mut self, keycode: KeyCode, action: Action) {
+        self.bindings.retain(|(key, _)| *key != keycode);
+        self.bindings.push((keycode, action));
+    }
+
+    /// `action`의 키를 모두 `keys`로 바꿈. 비어있으면 그 동작은 쓸 수 없음
+    pub fn rebind(&mut self, action: Action, keys: &[KeyCode]) {
+        self.bindings.retain(|(_, bound)| *bound != action);
+        for keycode in keys {
+            self.bind(*keycode, action);
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebind() {
+        let mut map = InputMap::default();
+        assert_eq!(map.action(KeyCode::KeyW), Some(Action::MoveUp));
+        assert_eq!(map.keys(Action::OpenChat).collect::<Vec<_>>(), [KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::KeyT]);
+
+        map.rebind(Action::MoveUp, &[KeyCode::ArrowUp, KeyCode::KeyW]);
+        assert_eq!(map.keys(Action::MoveUp).collect::<Vec<_>>(), [KeyCode::ArrowUp, KeyCode::KeyW]);
+
+        // 다른 동작의 키를 가져오면 그 동작에서는 빠짐
+        map.bind(KeyCode::KeyT, Action::ToggleScoreboard);
+        assert_eq!(map.action(KeyCode::KeyT), Some(Action::ToggleScoreboard));
+        assert_eq!(map.keys(Action::OpenChat).count(), 2);
+
+        map.rebind(Action::Pause, &[]);
+        assert_eq!(map.action(KeyCode::Escape), None);
+    }
+
+    #[test]
+    fn test_bind_button() {
+        let mut map = InputMap::default();
+        assert_eq!(map.button_action(GamepadAction::Confirm), Some(Action::OpenChat));
+        assert_eq!(map.button_action(GamepadAction::Select), Some(Action::CycleSpectateTarget));
+
+        map.bind_button(GamepadAction::Select, Action::ToggleScoreboard);
+        assert_eq!(map.button_action(GamepadAction::Select), Some(Action::ToggleScoreboard));
+    }
+
+    #[test]
+    fn test_action_names() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+        }
+        assert_eq!(Action::from_name("jump"), None);
+    }
+}
@@ -0,0 +1,151 @@
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3};
+
+use super::color::Color;
+
+
+/// 빛이 없는 방향으로 향한 면도 이만큼은 밝음
+pub const DEFAULT_AMBIENT: f32 = 0.3;
+/// 씬이 정하지 않았을 때 그림자를 만드는 범위 (원점에서의 거리)
+pub const DEFAULT_SHADOW_RADIUS: f32 = 8.0;
+
+
+/// 한 방향에서 오는 빛 (태양광). 씬마다 `Scene::light`로 정함
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// 빛이 나아가는 방향. 길이는 상관없음
+    pub direction: Vector3<f32>,
+    pub color: Color,
+    /// 0..=1
+    pub ambient: f32,
+    /// 그림자는 `center`에서 `radius` 안의 오브젝트만 만들고 받음
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+impl Default for DirectionalLight {
+    /// 비스듬히 위에서 내려오는 흰 빛
+    fn default() -> Self {
+        Self {
+            direction: Vector3::new(-0.4, -1.0, -0.3),
+            color: Color::WHITE,
+            ambient: DEFAULT_AMBIENT,
+            center: Point3::new(0.0, 0.0, 0.0),
+            radius: DEFAULT_SHADOW_RADIUS,
+        }
+    }
+}
+
+impl DirectionalLight {
+    /// 정규화된 방향. 0이거나 유효하지 않으면 바로 아래
+    pub fn normalized_direction(&self) -> Vector3<f32> {
+        Some(self.direction)
+            .filter(|direction| direction.magnitude2().is_normal())
+            .map_or(Vector3::new(0.0, -1.0, 0.0), |direction| direction.normalize())
+    }
+
+    /// 그림자 맵에 그릴 때의 변환. 빛 방향으로 `center`를 보는 직교 투영으로,
+    /// `radius` 안의 점이 모두 화면 (-1..=1, 깊이 0..=1) 안에 들어옴
+    pub fn view_proj(&self) -> Matrix4<f32> {
+        let direction = self.normalized_direction();
+        let radius = if self.radius.is_normal() { self.radius.abs() } else { DEFAULT_SHADOW_RADIUS };
+
+        // 깊이가 wgpu와 같은 0..=1인 직교 투영. 앞뒤로 `center`에서 `radius`까지
+        let (near, far) = (radius, radius * 3.0);
+        #[rustfmt::skip]
+        let proj = Matrix4::new(
+            1.0 / radius, 0.0, 0.0, 0.0,
+            0.0, 1.0 / radius, 0.0, 0.0,
+            0.0, 0.0, -1.0 / (far - near), 0.0,
+            0.0, 0.0, -near / (far - near), 1.0,
+        );
+
+        // 빛이 바로 위아래로 향하면 y축을 위로 쓸 수 없음
+        let up = if direction.y.abs() > 0.99 { Vector3::unit_z() } else { Vector3::unit_y() };
+        let eye = self.center - direction * radius * 2.0;
+        let view = Matrix4::look_at_rh(eye, self.center, up);
+
+        proj * view
+    }
+}
+
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    /// 정규화된 방향. WGSL의 `vec3` 정렬에 맞추기 위해 뒤에 `ambient`를 둠
+    direction: [f32; 3],
+    ambient: f32,
+    color: [f32; 3],
+    /// 1이면 그림자 맵을 사용, 0이면 그림자 없음
+    shadows: f32,
+    view_proj: [[f32; 4]; 4],
+}
+
+impl Default for LightUniform {
+    fn default() -> Self {
+        Self::new(&DirectionalLight::default(), false)
+    }
+}
+
+impl LightUniform {
+    pub fn new(light: &DirectionalLight, shadows: bool) -> Self {
+        Self {
+            direction: light.normalized_direction().into(),
+            ambient: light.ambient.clamp(0.0, 1.0),
+            color: [light.color.r(), light.color.g(), light.color.b()],
+            shadows: if shadows { 1.0 } else { 0.0 },
+            view_proj: light.view_proj().into(),
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{EuclideanSpace, Vector4};
+
+    #[test]
+    fn test_light_uniform() {
+        let uniform = LightUniform::new(&DirectionalLight {
+            direction: Vector3::new(0.0, -2.0, 0.0),
+            color: Color::RED,
+            ambient: 1.5,
+            ..Default::default()
+        }, true);
+        assert_eq!(uniform.direction, [0.0, -1.0, 0.0]);
+        assert_eq!(uniform.color, [1.0, 0.0, 0.0]);
+        assert_eq!(uniform.ambient, 1.0);
+        assert_eq!(uniform.shadows, 1.0);
+        assert_eq!(std::mem::size_of::<LightUniform>(), 96);
+
+        let zero = DirectionalLight { direction: Vector3::new(0.0, 0.0, 0.0), ..Default::default() };
+        assert_eq!(LightUniform::new(&zero, false).direction, [0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_light_view_proj() {
+        for direction in [Vector3::new(-0.4, -1.0, -0.3), Vector3::new(0.0, -1.0, 0.0)] {
+            let light = DirectionalLight { direction, center: Point3::new(3.0, 0.0, 3.0), radius: 5.0, ..Default::default() };
+            let project = |p: Vector3<f32>| {
+                let clip = light.view_proj() * Vector4::new(p.x, p.y, p.z, 1.0);
+                clip.truncate() / clip.w
+            };
+
+            let center = project(light.center.to_vec());
+            assert!(center.x.abs() < 1e-5 && center.y.abs() < 1e-5);
+            assert!((center.z - 0.5).abs() < 1e-5);
+
+            // 빛에 가까운 쪽이 더 얕음
+            let near = project(light.center.to_vec() - light.normalized_direction() * 4.0);
+            assert!(near.z < center.z);
+
+            for offset in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()] {
+                for p in [project(light.center.to_vec() + offset * 4.9), project(light.center.to_vec() - offset * 4.9)] {
+                    assert!(p.x.abs() <= 1.0 && p.y.abs() <= 1.0 && (0.0..=1.0).contains(&p.z), "{:?}", p);
+                }
+            }
+        }
+    }
+}
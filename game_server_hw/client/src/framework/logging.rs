@@ -0,0 +1,85 @@
+//! 서브시스템별 로그 target.  
+//! `RUST_LOG=gameserver::net=debug` 처럼 필요한 부분의 로그만 볼 수 있음.
+
+use std::time::{Duration, Instant};
+
+
+pub const NET: &str = "gameserver::net";
+pub const RENDER: &str = "gameserver::render";
+pub const INPUT: &str = "gameserver::input";
+pub const SCENE: &str = "gameserver::scene";
+
+
+/// 같은 경고가 쏟아질 때 `interval`마다 한 번만 출력하도록 제한
+pub struct Throttle {
+    interval: Duration,
+    last: Option<Instant>,
+    suppressed: u32,
+}
+
+impl Throttle {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last: None, suppressed: 0 }
+    }
+
+    /// 지금 출력해야 하면 그동안 생략된 횟수를 반환. 아니면 생략 횟수만 늘리고 `None`
+    pub fn hit(&mut self, now: Instant) -> Option<u32> {
+        if self.last.is_some_and(|last| now.saturating_duration_since(last) < self.interval) {
+            self.suppressed += 1;
+            return None;
+        }
+
+        self.last = Some(now);
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+
+
+/// 테스트에서 현재 스레드가 남긴 로그를 확인하기 위한 logger
+#[cfg(test)]
+pub(crate) mod capture {
+    use std::cell::RefCell;
+
+    #[derive(Debug, Clone)]
+    pub struct Record {
+        pub target: String,
+        pub level: log::Level,
+        pub message: String,
+    }
+
+    thread_local! {
+        static RECORDS: RefCell<Vec<Record>> = const { RefCell::new(Vec::new()) };
+    }
+
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            RECORDS.with(|records| records.borrow_mut().push(Record {
+                target: record.target().to_string(),
+                level: record.level(),
+                message: record.args().to_string(),
+            }));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger;
+
+    /// `f`를 실행하는 동안 남긴 로그를 반환
+    pub fn capture<F: FnOnce()>(f: F) -> Vec<Record> {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Trace);
+        }
+
+        RECORDS.with(|records| records.borrow_mut().clear());
+        f();
+        RECORDS.with(|records| records.borrow_mut().drain(..).collect())
+    }
+}
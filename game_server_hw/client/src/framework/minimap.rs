@@ -0,0 +1,97 @@
+//! 화면 오른쪽 아래에 그리는 보드 지도 (미니맵).
+//! 보드를 위에서 내려다본 모양으로, 칸은 사각형이고 오브젝트는 점. 모두 `Hud::fill_rect`로 그림
+
+use cgmath::Vector3;
+
+use super::{
+    board::BoardLayout,
+    color::Color,
+    hud::Hud,
+};
+
+
+/// 한 칸의 크기 (화면 픽셀)보다 작게 그리지 않음
+const MIN_CELL_SIZE: f32 = 2.0;
+
+
+/// 화면에 놓인 미니맵. 보드 `+x`가 화면 오른쪽, `+z`가 화면 아래
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minimap {
+    board: BoardLayout,
+    /// 왼쪽 위 (화면 픽셀)
+    pos: (f32, f32),
+    /// 한 칸의 한 변 (화면 픽셀)
+    cell: f32,
+}
+
+impl Minimap {
+    /// `viewport` 오른쪽 아래 모서리에서 `margin`만큼 떨어진 곳에 긴 변이 `size`인 지도
+    pub fn new(board: BoardLayout, size: f32, viewport: (f32, f32), margin: f32) -> Self {
+        let cells = board.width.max(board.height).max(1) as f32;
+        let cell = (size / cells).max(MIN_CELL_SIZE);
+        let pos = (
+            viewport.0 - margin - cell * board.width as f32,
+            viewport.1 - margin - cell * board.height as f32,
+        );
+        Self { board, pos, cell }
+    }
+
+    pub fn pos(&self) -> (f32, f32) {
+        self.pos
+    }
+
+    pub fn size(&self) -> (f32, f32) {
+        (self.cell * self.board.width as f32, self.cell * self.board.height as f32)
+    }
+
+    /// 월드 좌표 `position`의 화면 위치. 보드 밖이면 `None`
+    pub fn to_screen(&self, position: Vector3<f32>) -> Option<(f32, f32)> {
+        let board = &self.board;
+        let x = (position.x - board.origin.x) / board.spacing_x + 0.5;
+        let z = (position.z - board.origin.z) / board.spacing_z + 0.5;
+        if !(0.0..=board.width as f32).contains(&x) || !(0.0..=board.height as f32).contains(&z) {
+            return None;
+        }
+        Some((self.pos.0 + x * self.cell, self.pos.1 + z * self.cell))
+    }
+
+    pub fn draw_background(&self, hud: &mut Hud, color: Color) {
+        hud.fill_rect(self.pos, self.size(), color);
+    }
+
+    pub fn draw_cell(&self, hud: &mut Hud, x: i32, z: i32, color: Color) {
+        let pos = (self.pos.0 + x as f32 * self.cell, self.pos.1 + z as f32 * self.cell);
+        hud.fill_rect(pos, (self.cell, self.cell), color);
+    }
+
+    /// `position`에 한 칸의 `scale`배 크기인 점. 보드 밖이면 그리지 않음
+    pub fn draw_dot(&self, hud: &mut Hud, position: Vector3<f32>, scale: f32, color: Color) {
+        let Some((x, y)) = self.to_screen(position) else {
+            return;
+        };
+        let size = (self.cell * scale).max(MIN_CELL_SIZE);
+        hud.fill_rect((x - size / 2.0, y - size / 2.0), (size, size), color);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap_placement() {
+        let board = BoardLayout { width: 4, height: 2, ..BoardLayout::uniform(2.0) };
+        let minimap = Minimap::new(board, 100.0, (800.0, 600.0), 10.0);
+        // 긴 변 (가로 4칸)이 100픽셀
+        assert_eq!(minimap.size(), (100.0, 50.0));
+        assert_eq!(minimap.pos(), (690.0, 540.0));
+
+        // 칸 (0, 0)의 중심은 첫 칸의 가운데, 칸 (3, 1)의 오른쪽 아래 모서리는 지도의 끝
+        assert_eq!(minimap.to_screen(board.cell_to_world(0, 0)), Some((702.5, 552.5)));
+        assert_eq!(minimap.to_screen(board.cell_to_world(3, 1) + Vector3::new(1.0, 0.0, 1.0)), Some((790.0, 590.0)));
+        assert_eq!(minimap.to_screen(board.cell_to_world(4, 0)), None);
+        assert_eq!(minimap.to_screen(board.cell_to_world(0, -1)), None);
+    }
+}
@@ -1,11 +1,39 @@
 pub mod texture;
+pub mod animation;
+pub mod asset_cache;
+pub mod board;
 pub mod resources;
 pub mod color;
 pub mod model;
 pub mod transform;
 pub mod object;
+pub mod particles;
 pub mod camera;
+pub mod chat;
+pub mod config;
+pub mod debug_draw;
+pub mod ecs;
+pub mod easing;
+pub mod frame_stats;
+pub mod frustum;
+pub mod gamepad;
+pub mod hot_reload;
+pub mod hud;
+pub mod input;
+pub mod light;
+pub mod logging;
+pub mod minimap;
+pub mod name_tag;
+pub mod netsim;
+pub mod ray;
+pub mod replay;
 pub mod scene;
+pub mod shadow;
+pub mod skybox;
+pub mod snapshot;
+pub mod text_input;
+pub mod timestep;
+pub mod transport;
 
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -18,14 +46,23 @@ use winit::{
 use wgpu::util::DeviceExt;
 
 use texture::*;
-use transform::*;
 use model::*;
+use object::*;
+use ecs::World;
 use camera::*;
+use light::LightUniform;
+use shadow::{ShadowPass, SHADOW_MAP_SIZE};
+use hud::{Hud, HudRenderer};
+use skybox::SkyboxPass;
+use particles::ParticleRenderer;
+use debug_draw::{DebugLines, DebugRenderer};
+use config::*;
 use scene::*;
 
 
 pub const SCREEN_WIDTH: u32 = 800;
 pub const SCREEN_HEIGHT: u32 = 600;
+pub const WINDOW_TITLE: &str = "GameServerHW";
 
 
 pub struct State<'a> {
@@ -40,31 +77,78 @@ pub struct State<'a> {
     window: &'a Window,
     pub background_color: wgpu::Color,
 
+    shader: wgpu::ShaderModule,
+    render_pipeline_layout: wgpu::PipelineLayout,
     render_pipeline: wgpu::RenderPipeline,
+    /// 디버그 렌더 모드에서 모델을 선으로 그림. 장치가 `POLYGON_MODE_LINE`을 지원하지 않으면 `None`
+    wireframe_pipeline: Option<wgpu::RenderPipeline>,
 
     depth_texture: Texture,
+    /// MSAA를 켜면 주 렌더 패스가 여기에 그리고 화면 텍스처로 합침
+    msaa_texture: Option<Texture>,
+    /// 장치가 지원하는 MSAA 샘플 수 (`config::MSAA_SAMPLES` 중에서). F2로 차례로 바꿈
+    msaa_samples: Vec<u32>,
+    /// 표면이 지원하는 방식. F4로 `present_mode`를 바꿈
+    present_modes: Vec<wgpu::PresentMode>,
+    present_mode: PresentMode,
 
     camera_uniform: CameraUniform,
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
 
-    scene: GameScene,
+    light_uniform: LightUniform,
+    light_buffer: wgpu::Buffer,
+    light_bind_group: wgpu::BindGroup,
+    shadow_pass: ShadowPass,
+    skybox: SkyboxPass,
+    particle_renderer: ParticleRenderer,
+    debug_renderer: DebugRenderer,
+    /// 매 프레임 씬이 채우는 디버그 선
+    debug_lines: DebugLines,
+    /// F5로 켠 디버그 렌더 모드. 켤 때의 카메라 범위를 고정해서 자유 카메라로 둘러볼 수 있음
+    debug_frustum: Option<cgmath::Matrix4<f32>>,
+
+    /// 씬이 매 프레임 채우는 화면 위 글자
+    hud: Hud,
+    hud_renderer: HudRenderer,
+
+    /// 개발 빌드에서만 리소스와 셰이더 파일이 바뀌면 다시 읽음
+    hot_reload: Option<hot_reload::ResourceWatcher>,
+
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<gamepad::Gamepad>,
+
+    pub render_config: RenderConfig,
+
+    scene: SceneManager,
+    /// F1로 켜고 끄며, 켜져 있으면 1초마다 프레임 통계를 로그로 남김
+    pub show_frame_stats: bool,
+    frame_stats: frame_stats::FrameStats,
+    /// 마지막으로 `update`를 호출한 시간
+    last_update: std::time::Instant,
+    /// 지금 창 제목. 바뀔 때만 `set_title`을 호출함
+    shown_title: String,
 }
 
 
 impl<'a> State<'a> {
     // Creating some of the wgpu types requires async code
-    pub async fn new(window: &'a Window) -> Self {
+    /// `server`가 있으면 바로 접속하고, 없으면 첫 화면에서 주소를 입력받음
+    /// (설정 파일의 주소나 `TitleScene::default_address`로 채워둠).
+    /// `replay`가 있으면 서버에 접속하지 않고 로비 없이 바로 재생함
+    pub async fn new(
+        window: &'a Window, 
+        client_config: &ClientConfig,
+        server: Option<ServerConfig>, 
+        network: NetworkConfig, 
+        replay: Option<replay::Replay>
+    ) -> anyhow::Result<Self> {
         use winit::dpi::PhysicalSize;
 
         let size = window.request_inner_size(
-            PhysicalSize::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+            PhysicalSize::new(client_config.window.width, client_config.window.height)
         );
-        let size = if size.is_none() {
-            window.inner_size()
-        } else {
-            size.unwrap()
-        };
+        let size = size.unwrap_or_else(|| window.inner_size());
         // let size = window.inner_size();
 
         // The instance is a handle to our GPU
@@ -77,7 +161,7 @@ impl<'a> State<'a> {
             ..Default::default()
         });
         
-        let surface = instance.create_surface(window).unwrap();
+        let surface = instance.create_surface(window)?;
 
         let adapter = instance.request_adapter(
             &wgpu::RequestAdapterOptions {
@@ -85,7 +169,7 @@ impl<'a> State<'a> {
                 compatible_surface: Some(&surface),
                 force_fallback_adapter: false,
             },
-        ).await.unwrap();
+        ).await.ok_or_else(|| anyhow::anyhow!("no suitable graphics adapter found"))?;
 
         // // instead of request_adapter
         // let adapter = instance
@@ -98,9 +182,13 @@ impl<'a> State<'a> {
         //     .next()
         //     .unwrap()
 
+        // 없으면 MSAA 샘플 수는 WebGPU가 보장하는 1과 4만 쓸 수 있음
+        // 선으로 그리기 (디버그 렌더 모드)는 지원할 때만 씀
+        let required_features = adapter.features() 
+            & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES | wgpu::Features::POLYGON_MODE_LINE);
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
+                required_features,
                 // WebGL doesn't support all of wgpu's features, so if
                 // we're building for the web, we'll have to disable some.
                 required_limits: if cfg!(target_arch = "wasm32") {
@@ -111,7 +199,7 @@ impl<'a> State<'a> {
                 label: None,
             },
             None, // Trace path
-        ).await.unwrap();
+        ).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
         // Shader code in this tutorial assumes an sRGB surface texture. Using a different
@@ -126,7 +214,8 @@ impl<'a> State<'a> {
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps.present_modes[0],
+            // 지원하지 않으면 가능한 다른 방식으로 바뀜
+            present_mode: client_config.window.present_mode.to_wgpu(&surface_caps.present_modes),
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -153,7 +242,7 @@ impl<'a> State<'a> {
                 entries: &[
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Buffer {
                             ty: wgpu::BufferBindingType::Uniform,
                             has_dynamic_offset: false,
@@ -178,64 +267,102 @@ impl<'a> State<'a> {
             }
         );
 
+        let mut render_config = client_config.render.clone();
+        let msaa_samples = supported_msaa_samples(&adapter, required_features, config.format);
+        let sample_count = clamp_msaa(render_config.msaa, &msaa_samples);
+        if sample_count != render_config.msaa {
+            log::warn!(target: logging::RENDER, "{}x MSAA is not supported, using {}x", render_config.msaa, sample_count);
+            render_config.msaa = sample_count;
+        }
+        let light_uniform = LightUniform::default();
+
+        let light_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Light Buffer"),
+                contents: bytemuck::cast_slice(&[light_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        // 그림자를 끄면 맵을 그리지 않으므로 가장 작게 만듦
+        let shadow_map_size = if render_config.shadows { SHADOW_MAP_SIZE } else { 1 };
+        let shadow_pass = ShadowPass::new(&device, &light_buffer, shadow_map_size);
+
+        let light_bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("light_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Depth,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                        count: None,
+                    },
+                ]
+            }
+        );
+
+        let light_bind_group = device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("light_bind_group"),
+                layout: &light_bind_group_layout, 
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: light_buffer.as_entire_binding()
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&shadow_pass.map().view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&shadow_pass.map().sampler),
+                    },
+                ]
+            }
+        );
+
+        let texture_bind_group_layout = device.create_bind_group_layout(
+            &Material::bind_group_layout_descriptor()
+        );
+
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
                 bind_group_layouts: &[
-                    &camera_bind_group_layout
+                    &camera_bind_group_layout,
+                    &texture_bind_group_layout,
+                    &light_bind_group_layout,
                 ],
                 push_constant_ranges: &[],
             }
         );
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[
-                    ModelVertex::desc(),
-                    TransformRaw::desc(),
-                ],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: Texture::DEPTH_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default()
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+        let render_pipeline = create_render_pipeline(
+            &device, &render_pipeline_layout, &shader, config.format, sample_count, wgpu::PolygonMode::Fill
+        );
+        let wireframe_pipeline = create_wireframe_pipeline(&device, &render_pipeline_layout, &shader, config.format, sample_count);
 
         let background_color = wgpu::Color {
             r: 0.1,
@@ -244,13 +371,31 @@ impl<'a> State<'a> {
             a: 1.0,
         };
 
-        let depth_texture = Texture::create_depth_texture(&device, &config, "depth_texture");
-
-        let mut scene = GameScene::new().await;
-        scene.init(&device);
+        let depth_texture = Texture::create_depth_texture(&device, &config, sample_count, "depth_texture");
+        let msaa_texture = Texture::create_msaa_target(&device, &config, sample_count, "msaa_texture");
+        // HUD는 MSAA가 합쳐진 화면 텍스처 위에 그림
+        let hud_renderer = HudRenderer::new(&device, config.format);
+        let skybox = SkyboxPass::new(&device, config.format, sample_count);
+        let particle_renderer = ParticleRenderer::new(&device, config.format, sample_count);
+        let debug_renderer = DebugRenderer::new(&device, config.format, sample_count);
+
+        let scene = match (replay, server) {
+            (Some(replay), _) => ActiveScene::Loading(
+                LoadingScene::new(GameScene::with_replay(replay, network), GameScene::MODEL_FILES)
+            ),
+            // 서버를 정했으면 첫 화면 없이 바로 접속함
+            (None, Some(server)) => ActiveScene::Connecting(ConnectingScene::new(server, network)),
+            (None, None) => {
+                let address = client_config.server.as_ref().map_or_else(TitleScene::default_address, ServerConfig::to_string);
+                ActiveScene::Title(TitleScene::new(address, network))
+            }
+        };
+        // 전체 화면이거나 창 관리자가 크기를 바꿨으면 설정과 다를 수 있음
+        let mut scene = SceneManager::new(scene);
+        scene.resize(size.width, size.height);
 
 
-        Self {
+        Ok(Self {
             window,
             surface,
             device,
@@ -260,20 +405,52 @@ impl<'a> State<'a> {
 
             background_color,
 
+            shader,
+            render_pipeline_layout,
             render_pipeline,
+            wireframe_pipeline,
 
             depth_texture,
+            msaa_texture,
+            msaa_samples,
+            present_modes: surface_caps.present_modes,
+            present_mode: client_config.window.present_mode,
 
             camera_uniform,
             camera_buffer,
             camera_bind_group,
+            light_uniform,
+            light_buffer,
+            light_bind_group,
+            shadow_pass,
+            skybox,
+            particle_renderer,
+            debug_renderer,
+            debug_lines: DebugLines::new(),
+            debug_frustum: None,
+            hud: Hud::new(),
+            hud_renderer,
+
+            hot_reload: cfg!(debug_assertions).then(|| hot_reload::ResourceWatcher::new(hot_reload::watched_dirs())),
+
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::Gamepad::new(),
+            render_config,
 
             scene,
-        }
+            show_frame_stats: false,
+            shown_title: String::new(),
+            frame_stats: frame_stats::FrameStats::default(),
+            last_update: std::time::Instant::now(),
+        })
     }
 
     pub fn window(&self) -> &Window {
-        &self.window
+        self.window
+    }
+
+    pub fn on_exit(&mut self) {
+        self.scene.on_exit();
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
@@ -282,24 +459,253 @@ impl<'a> State<'a> {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+            self.scene.resize(new_size.width, new_size.height);
         }
 
-        self.depth_texture = Texture::create_depth_texture(&self.device, &self.config, "depth_texture");
+        self.create_render_targets();
+    }
+
+    /// 화면 크기나 MSAA 샘플 수가 바뀌면 깊이 버퍼와 MSAA 타깃을 다시 만듦
+    fn create_render_targets(&mut self) {
+        let sample_count = self.render_config.msaa;
+        self.depth_texture = Texture::create_depth_texture(&self.device, &self.config, sample_count, "depth_texture");
+        self.msaa_texture = Texture::create_msaa_target(&self.device, &self.config, sample_count, "msaa_texture");
+    }
+
+    /// MSAA 샘플 수를 바꿈. 지원하지 않으면 더 작은 값을 씀
+    pub fn set_msaa(&mut self, samples: u32) {
+        let sample_count = clamp_msaa(samples, &self.msaa_samples);
+        if sample_count == self.render_config.msaa {
+            return;
+        }
+
+        self.render_config.msaa = sample_count;
+        self.render_pipeline = create_render_pipeline(
+            &self.device, 
+            &self.render_pipeline_layout, 
+            &self.shader, 
+            self.config.format, 
+            sample_count,
+            wgpu::PolygonMode::Fill
+        );
+        self.wireframe_pipeline = create_wireframe_pipeline(
+            &self.device, 
+            &self.render_pipeline_layout, 
+            &self.shader, 
+            self.config.format, 
+            sample_count
+        );
+        self.skybox.set_sample_count(&self.device, sample_count);
+        self.particle_renderer.set_sample_count(&self.device, sample_count);
+        self.debug_renderer.set_sample_count(&self.device, sample_count);
+        self.create_render_targets();
+        log::info!(target: logging::RENDER, "MSAA {}x", sample_count);
+    }
+
+    /// 화면에 내보내는 방식을 바꿈. 표면을 다시 설정함
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.present_mode = present_mode;
+        self.config.present_mode = present_mode.to_wgpu(&self.present_modes);
+        self.surface.configure(&self.device, &self.config);
+        log::info!(target: logging::RENDER, "Present mode {} ({:?})", present_mode.name(), self.config.present_mode);
+    }
+
+    /// 디버그 렌더 모드를 켜거나 끔. 켤 때 지금 카메라 범위를 고정함
+    pub fn toggle_debug_render(&mut self) {
+        self.debug_frustum = match self.debug_frustum {
+            Some(_) => None,
+            None => {
+                if self.wireframe_pipeline.is_none() {
+                    log::warn!(target: logging::RENDER, "Wireframe is not supported, drawing filled meshes");
+                }
+                Some(self.scene.view_proj())
+            }
+        };
+        log::info!(target: logging::RENDER, "Debug render {}", if self.debug_frustum.is_some() { "on" } else { "off" });
     }
 
     pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        use winit::keyboard::{KeyCode, PhysicalKey};
+
+        if let WindowEvent::KeyboardInput {
+            event: KeyEvent {
+                state: ElementState::Pressed,
+                physical_key: PhysicalKey::Code(code @ (KeyCode::F1 | KeyCode::F2 | KeyCode::F4 | KeyCode::F5)),
+                repeat: false,
+                ..
+            },
+            ..
+        } = event {
+            match code {
+                KeyCode::F1 => {
+                    self.show_frame_stats = !self.show_frame_stats;
+                    self.frame_stats.clear();
+                }
+                KeyCode::F2 => self.set_msaa(next_msaa(self.render_config.msaa, &self.msaa_samples)),
+                KeyCode::F5 => self.toggle_debug_render(),
+                _ => self.set_present_mode(self.present_mode.next()),
+            }
+            return true;
+        }
+
         self.scene.handle_event(event)
     }
 
     pub fn update(&mut self) {
-        self.scene.update();
+        #[cfg(feature = "gamepad")]
+        if let (Some(gamepad), Some(game)) = (self.gamepad.as_mut(), self.scene.game_mut()) {
+            game.process_gamepad_input(&gamepad.poll());
+        }
+
+        let now = std::time::Instant::now();
+        let dt = now.saturating_duration_since(self.last_update);
+        self.last_update = now;
+        self.scene.update(dt, &self.device, &self.queue);
+        self.update_title();
+        self.reload_changed_resources(now);
+
+        if self.show_frame_stats {
+            self.frame_stats.push(dt);
+            if let Some(summary) = self.frame_stats.report(now) {
+                log::info!(target: logging::RENDER, "{}", summary);
+            }
+        }
 
         self.camera_uniform.update_view_proj(self.scene.view_proj());
+        self.camera_uniform.update_view_position(self.scene.camera_eye());
         self.queue.write_buffer(
             &self.camera_buffer, 
             0, 
             bytemuck::cast_slice(&[self.camera_uniform])
         );
+
+        self.skybox.set_skybox(&self.device, &self.queue, self.scene.skybox());
+        self.skybox.update(&self.queue, self.scene.view_proj(), self.scene.camera_eye());
+        self.particle_renderer.update(&self.device, &self.queue, self.scene.particles(), self.scene.view_proj());
+
+        self.debug_lines.clear();
+        if let Some(frustum) = self.debug_frustum {
+            self.scene.debug_lines(&mut self.debug_lines);
+            self.debug_lines.frustum(frustum, color::Color::MAGENTA);
+        }
+        let debug_lines = self.debug_frustum.map(|_| &self.debug_lines);
+        self.debug_renderer.update(&self.device, &self.queue, debug_lines, self.scene.view_proj());
+
+        // 빛은 거의 바뀌지 않으므로 바뀔 때만 다시 씀
+        let light_uniform = LightUniform::new(&self.scene.light(), self.render_config.shadows);
+        if light_uniform != self.light_uniform {
+            self.light_uniform = light_uniform;
+            self.queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[self.light_uniform]));
+        }
+    }
+
+    /// 바뀐 파일 종류에 따라 주 셰이더, 게임 씬의 모델이나 스카이박스를 다시 만듦
+    fn reload_changed_resources(&mut self, now: std::time::Instant) {
+        let Some(watcher) = self.hot_reload.as_mut() else {
+            return;
+        };
+
+        let mut models = false;
+        for path in watcher.poll(now) {
+            match hot_reload::ResourceKind::of(&path) {
+                Some(hot_reload::ResourceKind::Shader) if path.file_name() == Some("shader.wgsl".as_ref()) => {
+                    self.reload_shader(&path);
+                }
+                Some(hot_reload::ResourceKind::Shader) => {
+                    log::info!(target: logging::RENDER, "{} changed, restart to apply", path.display());
+                }
+                Some(hot_reload::ResourceKind::Model) => models = true,
+                Some(hot_reload::ResourceKind::Texture) => {
+                    models = true;
+                    self.skybox.reload();
+                }
+                None => {}
+            }
+        }
+
+        if let (true, Some(game)) = (models, self.scene.game_mut()) {
+            if let Err(e) = game.reload_models(&self.device, &self.queue) {
+                log::warn!(target: logging::RENDER, "Failed to reload models: {:#}", e);
+            }
+        }
+    }
+
+    /// 컴파일하지 못하면 경고를 남기고 이전 셰이더를 그대로 씀
+    fn reload_shader(&mut self, path: &std::path::Path) {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                log::warn!(target: logging::RENDER, "Failed to read {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let render_pipeline = create_render_pipeline(
+            &self.device, 
+            &self.render_pipeline_layout, 
+            &shader, 
+            self.config.format, 
+            self.render_config.msaa,
+            wgpu::PolygonMode::Fill
+        );
+        let wireframe_pipeline = create_wireframe_pipeline(
+            &self.device, 
+            &self.render_pipeline_layout, 
+            &shader, 
+            self.config.format, 
+            self.render_config.msaa
+        );
+        if let Some(e) = futures::executor::block_on(self.device.pop_error_scope()) {
+            log::warn!(target: logging::RENDER, "Failed to reload {}: {}", path.display(), e);
+            return;
+        }
+
+        self.shader = shader;
+        self.render_pipeline = render_pipeline;
+        self.wireframe_pipeline = wireframe_pipeline;
+        log::info!(target: logging::RENDER, "Reloaded {}", path.display());
+    }
+
+    /// HUD 글꼴은 영문 대문자와 숫자뿐이므로 채팅, 재생 상태와 로비의 방 목록은 창 제목에 표시.
+    /// 서버 왕복 시간과 연결 상태는 게임 씬의 HUD에 있음
+    fn update_title(&mut self) {
+        let title = match self.scene.active() {
+            // 서버가 종료했으면 다시 접속하지 않으므로 그대로 알림
+            ActiveScene::Game(game) if game.connection_state() == ConnectionState::Closed => {
+                format!("{} - server shut down", WINDOW_TITLE)
+            }
+            ActiveScene::Game(game) => {
+                let mut title = match game.replay_status() {
+                    Some(status) => format!("{} - replay {}", WINDOW_TITLE, status),
+                    None => WINDOW_TITLE.to_string(),
+                };
+                // 점수판을 켜면 채팅 대신 보여줌
+                let lines = game.scoreboard_overlay()
+                    .unwrap_or_else(|| game.chat_overlay(std::time::Instant::now()));
+                if !lines.is_empty() {
+                    title.push_str(" - ");
+                    title.push_str(&lines.join(" | "));
+                }
+                title
+            }
+            ActiveScene::Lobby(lobby) => format!("{} - {}", WINDOW_TITLE, lobby.title()),
+            ActiveScene::Title(title) => format!("{} - {}", WINDOW_TITLE, title.title()),
+            ActiveScene::Connecting(connecting) => format!("{} - {}", WINDOW_TITLE, connecting.title()),
+            ActiveScene::Pause(pause) => format!("{} - {}", WINDOW_TITLE, pause.title()),
+            ActiveScene::Loading(_) => WINDOW_TITLE.to_string(),
+            ActiveScene::Error(error) => format!("{} - error: {}", WINDOW_TITLE, error.message()),
+        };
+        if title == self.shown_title {
+            return;
+        }
+
+        self.window.set_title(&title);
+        self.shown_title = title;
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -311,19 +717,43 @@ impl<'a> State<'a> {
             label: Some("Render Encoder"),
         });
 
+        let eye = self.render_config.depth_sort.then(|| self.scene.camera_eye());
+        // 화면 밖이어도 그림자 맵 안에 있으면 화면에 그림자를 드리울 수 있음
+        let frusta = match (self.render_config.frustum_culling, self.render_config.shadows) {
+            (false, _) => vec![],
+            (true, false) => vec![frustum::Frustum::from_view_proj(self.scene.view_proj())],
+            (true, true) => vec![
+                frustum::Frustum::from_view_proj(self.scene.view_proj()),
+                frustum::Frustum::from_view_proj(self.scene.light().view_proj()),
+            ],
+        };
+        let instances = self.scene.world().map(World::instances).unwrap_or_default();
+        for (name, model) in self.scene.models() {
+            let instances = instances.get(name).map_or(&[][..], Vec::as_slice);
+            model.borrow_mut().update_instances(&self.device, &self.queue, instances, eye, &frusta);
+        }
+
         let models: Vec<_> = self.scene.models()
-            .map(|model| model.borrow())
+            .map(|(_, model)| model.borrow())
             .collect();
 
+        if self.render_config.shadows {
+            self.shadow_pass.render(&mut encoder, models.iter().map(|model| &**model));
+        }
+
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
+                // MSAA를 켜면 샘플 텍스처에 그리고 화면 텍스처로 합침. 샘플은 남길 필요가 없음
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    view: self.msaa_texture.as_ref().map_or(&view, |msaa| &msaa.view),
+                    resolve_target: self.msaa_texture.as_ref().map(|_| &view),
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(self.scene.background_color().into()),
-                        store: wgpu::StoreOp::Store,
+                        store: match self.msaa_texture {
+                            Some(_) => wgpu::StoreOp::Discard,
+                            None => wgpu::StoreOp::Store,
+                        },
                     },
                 })],
                 depth_stencil_attachment: Some(
@@ -340,14 +770,37 @@ impl<'a> State<'a> {
                 timestamp_writes: None,
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
+            self.skybox.draw(&mut render_pass);
+
+            let pipeline = match (self.debug_frustum, &self.wireframe_pipeline) {
+                (Some(_), Some(wireframe)) => wireframe,
+                _ => &self.render_pipeline,
+            };
+            render_pass.set_pipeline(pipeline);
             render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(2, &self.light_bind_group, &[]);
             
             
             for model in models.iter() {
-                model.draw(&self.queue, &mut render_pass);
+                model.draw(&mut render_pass);
             }
+            for model in models.iter() {
+                model.draw_transparent(&mut render_pass);
+            }
+            self.particle_renderer.draw(&mut render_pass);
+            self.debug_renderer.draw(&mut render_pass);
         }
+
+        self.hud.clear();
+        self.scene.hud(&mut self.hud);
+        self.hud_renderer.render(
+            &self.device, 
+            &self.queue, 
+            &mut encoder, 
+            &view, 
+            &self.hud, 
+            (self.config.width, self.config.height)
+        );
     
         // submit will accept anything that implements IntoIter
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -355,4 +808,104 @@ impl<'a> State<'a> {
     
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+
+
+/// 주 렌더 패스의 파이프라인. MSAA 샘플 수가 바뀌면 다시 만듦
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    render_pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    polygon_mode: wgpu::PolygonMode,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[
+                ModelVertex::desc(),
+                InstanceRaw::desc(),
+            ],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                // src * a + dst * (1 - a). 불투명(a = 1)이면 그대로 덮어씀
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            // Line requires Features::POLYGON_MODE_LINE
+            polygon_mode,
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default()
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+/// 디버그 렌더 모드의 파이프라인. 장치가 선으로 그리기를 지원하지 않으면 `None`
+fn create_wireframe_pipeline(
+    device: &wgpu::Device,
+    render_pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> Option<wgpu::RenderPipeline> {
+    device.features().contains(wgpu::Features::POLYGON_MODE_LINE).then(|| {
+        create_render_pipeline(device, render_pipeline_layout, shader, format, sample_count, wgpu::PolygonMode::Line)
+    })
+}
+
+/// `config::MSAA_SAMPLES` 중에서 화면 텍스처와 깊이 버퍼 형식이 모두 지원하는 샘플 수
+fn supported_msaa_samples(adapter: &wgpu::Adapter, features: wgpu::Features, format: wgpu::TextureFormat) -> Vec<u32> {
+    MSAA_SAMPLES.into_iter()
+        .filter(|&samples| {
+            samples == 1 || if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+                [format, Texture::DEPTH_FORMAT].into_iter()
+                    .all(|format| adapter.get_texture_format_features(format).flags.sample_count_supported(samples))
+            } else {
+                samples == 4
+            }
+        })
+        .collect()
+}
+
+
+/// GPU가 필요한 테스트용 장치. 사용할 수 있는 어댑터가 없으면 `None`
+#[cfg(test)]
+pub(crate) fn test_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    futures::executor::block_on(async {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await?;
+        adapter.request_device(&wgpu::DeviceDescriptor::default(), None).await.ok()
+    })
+}
@@ -9,9 +9,9 @@ pub trait Vertex {
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ModelVertex {
     pub position: [f32; 3],
-    // pub tex_coords: [f32; 2],
     pub base_color: Color,
     pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
 }
 
 impl Vertex for ModelVertex {
@@ -30,6 +30,16 @@ impl Vertex for ModelVertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::offset_of!(ModelVertex, tex_coords) as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::offset_of!(ModelVertex, normal) as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
             ]
         }
     }
@@ -42,147 +52,870 @@ pub struct Mesh {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub num_elements: u32,
-    // pub material: usize,
+    /// 모델 `materials`의 인덱스
+    pub material: usize,
+    /// 모델 원점에서 가장 먼 정점까지의 거리
+    pub radius: f32,
+}
+
+impl Mesh {
+    pub fn new(
+        device: &wgpu::Device, 
+        name: &str, 
+        vertices: &[ModelVertex], 
+        indices: &[u32], 
+        material: usize
+    ) -> Self {
+        use wgpu::util::DeviceExt;
+        use cgmath::InnerSpace;
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Vertex Buffer", name)),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{:?} Index Buffer", name)),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let radius = vertices.iter()
+            .map(|vertex| cgmath::Vector3::from(vertex.position).magnitude())
+            .fold(0.0, f32::max);
+
+        Self {
+            name: name.to_string(),
+            vertex_buffer,
+            index_buffer,
+            num_elements: indices.len() as u32,
+            material,
+            radius,
+        }
+    }
+}
+
+
+/// 메쉬 표면의 diffuse 텍스처. 셰이더에서 정점 색상에 곱해짐
+pub struct Material {
+    pub name: String,
+    pub diffuse_texture: Texture,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl Material {
+    /// 렌더 파이프라인의 `@group(1)`과 같은 레이아웃
+    pub fn bind_group_layout_descriptor() -> wgpu::BindGroupLayoutDescriptor<'static> {
+        wgpu::BindGroupLayoutDescriptor {
+            label: Some("texture_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        }
+    }
+
+    pub fn new(device: &wgpu::Device, name: &str, diffuse_texture: Texture) -> Self {
+        let layout = device.create_bind_group_layout(&Self::bind_group_layout_descriptor());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(name),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+                },
+            ],
+        });
+
+        Self { name: name.to_string(), diffuse_texture, bind_group }
+    }
+
+    /// 텍스처 없이 정점 색상을 그대로 쓰는 재질 (흰색 1x1 텍스처)
+    pub fn flat(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self::new(device, "flat", Texture::white(device, queue))
+    }
+
+    /// .mtl의 `Kd`와 `d`로 정한 색. `Kd`가 없으면 (0, 0, 0) 흰색
+    pub fn diffuse_color(material: &tobj::Material) -> Color {
+        let [r, g, b] = material.diffuse;
+        if [r, g, b] == [0.0; 3] {
+            return Color::from_rgba(1.0, 1.0, 1.0, material.dissolve);
+        }
+        Color::from_rgba(r, g, b, material.dissolve)
+    }
+
+    /// .mtl의 `map_Kd` 텍스처를 불러옴. 텍스처가 없거나 읽지 못하면 `diffuse_color` 한 색
+    pub async fn load(device: &wgpu::Device, queue: &wgpu::Queue, material: &tobj::Material) -> Self {
+        let solid = || Texture::solid(device, queue, Self::diffuse_color(material));
+        if material.diffuse_texture.is_empty() {
+            return Self::new(device, &material.name, solid());
+        }
+
+        match super::resources::load_texture(&material.diffuse_texture, device, queue, None).await {
+            Ok(texture) => Self::new(device, &material.name, texture),
+            Err(e) => {
+                log::warn!(
+                    target: logging::RENDER, 
+                    "Failed to load texture {:?} for material {:?}: {}", 
+                    material.diffuse_texture, 
+                    material.name, 
+                    e
+                );
+                Self::new(device, &material.name, solid())
+            }
+        }
+    }
+}
+
+
+/// 각 정점의 법선을 그 정점을 쓰는 삼각형 법선의 합 (넓은 면일수록 크게)으로 채움.
+/// 어느 삼각형에도 쓰이지 않거나 넓이가 0인 삼각형에만 쓰인 정점은 위(+y)를 향함
+pub fn fill_normals(vertices: &mut [ModelVertex], indices: &[u32]) {
+    use cgmath::{InnerSpace, Vector3};
+
+    let mut normals = vec![Vector3::new(0.0, 0.0, 0.0); vertices.len()];
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+        if a.max(b).max(c) >= vertices.len() {
+            continue;
+        }
+        let [pa, pb, pc] = [a, b, c].map(|i| Vector3::from(vertices[i].position));
+        let normal = (pb - pa).cross(pc - pa);
+        for i in [a, b, c] {
+            normals[i] += normal;
+        }
+    }
+
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        vertex.normal = if normal.magnitude2().is_normal() {
+            normal.normalize().into()
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+    }
+}
+
+
+/// y = 0 평면에 놓인 고리 모양 (위에서 봤을 때 앞면)
+pub fn ring_geometry(
+    inner_radius: f32, 
+    outer_radius: f32, 
+    segments: u32, 
+    base_color: Color
+) -> (Vec<ModelVertex>, Vec<u32>) {
+    let segments = segments.max(3);
+
+    let vertices = (0..segments)
+        .flat_map(|i| {
+            let theta = i as f32 / segments as f32 * std::f32::consts::TAU;
+            let (sin, cos) = theta.sin_cos();
+
+            [outer_radius, inner_radius].map(|r| ModelVertex {
+                position: [r * cos, 0.0, r * sin],
+                base_color,
+                normal: [0.0, 1.0, 0.0],
+                tex_coords: [0.0, 0.0],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // 정점 순서: (outer_i, inner_i), ...
+    let indices = (0..segments)
+        .flat_map(|i| {
+            let outer = i * 2;
+            let inner = outer + 1;
+            let next_outer = (i + 1) % segments * 2;
+            let next_inner = next_outer + 1;
+
+            [outer, inner, next_outer, inner, next_inner, next_outer]
+        })
+        .collect::<Vec<_>>();
+
+    (vertices, indices)
 }
 
 use super::object::*;
+use super::texture::Texture;
+use super::frustum::Frustum;
+use super::logging;
 
 use std::{
     rc::Rc, 
-    cell::RefCell, 
+    collections::HashMap,
+    ops::Range,
 };
 
-pub struct Model {
+/// 인스턴스 버퍼의 처음 크기(인스턴스 수). 부족하면 두 배씩 늘림
+pub const INITIAL_INSTANCE_CAPACITY: usize = 128;
+
+
+/// 이전에 올린 인스턴스 데이터 `uploaded`와 `current`를 비교해서 바뀐 슬롯 범위를 반환.  
+/// 연속으로 바뀐 슬롯은 한 범위로 묶고, `uploaded`는 `current`로 갱신됨.
+pub fn changed_instance_ranges(uploaded: &mut Vec<InstanceRaw>, current: &[InstanceRaw]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+
+    for (idx, raw) in current.iter().enumerate() {
+        let changed = uploaded.get(idx)
+            .is_none_or(|old| bytemuck::bytes_of(old) != bytemuck::bytes_of(raw));
+        if !changed {
+            continue;
+        }
+
+        match ranges.last_mut() {
+            Some(range) if range.end == idx => range.end += 1,
+            _ => ranges.push(idx..idx + 1),
+        }
+    }
+
+    uploaded.clear();
+    uploaded.extend_from_slice(current);
+    ranges
+}
+
+
+/// 인스턴스 버퍼에 올릴 순서로 정리한 데이터와 그 중 불투명 인스턴스 수.  
+/// `eye`가 있으면 불투명 인스턴스를 원래 순서대로 먼저 두고,
+/// 반투명(`opacity < 1`) 인스턴스는 `eye`에서 먼 순서로 뒤에 둠.
+/// `eye`가 없으면 정렬하지 않고 모두 불투명으로 취급.
+pub fn draw_order<'a>(
+    instances: impl IntoIterator<Item = &'a Instance>,
+    eye: Option<cgmath::Point3<f32>>,
+) -> (Vec<InstanceRaw>, usize) {
+    use cgmath::{EuclideanSpace, MetricSpace};
+
+    let Some(eye) = eye else {
+        let data = instances.into_iter().map(|instance| instance.raw).collect::<Vec<_>>();
+        let count = data.len();
+        return (data, count);
+    };
+
+    let (opaque, mut transparent): (Vec<&Instance>, Vec<&Instance>) = instances.into_iter()
+        .partition(|instance| instance.opacity >= 1.0);
+
+    transparent.sort_by(|a, b| {
+        let a = cgmath::Point3::from_vec(a.position).distance2(eye);
+        let b = cgmath::Point3::from_vec(b.position).distance2(eye);
+        b.total_cmp(&a)
+    });
+
+    let data = opaque.iter()
+        .chain(transparent.iter())
+        .map(|instance| instance.raw)
+        .collect();
+    (data, opaque.len())
+}
+
+
+/// 파일 하나에서 읽은 메쉬와 재질
+pub struct MeshData {
+    pub models: Vec<tobj::Model>,
+    pub materials: Vec<tobj::Material>,
+    /// 재질의 텍스처 파일별로 미리 디코딩한 이미지. 읽지 못한 텍스처는 `Material::load`가 다시 읽음
+    pub textures: HashMap<String, image::DynamicImage>,
+}
+
+
+/// 파일 이름별로 읽어둔 .obj 메쉬 데이터.
+/// 같은 파일을 색이나 크기만 바꿔 여러 번 불러올 때 다시 읽고 파싱하지 않음.
+#[derive(Default)]
+pub struct MeshCache {
+    meshes: HashMap<String, Rc<MeshData>>,
+    /// 파일을 실제로 읽은 횟수
+    misses: usize,
+}
+
+impl MeshCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub async fn get(&mut self, file_name: &str) -> anyhow::Result<Rc<MeshData>> {
+        if let Some(data) = self.meshes.get(file_name) {
+            return Ok(data.clone());
+        }
+
+        let data = parse_mesh(file_name).await?;
+        self.misses += 1;
+        Ok(self.insert(file_name, data))
+    }
+
+    /// 다른 곳(예: 로딩 스레드)에서 읽어둔 메쉬를 추가
+    pub fn insert(&mut self, file_name: &str, data: MeshData) -> Rc<MeshData> {
+        let data = Rc::new(data);
+        self.meshes.insert(file_name.to_string(), data.clone());
+        data
+    }
+}
+
+/// 모델 파일 형식. 확장자로 구분함
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    Obj,
+}
+
+impl ModelFormat {
+    pub fn from_file_name(file_name: &str) -> anyhow::Result<Self> {
+        let extension = std::path::Path::new(file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("obj") => Ok(ModelFormat::Obj),
+            Some(ext) => anyhow::bail!("unsupported model format {:?} for {:?}", ext, file_name),
+            None => anyhow::bail!("model file {:?} has no extension", file_name),
+        }
+    }
+}
+
+/// 확장자에 맞게 모델 파일을 읽어서 파싱. GPU를 쓰지 않으므로 다른 스레드에서 호출해도 됨
+pub async fn parse_mesh(file_name: &str) -> anyhow::Result<MeshData> {
+    match ModelFormat::from_file_name(file_name)? {
+        ModelFormat::Obj => parse_obj(file_name).await,
+    }
+}
+
+/// .obj 파일(과 .mtl)을 읽어서 파싱. .mtl을 읽지 못하면 재질 없이 진행
+async fn parse_obj(file_name: &str) -> anyhow::Result<MeshData> {
+    use std::io::{BufReader, Cursor};
+    use super::resources::*;
+
+    let obj_text = load_string(file_name).await?;
+    let obj_cursor = Cursor::new(obj_text);
+    let mut obj_reader = BufReader::new(obj_cursor);
+
+    let (models, obj_materials) = tobj::load_obj_buf_async(
+        &mut obj_reader,
+        &tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        },
+        |p| async move {
+            match load_string(&p).await {
+                Ok(mat_text) => tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text))),
+                Err(ResourceError::NotFound { .. }) => Err(tobj::LoadError::OpenFileFailed),
+                Err(_) => Err(tobj::LoadError::ReadError),
+            }
+        },
+    )
+    .await?;
+
+    let materials = obj_materials.unwrap_or_else(|e| {
+        log::warn!(target: logging::RENDER, "Failed to load materials for {:?}: {}", file_name, e);
+        Vec::new()
+    });
+
+    // 텍스처 디코딩도 메쉬를 읽는 스레드에서 미리 해둠
+    let mut textures = HashMap::new();
+    for material in materials.iter().filter(|material| !material.diffuse_texture.is_empty()) {
+        let file = &material.diffuse_texture;
+        if textures.contains_key(file) {
+            continue;
+        }
+        let decoded = load_binary(file).await
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(image::load_from_memory(&data)?));
+        match decoded {
+            Ok(image) => {
+                textures.insert(file.clone(), image);
+            }
+            Err(e) => log::debug!(target: logging::RENDER, "Failed to decode texture {:?} for {:?}: {}", file, file_name, e),
+        }
+    }
+
+    Ok(MeshData { models, materials, textures })
+}
+
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(
+        &wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"), 
+            mapped_at_creation: false, 
+            size: (std::mem::size_of::<InstanceRaw>() * capacity) as wgpu::BufferAddress, 
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST, 
+        }
+    )
+}
+
+
+/// GPU에 올린 메쉬와 재질. 같은 파일과 크기, 색으로 만든 모델끼리 공유함 (`AssetCache`)
+pub struct ModelGeometry {
     pub meshes: Vec<Mesh>,
-    // pub materials: Vec<Material>,
-    pub buffer: wgpu::Buffer, 
-    pub instances: Vec<Rc<RefCell<Object>>>,
+    pub materials: Vec<Material>,
 }
 
-impl Model {
+impl ModelGeometry {
+    /// 모델 파일을 크기와 색에 맞게 GPU에 올림. `scale_factor`는 `Model::load`와 같음
     pub async fn load(
         file_name: &str,
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         scale_factor: f32,
         base_color: Color,
-    ) -> anyhow::Result<Model> {
-        use std::{mem, io::{BufReader, Cursor}};
-        use wgpu::util::DeviceExt;
-        use super::transform::*;
-        use super::resources::*;
-
-        let obj_text = load_string(file_name).await?;
-        let obj_cursor = Cursor::new(obj_text);
-        let mut obj_reader = BufReader::new(obj_cursor);
-    
-        let (models, _obj_materials) = tobj::load_obj_buf_async(
-            &mut obj_reader,
-            &tobj::LoadOptions {
-                triangulate: true,
-                single_index: true,
-                ..Default::default()
-            },
-            |p| async move {
-                let mat_text = load_string(&p).await.unwrap();
-                tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text)))
-            },
-        )
-        .await?;
-    
-        let buffer = device.create_buffer(
-            &wgpu::BufferDescriptor {
-                label: Some("Instance Buffer"), 
-                mapped_at_creation: false, 
-                size: (mem::size_of::<TransformRaw>() * 128) as wgpu::BufferAddress, 
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST, 
-            }
-        );
+        cache: &mut MeshCache,
+    ) -> anyhow::Result<Self> {
+        if !(scale_factor.is_finite() && scale_factor > 0.0) {
+            anyhow::bail!("invalid scale factor {} for model {:?}: must be positive and finite", scale_factor, file_name);
+        }
+        let channels = [base_color.r(), base_color.g(), base_color.b(), base_color.a()];
+        if !channels.iter().all(|c| c.is_finite()) {
+            anyhow::bail!("invalid base color {:?} for model {:?}", base_color, file_name);
+        }
+
+        let data = cache.get(file_name).await?;
+
+        // 재질이 없는 메쉬는 마지막의 flat 재질을 사용
+        let mut materials = Vec::with_capacity(data.materials.len() + 1);
+        for material in data.materials.iter() {
+            let material = match data.textures.get(&material.diffuse_texture) {
+                Some(image) => {
+                    let texture = Texture::from_image(device, queue, image, Some(&material.diffuse_texture), wgpu::FilterMode::Linear);
+                    Material::new(device, &material.name, texture)
+                }
+                None => Material::load(device, queue, material).await,
+            };
+            materials.push(material);
+        }
+        materials.push(Material::flat(device, queue));
+        let flat = materials.len() - 1;
 
-        let meshes = models
-            .into_iter()
+        let meshes = data.models
+            .iter()
             .map(|m| {
-                    let vertices = (0..m.mesh.positions.len() / 3)
-                    .map(|i| {
-                        if m.mesh.normals.is_empty() {
-                            ModelVertex {
-                                position: [
-                                    m.mesh.positions[i * 3] * scale_factor,
-                                    m.mesh.positions[i * 3 + 1] * scale_factor,
-                                    m.mesh.positions[i * 3 + 2] * scale_factor,
-                                ],
-                                base_color,
-                                // tex_coords: [m.mesh.texcoords[i * 2], 1.0 - m.mesh.texcoords[i * 2 + 1]],
-                                normal: [0.0, 0.0, 0.0],
-                            }
-                        }
-                        else {
-                            ModelVertex {
-                                position: [
-                                    m.mesh.positions[i * 3] * scale_factor,
-                                    m.mesh.positions[i * 3 + 1] * scale_factor,
-                                    m.mesh.positions[i * 3 + 2] * scale_factor,
-                                ],
-                                // tex_coords: [m.mesh.texcoords[i * 2], 1.0 - m.mesh.texcoords[i * 2 + 1]],
-                                base_color,
-                                normal: [
-                                    m.mesh.normals[i * 3],
-                                    m.mesh.normals[i * 3 + 1],
-                                    m.mesh.normals[i * 3 + 2],
-                                ],
-                            }
-                        }
+                let mut vertices = (0..m.mesh.positions.len() / 3)
+                    .map(|i| ModelVertex {
+                        position: [
+                            m.mesh.positions[i * 3] * scale_factor,
+                            m.mesh.positions[i * 3 + 1] * scale_factor,
+                            m.mesh.positions[i * 3 + 2] * scale_factor,
+                        ],
+                        base_color,
+                        normal: if m.mesh.normals.is_empty() {
+                            [0.0, 0.0, 0.0]
+                        } else {
+                            [m.mesh.normals[i * 3], m.mesh.normals[i * 3 + 1], m.mesh.normals[i * 3 + 2]]
+                        },
+                        tex_coords: tex_coords(&m.mesh, i),
                     })
                     .collect::<Vec<_>>();
-    
-                let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("{:?} Vertex Buffer", file_name)),
-                    contents: bytemuck::cast_slice(&vertices),
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-                let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("{:?} Index Buffer", file_name)),
-                    contents: bytemuck::cast_slice(&m.mesh.indices),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
-    
-                Mesh {
-                    name: file_name.to_string(),
-                    vertex_buffer,
-                    index_buffer,
-                    num_elements: m.mesh.indices.len() as u32,
-                    // material: m.mesh.material_id.unwrap_or(0),
+                // 법선이 없는 파일 (airplane.obj 등)은 면에서 계산
+                if m.mesh.normals.is_empty() {
+                    fill_normals(&mut vertices, &m.mesh.indices);
                 }
+
+                let material = m.mesh.material_id
+                    .filter(|&id| id < flat)
+                    .unwrap_or(flat);
+                Mesh::new(device, file_name, &vertices, &m.mesh.indices, material)
             })
             .collect::<Vec<_>>();
-    
-        Ok(Model { meshes, buffer, instances: Vec::with_capacity(128) })
+
+        Ok(Self { meshes, materials })
     }
+}
 
-    pub fn add_instance(&mut self, object: Rc<RefCell<Object>>) {
-        self.instances.push(object);
+
+/// 공유하는 `geometry`와 이 모델로 그리는 인스턴스의 버퍼
+pub struct Model {
+    pub geometry: Rc<ModelGeometry>,
+    pub buffer: wgpu::Buffer, 
+    /// `buffer`에 들어가는 인스턴스 수
+    capacity: usize,
+    /// 지금까지 인스턴스 버퍼에 쓴 횟수
+    buffer_writes: usize,
+    /// 마지막 `update_instances`에 넘어온 인스턴스 수 (보이지 않는 인스턴스 포함)
+    instance_count: usize,
+    /// 인스턴스 버퍼에 마지막으로 올린 데이터
+    uploaded: Vec<InstanceRaw>,
+    /// 인스턴스 버퍼 앞쪽의 불투명 인스턴스 수
+    opaque_count: usize,
+}
+
+/// .obj는 텍스처 좌표의 v축이 위로 향하므로 뒤집음. 좌표가 없으면 (0, 0)
+fn tex_coords(mesh: &tobj::Mesh, i: usize) -> [f32; 2] {
+    match mesh.texcoords.get(i * 2..i * 2 + 2) {
+        Some(&[u, v]) => [u, 1.0 - v],
+        _ => [0.0, 0.0],
     }
+}
 
-    pub fn remove_instance(&mut self, object: Rc<RefCell<Object>>) {
-        self.instances.retain(|obj| obj.as_ptr() != object.as_ptr());
+
+impl Model {
+    /// `scale_factor`는 0보다 큰 유한한 값이어야 함.
+    /// 0이면 메쉬가 한 점으로 줄어들고, 음수면 면이 뒤집힘.
+    pub async fn load(
+        file_name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scale_factor: f32,
+        base_color: Color,
+    ) -> anyhow::Result<Model> {
+        Self::load_cached(file_name, device, queue, scale_factor, base_color, &mut MeshCache::new()).await
     }
 
-    pub fn draw<'a>(&'a self, queue: &wgpu::Queue, rpass: &mut wgpu::RenderPass<'a>) {
-        let data: Vec<_> = self.instances.iter()
-            .map(|instance| instance.borrow().transform.to_raw())
-            .take(128)
-            .collect();
+    /// `load`와 같지만 이미 `cache`에 있는 파일은 다시 읽지 않음
+    pub async fn load_cached(
+        file_name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scale_factor: f32,
+        base_color: Color,
+        cache: &mut MeshCache,
+    ) -> anyhow::Result<Model> {
+        let geometry = ModelGeometry::load(file_name, device, queue, scale_factor, base_color, cache).await?;
+        Ok(Model::from_geometry(device, Rc::new(geometry)))
+    }
+
+    /// 각 메쉬의 `material`은 `materials` 안의 인덱스여야 함
+    pub fn from_meshes(device: &wgpu::Device, meshes: Vec<Mesh>, materials: Vec<Material>) -> Model {
+        Model::from_geometry(device, Rc::new(ModelGeometry { meshes, materials }))
+    }
+
+    /// 다른 모델과 `geometry`를 공유하고 인스턴스는 따로 가짐
+    pub fn from_geometry(device: &wgpu::Device, geometry: Rc<ModelGeometry>) -> Model {
+        Model { 
+            geometry,
+            buffer: create_instance_buffer(device, INITIAL_INSTANCE_CAPACITY), 
+            capacity: INITIAL_INSTANCE_CAPACITY,
+            buffer_writes: 0,
+            instance_count: 0,
+            uploaded: Vec::with_capacity(INITIAL_INSTANCE_CAPACITY),
+            opaque_count: 0,
+        }
+    }
+
+    /// 선택/플레이어 표시용 고리 모델
+    pub fn ring(
+        device: &wgpu::Device, 
+        queue: &wgpu::Queue, 
+        inner_radius: f32, 
+        outer_radius: f32, 
+        base_color: Color
+    ) -> Model {
+        let (vertices, indices) = ring_geometry(inner_radius, outer_radius, 32, base_color);
+        let mesh = Mesh::new(device, "ring", &vertices, &indices, 0);
+        Model::from_meshes(device, vec![mesh], vec![Material::flat(device, queue)])
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.instance_count
+    }
+
+    pub fn instance_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn buffer_writes(&self) -> usize {
+        self.buffer_writes
+    }
+
+    /// 마지막 `update_instances`에서 보이는 범위 안에 있어서 그리는 인스턴스 수
+    pub fn drawn_count(&self) -> usize {
+        self.uploaded.len()
+    }
+
+    /// 모든 메쉬를 감싸는 구의 반지름 (모델 원점 기준, 인스턴스 크기 배율 전)
+    pub fn bounding_radius(&self) -> f32 {
+        self.geometry.meshes.iter().map(|mesh| mesh.radius).fold(0.0, f32::max)
+    }
+
+    /// `frusta` 중 하나라도 인스턴스의 경계 구 (`bounding_radius`)와 겹치면 보임. 비어있으면 모두 보임
+    fn is_visible(&self, instance: &Instance, frusta: &[Frustum]) -> bool {
+        let radius = self.bounding_radius() * instance.scale.abs();
+        frusta.is_empty() || frusta.iter().any(|frustum| frustum.intersects_sphere(instance.position, radius))
+    }
+
+    /// 이번 프레임의 `instances` (`World::instances`)에서 바뀐 첫 슬롯부터 마지막 슬롯까지를
+    /// 한 번에 인스턴스 버퍼에 씀 (프레임당 최대 한 번).
+    /// 쓴 슬롯 수를 반환. 인스턴스가 버퍼보다 많으면 버퍼를 다시 만들고 전부 씀.  
+    /// `eye`가 있으면 반투명 인스턴스를 정렬함 (`draw_order` 참고).
+    /// `frusta`가 있으면 그 어느 범위에도 들어가지 않는 인스턴스는 버퍼에 올리지 않음 (`is_visible`)
+    pub fn update_instances(
+        &mut self, 
+        device: &wgpu::Device, 
+        queue: &wgpu::Queue, 
+        instances: &[Instance],
+        eye: Option<cgmath::Point3<f32>>,
+        frusta: &[Frustum],
+    ) -> usize {
+        self.instance_count = instances.len();
+        let (data, opaque_count) = draw_order(
+            instances.iter().filter(|instance| self.is_visible(instance, frusta)),
+            eye,
+        );
+        self.opaque_count = opaque_count;
+
+        if data.len() > self.capacity {
+            self.capacity = data.len().next_power_of_two();
+            self.buffer = create_instance_buffer(device, self.capacity);
+            self.uploaded.clear();
+        }
+
+        let ranges = changed_instance_ranges(&mut self.uploaded, &data);
+        let (Some(first), Some(last)) = (ranges.first(), ranges.last()) else {
+            return 0;
+        };
+        let span = first.start..last.end;
 
         queue.write_buffer(
             &self.buffer, 
-            0, 
-            bytemuck::cast_slice(&data)
+            (span.start * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress, 
+            bytemuck::cast_slice(&data[span.clone()])
         );
+        self.buffer_writes += 1;
+
+        span.len()
+    }
+
+    /// 불투명 인스턴스를 그림. `update_instances` 이후에 호출
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        self.draw_range(rpass, 0..self.opaque_count as u32);
+    }
+
+    /// 반투명 인스턴스를 그림. 모든 모델의 `draw` 이후에 호출
+    pub fn draw_transparent<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        let count = self.uploaded.len() as u32;
+        self.draw_range(rpass, self.opaque_count as u32..count);
+    }
+
+    /// 불투명 인스턴스의 깊이만 그림 (그림자 맵). 재질은 사용하지 않음
+    pub fn draw_depth<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if self.opaque_count == 0 {
+            return;
+        }
 
         rpass.set_vertex_buffer(1, self.buffer.slice(..));
-        for mesh in self.meshes.iter() {
+        for mesh in self.geometry.meshes.iter() {
             rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
             rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-            rpass.draw_indexed(0..mesh.num_elements, 0, 0..self.instances.len() as u32);
+            rpass.draw_indexed(0..mesh.num_elements, 0, 0..self.opaque_count as u32);
+        }
+    }
+
+    fn draw_range<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
+        if instances.is_empty() {
+            return;
+        }
+
+        rpass.set_vertex_buffer(1, self.buffer.slice(..));
+        for mesh in self.geometry.meshes.iter() {
+            rpass.set_bind_group(1, &self.geometry.materials[mesh.material].bind_group, &[]);
+            rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            rpass.draw_indexed(0..mesh.num_elements, 0, instances.clone());
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_device;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_ring_geometry() {
+        let (vertices, indices) = ring_geometry(0.3, 0.4, 8, Color::YELLOW);
+        assert_eq!(vertices.len(), 16);
+        assert_eq!(indices.len(), 8 * 6);
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+
+        // 모든 삼각형이 위(+y)를 향함
+        for tri in indices.chunks(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]]
+                .map(|i| cgmath::Vector3::from(vertices[i as usize].position));
+            assert!((b - a).cross(c - a).y > 0.0);
         }
     }
+
+    #[test]
+    fn test_fill_normals() {
+        // 바닥에 누운 사각형 (위에서 보면 반시계)과 쓰이지 않는 정점
+        let mut vertices = [[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 0.0, 0.0], [5.0, 5.0, 5.0]]
+            .map(|position| ModelVertex { position, base_color: Color::WHITE, normal: [0.0; 3], tex_coords: [0.0; 2] });
+        fill_normals(&mut vertices, &[0, 1, 2, 0, 2, 3, 0, 1, 9]);
+        assert!(vertices.iter().all(|vertex| vertex.normal == [0.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_load_invalid_scale() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        for scale in [0.0, -1.0, f32::NAN] {
+            let err = block_on(Model::load("cube.obj", &device, &queue, scale, Color::WHITE)).err().unwrap();
+            assert!(err.to_string().contains("invalid scale factor"));
+        }
+    }
+
+    #[test]
+    fn test_mesh_cache() {
+        let mut cache = MeshCache::new();
+        let first = block_on(cache.get("cube.obj")).unwrap();
+        let second = block_on(cache.get("cube.obj")).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(cache.misses(), 1);
+
+        block_on(cache.get("pawn.obj")).unwrap();
+        block_on(cache.get("pawn.obj")).unwrap();
+        assert_eq!(cache.misses(), 2);
+
+        // 읽지 못한 파일은 저장하지 않음
+        assert!(block_on(cache.get("missing.obj")).is_err());
+        assert_eq!(cache.misses(), 2);
+    }
+
+    #[test]
+    fn test_load_material_texture() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+
+        // cube.mtl의 map_Kd
+        let cube = block_on(Model::load("cube.obj", &device, &queue, 0.5, Color::WHITE)).unwrap();
+        let material = &cube.geometry.materials[cube.geometry.meshes[0].material];
+        assert_eq!(material.name, "Material.001");
+        assert!(material.diffuse_texture.texture.width() > 1);
+
+        // 재질이 없는 모델은 흰색 1x1 텍스처
+        let pawn = block_on(Model::load("pawn.obj", &device, &queue, 0.5, Color::WHITE)).unwrap();
+        assert_eq!(pawn.geometry.materials[pawn.geometry.meshes[0].material].diffuse_texture.texture.width(), 1);
+
+        let missing = tobj::Material { 
+            name: "missing".to_string(), 
+            diffuse_texture: "missing.png".to_string(), 
+            ..Default::default() 
+        };
+        let material = block_on(Material::load(&device, &queue, &missing));
+        assert_eq!(material.diffuse_texture.texture.width(), 1);
+    }
+
+    #[test]
+    fn test_material_diffuse_color() {
+        let red = tobj::Material { diffuse: [1.0, 0.0, 0.0], dissolve: 0.5, ..Default::default() };
+        assert_eq!(Material::diffuse_color(&red), Color::from_rgba(1.0, 0.0, 0.0, 0.5));
+        assert_eq!(Material::diffuse_color(&tobj::Material::default()), Color::WHITE);
+    }
+
+    #[test]
+    fn test_model_format() {
+        assert_eq!(ModelFormat::from_file_name("pawn.obj").unwrap(), ModelFormat::Obj);
+        assert_eq!(ModelFormat::from_file_name("PAWN.OBJ").unwrap(), ModelFormat::Obj);
+
+        let err = block_on(MeshCache::new().get("pawn.glb")).err().unwrap();
+        assert!(err.to_string().contains("unsupported model format"));
+        assert!(ModelFormat::from_file_name("pawn").is_err());
+    }
+
+    fn instance(x: f32, alpha: f32) -> Instance {
+        let mut transform = super::super::transform::Transform::default();
+        transform.position.x = x;
+        let renderable = Renderable { alpha, ..Renderable::new("cube") };
+        Instance::new(&transform, &renderable, None)
+    }
+
+    #[test]
+    fn test_changed_instance_ranges() {
+        let mut uploaded = Vec::new();
+
+        let raw = [0.0; 5].map(|x| instance(x, 1.0).raw);
+        assert_eq!(changed_instance_ranges(&mut uploaded, &raw), vec![Range { start: 0, end: 5 }]);
+
+        // 움직이지 않은 프레임은 갱신 없음
+        assert!(changed_instance_ranges(&mut uploaded, &raw).is_empty());
+
+        let raw = [(0.0, 1.0), (1.0, 1.0), (0.0, 0.5), (0.0, 1.0), (2.0, 1.0)].map(|(x, alpha)| instance(x, alpha).raw);
+        assert_eq!(changed_instance_ranges(&mut uploaded, &raw), [1..3, 4..5]);
+    }
+
+    #[test]
+    fn test_draw_order() {
+        let instances = [(0.0, 1.0), (1.0, 0.5), (5.0, 0.5), (2.0, 1.0), (3.0, 0.5)]
+            .map(|(x, alpha)| instance(x, alpha));
+        let expected = [0, 3, 2, 4, 1].map(|i| instances[i].raw);
+
+        let (data, opaque) = draw_order(&instances, Some(cgmath::Point3::new(0.0, 0.0, 0.0)));
+        assert_eq!(opaque, 2);
+        assert_eq!(bytemuck::cast_slice::<_, u8>(&data), bytemuck::cast_slice::<_, u8>(&expected));
+
+        // 정렬하지 않으면 원래 순서
+        let (data, opaque) = draw_order(&instances, None);
+        assert_eq!(opaque, 5);
+        assert_eq!(bytemuck::bytes_of(&data[1]), bytemuck::bytes_of(&instances[1].raw));
+    }
+
+    #[test]
+    fn test_one_write_per_update() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut model = block_on(Model::load("cube.obj", &device, &queue, 0.5, Color::WHITE)).unwrap();
+        assert_eq!(model.instance_count(), 0);
+        let instances = vec![instance(0.0, 1.0); 64];
+
+        assert_eq!(model.update_instances(&device, &queue, &instances, None, &[]), 64);
+        assert_eq!(model.buffer_writes(), 1);
+        assert_eq!(model.update_instances(&device, &queue, &instances, None, &[]), 0);
+        assert_eq!(model.buffer_writes(), 1);
+
+        // 모두 움직이고 일부가 사라지고 새로 생겨도 한 번만 씀
+        let instances = (0..INITIAL_INSTANCE_CAPACITY + 20)
+            .map(|i| instance(i as f32, 1.0))
+            .collect::<Vec<_>>();
+        model.update_instances(&device, &queue, &instances, None, &[]);
+        assert_eq!(model.buffer_writes(), 2);
+
+        // 버퍼보다 많으면 늘림
+        assert!(model.instance_count() > INITIAL_INSTANCE_CAPACITY);
+        assert_eq!(model.instance_capacity(), INITIAL_INSTANCE_CAPACITY * 2);
+        assert_eq!(model.update_instances(&device, &queue, &instances, None, &[]), 0);
+    }
+
+    #[test]
+    fn test_frustum_culling() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut model = block_on(Model::load("cube.obj", &device, &queue, 0.5, Color::WHITE)).unwrap();
+        assert!(model.bounding_radius() > 0.5);
+
+        let instances = [0.0, 0.9, 3.0].map(|x| instance(x, 1.0));
+
+        // x가 -0.5..=0.5인 범위. 경계에 걸친 인스턴스는 그림
+        let view_proj = cgmath::ortho(-0.5, 0.5, -10.0, 10.0, -10.0, 10.0);
+        let frustum = Frustum::from_view_proj(view_proj);
+        model.update_instances(&device, &queue, &instances, None, &[frustum]);
+        assert_eq!(model.drawn_count(), 2);
+        assert_eq!(model.instance_count(), 3);
+
+        model.update_instances(&device, &queue, &instances, None, &[]);
+        assert_eq!(model.drawn_count(), 3);
+    }
 }
@@ -0,0 +1,115 @@
+use cgmath::Vector2;
+
+
+/// 글자 하나의 픽셀 수
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+/// 글자 사이 간격 (픽셀)
+const GLYPH_SPACING: usize = 1;
+
+
+/// 3x5 글꼴. 한 줄에 3비트(왼쪽이 높은 비트), 위 줄부터.
+/// 서버가 허용하는 이름 글자(영문, 숫자, `_`, `-`)와 HUD에 쓰는 몇 가지 기호만 있고 소문자는 대문자로 그림. 나머지는 `?`
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// 한 줄로 쓴 `text`의 가로 픽셀 수
+pub fn text_width(text: &str) -> usize {
+    (text.chars().count() * (GLYPH_WIDTH + GLYPH_SPACING)).saturating_sub(GLYPH_SPACING)
+}
+
+/// `text`를 그리는 픽셀들의 (열, 행). 왼쪽 위가 (0, 0)
+pub fn glyph_pixels(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    text.chars()
+        .enumerate()
+        .flat_map(|(idx, c)| {
+            let left = idx * (GLYPH_WIDTH + GLYPH_SPACING);
+            glyph(c).into_iter()
+                .enumerate()
+                .flat_map(move |(row, bits)| {
+                    (0..GLYPH_WIDTH)
+                        .filter(move |col| bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0)
+                        .map(move |col| (left + col, row))
+                })
+        })
+}
+
+/// `text`를 그리는 픽셀들의 중심 (픽셀 단위).
+/// 가로는 가운데가 0, 세로는 글자 아래가 0이고 위로 +
+pub fn layout(text: &str) -> Vec<Vector2<f32>> {
+    let width = text_width(text) as f32;
+
+    glyph_pixels(text)
+        .map(|(x, row)| Vector2::new(x as f32 + 0.5 - width / 2.0, (GLYPH_HEIGHT - 1 - row) as f32 + 0.5))
+        .collect()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        // `-`는 가운데 줄의 3픽셀
+        assert_eq!(layout("-"), [Vector2::new(-1.0, 2.5), Vector2::new(0.0, 2.5), Vector2::new(1.0, 2.5)]);
+
+        // 두 글자는 가운데 간격을 기준으로 좌우 대칭
+        let pixels = layout("_1");
+        assert_eq!(pixels.len(), 3 + 8);
+        assert_eq!(pixels[0], Vector2::new(-3.0, 0.5));
+        assert!(pixels.iter().all(|p| p.x.abs() <= 3.0 && p.y > 0.0 && p.y < GLYPH_HEIGHT as f32));
+
+        assert_eq!(layout("a"), layout("A"));
+        assert!(layout("").is_empty());
+        assert!(layout(" ").is_empty());
+        assert_eq!(text_width("_1"), 7);
+        assert_eq!(glyph_pixels(".").collect::<Vec<_>>(), [(1, 4)]);
+    }
+}
@@ -0,0 +1,327 @@
+//! 개발용 네트워크 상태 흉내.
+//! 서버와의 연결을 감싸서 메세지마다 지연, 흔들림, 손실을 주므로 나쁜 네트워크 없이도
+//! 예측, 보간, 재접속을 확인할 수 있음.
+
+use std::{
+    collections::{hash_map::RandomState, VecDeque},
+    hash::{BuildHasher, Hasher},
+    io,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use super::{
+    logging,
+    transport::Transport,
+};
+
+
+/// 한 방향의 지연 (ms)
+pub const SIM_LATENCY_ENV: &str = "GAMESERVER_SIM_LATENCY";
+/// 지연에 더하거나 빼는 최대 흔들림 (ms)
+pub const SIM_JITTER_ENV: &str = "GAMESERVER_SIM_JITTER";
+/// 메세지를 버리는 확률 (%)
+pub const SIM_LOSS_ENV: &str = "GAMESERVER_SIM_LOSS";
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkConditions {
+    pub latency: Duration,
+    pub jitter: Duration,
+    /// 0 ~ 1
+    pub loss: f32,
+}
+
+impl NetworkConditions {
+    /// 환경변수가 하나도 없거나 모두 0이면 `None`. 잘못된 값은 0
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok()
+            .and_then(|value| value.trim().parse::<f32>().ok())
+            .filter(|value| value.is_finite() && *value > 0.0)
+            .unwrap_or(0.0);
+
+        let conditions = Self {
+            latency: Duration::from_micros((var(SIM_LATENCY_ENV) * 1000.0) as u64),
+            jitter: Duration::from_micros((var(SIM_JITTER_ENV) * 1000.0) as u64),
+            loss: (var(SIM_LOSS_ENV) / 100.0).min(1.0),
+        };
+        conditions.is_active().then_some(conditions)
+    }
+
+    fn is_active(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero() || self.loss > 0.0
+    }
+}
+
+impl std::fmt::Display for NetworkConditions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "latency {}ms, jitter {}ms, loss {}%",
+            self.latency.as_millis(),
+            self.jitter.as_millis(),
+            self.loss * 100.0
+        )
+    }
+}
+
+
+/// 한 방향으로 지나가는 메세지들. 한 줄이 한 메세지이며 줄 단위로 늦추거나 버림
+struct Lane {
+    /// 아직 줄바꿈이 오지 않은 메세지
+    partial: Vec<u8>,
+    /// 보낼 시각과 메세지. 시각은 늘어나기만 하므로 순서가 바뀌지 않음 (TCP와 같음)
+    queued: VecDeque<(Instant, Vec<u8>)>,
+}
+
+impl Lane {
+    fn new() -> Self {
+        Self { partial: Vec::new(), queued: VecDeque::new() }
+    }
+
+    fn clear(&mut self) {
+        self.partial.clear();
+        self.queued.clear();
+    }
+
+    /// 시각이 지난 첫 메세지
+    fn due(&mut self, now: Instant) -> Option<&mut Vec<u8>> {
+        self.queued.front_mut().filter(|(at, _)| *at <= now).map(|(_, data)| data)
+    }
+}
+
+
+/// `inner`로 주고받는 메세지를 `conditions`에 따라 늦추거나 버리는 연결.
+/// `write`는 항상 모두 받아두고, 보낼 시각이 된 메세지는 `read`나 `write`를 호출할 때 보냄
+pub struct SimulatedTransport {
+    inner: Box<dyn Transport>,
+    conditions: NetworkConditions,
+    /// xorshift 상태
+    rng: u64,
+    incoming: Lane,
+    outgoing: Lane,
+    /// `inner`가 `Ok(0)`을 반환함. 남은 메세지를 다 읽으면 `read`도 `Ok(0)`
+    closed_by_peer: bool,
+}
+
+impl SimulatedTransport {
+    pub fn new(inner: Box<dyn Transport>, conditions: NetworkConditions) -> Self {
+        Self {
+            inner,
+            conditions,
+            // xorshift는 0에서 벗어나지 못함
+            rng: RandomState::new().build_hasher().finish() | 1,
+            incoming: Lane::new(),
+            outgoing: Lane::new(),
+            closed_by_peer: false,
+        }
+    }
+
+    /// 0 ~ 1
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// 완성된 메세지마다 손실이면 버리고, 아니면 지연을 정해 `queued`에 추가
+    fn schedule(&mut self, outgoing: bool, data: &[u8], now: Instant) {
+        for &byte in data {
+            let lane = if outgoing { &mut self.outgoing } else { &mut self.incoming };
+            lane.partial.push(byte);
+            if byte != b'\n' {
+                continue;
+            }
+
+            let message = std::mem::take(&mut lane.partial);
+            if self.random() < self.conditions.loss {
+                log::debug!(target: logging::NET, "Simulated loss of {:?}", String::from_utf8_lossy(&message));
+                continue;
+            }
+            // latency ± jitter
+            let offset = self.random() * 2.0 - 1.0;
+            let jitter = self.conditions.jitter.mul_f32(offset.abs());
+            let delay = match offset < 0.0 {
+                true => self.conditions.latency.saturating_sub(jitter),
+                false => self.conditions.latency + jitter,
+            };
+
+            let lane = if outgoing { &mut self.outgoing } else { &mut self.incoming };
+            let at = lane.queued.back().map_or(now + delay, |(last, _)| (*last).max(now + delay));
+            lane.queued.push_back((at, message));
+        }
+    }
+
+    /// 보낼 시각이 된 메세지를 `inner`로 보냄. 일부만 보냈으면 나머지는 다음에 이어서 보냄
+    fn send_due(&mut self, now: Instant) -> io::Result<()> {
+        while let Some(data) = self.outgoing.due(now) {
+            match self.inner.write(data) {
+                Ok(0) => return Ok(()),
+                Ok(n) if n < data.len() => {
+                    data.drain(..n);
+                    return Ok(());
+                }
+                Ok(_) => {
+                    self.outgoing.queued.pop_front();
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// `inner`에서 읽을 수 있는 만큼 읽어 `incoming`에 넣음
+    fn receive(&mut self, now: Instant) -> io::Result<()> {
+        let mut buf = [0; 1024];
+        while !self.closed_by_peer {
+            match self.inner.read(&mut buf) {
+                Ok(0) => self.closed_by_peer = true,
+                Ok(n) => self.schedule(false, &buf[..n], now),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn read_at(&mut self, buf: &mut [u8], now: Instant) -> io::Result<usize> {
+        self.send_due(now)?;
+        self.receive(now)?;
+
+        let Some(data) = self.incoming.due(now) else {
+            return match self.closed_by_peer && self.incoming.queued.is_empty() {
+                true => Ok(0),
+                false => Err(io::ErrorKind::WouldBlock.into()),
+            };
+        };
+        let n = buf.len().min(data.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        data.drain(..n);
+        if data.is_empty() {
+            self.incoming.queued.pop_front();
+        }
+        Ok(n)
+    }
+
+    fn write_at(&mut self, data: &[u8], now: Instant) -> io::Result<usize> {
+        if !self.inner.is_connected() {
+            return Err(io::ErrorKind::NotConnected.into());
+        }
+
+        self.schedule(true, data, now);
+        self.send_due(now)?;
+        Ok(data.len())
+    }
+}
+
+impl Transport for SimulatedTransport {
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_at(buf, Instant::now())
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.write_at(data, Instant::now())
+    }
+
+    /// 지나가던 메세지는 버림
+    fn reconnect(&mut self, addr: SocketAddr, timeout: Duration) -> io::Result<()> {
+        self.incoming.clear();
+        self.outgoing.clear();
+        self.closed_by_peer = false;
+        self.inner.reconnect(addr, timeout)
+    }
+
+    fn is_connecting(&self) -> bool {
+        self.inner.is_connecting()
+    }
+
+    /// 늦추던 메세지도 바로 보내고 닫음 (`disconnect`가 전달되도록)
+    fn close(&mut self) -> io::Result<()> {
+        for (_, data) in self.outgoing.queued.drain(..) {
+            let mut data = &data[..];
+            while !data.is_empty() {
+                match self.inner.write(data) {
+                    Ok(0) => break,
+                    Ok(n) => data = &data[n..],
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        self.incoming.clear();
+        self.outgoing.clear();
+        self.inner.close()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::transport::MockTransport;
+
+    fn simulated(conditions: NetworkConditions) -> (SimulatedTransport, MockTransport) {
+        let mock = MockTransport::connected();
+        (SimulatedTransport::new(Box::new(mock.clone()), conditions), mock)
+    }
+
+    #[test]
+    fn test_latency() {
+        let latency = Duration::from_millis(100);
+        let (mut transport, mock) = simulated(NetworkConditions { latency, jitter: Duration::ZERO, loss: 0.0 });
+        let now = Instant::now();
+        let mut buf = [0; 64];
+
+        assert_eq!(transport.write_at(b"ping 1\nmo", now).unwrap(), 9);
+        assert_eq!(mock.take_outgoing(), "");
+        transport.write_at(b"ve 1 0 1\n", now + latency / 2).unwrap();
+        assert!(transport.read_at(&mut buf, now + latency).is_err());
+        assert_eq!(mock.take_outgoing(), "ping 1\n");
+        assert!(transport.read_at(&mut buf, now + latency * 2).is_err());
+        assert_eq!(mock.take_outgoing(), "move 1 0 1\n");
+
+        mock.push_incoming(b"GAMESERVER pong 1\n");
+        assert_eq!(transport.read_at(&mut buf, now).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        let n = transport.read_at(&mut buf, now + latency).unwrap();
+        assert_eq!(&buf[..n], b"GAMESERVER pong 1\n");
+
+        // 늦추던 메세지도 닫기 전에 보냄
+        transport.write_at(b"disconnect 1\n", now).unwrap();
+        transport.close().unwrap();
+        assert_eq!(mock.take_outgoing(), "disconnect 1\n");
+        assert!(!transport.is_connected());
+    }
+
+    #[test]
+    fn test_jitter_keeps_order() {
+        let conditions = NetworkConditions { latency: Duration::from_millis(50), jitter: Duration::from_millis(50), loss: 0.0 };
+        let (mut transport, mock) = simulated(conditions);
+        let now = Instant::now();
+
+        for seq in 0..100 {
+            transport.write_at(format!("ping {}\n", seq).as_bytes(), now).unwrap();
+        }
+        transport.read_at(&mut [0; 8], now + Duration::from_millis(100)).unwrap_err();
+        let expected = (0..100).map(|seq| format!("ping {}\n", seq)).collect::<String>();
+        assert_eq!(mock.take_outgoing(), expected);
+    }
+
+    #[test]
+    fn test_loss() {
+        let (mut transport, mock) = simulated(NetworkConditions { latency: Duration::ZERO, jitter: Duration::ZERO, loss: 1.0 });
+        transport.write_at(b"ping 1\n", Instant::now()).unwrap();
+        assert_eq!(mock.take_outgoing(), "");
+
+        // 서버가 닫으면 남은 메세지가 없으므로 바로 `Ok(0)`
+        mock.push_incoming(b"GAMESERVER pong 1\n");
+        mock.close_by_peer();
+        assert_eq!(transport.read_at(&mut [0; 64], Instant::now()).unwrap(), 0);
+    }
+}
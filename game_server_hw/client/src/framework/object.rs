@@ -1,26 +1,128 @@
-use super::model::*;
 use super::transform::*;
+use super::color::Color;
+use super::animation::Animator;
 
-use std::{
-    rc::Weak, 
-    cell::RefCell,
-};
+/// 모델 인스턴스로 그리는 엔티티 (`World::renderables`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Renderable {
+    /// 그리는 모델의 이름 (`AssetCache`)
+    pub model: &'static str,
+    /// 모델의 기본 색상 대신 사용할 색상. 색상의 alpha는 `alpha`에 곱해짐
+    pub color: Option<Color>,
+    /// 불투명도 (1.0이면 불투명)
+    pub alpha: f32,
+}
+
+/// 인스턴스 버퍼에 들어가는 오브젝트별 데이터.  
+/// `color`의 alpha는 모델 색상 대신 `color`를 사용할 비율 (0이면 모델 색상 그대로)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    transform: TransformRaw,
+    color: [f32; 4],
+    alpha: f32,
+}
+
+/// 렌더러가 한 프레임에 그리는 인스턴스 하나. 컬링과 정렬에 쓰는 값과 버퍼에 올릴 데이터
+#[derive(Debug, Clone, Copy)]
+pub struct Instance {
+    pub position: cgmath::Vector3<f32>,
+    /// 경계 구 반지름에 곱하는 크기
+    pub scale: f32,
+    pub opacity: f32,
+    pub raw: InstanceRaw,
+}
+
+
+impl Renderable {
+    pub fn new(model: &'static str) -> Self {
+        Self { model, color: None, alpha: 1.0 }
+    }
+
+    /// 실제로 그려지는 불투명도. 1.0 미만이면 반투명으로 그림
+    pub fn opacity(&self) -> f32 {
+        self.alpha * self.color.map_or(1.0, |color| color.a())
+    }
+}
 
-pub struct Object {
-    pub model: Weak<RefCell<Model>>, 
-    pub transform: Transform,
+impl Instance {
+    /// 애니메이션 자세는 `transform` 안쪽에 곱함
+    pub fn new(transform: &Transform, renderable: &Renderable, animator: Option<&Animator>) -> Self {
+        let raw = InstanceRaw {
+            transform: match animator {
+                Some(animator) => TransformRaw::from(transform.matrix() * animator.pose()),
+                None => transform.to_raw(),
+            },
+            color: match renderable.color {
+                Some(color) => [color.r(), color.g(), color.b(), 1.0],
+                None => [0.0; 4],
+            },
+            alpha: renderable.opacity(),
+        };
+        Self { position: transform.position, scale: transform.scale, opacity: renderable.opacity(), raw }
+    }
 }
 
+impl InstanceRaw {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        use std::mem;
 
-impl Object {
-    pub fn new() -> Self {
-        Self {
-            model: Weak::new(), 
-            transform: Transform::default(),
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<TransformRaw>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<TransformRaw>() + mem::size_of::<[f32; 4]>()) as wgpu::BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
         }
     }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opacity() {
+        let mut renderable = Renderable::new("cube");
+        assert_eq!(renderable.opacity(), 1.0);
+
+        renderable.color = Some(Color::RED);
+        assert_eq!(renderable.opacity(), 1.0);
 
-    pub fn set_model(&mut self, model: Weak<RefCell<Model>>) {
-        self.model = model;
+        renderable.color = Some(Color::from_rgba(1.0, 0.0, 0.0, 0.5));
+        renderable.alpha = 0.5;
+        assert_eq!(renderable.opacity(), 0.25);
+        assert_eq!(Instance::new(&Transform::default(), &renderable, None).opacity, 0.25);
     }
 }
@@ -0,0 +1,508 @@
+//! 파티클 효과.
+//! 씬이 `ParticleSystem::emit`으로 효과를 만들고 매 프레임 `update`로 움직이면,
+//! `ParticleRenderer`가 주 렌더 패스에서 반투명 모델 뒤에 카메라를 향하는 사각형으로 한 번에 그림.
+
+use std::time::Duration;
+use cgmath::{InnerSpace, Matrix, Matrix4, SquareMatrix, Vector3};
+use wgpu::util::DeviceExt;
+
+use super::{
+    color::Color,
+    model::Vertex,
+    texture::Texture,
+};
+
+
+/// 한 번에 살아 있을 수 있는 파티클 수. 넘으면 새 파티클을 만들지 않음
+pub const MAX_PARTICLES: usize = 2048;
+/// 아래로 당기는 가속도 (초당 월드 단위)
+const GRAVITY: f32 = 9.8;
+/// 인스턴스 버퍼의 처음 크기 (파티클 수). 부족하면 두 배씩 늘림
+const INITIAL_CAPACITY: usize = 256;
+
+
+/// 씬에서 만드는 효과의 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// 오브젝트가 떠난 칸에서 낮게 퍼지는 먼지
+    MoveDust,
+    /// 잡힌 오브젝트에서 사방으로 튀는 불꽃
+    Capture,
+    /// 주운 아이템에서 위로 솟는 반짝임
+    Pickup,
+}
+
+/// 효과 하나가 만드는 파티클의 범위
+struct EffectParams {
+    count: usize,
+    /// 수평 속도의 최대 크기
+    spread: f32,
+    /// 위쪽 속도의 범위
+    rise: (f32, f32),
+    lifetime: (f32, f32),
+    size: f32,
+    /// 중력을 받는 비율
+    gravity: f32,
+    color: Color,
+}
+
+impl Effect {
+    fn params(self) -> EffectParams {
+        match self {
+            Effect::MoveDust => EffectParams {
+                count: 6,
+                spread: 0.8,
+                rise: (0.2, 0.6),
+                lifetime: (0.3, 0.5),
+                size: 0.12,
+                gravity: 0.3,
+                color: Color::from_rgba(0.7, 0.65, 0.55, 0.6),
+            },
+            Effect::Capture => EffectParams {
+                count: 32,
+                spread: 3.0,
+                rise: (1.0, 4.0),
+                lifetime: (0.5, 0.9),
+                size: 0.1,
+                gravity: 1.0,
+                color: Color::from_rgba(1.0, 0.55, 0.15, 1.0),
+            },
+            Effect::Pickup => EffectParams {
+                count: 16,
+                spread: 0.6,
+                rise: (1.5, 2.5),
+                lifetime: (0.6, 1.0),
+                size: 0.08,
+                gravity: -0.1,
+                color: Color::from_rgba(1.0, 0.9, 0.3, 1.0),
+            },
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Particle {
+    position: Vector3<f32>,
+    velocity: Vector3<f32>,
+    color: Color,
+    size: f32,
+    gravity: f32,
+    /// 초 단위
+    age: f32,
+    lifetime: f32,
+}
+
+
+/// 살아 있는 파티클. CPU에서 움직이고 매 프레임 `instances`로 그림
+#[derive(Debug)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    /// 퍼지는 방향과 수명에 쓰는 xorshift 상태
+    rng: u32,
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self { particles: Vec::new(), rng: 0x9e37_79b9 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// `position`에서 `effect`를 시작. `color`가 있으면 효과의 기본 색 대신 사용 (alpha는 효과의 값)
+    pub fn emit(&mut self, effect: Effect, position: Vector3<f32>, color: Option<Color>) {
+        let params = effect.params();
+        let color = match color {
+            Some(color) => Color::from_rgba(color.r(), color.g(), color.b(), params.color.a()),
+            None => params.color,
+        };
+
+        let count = params.count.min(MAX_PARTICLES.saturating_sub(self.particles.len()));
+        for _ in 0..count {
+            let angle = self.random() * std::f32::consts::TAU;
+            let speed = self.random() * params.spread;
+            let rise = params.rise.0 + self.random() * (params.rise.1 - params.rise.0);
+            let lifetime = params.lifetime.0 + self.random() * (params.lifetime.1 - params.lifetime.0);
+            self.particles.push(Particle {
+                position,
+                velocity: Vector3::new(angle.cos() * speed, rise, angle.sin() * speed),
+                color,
+                size: params.size,
+                gravity: params.gravity,
+                age: 0.0,
+                lifetime,
+            });
+        }
+    }
+
+    /// 움직이고 수명이 다한 파티클을 지움
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        self.particles.retain_mut(|particle| {
+            particle.age += dt;
+            particle.velocity.y -= GRAVITY * particle.gravity * dt;
+            particle.position += particle.velocity * dt;
+            particle.age < particle.lifetime
+        });
+    }
+
+    /// 그릴 파티클. 수명이 다할수록 투명해지고 작아짐
+    pub fn instances(&self) -> Vec<ParticleInstance> {
+        self.particles.iter()
+            .map(|particle| {
+                let remaining = 1.0 - (particle.age / particle.lifetime).clamp(0.0, 1.0);
+                let color = particle.color;
+                ParticleInstance {
+                    position: particle.position.into(),
+                    size: particle.size * (0.5 + 0.5 * remaining),
+                    color: [color.r(), color.g(), color.b(), color.a() * remaining],
+                }
+            })
+            .collect()
+    }
+
+    /// `0.0..1.0`
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        (self.rng >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ParticleInstance {
+    /// 중심의 월드 좌표
+    pub position: [f32; 3],
+    /// 한 변의 길이 (월드 단위)
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl Vertex for ParticleInstance {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ParticleInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::offset_of!(ParticleInstance, color) as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ]
+        }
+    }
+}
+
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticleUniform {
+    view_proj: [[f32; 4]; 4],
+    /// 화면의 오른쪽과 위쪽 (월드 방향). 사각형이 항상 카메라를 향하게 함
+    right: [f32; 4],
+    up: [f32; 4],
+}
+
+impl ParticleUniform {
+    /// 투영 행렬은 x, y를 섞지 않으므로 `view_proj`의 첫 두 행이 화면의 오른쪽과 위쪽
+    fn new(view_proj: Matrix4<f32>) -> Self {
+        let axis = |row: usize, fallback: Vector3<f32>| {
+            let v = view_proj.row(row).truncate();
+            let v = if v.magnitude2().is_normal() { v.normalize() } else { fallback };
+            [v.x, v.y, v.z, 0.0]
+        };
+
+        Self {
+            view_proj: view_proj.into(),
+            right: axis(0, Vector3::unit_x()),
+            up: axis(1, Vector3::unit_y()),
+        }
+    }
+}
+
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(
+        &wgpu::BufferDescriptor {
+            label: Some("Particle Instance Buffer"),
+            mapped_at_creation: false,
+            size: (std::mem::size_of::<ParticleInstance>() * capacity) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        }
+    )
+}
+
+
+pub struct ParticleRenderer {
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    instance_buffer: wgpu::Buffer,
+    /// `instance_buffer`에 들어가는 파티클 수
+    capacity: usize,
+    /// 마지막 `update`에서 쓴 파티클 수
+    count: u32,
+}
+
+impl ParticleRenderer {
+    /// `format`은 그릴 화면의 텍스처 형식, `sample_count`는 주 렌더 패스의 MSAA 샘플 수
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../particle.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Particle Buffer"),
+                contents: bytemuck::cast_slice(&[ParticleUniform::new(Matrix4::identity())]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("particle_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ]
+            }
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("particle_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = create_pipeline(device, &pipeline_layout, &shader, format, sample_count);
+        let capacity = INITIAL_CAPACITY;
+        let instance_buffer = create_instance_buffer(device, capacity);
+
+        Self {
+            shader,
+            pipeline_layout,
+            format,
+            sample_count,
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            instance_buffer,
+            capacity,
+            count: 0,
+        }
+    }
+
+    /// 주 렌더 패스의 MSAA 샘플 수가 바뀌면 파이프라인을 다시 만듦
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        if sample_count != self.sample_count {
+            self.sample_count = sample_count;
+            self.pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, sample_count);
+        }
+    }
+
+    /// 렌더 패스를 시작하기 전에 호출. `particles`가 `None`이면 그리지 않음
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        particles: Option<&ParticleSystem>,
+        view_proj: Matrix4<f32>,
+    ) {
+        let instances = particles.map(ParticleSystem::instances).unwrap_or_default();
+        self.count = instances.len() as u32;
+        if instances.is_empty() {
+            return;
+        }
+
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.instance_buffer = create_instance_buffer(device, self.capacity);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[ParticleUniform::new(view_proj)]));
+    }
+
+    /// 주 렌더 패스에서 반투명 모델 뒤에 호출. 파이프라인을 바꾸므로 다음에 다시 설정해야 함
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if self.count == 0 {
+            return;
+        }
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        // 파티클마다 사각형 하나 (삼각형 두 개)
+        rpass.draw(0..6, 0..self.count);
+    }
+}
+
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Particle Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[ParticleInstance::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        // 오브젝트에 가려지지만 서로를 가리지 않도록 깊이를 남기지 않음
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emit_and_update() {
+        let mut particles = ParticleSystem::new();
+        particles.emit(Effect::Capture, Vector3::new(1.0, 0.0, 2.0), None);
+        assert_eq!(particles.len(), Effect::Capture.params().count);
+        assert!(particles.instances().iter().all(|instance| instance.position == [1.0, 0.0, 2.0]));
+
+        // 위로 튀어 오름
+        particles.update(Duration::from_millis(50));
+        assert!(particles.particles.iter().all(|particle| particle.position.y > 0.0));
+
+        // 수명이 지나면 모두 사라짐
+        particles.update(Duration::from_secs(1));
+        assert!(particles.is_empty());
+    }
+
+    #[test]
+    fn test_emit_color_and_limit() {
+        let mut particles = ParticleSystem::new();
+        particles.emit(Effect::MoveDust, Vector3::new(0.0, 0.0, 0.0), Some(Color::RED));
+        let color = particles.instances()[0].color;
+        assert_eq!(color[..3], [1.0, 0.0, 0.0]);
+        assert_eq!(color[3], Effect::MoveDust.params().color.a());
+
+        while particles.len() < MAX_PARTICLES {
+            particles.emit(Effect::Capture, Vector3::new(0.0, 0.0, 0.0), None);
+        }
+        particles.emit(Effect::Capture, Vector3::new(0.0, 0.0, 0.0), None);
+        assert_eq!(particles.len(), MAX_PARTICLES);
+    }
+
+    #[test]
+    fn test_billboard_axes() {
+        let view = Matrix4::look_at_rh(
+            cgmath::Point3::new(0.0, 0.0, 5.0),
+            cgmath::Point3::new(0.0, 0.0, 0.0),
+            Vector3::unit_y(),
+        );
+        let uniform = ParticleUniform::new(cgmath::perspective(cgmath::Deg(60.0), 1.5, 0.1, 100.0) * view);
+        assert_eq!(uniform.right, [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(uniform.up, [0.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_renderer_capacity() {
+        let Some((device, queue)) = super::super::test_device() else {
+            return;
+        };
+
+        let mut renderer = ParticleRenderer::new(&device, wgpu::TextureFormat::Rgba8UnormSrgb, 1);
+        let mut particles = ParticleSystem::new();
+        while particles.len() <= INITIAL_CAPACITY {
+            particles.emit(Effect::Capture, Vector3::new(0.0, 0.0, 0.0), None);
+        }
+        renderer.update(&device, &queue, Some(&particles), Matrix4::identity());
+        assert_eq!(renderer.count as usize, particles.len());
+        assert!(renderer.capacity >= particles.len());
+
+        renderer.set_sample_count(&device, 4);
+        renderer.update(&device, &queue, None, Matrix4::identity());
+        assert_eq!(renderer.count, 0);
+    }
+}
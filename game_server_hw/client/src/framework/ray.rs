@@ -0,0 +1,70 @@
+//! 화면 좌표에서 월드로 쏘는 직선 (마우스로 보드 칸 고르기).
+//! view-projection 역행렬로 커서 위치를 가까운 면과 먼 면에 되돌려서 두 점을 잇는 직선을 만듦.
+
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector3, Vector4};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    /// 단위 벡터
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// 크기가 `viewport`인 창의 픽셀 좌표 `cursor` (왼쪽 위가 원점)에 보이는 점들을 지나는 직선.
+    /// `view_proj`의 역행렬이 없으면 `None`
+    pub fn from_screen(view_proj: Matrix4<f32>, cursor: (f32, f32), viewport: (f32, f32)) -> Option<Self> {
+        let ndc_x = cursor.0 / viewport.0 * 2.0 - 1.0;
+        let ndc_y = 1.0 - cursor.1 / viewport.1 * 2.0;
+        let inverse = view_proj.invert()?;
+
+        let unproject = |z: f32| {
+            let p = inverse * Vector4::new(ndc_x, ndc_y, z, 1.0);
+            p.truncate() / p.w
+        };
+        let near = unproject(0.0);
+        let direction = unproject(1.0) - near;
+        if !direction.magnitude2().is_normal() {
+            return None;
+        }
+
+        Some(Self { origin: near, direction: direction.normalize() })
+    }
+
+    /// 높이가 `y`인 수평면과 만나는 점. 평면과 나란하면 `None`
+    pub fn intersect_plane_y(&self, y: f32) -> Option<Vector3<f32>> {
+        if self.direction.y.abs() < f32::EPSILON {
+            return None;
+        }
+        let t = (y - self.origin.y) / self.direction.y;
+        Some(self.origin + self.direction * t)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Point3};
+
+    #[test]
+    fn test_from_screen() {
+        // (0, 10, 10)에서 원점을 내려다봄
+        let view = Matrix4::look_at_rh(Point3::new(0.0, 10.0, 10.0), Point3::new(0.0, 0.0, 0.0), Vector3::unit_y());
+        let view_proj = cgmath::perspective(Deg(60.0), 1.0, 0.1, 100.0) * view;
+
+        // 화면 가운데는 카메라가 보는 점
+        let ray = Ray::from_screen(view_proj, (50.0, 50.0), (100.0, 100.0)).unwrap();
+        let hit = ray.intersect_plane_y(0.0).unwrap();
+        assert!(hit.magnitude() < 1e-3, "{:?}", hit);
+
+        // 화면 위쪽은 더 먼 곳 (-z)
+        let ray = Ray::from_screen(view_proj, (50.0, 30.0), (100.0, 100.0)).unwrap();
+        assert!(ray.intersect_plane_y(0.0).unwrap().z < -0.5);
+
+        let flat = Ray { origin: Vector3::new(0.0, 1.0, 0.0), direction: Vector3::unit_x() };
+        assert_eq!(flat.intersect_plane_y(0.0), None);
+    }
+}
@@ -0,0 +1,169 @@
+//! 서버가 기록한 매치 리플레이 (`GAMESERVER_REPLAYS`).
+//! 한 줄에 `<기록을 시작한 뒤 ms> <서버 메세지>`이며, 재생 위치까지의 메세지를 차례로 꺼내 줌.
+
+use std::{
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+
+/// 재생 속도 범위. `faster`, `slower`는 두 배씩 바꿈
+pub const MIN_REPLAY_SPEED: f32 = 0.25;
+pub const MAX_REPLAY_SPEED: f32 = 8.0;
+
+
+pub struct Replay {
+    /// 시각 순서로 정렬된 메세지 (줄바꿈 없음)
+    messages: Vec<(Duration, String)>,
+    position: Duration,
+    /// 아직 꺼내지 않은 첫 메세지
+    next: usize,
+    speed: f32,
+    paused: bool,
+}
+
+impl FromStr for Replay {
+    type Err = anyhow::Error;
+
+    /// 빈 줄은 무시. 시각이 없거나 잘못된 줄이 있으면 오류
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut messages = Vec::new();
+        for (idx, line) in s.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some((ms, message)) = line.split_once(' ') else {
+                anyhow::bail!("line {}: expected <ms> <message>", idx + 1);
+            };
+            let ms = ms.parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("line {}: invalid time {:?}: {}", idx + 1, ms, e))?;
+            messages.push((Duration::from_millis(ms), message.to_string()));
+        }
+        messages.sort_by_key(|(at, _)| *at);
+
+        Ok(Self { messages, position: Duration::ZERO, next: 0, speed: 1.0, paused: false })
+    }
+}
+
+impl std::fmt::Display for Replay {
+    /// `12.3s / 60.0s x2 paused`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1}s / {:.1}s x{}", self.position.as_secs_f32(), self.duration().as_secs_f32(), self.speed)?;
+        if self.paused {
+            write!(f, " paused")?;
+        }
+        Ok(())
+    }
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read replay {}: {}", path.display(), e))?;
+        text.parse()
+            .map_err(|e: anyhow::Error| anyhow::anyhow!("invalid replay {}: {}", path.display(), e))
+    }
+
+    /// 마지막 메세지의 시각
+    pub fn duration(&self) -> Duration {
+        self.messages.last().map_or(Duration::ZERO, |(at, _)| *at)
+    }
+
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_REPLAY_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_REPLAY_SPEED);
+    }
+
+    /// 재생 위치를 `dt * speed`만큼 옮기고 그 사이의 메세지를 꺼냄. 멈춰 있어도 `seek`한 위치까지는 꺼냄
+    pub fn advance(&mut self, dt: Duration) -> Vec<String> {
+        if !self.paused {
+            self.position = self.position.saturating_add(dt.mul_f32(self.speed)).min(self.duration());
+        }
+
+        let end = self.next + self.messages[self.next..].partition_point(|(at, _)| *at <= self.position);
+        let messages = self.messages[self.next..end].iter().map(|(_, message)| message.clone()).collect();
+        self.next = end;
+        messages
+    }
+
+    /// 재생 위치를 `to`로 옮김. 뒤로 가면 처음부터 다시 꺼내야 하므로 `true`를 반환하며,
+    /// 호출한 쪽은 지금까지 적용한 상태를 지워야 함
+    pub fn seek(&mut self, to: Duration) -> bool {
+        let to = to.min(self.duration());
+        let rewound = to < self.position;
+        if rewound {
+            self.next = 0;
+        }
+        self.position = to;
+        rewound
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLAY: &str = "0 GAMESERVER map 2 1 ..\n50 GAMESERVER update 1 1 0 0\n\n100 GAMESERVER update 2 1 1 0\n";
+
+    #[test]
+    fn test_advance() {
+        let mut replay = REPLAY.parse::<Replay>().unwrap();
+        assert_eq!(replay.duration(), Duration::from_millis(100));
+        assert_eq!(replay.advance(Duration::ZERO), ["GAMESERVER map 2 1 .."]);
+        assert!(replay.advance(Duration::from_millis(40)).is_empty());
+
+        replay.toggle_pause();
+        assert!(replay.advance(Duration::from_millis(40)).is_empty());
+        assert_eq!(replay.to_string(), "0.0s / 0.1s x1 paused");
+
+        replay.toggle_pause();
+        replay.faster();
+        assert_eq!(replay.advance(Duration::from_millis(40)), ["GAMESERVER update 1 1 0 0", "GAMESERVER update 2 1 1 0"]);
+        assert_eq!(replay.position(), replay.duration());
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut replay = REPLAY.parse::<Replay>().unwrap();
+        assert!(!replay.seek(Duration::from_millis(60)));
+        assert_eq!(replay.advance(Duration::ZERO).len(), 2);
+
+        // 뒤로 가면 처음부터 다시 꺼냄
+        assert!(replay.seek(Duration::from_millis(10)));
+        assert_eq!(replay.advance(Duration::ZERO), ["GAMESERVER map 2 1 .."]);
+
+        for _ in 0..10 {
+            replay.slower();
+        }
+        assert_eq!(replay.speed(), MIN_REPLAY_SPEED);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("GAMESERVER map 2 1 ..".parse::<Replay>().is_err());
+        assert!("soon GAMESERVER map 2 1 ..".parse::<Replay>().is_err());
+        assert!(Replay::load(Path::new("missing.replay")).is_err());
+    }
+}
@@ -1,17 +1,292 @@
-pub async fn load_string(file_name: &str) -> anyhow::Result<String> {
-    let path = std::path::Path::new(env!("OUT_DIR"))
-        .join("resource")
-        .join(file_name);
-    let txt = std::fs::read_to_string(path)?;
+use std::{
+    path::{Path, PathBuf},
+    string::FromUtf8Error,
+    sync::OnceLock,
+};
+use asset_pack::{AssetPack, DEFAULT_PACK_FILE};
+
+use super::{logging, texture::Texture};
+
+
+/// 리소스 폴더를 직접 지정할 때 사용하는 환경변수
+pub const RESOURCE_DIR_ENV: &str = "GAMESERVER_RES_DIR";
+
+/// 리소스 폴더를 찾지 못했을 때 사용하는, 실행파일에 포함된 리소스
+const EMBEDDED: &[(&str, &[u8])] = &[
+    ("cube.obj", include_bytes!("../../../resource/cube.obj")),
+    ("cube.mtl", include_bytes!("../../../resource/cube.mtl")),
+    ("cube-diffuse.jpg", include_bytes!("../../../resource/cube-diffuse.jpg")),
+    ("pawn.obj", include_bytes!("../../../resource/pawn.obj")),
+];
+
+
+/// 리소스 읽기 오류
+#[derive(Debug)]
+pub enum ResourceError {
+    /// 어느 폴더에도 없고 실행파일에도 포함되지 않음. `tried`는 찾아본 경로
+    NotFound { path: String, tried: Vec<PathBuf> },
+    /// 파일은 있지만 읽지 못함
+    Io(std::io::Error),
+    /// 문자열로 읽으려는 파일이 UTF-8이 아님
+    Utf8(FromUtf8Error),
+}
+
+impl std::fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceError::NotFound { path, tried } => {
+                let tried = tried.iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "resource {:?} not found (tried: {})", path, tried.join(", "))
+            }
+            ResourceError::Io(e) => write!(f, "failed to read resource: {}", e),
+            ResourceError::Utf8(e) => write!(f, "resource is not valid UTF-8: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResourceError::NotFound { .. } => None,
+            ResourceError::Io(e) => Some(e),
+            ResourceError::Utf8(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for ResourceError {
+    fn from(e: std::io::Error) -> Self {
+        ResourceError::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for ResourceError {
+    fn from(e: FromUtf8Error) -> Self {
+        ResourceError::Utf8(e)
+    }
+}
+
+
+/// 리소스를 찾는 폴더 순서 (환경변수가 없으면 실행파일 옆 팩 파일을 먼저 봄): 환경변수 -> 실행파일 옆 `resource` -> 빌드시 `OUT_DIR/resource`
+fn search_roots(env_dir: Option<PathBuf>, exe_dir: Option<PathBuf>) -> Vec<PathBuf> {
+    env_dir.into_iter()
+        .chain(exe_dir.map(|dir| dir.join("resource")))
+        .chain(std::iter::once(Path::new(env!("OUT_DIR")).join("resource")))
+        .collect()
+}
+
+fn default_search_roots() -> Vec<PathBuf> {
+    let env_dir = std::env::var_os(RESOURCE_DIR_ENV).map(PathBuf::from);
+    search_roots(env_dir, exe_dir())
+}
+
+/// 리소스를 찾는 폴더 중 실제로 있는 폴더 (찾는 순서)
+pub fn resource_dirs() -> Vec<PathBuf> {
+    default_search_roots().into_iter()
+        .filter(|root| root.is_dir())
+        .collect()
+}
+
+/// 파일이 아니더라도 경로가 있으면 찾은 것으로 보고, 읽기 오류로 알림
+fn find_in(roots: &[PathBuf], file_name: &str) -> Option<PathBuf> {
+    roots.iter()
+        .map(|root| root.join(file_name))
+        .find(|path| path.exists())
+}
+
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+}
+
+/// 실행파일 옆의 팩 파일 (`asset_pack`으로 만듦). 처음 읽을 때 한 번만 열고,
+/// 원본을 고치려고 `RESOURCE_DIR_ENV`를 지정했으면 사용하지 않음
+fn installed_pack() -> Option<&'static AssetPack> {
+    static PACK: OnceLock<Option<AssetPack>> = OnceLock::new();
+    PACK.get_or_init(|| {
+        if std::env::var_os(RESOURCE_DIR_ENV).is_some() {
+            return None;
+        }
+        let path = exe_dir()?.join(DEFAULT_PACK_FILE);
+        if !path.exists() {
+            return None;
+        }
+        match AssetPack::open(&path) {
+            Ok(pack) => {
+                log::info!(target: logging::SCENE, "Using {} ({} files)", path.display(), pack.len());
+                Some(pack)
+            }
+            Err(e) => {
+                log::warn!(target: logging::SCENE, "Ignoring {}: {}", path.display(), e);
+                None
+            }
+        }
+    }).as_ref()
+}
+
+fn embedded(file_name: &str) -> Option<&'static [u8]> {
+    EMBEDDED.iter()
+        .find(|(name, _)| *name == file_name)
+        .map(|(_, data)| *data)
+}
+
+
+pub async fn load_string(file_name: &str) -> Result<String, ResourceError> {
+    let data = load_binary(file_name).await?;
+    let txt = String::from_utf8(data)?;
 
     Ok(txt)
 }
 
-pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
-    let path = std::path::Path::new(env!("OUT_DIR"))
-        .join("resource")
-        .join(file_name);
-    let data = std::fs::read(path)?;
+pub async fn load_binary(file_name: &str) -> Result<Vec<u8>, ResourceError> {
+    read_from(installed_pack(), &default_search_roots(), file_name)
+}
+
+/// 팩에 없으면 폴더의 낱개 파일, 그 다음 실행파일에 포함된 리소스
+fn read_from(pack: Option<&AssetPack>, roots: &[PathBuf], file_name: &str) -> Result<Vec<u8>, ResourceError> {
+    match pack.and_then(|pack| pack.get(file_name)) {
+        Some(data) => Ok(data.to_vec()),
+        None => read_in(roots, file_name),
+    }
+}
+
+fn read_in(roots: &[PathBuf], file_name: &str) -> Result<Vec<u8>, ResourceError> {
+    if let Some(path) = find_in(roots, file_name) {
+        return Ok(std::fs::read(path)?);
+    }
+
+    match embedded(file_name) {
+        Some(data) => Ok(data.to_vec()),
+        None => Err(not_found(roots, file_name)),
+    }
+}
+
+fn not_found(roots: &[PathBuf], file_name: &str) -> ResourceError {
+    ResourceError::NotFound {
+        path: file_name.to_string(),
+        tried: roots.iter().map(|root| root.join(file_name)).collect(),
+    }
+}
+
+/// `filter`를 지정하지 않으면 `Linear`
+pub async fn load_texture(
+    file_name: &str,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    filter: Option<wgpu::FilterMode>,
+) -> anyhow::Result<Texture> {
+    let data = load_binary(file_name).await?;
+    Texture::from_bytes(
+        device,
+        queue,
+        &data,
+        file_name,
+        filter.unwrap_or(wgpu::FilterMode::Linear)
+    )
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("gameserver-res-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_exe_relative_lookup() {
+        let exe_dir = temp_dir("exe");
+        std::fs::create_dir_all(exe_dir.join("resource")).unwrap();
+        std::fs::write(exe_dir.join("resource").join("only-here.txt"), "exe").unwrap();
 
-    Ok(data)
-}
\ No newline at end of file
+        let roots = search_roots(None, Some(exe_dir.clone()));
+        assert_eq!(
+            find_in(&roots, "only-here.txt"),
+            Some(exe_dir.join("resource").join("only-here.txt"))
+        );
+        // 실행파일 옆에 없으면 OUT_DIR에서 찾음
+        assert_eq!(
+            find_in(&roots, "cube.obj"),
+            Some(Path::new(env!("OUT_DIR")).join("resource").join("cube.obj"))
+        );
+
+        std::fs::remove_dir_all(exe_dir).unwrap();
+    }
+
+    #[test]
+    fn test_env_dir_before_exe_dir() {
+        let env_dir = temp_dir("env");
+        let exe_dir = temp_dir("env-exe");
+        std::fs::create_dir_all(exe_dir.join("resource")).unwrap();
+        std::fs::write(env_dir.join("cube.obj"), "env").unwrap();
+        std::fs::write(exe_dir.join("resource").join("cube.obj"), "exe").unwrap();
+
+        let roots = search_roots(Some(env_dir.clone()), Some(exe_dir.clone()));
+        assert_eq!(find_in(&roots, "cube.obj"), Some(env_dir.join("cube.obj")));
+
+        std::fs::remove_dir_all(env_dir).unwrap();
+        std::fs::remove_dir_all(exe_dir).unwrap();
+    }
+
+    #[test]
+    fn test_embedded_fallback() {
+        assert!(find_in(&[], "pawn.obj").is_none());
+        assert!(embedded("pawn.obj").is_some_and(|data| !data.is_empty()));
+        assert!(embedded("missing.obj").is_none());
+    }
+
+    #[test]
+    fn test_pack_before_loose_files() {
+        let dir = temp_dir("pack");
+        std::fs::write(dir.join("cube.obj"), "loose").unwrap();
+        std::fs::write(dir.join("only-loose.obj"), "loose").unwrap();
+
+        let mut data = Vec::new();
+        asset_pack::write_pack(&[("cube.obj".to_string(), b"packed".to_vec())], &mut data).unwrap();
+        let pack = AssetPack::parse(data).unwrap();
+
+        let roots = vec![dir.clone()];
+        assert_eq!(read_from(Some(&pack), &roots, "cube.obj").unwrap(), b"packed");
+        assert_eq!(read_from(Some(&pack), &roots, "only-loose.obj").unwrap(), b"loose");
+        assert_eq!(read_from(None, &roots, "cube.obj").unwrap(), b"loose");
+        // 팩에도 폴더에도 없으면 실행파일에 포함된 리소스
+        assert!(read_from(Some(&pack), &roots, "pawn.obj").is_ok_and(|data| !data.is_empty()));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let dir = temp_dir("errors");
+        let roots = vec![dir.clone()];
+        assert!(matches!(
+            read_in(&roots, "missing.obj"), 
+            Err(ResourceError::NotFound { path, .. }) if path == "missing.obj"
+        ));
+
+        // 경로는 있지만 파일로 읽을 수 없음
+        std::fs::create_dir_all(dir.join("folder.obj")).unwrap();
+        assert!(matches!(read_in(&roots, "folder.obj"), Err(ResourceError::Io(_))));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_not_found_lists_tried_paths() {
+        let roots = search_roots(Some(PathBuf::from("env-dir")), Some(PathBuf::from("exe-dir")));
+        let message = not_found(&roots, "missing.obj").to_string();
+
+        let env = message.find(&Path::new("env-dir").join("missing.obj").display().to_string()).unwrap();
+        let exe = message.find(&Path::new("exe-dir").join("resource").join("missing.obj").display().to_string()).unwrap();
+        assert!(env < exe);
+        assert!(message.contains(env!("OUT_DIR")));
+    }
+}
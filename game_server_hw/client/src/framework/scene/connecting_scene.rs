@@ -0,0 +1,184 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    net::{SocketAddr, TcpStream},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant},
+};
+use cgmath::{Point3, SquareMatrix};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use super::super::{
+    model::Model,
+    color::Color,
+    config::{NetworkConfig, ServerConfig},
+    logging,
+};
+use super::{ActiveScene, GameScene, LoadingScene, Scene, SceneTransition, TitleScene};
+
+
+const CONNECTING_BACKGROUND_COLOR: Color = Color::from_rgb(0.05, 0.05, 0.1);
+
+
+/// 다른 스레드에서 서버 주소를 찾고 접속하는 동안 기다리는 씬.
+/// 접속하면 로딩 씬 (다 읽으면 로비)으로, 실패하면 오류와 함께 첫 화면으로 돌아감. Escape로 취소함
+pub struct ConnectingScene {
+    server: ServerConfig,
+    network_config: NetworkConfig,
+    receiver: Receiver<anyhow::Result<(SocketAddr, TcpStream)>>,
+    started: Instant,
+    cancelled: bool,
+}
+
+impl ConnectingScene {
+    /// 접속은 `network_config.handshake_timeout`까지 기다림
+    pub fn new(server: ServerConfig, network_config: NetworkConfig) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let timeout = network_config.handshake_timeout;
+        let target = server.clone();
+
+        std::thread::spawn(move || {
+            let result = GameScene::resolve(&target).and_then(|addr| {
+                TcpStream::connect_timeout(&addr, timeout)
+                    .map(|stream| (addr, stream))
+                    .map_err(|e| anyhow::anyhow!("failed to connect to {}: {}", addr, e))
+            });
+            // 취소했으면 받는 쪽이 없음
+            let _ = sender.send(result);
+        });
+
+        Self { server, network_config, receiver, started: Instant::now(), cancelled: false }
+    }
+
+    pub fn server(&self) -> &ServerConfig {
+        &self.server
+    }
+
+    /// 창 제목에 붙일 접속 중인 서버
+    pub fn title(&self) -> String {
+        format!("connecting to {}… {}s", self.server, self.started.elapsed().as_secs())
+    }
+
+    /// 첫 화면으로 돌아감
+    fn back_to_title(&self, error: Option<String>) -> SceneTransition {
+        let mut title = TitleScene::new(self.server.to_string(), self.network_config.clone());
+        if let Some(error) = error {
+            title = title.with_error(error);
+        }
+        SceneTransition::Replace(ActiveScene::Title(title))
+    }
+}
+
+impl Scene for ConnectingScene {
+    fn init(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state: ElementState::Pressed,
+                    physical_key: PhysicalKey::Code(KeyCode::Escape),
+                    ..
+                },
+                ..
+            } => {
+                self.cancelled = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn update(&mut self, _dt: Duration) -> Option<SceneTransition> {
+        if self.cancelled {
+            log::info!(target: logging::NET, "Cancelled connecting to {}", self.server);
+            return Some(self.back_to_title(None));
+        }
+
+        let result = match self.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(anyhow::anyhow!("connecting thread stopped")),
+        };
+
+        match result {
+            Ok((addr, stream)) => {
+                log::info!(target: logging::NET, "Connected to {}", addr);
+                let game = GameScene::with_connection(Some(stream), addr, self.network_config.clone());
+                let loading = LoadingScene::new(game, GameScene::MODEL_FILES).with_lobby();
+                Some(SceneTransition::Replace(ActiveScene::Loading(loading)))
+            }
+            Err(e) => {
+                log::warn!(target: logging::NET, "{:#}", e);
+                Some(self.back_to_title(Some(format!("{:#}", e))))
+            }
+        }
+    }
+
+    fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::identity()
+    }
+
+    fn camera_eye(&self) -> Point3<f32> {
+        Point3::new(0.0, 0.0, 0.0)
+    }
+
+    fn background_color(&self) -> Color {
+        CONNECTING_BACKGROUND_COLOR
+    }
+
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        std::iter::empty()
+    }
+
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn wait(connecting: &mut ConnectingScene) -> SceneTransition {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(transition) = connecting.update(Duration::ZERO) {
+                return transition;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("connecting did not finish");
+    }
+
+    #[test]
+    fn test_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = ServerConfig { ip: "127.0.0.1".to_string(), port };
+
+        let mut connecting = ConnectingScene::new(server, NetworkConfig::default());
+        assert!(matches!(wait(&mut connecting), SceneTransition::Replace(ActiveScene::Loading(_))));
+        assert!(listener.accept().is_ok());
+    }
+
+    #[test]
+    fn test_connect_failure() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let server = ServerConfig { ip: "127.0.0.1".to_string(), port };
+
+        let mut connecting = ConnectingScene::new(server, NetworkConfig::default());
+        let SceneTransition::Replace(ActiveScene::Title(title)) = wait(&mut connecting) else {
+            panic!("expected title scene");
+        };
+        assert_eq!(title.address(), format!("127.0.0.1:{}", port));
+        assert!(title.error().unwrap().contains("failed to connect"));
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    time::Duration,
+};
+use cgmath::{Point3, SquareMatrix};
+
+use super::super::{
+    model::Model,
+    color::Color,
+};
+use super::{Scene, SceneTransition};
+
+
+const ERROR_BACKGROUND_COLOR: Color = Color::from_rgb(0.25, 0.05, 0.05);
+
+
+/// 서버 주소를 찾지 못했거나 게임 씬을 준비하지 못했을 때 보여주는 씬.
+/// 글자를 그릴 수 없으므로 오류 내용은 창 제목으로 보여주며, 닫을 때까지 그대로 머무름
+pub struct ErrorScene {
+    message: String,
+}
+
+impl ErrorScene {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+
+    /// `anyhow` 오류는 원인까지 한 줄로 보여줌
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        Self::new(format!("{:#}", error))
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Scene for ErrorScene {
+    fn init(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn handle_event(&mut self, _event: &winit::event::WindowEvent) -> bool {
+        false
+    }
+
+    fn update(&mut self, _dt: Duration) -> Option<SceneTransition> {
+        None
+    }
+
+    fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::identity()
+    }
+
+    fn camera_eye(&self) -> Point3<f32> {
+        Point3::new(0.0, 0.0, 0.0)
+    }
+
+    fn background_color(&self) -> Color {
+        ERROR_BACKGROUND_COLOR
+    }
+
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        std::iter::empty()
+    }
+
+}
@@ -1,312 +1,4897 @@
 use winit::{
-    event::{ElementState, KeyEvent, WindowEvent},
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
     keyboard::{KeyCode, PhysicalKey},
 };
-use cgmath::{Vector2, Vector3, Point3};
+use cgmath::{Deg, EuclideanSpace, InnerSpace, MetricSpace, Quaternion, Rotation3, Vector2, Vector3, Point3};
 use futures::executor::block_on;
 use std::{
     rc::Rc, 
     cell::RefCell, 
-    io::{Read, Write}, 
-    net::TcpStream,
-    collections::HashMap,
+    net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    collections::{HashMap, HashSet, VecDeque},
     iter::IntoIterator,
+    time::{Duration, Instant},
 };
 use get_addr::get_addr;
-use network::PacketParser;
+use network::{
+    decompress, facing_yaw, is_supported_version, ClientMessage, EntityKind, ObjectState, PacketParser, RoomInfo, ScoreEntry, ServerMessage, Tile,
+    COLORS_VERSION, COMPRESSION_VERSION, KINDS_VERSION, LOBBY_ROOM, MATCHMAKING_VERSION, MAX_MOVE_BATCH,
+    NAME_KEY_VERSION, NAMES_VERSION, PROTOCOL_VERSION, PUSHED_UPDATES_VERSION, ROOMS_VERSION, SCOREBOARD_VERSION, SPECTATOR_VERSION,
+};
 
 use super::super::{
     camera::{Camera, CameraComponent, DefaultCamera},
-    object::Object,
-    model::Model,
+    ray::Ray,
+    board::BoardLayout,
+    chat::{ChatHistory, ChatMessage, CHAT_OVERLAY_DURATION, CHAT_OVERLAY_LINES},
+    ecs::{Entity, Interpolated, World},
+    object::Renderable,
+    transform::Transform,
+    model::{MeshCache, Model},
+    asset_cache::{AssetCache, ModelParams},
     color::Color,
-    SCREEN_WIDTH, SCREEN_HEIGHT,
+    config::{CameraConfig, ClientConfig, NetworkConfig, OverflowPolicy, ServerConfig},
+    transport::{TcpTransport, Transport},
+    netsim::{NetworkConditions, SimulatedTransport},
+    text_input::{TextInput, TextInputEvent},
+    gamepad::GamepadInput,
+    input::{Action, InputAction, InputMap},
+    light::DirectionalLight,
+    hud::{Hud, DEFAULT_TEXT_SIZE},
+    particles::{Effect, ParticleSystem},
+    animation::{AnimationSet, Animator, PAWN_ANIMATION_FILE, WALK},
+    frame_stats::FrameStats,
+    name_tag,
+    easing,
+    logging,
+    replay::Replay,
+    snapshot::{Snapshot, SnapshotBuffer},
+    debug_draw::DebugLines,
+    minimap::Minimap,
 };
-use super::Scene;
+use super::{ActiveScene, ErrorScene, PauseScene, ProtocolError, Scene, SceneTransition, TitleScene};
+
+
+const DEFAULT_BACKGROUND_COLOR: Color = Color::from_rgb(0.1, 0.3, 0.5);
+/// 플레이어 자신을 다른 플레이어와 구분하는 기본 색
+const PLAYER_COLOR: Color = Color::CYAN;
+/// 서버가 배경색을 바꿀 때 이전 색에서 새 색으로 바뀌는 시간
+const BACKGROUND_TRANSITION: Duration = Duration::from_millis(500);
+/// 주운 아이템이 떠오르며 돌다가 사라지는 시간과 떠오르는 높이
+const PICKUP_EFFECT: Duration = Duration::from_millis(600);
+const PICKUP_RISE: f32 = 1.0;
+const PICKUP_SPIN_DEGREES: f32 = 720.0;
+
+/// 한 프레임에 반영하는 최대 경과 시간. 프레임이 크게 늦어져도 이 이상 진행하지 않음
+const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+/// 초당 목표 위치까지 남은 거리를 좁히는 비율의 기본값
+const DEFAULT_INTERPOLATION_RATE: f32 = 15.0;
+/// 디버그 패널에서 -/= 키로 `interpolation_delay`를 바꾸는 단위와 최대값
+const INTERPOLATION_DELAY_STEP: Duration = Duration::from_millis(10);
+const MAX_INTERPOLATION_DELAY: Duration = Duration::from_millis(500);
+
+/// 다시 접속할 때 기다리는 최대 시간. 접속은 다른 스레드에서 하며 그동안 `try_reconnect`가 결과를 확인함
+const RECONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+/// 다시 접속에 실패한 뒤 다음 시도까지의 첫 대기 시간. 실패할 때마다 두 배
+const RECONNECT_BACKOFF_BASE: Duration = Duration::from_millis(100);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(5);
+
+/// 예측한 플레이어 위치와 서버 위치의 차이가 이 이하면 보정하지 않음
+const PREDICTION_TOLERANCE: f32 = 0.01;
+/// 차이가 이보다 크면 보간 없이 서버 위치로 바로 이동
+const PREDICTION_SNAP_DISTANCE: f32 = 2.0;
+/// 서버 `ack`를 기다리는 이동을 최대 몇 개까지 기억할지
+const MAX_PENDING_MOVES: usize = 64;
+
+/// 잘못된 메세지 경고를 출력하는 최소 간격
+const PARSE_WARNING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 선택/플레이어 표시 고리 모델의 이름
+const MARKER_MODEL: &str = "marker";
+
+/// 점수판을 켰을 때 보여주는 최대 줄 수
+const SCOREBOARD_OVERLAY_LINES: usize = 5;
+
+/// 잡기 알림을 채팅 기록에 남길 때의 이름
+const CAPTURE_SENDER: &str = "*";
+
+/// 바닥 타일 모델의 이름. 칸마다 번갈아 씀
+const TILE_MODELS: [&str; 2] = ["tile_light", "tile_dark"];
+/// 자기 플레이어 모델의 이름
+const PLAYER_MODEL: &str = "player";
+/// 벽 칸 타일 모델의 이름. 바닥 타일보다 한 칸 높게 놓음
+const WALL_MODEL: &str = "wall";
+/// 서버 오브젝트의 종류별 모델 이름. 자기 플레이어는 `PLAYER_MODEL`
+const ENTITY_MODELS: [(EntityKind, &str); 4] = [
+    (EntityKind::Player, "pawn"),
+    (EntityKind::Npc, "npc"),
+    (EntityKind::Item, "item"),
+    (EntityKind::Obstacle, "obstacle"),
+];
+
+/// 폰 모델 (`pawn.obj`)의 이름. 이 모델의 오브젝트는 `PAWN_ANIMATION_FILE`의 애니메이션을 재생
+const PAWN_MODELS: [&str; 3] = [PLAYER_MODEL, "pawn", "npc"];
+
+/// 이름표 픽셀로 쓰는 정육면체 모델의 이름. 밝은 바닥 타일과 GPU 데이터를 공유하고, 색은 `NAME_TAG_COLOR`로 덮어씀
+const NAME_TAG_MODEL: &str = "name_tag";
+const NAME_TAG_COLOR: Color = Color::WHITE;
+
+/// 파일에서 읽는 모델 (이름, 파일, 크기와 색). 이 순서로 그림
+const FILE_MODELS: [(&str, &str, ModelParams); 9] = [
+    (TILE_MODELS[0], "cube.obj", ModelParams::new(0.5, Color::LIGHT_GRAY)),
+    (TILE_MODELS[1], "cube.obj", ModelParams::new(0.5, Color::DARK_GRAY)),
+    (PLAYER_MODEL, "pawn.obj", ModelParams::new(0.8, Color::WHITE)),
+    ("pawn", "pawn.obj", ModelParams::new(0.8, Color::BLACK)),
+    ("npc", "pawn.obj", ModelParams::new(0.8, Color::from_rgb(0.6, 0.2, 0.2))),
+    ("item", "cube.obj", ModelParams::new(0.2, Color::YELLOW)),
+    ("obstacle", "cube.obj", ModelParams::new(0.45, Color::from_rgb(0.4, 0.25, 0.1))),
+    (WALL_MODEL, "cube.obj", ModelParams::new(0.5, Color::from_rgb(0.35, 0.35, 0.4))),
+    (NAME_TAG_MODEL, "cube.obj", ModelParams::new(0.5, Color::LIGHT_GRAY)),
+];
+/// 이름표 픽셀 하나의 크기 (월드 단위)
+const NAME_TAG_PIXEL: f32 = 0.04;
+
+/// HUD 글자의 화면 가장자리에서의 거리와 줄 간격 (픽셀)
+const HUD_MARGIN: f32 = 8.0;
+const HUD_LINE_SPACING: f32 = 6.0;
+const HUD_COLOR: Color = Color::WHITE;
+/// HUD의 FPS를 계산하는 최근 프레임 수
+const HUD_FPS_FRAMES: usize = 60;
+/// 오브젝트 위치에서 이름표 아래까지의 높이
+const NAME_TAG_HEIGHT: f32 = 0.9;
+
+const MINIMAP_BACKGROUND: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.5);
+const MINIMAP_WALL_COLOR: Color = Color::from_rgba(0.6, 0.6, 0.6, 0.8);
+/// 오브젝트 점의 크기 (칸 대비)
+const MINIMAP_DOT_SCALE: f32 = 0.6;
+/// 미니맵의 오브젝트 종류별 색. 자기 플레이어는 `PLAYER_COLOR`
+const MINIMAP_COLORS: [(EntityKind, Color); 4] = [
+    (EntityKind::Player, Color::WHITE),
+    (EntityKind::Npc, Color::RED),
+    (EntityKind::Item, Color::YELLOW),
+    (EntityKind::Obstacle, Color::GRAY),
+];
+
+/// 디버그 렌더 모드에서 보드 격자를 칸 윗면보다 띄우는 높이 (z-fighting 방지)
+const DEBUG_GRID_LIFT: f32 = 0.01;
+const DEBUG_GRID_COLOR: Color = Color::from_rgba(1.0, 1.0, 1.0, 0.5);
+/// 서버가 알려준 위치 표시
+const DEBUG_SERVER_COLOR: Color = Color::YELLOW;
+const DEBUG_SERVER_MARKER: f32 = 0.3;
+/// 실제로 그리는 (보간한) 위치 표시
+const DEBUG_RENDERED_COLOR: Color = Color::WHITE;
+const DEBUG_RENDERED_MARKER: f32 = 0.1;
+
+
+/// 서버와의 연결 상태
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// 연결은 됐지만 아직 `init`을 받지 못함
+    Connecting,
+    Connected,
+    /// 연결이 끊겨 다시 접속하는 중. `attempt`는 1부터 시작하는 시도 횟수
+    Reconnecting { attempt: u32 },
+    Disconnected,
+    /// 서버가 `server_closing`으로 종료를 알림. 다시 접속하지 않음
+    Closed,
+}
+
+/// 서버에서 받은 오브젝트 하나의 상태
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectSnapshot {
+    pub id: u32,
+    /// 보간 중인 현재 위치 (서버가 보낸 목표 위치가 아님)
+    pub position: Vector3<f32>,
+    /// 플레이어 자신인지
+    pub is_local: bool,
+}
+
+/// 클라이언트가 알고 있는 월드 상태. 씬 내부 타입과 무관한 복사본
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldSnapshot {
+    pub player_id: Option<u32>,
+    /// id 순서. 사라지는 중인 오브젝트는 포함하지 않음
+    pub objects: Vec<ObjectSnapshot>,
+    pub connection: ConnectionState,
+}
+
+
+/// 오브젝트 색상을 바꾸지 않고 위에 겹쳐 그리는 표시
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Marker {
+    LocalPlayer,
+    Selection,
+}
+
+/// 오브젝트 위에 떠 있는 이름. 글자는 작은 정육면체 픽셀로 그림 (`name_tag::layout`)
+struct NameTag {
+    text: String,
+    /// 픽셀 위치 (픽셀 단위)와 그 픽셀 엔티티
+    pixels: Vec<(Vector2<f32>, Entity)>,
+}
+
+/// 카메라와 플레이어 사이 거리의 최소/최대값
+const MIN_CAMERA_DISTANCE: f32 = 1.0;
+const MAX_CAMERA_DISTANCE: f32 = 50.0;
+/// 카메라가 바닥 아래나 플레이어 머리 위를 넘어가지 않도록 제한하는 pitch (라디안)
+const MIN_CAMERA_PITCH: f32 = 0.0;
+const MAX_CAMERA_PITCH: f32 = 1.5;
+/// 그림자 범위를 보드보다 이만큼 넓힘 (보드 가장자리에 선 말의 높이)
+const SHADOW_MARGIN: f32 = 1.0;
+/// 마우스를 1픽셀 끌 때 회전하는 각도 (라디안)
+const ORBIT_SENSITIVITY: f32 = 0.01;
+/// 마우스 휠 한 칸에 바뀌는 카메라 거리
+const ZOOM_STEP: f32 = 1.0;
+/// 자유 카메라의 초당 이동 거리
+const FREE_CAMERA_SPEED: f32 = 5.0;
+/// 리플레이에서 ←/→ 한 번에 이동하는 시간
+const REPLAY_SEEK_STEP: Duration = Duration::from_secs(5);
 
 
 pub struct GameScene {
     camera: DefaultCamera,
+    board: BoardLayout,
+    /// 서버가 `map`으로 알려준 벽 칸의 타일 인덱스
+    walls: HashSet<usize>,
+    /// 창 크기 (픽셀)
+    viewport: (f32, f32),
+    /// 마지막 마우스 위치 (창 픽셀 좌표)
+    cursor: Option<(f32, f32)>,
+    /// 오른쪽 버튼을 누른 채 끌면 카메라가 플레이어를 중심으로 회전
+    orbiting: bool,
+    /// `camera_distance`, `camera_yaw`, `camera_pitch`로 `follow_camera`에서 다시 계산됨
     camera_offset: Vector3<f32>,
+    /// 위치는 `reset_camera`로 돌아갈 곳, 나머지는 따라가는 방식
+    camera_config: CameraConfig,
+    /// 카메라가 바라보는 점. 따라가는 대상 쪽으로 `follow_speed`에 맞춰 다가감
+    camera_focus: Option<Point3<f32>>,
+    /// 따라가던 오브젝트 id와 지난 프레임 위치
+    followed_last: Option<(u32, Vector3<f32>)>,
+    /// 따라가는 대상이 마지막으로 움직인 바닥 방향 (크기 1)
+    look_ahead_direction: Vector3<f32>,
+    camera_distance: f32,
+    /// +z축에서 y축을 중심으로 돌린 각도 (라디안)
+    camera_yaw: f32,
+    /// 수평면에서 올려다본 각도 (라디안)
+    camera_pitch: f32,
 
     background_color: Color,
+    /// 배경색 전환 (시작 색, 목표 색, 시작 시각)
+    background_transition: Option<(Color, Color, Instant)>,
+    /// 설정의 스카이박스. 서버가 배경색을 바꾸면 그 색을 대신 보여줌
+    skybox: Option<String>,
 
-    models: Vec<Rc<RefCell<Model>>>,
-    objects: Vec<Rc<RefCell<Object>>>,
-    objects_from_server: HashMap<u32, Rc<RefCell<Object>>>,
+    /// 이름별 모델. 씬이 살아있는 동안 같은 .obj 파일을 다시 읽거나 GPU에 다시 올리지 않도록 보관
+    assets: AssetCache,
+    /// 보드 타일, 서버 오브젝트 (`NetworkId`), 표시와 이름표 픽셀
+    world: World,
+    /// 보드 타일 인덱스별 엔티티
+    tiles: Vec<Entity>,
+    /// 현재 보이는 표시들. 따라가는 오브젝트 위치로 매 프레임 이동
+    markers: HashMap<Marker, Entity>,
+    /// 선택된 서버 오브젝트 id
+    selected: Option<u32>,
+    /// 서버가 알려준 플레이어 이름 (`names`)
+    names: HashMap<u32, String>,
+    /// 이름이 있는 서버 오브젝트의 이름표
+    name_tags: HashMap<u32, NameTag>,
+    /// HUD에 보여주는 FPS
+    frame_stats: FrameStats,
+    /// 서버가 플레이어마다 정한 색 (`colors`)
+    colors: HashMap<u32, Color>,
+    /// 서버가 `spawn`으로 알려준 오브젝트 종류. 없으면 `EntityKind::Player`
+    kinds: HashMap<u32, EntityKind>,
+    /// 서버가 마지막으로 보낸 점수판
+    scoreboard: Vec<ScoreEntry>,
+    /// Tab으로 켜고 끄는 점수판 표시
+    show_scoreboard: bool,
+    /// F3으로 켜고 끄는 디버그 패널 (`debug_panel`)
+    show_debug_panel: bool,
+    /// M으로 켜고 끄는 화면 오른쪽 아래의 보드 지도
+    show_minimap: bool,
+    /// 미니맵의 긴 변 (픽셀)
+    minimap_size: f32,
+    /// 서버에서 사라져 투명해지고 있는 오브젝트와 사라지기 시작한 시각. 엔티티에는 `NetworkId`가 없음
+    despawning: Vec<(u32, Entity, Instant)>,
+    /// 주운 아이템 효과. 엔티티, 시작 위치, 시작 시각
+    pickups: Vec<(Entity, Vector3<f32>, Instant)>,
+    /// 이동 먼지, 잡기와 아이템 줍기 효과
+    particles: ParticleSystem,
+    /// 폰 애니메이션. 읽지 못했으면 움직이지 않음
+    animations: Option<Rc<AnimationSet>>,
+    /// `None`이면 사라진 오브젝트를 바로 삭제
+    despawn_fade: Option<Duration>,
+    /// 플레이어 자신의 오브젝트에 입히는 색. `None`이면 모델 색 그대로
+    player_color: Option<Color>,
+    /// 다른 오브젝트가 목표 위치로 따라가는 속도 (`set_interpolation_rate`)
+    interpolation_rate: f32,
 
-    player_id: u32,
+    /// 서버에서 `init`을 받기 전에는 `None`
+    player_id: Option<u32>,
+    /// 관전중인 다른 플레이어 id. `Some`이면 카메라가 해당 오브젝트를 따라감
+    spectate_target: Option<u32>,
+    /// F로 켜고 끄는 자유 카메라. 켜져 있으면 이동키로 플레이어 대신 카메라를 움직임
+    free_camera: bool,
+    /// 서버에 `spectate`를 보냄. 플레이어가 없으므로 이동을 보내지 않고 자유 카메라를 씀
+    spectating: bool,
 
-    stream: TcpStream,
+    /// 키를 동작으로 바꿈
+    input_map: InputMap,
+    held_keys: HashSet<KeyCode>,
+    /// Escape를 누름. 다음 `update`에서 멈춤 화면을 쌓음
+    pause_requested: bool,
+    /// 누르고 있는 이동키로 마지막 `move`를 보낸 시간. 키를 처음 누르면 `None`이 되어 바로 보냄
+    last_move_sent: Option<Instant>,
+    gamepad_direction: Option<Vector2<f32>>,
+    last_resend: Instant,
+    /// `update`에 넘어온 `dt`를 누적한 시간. 이동 전송 간격 계산에 사용
+    clock: Instant,
+    text_input: TextInput,
+    chat: ChatHistory,
+    /// 아직 처리하지 않은 입력 동작
+    input_actions: VecDeque<InputAction>,
+    /// 아직 보내지 않은 이동 (`move_batch_ticks` 사용 시)
+    move_batch: Vec<(f32, f32)>,
+    /// 다음에 모을 이동의 일련번호
+    move_seq: u32,
+    /// 서버가 아직 `ack`하지 않은 이동 (일련번호, 칸 단위 이동량)
+    pending_moves: VecDeque<(u32, Vector2<i32>)>,
+    /// 서버가 마지막으로 알려준 플레이어 칸
+    server_cell: Option<Vector2<i32>>,
+    /// 마지막으로 `moves`를 보낸 뒤 지난 tick 수
+    batch_ticks: u32,
+
+    network_config: NetworkConfig,
+    server_addr: Option<SocketAddr>,
+    /// 서버에 접속하지 못했거나 연결이 끊기면 `is_connected`가 `false`
+    transport: Box<dyn Transport>,
+    /// 연결이 없을 때 다음으로 접속을 시도할 시간
+    next_retry: Instant,
+    /// 연속으로 실패한 접속 시도 횟수
+    retry_attempts: u32,
+    /// 마지막으로 연결한 시각. `init`을 받기 전까지 handshake timeout 기준
+    handshake_started: Instant,
+    /// 마지막으로 `ping`을 보낸 시간
+    last_heartbeat: Instant,
+    /// 마지막으로 서버에서 `pong`을 받은 시간
+    last_server_seen: Instant,
+    /// `ping`에 담는 시각(ms)의 기준
+    ping_epoch: Instant,
+    /// 마지막 `ping`의 왕복 시간. `ping_ms` 참고
+    round_trip: Option<Duration>,
+    handshake_error: Option<String>,
+    /// `init`으로 받은, 이 클라이언트가 읽을 수 없는 서버 버전. `update`에서 오류 화면으로 바꿈
+    incompatible_server: Option<u32>,
     packet_parser: PacketParser,
+    /// 마지막으로 `update`를 요청한 시간 (`clock` 기준)
+    last_update_request: Option<Instant>,
+    /// 요청한 `update`의 응답을 아직 받지 못함
+    update_pending: bool,
+    /// 서버가 요청 없이 위치를 보냄 (`PUSHED_UPDATES_VERSION`). 이때는 TCP로 `update`를 요청하지 않음
+    server_pushes_updates: bool,
+    /// 마지막으로 적용한 위치 메세지의 일련번호. 서버가 일련번호를 보내지 않으면 `None`
+    last_seq: Option<u32>,
+    /// `init`에서 받은 플레이어 id와 토큰. 다시 접속하면 `resume`으로 같은 플레이어를 이어서 사용
+    session: Option<(u32, u64)>,
+    /// 서버에 저장된 이름의 상태를 찾는 열쇠 (`NAME_KEY_VERSION`). 처음 받은 `init`의 토큰을 클라이언트가 끝날 때까지 씀
+    name_key: Option<u64>,
+    /// 서버에 방이 있음 (`ROOMS_VERSION`). `init`을 받기 전에는 `false`
+    rooms_supported: bool,
+    /// 서버에 매치 대기열이 있음 (`MATCHMAKING_VERSION`)
+    matchmaking_supported: bool,
+    /// 매치 대기열에 있으면 서버가 알려준 (기다리는 인원, 매치 인원). 알려주기 전에는 `(0, 0)`
+    match_search: Option<(usize, usize)>,
+    /// 마지막으로 잡힌 매치의 플레이어 id
+    match_players: Vec<u32>,
+    /// 마지막으로 받은 방 목록 (`request_rooms`)
+    rooms: Vec<RoomInfo>,
+    /// 플레이어가 있는 방. 방이 없는 서버면 `None`
+    room: Option<u32>,
+    /// 마지막으로 방에 들어가지 못한 이유. 방에 들어가면 `None`
+    room_error: Option<String>,
+    /// `udp_updates`일 때 `update`를 주고받는 소켓. `init`에서 토큰을 받으면 엶
+    udp: Option<UdpSocket>,
+    connection_state: ConnectionState,
+    /// 아직 보내지 못한 메세지 바이트. `update`마다 한 번 `flush_outgoing`으로 보냄
+    outgoing: Vec<u8>,
+    parse_warnings: logging::Throttle,
+    /// `Some`이면 서버 대신 리플레이의 메세지를 재생함. 서버에 접속하지 않음
+    replay: Option<Replay>,
+}
+
+/// `addr`로 보내는 non-blocking UDP 소켓. 열지 못하면 TCP로 `update`를 받음
+fn open_udp(addr: SocketAddr) -> Option<UdpSocket> {
+    let local = match addr {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let socket = UdpSocket::bind(local)
+        .and_then(|socket| socket.connect(addr).map(|_| socket))
+        .and_then(|socket| socket.set_nonblocking(true).map(|_| socket));
+
+    match socket {
+        Ok(socket) => Some(socket),
+        Err(e) => {
+            log::warn!(target: logging::NET, "Failed to open udp socket to {}: {}, using tcp for updates", addr, e);
+            None
+        }
+    }
+}
+
+/// `attempt`번째(0부터) 접속 실패 후 기다리는 시간 (100ms, 200ms, 400ms, ... 최대 5초)
+fn reconnect_backoff(attempt: u32) -> Duration {
+    RECONNECT_BACKOFF_BASE
+        .checked_mul(1 << attempt.min(31))
+        .map_or(RECONNECT_BACKOFF_MAX, |backoff| backoff.min(RECONNECT_BACKOFF_MAX))
+}
+
+/// 서버가 `server_version`을 보냈을 때 오류 화면에 보여줄 메세지
+fn client_out_of_date(server_version: u32) -> String {
+    format!(
+        "Client out of date: server uses protocol version {} but this client supports up to {}. Update the client to join this server.",
+        server_version,
+        PROTOCOL_VERSION
+    )
 }
 
+
+
 impl GameScene {
-    pub async fn new() -> Self {
+    /// `server`가 없으면 실행 인자(`get_addr`)에서 주소를 가져옴.  
+    /// 주소가 잘못되었으면 오류지만, 서버에 접속하지 못한 것은 오류가 아니며 나중에 다시 접속을 시도함.  
+    pub fn new(network_config: NetworkConfig, server: Option<ServerConfig>) -> anyhow::Result<Self> {
+        let server = match server {
+            Some(server) => server,
+            None => {
+                let (ip, port) = get_addr().map_err(anyhow::Error::msg)?;
+                ServerConfig { ip, port }
+            }
+        };
+        let addr = Self::resolve(&server)?;
+
+        // 접속은 다른 스레드에서 하고, `try_reconnect`에서 결과를 확인함
+        let transport = TcpTransport::connect(addr, network_config.handshake_timeout);
+        Ok(Self::with_tcp(transport, addr, network_config))
+    }
+
+    /// `server`의 첫 번째 주소
+    pub fn resolve(server: &ServerConfig) -> anyhow::Result<SocketAddr> {
+        (server.ip.as_str(), server.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("no address found for {}:{}", server.ip, server.port))
+    }
+
+    /// `stream`이 없으면 나중에 `addr`로 다시 접속을 시도함.  
+    /// `GAMESERVER_SIM_*` 환경변수가 있으면 연결을 `SimulatedTransport`로 감쌈.
+    pub fn with_connection(stream: Option<TcpStream>, addr: SocketAddr, network_config: NetworkConfig) -> Self {
+        Self::with_tcp(TcpTransport::new(stream), addr, network_config)
+    }
+
+    fn with_tcp(transport: TcpTransport, addr: SocketAddr, network_config: NetworkConfig) -> Self {
+        let mut transport: Box<dyn Transport> = Box::new(transport);
+        if let Some(conditions) = NetworkConditions::from_env() {
+            log::warn!(target: logging::NET, "Simulating network conditions: {}", conditions);
+            transport = Box::new(SimulatedTransport::new(transport, conditions));
+        }
+
+        Self::with_transport(transport, Some(addr), network_config)
+    }
+
+    /// 서버에 접속하지 않고 `replay`를 재생하는 씬. 플레이어가 없으므로 자유 카메라로 봄
+    pub fn with_replay(replay: Replay, network_config: NetworkConfig) -> Self {
+        let mut scene = Self::with_transport(Box::new(TcpTransport::new(None)), None, network_config);
+        scene.replay = Some(replay);
+        scene
+    }
+
+    /// 이미 연결된 `stream`으로 씬 생성
+    pub fn with_stream(stream: TcpStream, network_config: NetworkConfig) -> Self {
+        let transport = TcpTransport::new(Some(stream));
+        let server_addr = transport.peer_addr();
+        Self::with_transport(Box::new(transport), server_addr, network_config)
+    }
+
+    /// `server_addr`는 연결이 끊겼을 때 다시 접속할 주소. 카메라와 이동키는 `ClientConfig::current`를 따름
+    pub fn with_transport(
+        transport: Box<dyn Transport>, 
+        server_addr: Option<SocketAddr>, 
+        network_config: NetworkConfig
+    ) -> Self {
+        let client_config = ClientConfig::current();
+        let camera_config = client_config.camera.clone();
         let camera = DefaultCamera::from(CameraComponent {
             eye: Point3::new(0.0, 1.0, 2.0),
             target: Point3::new(0.0, 0.0, 0.0),
             up: Vector3::new(0.0, 1.0, 0.0),
-            aspect: SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32,
-            fovy: 60.0,
-            znear: 0.1,
-            zfar: 100.0,
+            aspect: client_config.window.width as f32 / client_config.window.height as f32,
+            fovy: camera_config.fovy,
+            znear: camera_config.znear,
+            zfar: camera_config.zfar,
         });
 
-        let (ip, port) = match get_addr() {
-            Ok((ip, port)) => (ip, port),
-            Err(e) => {
-                panic!("{}", e);
-            }
+        let connection_state = match transport.is_connected() || transport.is_connecting() {
+            true => ConnectionState::Connecting,
+            false => ConnectionState::Disconnected,
         };
-        // let ip = "127.0.0.1".to_string();
-        // let port = 8080;
-        let addr = format!("{}:{}", ip, port);
-        let stream = TcpStream::connect(addr).unwrap();
-        stream.set_nonblocking(true).unwrap();
 
-        Self {
+        let mut scene = Self {
             camera,
-            camera_offset: Vector3::new(0.0, 2.0, 4.0),
+            board: BoardLayout::default(),
+            walls: HashSet::new(),
+            viewport: (client_config.window.width as f32, client_config.window.height as f32),
+            cursor: None,
+            orbiting: false,
+            camera_offset: Vector3::new(0.0, 0.0, 0.0),
+            camera_config: camera_config.clone(),
+            camera_focus: None,
+            followed_last: None,
+            look_ahead_direction: Vector3::new(0.0, 0.0, 0.0),
+            camera_distance: 0.0,
+            camera_yaw: 0.0,
+            camera_pitch: 0.0,
+
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            background_transition: None,
+            skybox: client_config.render.skybox.clone(),
 
-            background_color: Color::from_rgb(0.1, 0.3, 0.5),
+            assets: AssetCache::new(),
+            world: World::new(),
+            tiles: Vec::new(),
+            markers: HashMap::new(),
+            selected: None,
+            names: HashMap::new(),
+            name_tags: HashMap::new(),
+            frame_stats: FrameStats::new(HUD_FPS_FRAMES),
+            colors: HashMap::new(),
+            kinds: HashMap::new(),
+            scoreboard: Vec::new(),
+            show_scoreboard: false,
+            show_debug_panel: false,
+            show_minimap: client_config.hud.minimap,
+            minimap_size: client_config.hud.minimap_size as f32,
+            despawning: Vec::new(),
+            pickups: Vec::new(),
+            particles: ParticleSystem::new(),
+            animations: None,
+            despawn_fade: None,
+            player_color: Some(PLAYER_COLOR),
+            interpolation_rate: DEFAULT_INTERPOLATION_RATE,
 
-            models: Vec::new(),
-            objects: Vec::new(),
-            objects_from_server: HashMap::new(),
+            player_id: None,
+            spectate_target: None,
+            free_camera: false,
+            spectating: false,
 
-            player_id: 0,
+            input_map: client_config.keys.clone(),
+            held_keys: HashSet::new(),
+            pause_requested: false,
+            last_move_sent: None,
+            gamepad_direction: None,
+            last_resend: Instant::now(),
+            clock: Instant::now(),
+            text_input: TextInput::new(),
+            chat: ChatHistory::default(),
+            input_actions: VecDeque::new(),
+            move_batch: Vec::new(),
+            move_seq: 0,
+            pending_moves: VecDeque::new(),
+            server_cell: None,
+            batch_ticks: 0,
 
             // ip,
             // port,
-            stream,
+            network_config,
+            server_addr,
+            transport,
+            next_retry: Instant::now(),
+            retry_attempts: 0,
+            handshake_started: Instant::now(),
+            last_heartbeat: Instant::now(),
+            ping_epoch: Instant::now(),
+            round_trip: None,
+            last_server_seen: Instant::now(),
+            handshake_error: None,
+            incompatible_server: None,
             packet_parser: PacketParser::new(),
-        }
+            last_update_request: None,
+            update_pending: false,
+            server_pushes_updates: false,
+            last_seq: None,
+            session: None,
+            name_key: None,
+            rooms_supported: false,
+            matchmaking_supported: false,
+            match_search: None,
+            match_players: Vec::new(),
+            rooms: Vec::new(),
+            room: None,
+            room_error: None,
+            udp: None,
+            outgoing: Vec::new(),
+            connection_state,
+            parse_warnings: logging::Throttle::new(PARSE_WARNING_INTERVAL),
+            replay: None,
+        };
+        scene.set_camera_offset(camera_config.offset);
+        scene
     }
 
-    fn load_models(&mut self, device: &wgpu::Device) {
+    /// 시야각, 클리핑 거리, 카메라 위치와 따라가는 방식을 `config`로 바꿈
+    pub fn set_camera_config(&mut self, config: &CameraConfig) {
+        let component = &mut self.camera.component;
+        component.fovy = config.fovy;
+        component.znear = config.znear;
+        component.zfar = config.zfar;
+        self.camera_config = config.clone();
+        self.set_camera_offset(config.offset);
+    }
+
+    /// 로딩 화면에서 미리 `mesh_cache`에 넣어둘 파일
+    pub const MODEL_FILES: &'static [&'static str] = &["cube.obj", "pawn.obj"];
+
+    pub fn mesh_cache(&self) -> &MeshCache {
+        self.assets.mesh_cache()
+    }
+
+    pub fn mesh_cache_mut(&mut self) -> &mut MeshCache {
+        self.assets.mesh_cache_mut()
+    }
+
+    pub fn assets(&self) -> &AssetCache {
+        &self.assets
+    }
+
+    /// 모델 파일을 읽지 못하면 오류 (게임 씬을 보여줄 수 없음)
+    fn load_models(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<()> {
         block_on(async {
-            self.models = [
-                Model::load("cube.obj", device, 0.5, Color::LIGHT_GRAY).await.unwrap(),
-                Model::load("cube.obj", device, 0.5, Color::DARK_GRAY).await.unwrap(),
-                Model::load("pawn.obj", device, 0.8, Color::WHITE).await.unwrap(),
-                Model::load("pawn.obj", device, 0.8, Color::BLACK).await.unwrap(),
-            ].into_iter()
-            .map(|model| Rc::new(RefCell::new(model)))
-            .collect();
-        });
+            for (name, file_name, params) in FILE_MODELS {
+                self.assets.load_model(name, file_name, params, device, queue).await?;
+            }
+            self.assets.insert_model(MARKER_MODEL, Model::ring(device, queue, 0.35, 0.45, Color::YELLOW));
+
+            self.animations = AnimationSet::load(PAWN_ANIMATION_FILE).await
+                .inspect_err(|e| log::warn!(target: logging::SCENE, "Failed to load animations: {:#}", e))
+                .ok()
+                .map(Rc::new);
+            Ok(())
+        })
+    }
+
+    /// 파일이 바뀐 모델, 텍스처와 애니메이션을 다시 읽음. 엔티티는 모델 이름으로 그리므로 새 모델로 그려짐.
+    /// 읽지 못하면 이전 모델을 그대로 씀
+    pub fn reload_models(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<()> {
+        let old_assets = std::mem::take(&mut self.assets);
+        if let Err(e) = self.load_models(device, queue) {
+            self.assets = old_assets;
+            return Err(e);
+        }
+
+        let animated = self.world.network_entities()
+            .filter_map(|(_, entity)| Some((entity, self.world.renderables.get(entity)?.model)))
+            .collect::<Vec<_>>();
+        for (entity, model) in animated {
+            match self.animator_for(model) {
+                Some(animator) => self.world.animators.insert(entity, animator),
+                None => self.world.animators.remove(entity),
+            };
+        }
+        log::info!(target: logging::SCENE, "Reloaded {} models", self.assets.model_count());
+        Ok(())
     }
 
+    /// 보드 타일을 `board`에 맞게 다시 만듦
     fn build_objects(&mut self) {
-        self.objects = (0..64)
-            .into_iter()
-            .map(|idx| {
-                let object = Rc::new(RefCell::new(Object::new()));
-                
-                let x = idx % 8;
-                let z = idx / 8;
-                let model = self.models[(x+z) & 1].clone();
-                model.borrow_mut().add_instance(object.clone());
-                object.borrow_mut().set_model(Rc::downgrade(&model));
-                object.borrow_mut().transform.position = Vector3::new(
-                    x as f32, 
-                    -0.5, 
-                    z as f32
-                );
+        for tile in self.tiles.drain(..) {
+            self.world.despawn(tile);
+        }
 
-                object
+        self.tiles = (0..self.board.tile_count())
+            .map(|idx| {
+                let (x, z) = self.board.tile_cell(idx);
+                let model = match self.walls.contains(&idx) {
+                    true => WALL_MODEL,
+                    false => TILE_MODELS[((x+z) & 1) as usize],
+                };
+                let transform = Transform { position: self.tile_position(x, z), ..Transform::default() };
+                self.world.spawn_renderable(Renderable::new(model), transform)
             })
             .collect();
     }
 
-    fn player(&self) -> Option<Rc<RefCell<Object>>> {
-        self.objects_from_server.get(&self.player_id).cloned()
+    /// 바닥 타일은 윗면이 `y = 0`, 벽은 그 위에 놓임
+    fn tile_position(&self, x: i32, z: i32) -> Vector3<f32> {
+        let height = match self.walls.contains(&self.board.tile_index(x, z)) {
+            true => 0.5,
+            false => -0.5,
+        };
+        self.board.cell_to_world(x, z) + Vector3::new(0.0, height, 0.0)
     }
 
-    fn update_camera(&mut self) {
-        if let Some(player) = self.player(){
-            let p = player.borrow().transform.position;
-    
-            let point = Point3::new(p.x, p.y, p.z);
-    
-            self.camera.component.target = point;
-            self.camera.component.eye = point + self.camera_offset;
+    /// 서버가 보낸 맵으로 보드 크기와 벽을 바꾸고 타일을 다시 만듦. 간격과 위치는 그대로
+    fn apply_map(&mut self, width: i32, height: i32, tiles: &[Tile]) {
+        let size = width.checked_mul(height).filter(|_| width > 0 && height > 0);
+        if size.map(|size| size as usize) != Some(tiles.len()) {
+            log::warn!(target: logging::NET, "Ignoring {}x{} map with {} tiles", width, height, tiles.len());
+            return;
+        }
+
+        self.walls = tiles.iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile == Tile::Wall)
+            .map(|(idx, _)| idx)
+            .collect();
+        self.board = BoardLayout { width, height, ..self.board };
+        if !self.tiles.is_empty() {
+            self.build_objects();
         }
     }
 
-    fn pull_messages(&mut self) {
-        let mut buf = [0; 1024];
+    /// 보드 크기, 간격, 위치 변경. 타일은 바로 옮기고 (크기가 바뀌면 다시 만듦), 
+    /// 서버 오브젝트는 다음 `update`부터 새 위치로 이동
+    pub fn set_board_layout(&mut self, layout: BoardLayout) {
+        let resized = (layout.width, layout.height) != (self.board.width, self.board.height);
+        self.board = layout;
+        if resized && !self.tiles.is_empty() {
+            self.build_objects();
+            return;
+        }
 
-        match self.stream.read(&mut buf) {
-            Ok(0) => {
-                println!("Connection closed");
-            },
-            Ok(n) => {
-                // println!("Received: {}", msg);
-                self.packet_parser.push(&buf[..n]);
-            },
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                // println!("Would block");
-            },
-            Err(_) => {
-                
+        for (idx, &tile) in self.tiles.iter().enumerate() {
+            let (x, z) = self.board.tile_cell(idx);
+            let position = self.tile_position(x, z);
+            if let Some(transform) = self.world.transforms.get_mut(tile) {
+                transform.position = position;
             }
         }
     }
-    
-    fn process_message(&mut self, msg: &str) {
-        let msg = msg.trim().split_whitespace()
-            .map(|s| s.trim())
-            .collect::<Vec<&str>>();
 
-        // println!("Received: {:?}", msg);
+    pub fn board_layout(&self) -> &BoardLayout {
+        &self.board
+    }
+
+    /// 화면 좌표(창 픽셀)에 보이는 보드 칸 (서버 좌표).  
+    /// 화면 좌표에 투영되는 직선을 타일 윗면(`y = 0`)과 교차시켜 칸을 찾음.
+    pub fn pick_cell(&self, x: f32, y: f32) -> Option<(i32, i32)> {
+        let ray = Ray::from_screen(self.camera.build_view_projection_matrix(), (x, y), self.viewport)?;
+        let hit = ray.intersect_plane_y(self.board.origin.y)?;
+        self.board.world_to_cell(hit.x, hit.z)
+    }
+
+    /// `pick_cell` 칸의 보드 타일 인덱스
+    pub fn pick_tile(&self, x: f32, y: f32) -> Option<usize> {
+        let (cell_x, cell_z) = self.pick_cell(x, y)?;
+        Some(self.board.tile_index(cell_x, cell_z))
+    }
 
-        if msg.len() == 0 {
+    /// 클릭한 타일 위의 서버 오브젝트를 선택 (없으면 선택 해제)하고, 벽이 아니면 플레이어를 그 칸으로 보냄.
+    /// 가는 길은 서버가 찾고, 이동 키를 누르면 멈춤
+    fn process_click(&mut self, x: f32, y: f32) {
+        let Some((cell_x, cell_z)) = self.pick_cell(x, y) else {
             return;
+        };
+        let tile = self.board.tile_index(cell_x, cell_z);
+        if !self.walls.contains(&tile) && self.player_id.is_some() && !self.spectating {
+            log::debug!(target: logging::INPUT, "Move to ({} {})", cell_x, cell_z);
+            self.send(&ClientMessage::MoveTo { x: cell_x, z: cell_z });
         }
 
-        if msg[0] != "GAMESERVER" {
-            return;
+        let selected = self.world.network_entities()
+            .find(|&(_, entity)| {
+                self.world.transforms.get(entity)
+                    .and_then(|transform| self.board.world_to_cell(transform.position.x, transform.position.z))
+                    .is_some_and(|(x, z)| self.board.tile_index(x, z) == tile)
+            })
+            .map(|(id, _)| id);
+
+        self.select(selected);
+    }
+
+    fn player(&self) -> Option<Entity> {
+        self.world.network_entity(self.player_id?)
+    }
+
+    /// 서버 오브젝트 `id`의 색. 자기 플레이어는 `player_color`가 있으면 그 색, 나머지는 서버가 정한 색
+    fn object_color(&self, id: u32) -> Option<Color> {
+        match self.player_color {
+            Some(color) if Some(id) == self.player_id => Some(color),
+            _ => self.colors.get(&id).copied(),
         }
+    }
 
-        let msg = &msg[1..];
+    /// 서버 오브젝트 `id`를 그리는 모델의 이름
+    fn model_for(&self, id: u32) -> &'static str {
+        if Some(id) == self.player_id {
+            return PLAYER_MODEL;
+        }
+        let kind = self.kinds.get(&id).copied().unwrap_or_default();
+        ENTITY_MODELS.iter()
+            .find(|(entity, _)| *entity == kind)
+            .map(|(_, model)| *model)
+            .unwrap()
+    }
 
-        match msg[0] {
-            "init" => {
-                if msg.len() < 2 {
-                    return;
-                }
+    /// `model`로 그리는 오브젝트가 재생할 애니메이션
+    fn animator_for(&self, model: &str) -> Option<Animator> {
+        PAWN_MODELS.contains(&model)
+            .then(|| self.animations.clone())
+            .flatten()
+            .map(Animator::new)
+    }
 
-                self.player_id = msg[1].parse::<u32>().unwrap();
-            }
+    /// 알림에 쓰는 이름. 이름이 없으면 NPC는 `npc`, 나머지는 id
+    fn display_name(&self, id: u32) -> String {
+        match (self.name_of(id), self.kind_of(id)) {
+            (Some(name), _) => name.to_string(),
+            (None, EntityKind::Npc) => EntityKind::Npc.as_str().to_string(),
+            (None, _) => id.to_string(),
+        }
+    }
 
-            "update" => {
-                if msg.len() < 2 {
-                    return;
-                }
+    pub fn kind_of(&self, id: u32) -> EntityKind {
+        self.kinds.get(&id).copied().unwrap_or_default()
+    }
 
-                let num_objects = msg[1].parse::<usize>().unwrap();
-                let mut valid_ids: Vec<u32> = Vec::new();
+    /// 이후에 만들어지는 플레이어 오브젝트에 적용됨
+    pub fn set_player_color(&mut self, color: Option<Color>) {
+        self.player_color = color;
+    }
 
-                for i in 0..num_objects {
-                    let idx = 2 + i * 3;
-                    let id = msg[idx].parse::<u32>().unwrap();
-                    let x = msg[idx+1].parse::<i32>().unwrap();
-                    let z = msg[idx+2].parse::<i32>().unwrap();
+    /// 서버에서 사라진 오브젝트가 투명해지며 사라지는 시간 설정.
+    /// `None`이면 바로 삭제.
+    pub fn set_despawn_fade(&mut self, duration: Option<Duration>) {
+        self.despawn_fade = duration;
+    }
 
-                    let object = self.objects_from_server.entry(id)
-                        .or_insert_with(|| {
-                            let object = Rc::new(RefCell::new(Object::new()));
-                            let idx = if id == self.player_id { 2 } else { 3 };
-                            let model = self.models[idx].clone();
-                            
-                            model.borrow_mut().add_instance(object.clone());
-                            object.borrow_mut().set_model(Rc::downgrade(&model));
-                            object
-                        });
+    /// 초당 목표 위치까지 남은 거리를 좁히는 비율. 클수록 서버 위치를 빨리 따라감.
+    /// 0 이하이면 보간 없이 바로 이동.
+    pub fn set_interpolation_rate(&mut self, rate: f32) {
+        self.interpolation_rate = rate;
+    }
 
-                    let mut object = object.borrow_mut();
-                    object.transform.position.x = x as f32;
-                    object.transform.position.z = z as f32;
-                    
-                    valid_ids.push(id);
-                }
+    /// 다른 오브젝트를 받은 스냅샷 사이로 보간해서 이 시간만큼 늦게 그림.
+    /// 0이면 스냅샷을 버리고 `interpolation_rate`로 목표 위치를 따라감.
+    pub fn set_interpolation_delay(&mut self, delay: Duration) {
+        self.network_config.interpolation_delay = delay;
 
-                // 기존에 있던 id가 안보이면 삭제
-                self.objects_from_server.retain(|k, object| {
-                    let contains = valid_ids.contains(k);
-                    if !contains {
-                        if let Some(model) = object.borrow().model.upgrade() {
-                            let mut model = model.borrow_mut();
-                            model.remove_instance(object.clone());
-                        }
-                    }
-                    contains
-                });
+        let remote = self.world.network_entities()
+            .filter(|(id, _)| Some(*id) != self.player_id)
+            .map(|(_, entity)| entity)
+            .collect::<Vec<_>>();
+        let now = Instant::now();
+        for entity in remote {
+            if !self.buffers_snapshots() {
+                self.world.snapshots.remove(entity);
+            } else if !self.world.snapshots.contains(entity) {
+                let Some(transform) = self.world.transforms.get(entity) else {
+                    continue;
+                };
+                let snapshot = Snapshot::new(now, transform.position, transform.rotation);
+                self.world.snapshots.insert(entity, SnapshotBuffer::new(snapshot));
             }
-            _ => {}
         }
     }
 
-    fn process_keyboard_input(&mut self, state: &ElementState, keycode: &KeyCode) -> bool {
-        match state {
-            ElementState::Pressed => {
-                let mut direction = Vector2::new(0, 0);
+    /// 다른 오브젝트를 스냅샷 버퍼로 움직이는지. 보간하지 않으면 (`interpolation_rate` 0 이하) 버퍼도 쓰지 않음
+    fn buffers_snapshots(&self) -> bool {
+        self.interpolation_rate > 0.0 && !self.network_config.interpolation_delay.is_zero()
+    }
 
-                match keycode {
-                    KeyCode::KeyW => direction.y = -1,
-                    KeyCode::KeyA => direction.x = -1,
-                    KeyCode::KeyS => direction.y = 1,
-                    KeyCode::KeyD => direction.x = 1,
-                    _ => return false,
-                }
-                
-                println!("Move ({} {})", direction.x, direction.y);
+    /// 서버에서 받은 오브젝트를 사라지는 중인 것까지 바로 모두 삭제
+    fn clear_remote_objects(&mut self) {
+        let remote = self.world.network_entities().map(|(_, entity)| entity).collect::<Vec<_>>();
+        for entity in remote {
+            self.world.despawn(entity);
+        }
+        for (_, entity, _) in self.despawning.drain(..) {
+            self.world.despawn(entity);
+        }
+        for (entity, _, _) in self.pickups.drain(..) {
+            self.world.despawn(entity);
+        }
+    }
+
+    /// 사라지는 중에 서버가 다시 보낸 오브젝트를 원래 모습으로 되돌림
+    fn restore_despawning(&mut self, id: u32) {
+        let Some(idx) = self.despawning.iter().position(|(despawning, _, _)| *despawning == id) else {
+            return;
+        };
+
+        let (_, entity, _) = self.despawning.swap_remove(idx);
+        if let Some(renderable) = self.world.renderables.get_mut(entity) {
+            renderable.alpha = 1.0;
+        }
+        if let Some(transform) = self.world.transforms.get_mut(entity) {
+            transform.scale = 1.0;
+        }
+        self.world.set_network_id(entity, id);
+    }
 
-                // println!("{}", self.stream.peer_addr().unwrap());
-                let msg = format!("move {} {} {}\n", self.player_id, direction.x, direction.y);
-                self.stream.write_all(msg.as_bytes())
-                    .expect("Failed to write to stream");
+    /// 서버에 전체 상태를 요청. 응답(`keyframe`)을 받으면 오브젝트를 모두 교체함
+    pub fn request_resync(&mut self) {
+        self.send(&ClientMessage::Resync);
+    }
 
-                println!("Sent ok");
+    /// 서버에 방 목록을 요청. 응답을 받으면 `rooms`가 바뀜
+    pub fn request_rooms(&mut self) {
+        self.send(&ClientMessage::Rooms);
+    }
 
-                true
-            }
-            ElementState::Released => false
+    /// 방을 만들고 들어감. 들어가면 `room`이 바뀌고, 실패하면 `room_error`에 이유가 남음
+    pub fn create_room(&mut self, name: &str, capacity: usize) {
+        self.room_error = None;
+        self.send(&ClientMessage::CreateRoom { name: name.to_string(), capacity });
+    }
+
+    pub fn join_room(&mut self, room: u32) {
+        self.room_error = None;
+        self.send(&ClientMessage::JoinRoom { room });
+    }
+
+    /// 매치 대기열에 들어감. 매치가 잡히면 서버가 새 방으로 옮기고 `room`이 바뀜
+    pub fn find_match(&mut self) {
+        if !self.matchmaking_supported {
+            return;
         }
+        self.room_error = None;
+        self.match_search = Some((0, 0));
+        self.send(&ClientMessage::Queue);
     }
-}
 
-impl Scene for GameScene {
-    fn init(&mut self, device: &wgpu::Device) {
-        self.load_models(device);
-        self.build_objects();
+    pub fn cancel_match(&mut self) {
+        if self.match_search.take().is_some() {
+            self.send(&ClientMessage::Unqueue);
+        }
     }
 
-    fn handle_event(&mut self, event: &WindowEvent) -> bool {
-        match event {
-            WindowEvent::KeyboardInput {
-                event: KeyEvent {
-                    state,
-                    physical_key: PhysicalKey::Code(keycode),
-                    repeat: false,
-                    ..
-                },
-                ..
-            } => self.process_keyboard_input(state, keycode),
-            _ => false,
+    /// 매치 대기열에 있으면 (기다리는 인원, 매치 인원)
+    pub fn match_search(&self) -> Option<(usize, usize)> {
+        self.match_search
+    }
+
+    pub fn match_players(&self) -> &[u32] {
+        &self.match_players
+    }
+
+    pub fn supports_matchmaking(&self) -> bool {
+        self.matchmaking_supported
+    }
+
+    /// 서버가 방을 지원하는지. `init`을 받기 전에는 `false`
+    pub fn supports_rooms(&self) -> bool {
+        self.rooms_supported
+    }
+
+    pub fn rooms(&self) -> &[RoomInfo] {
+        &self.rooms
+    }
+
+    pub fn room(&self) -> Option<u32> {
+        self.room
+    }
+
+    pub fn room_error(&self) -> Option<&str> {
+        self.room_error.as_deref()
+    }
+
+    /// 최근 채팅 메세지 `n`개 (오래된 순서)
+    pub fn recent_chat(&self, n: usize) -> impl Iterator<Item = &ChatMessage> {
+        self.chat.recent(n)
+    }
+
+    /// 화면에 보여줄 채팅 줄 (오래된 순서). 입력 중이면 오래된 메세지와 입력 중인 줄도 보여줌
+    pub fn chat_overlay(&self, now: Instant) -> Vec<String> {
+        if !self.text_input.is_focused() {
+            return self.chat.visible(CHAT_OVERLAY_LINES, now, CHAT_OVERLAY_DURATION)
+                .map(ChatMessage::line)
+                .collect();
         }
+
+        self.chat.recent(CHAT_OVERLAY_LINES)
+            .map(ChatMessage::line)
+            .chain(std::iter::once(format!("> {}_", self.text_input.text())))
+            .collect()
     }
 
-    fn update(&mut self) {
-        self.stream.write_all(b"update\n")
-            .expect("Failed to write to stream");
+    pub fn scoreboard(&self) -> &[ScoreEntry] {
+        &self.scoreboard
+    }
 
-        self.pull_messages();
+    pub fn toggle_scoreboard(&mut self) {
+        self.show_scoreboard = !self.show_scoreboard;
+    }
 
-        while let Some(msg) = self.packet_parser.pop() {
-            let msg = String::from_utf8_lossy(&msg);
-            self.process_message(&msg);
+    pub fn toggle_debug_panel(&mut self) {
+        self.show_debug_panel = !self.show_debug_panel;
+    }
+
+    pub fn toggle_minimap(&mut self) {
+        self.show_minimap = !self.show_minimap;
+    }
+
+    /// 미니맵을 켰으면 그 위치와 크기
+    pub fn minimap(&self) -> Option<Minimap> {
+        self.show_minimap.then(|| Minimap::new(self.board, self.minimap_size, self.viewport, HUD_MARGIN))
+    }
+
+    /// 미니맵에 찍을 서버 오브젝트의 위치와 색. 자기 플레이어가 마지막이라 다른 점 위에 그려짐
+    pub fn minimap_dots(&self) -> Vec<(Vector3<f32>, Color)> {
+        let mut dots = self.world.network_entities()
+            .filter_map(|(id, entity)| {
+                let position = self.world.transforms.get(entity)?.position;
+                let color = match Some(id) == self.player_id {
+                    true => PLAYER_COLOR,
+                    false => MINIMAP_COLORS.iter()
+                        .find(|(kind, _)| *kind == self.kind_of(id))
+                        .map_or(Color::WHITE, |(_, color)| *color),
+                };
+                Some((Some(id) == self.player_id, position, color))
+            })
+            .collect::<Vec<_>>();
+        dots.sort_by_key(|(is_player, _, _)| *is_player);
+        dots.into_iter().map(|(_, position, color)| (position, color)).collect()
+    }
+
+    /// 디버그 패널을 켰으면 화면 오른쪽 위에 보여줄 카메라, 오브젝트 수와 네트워크 상태
+    pub fn debug_panel(&self) -> Option<Vec<String>> {
+        if !self.show_debug_panel {
+            return None;
         }
 
-        self.update_camera();
+        let vector = |v: Vector3<f32>| format!("{:.1} {:.1} {:.1}", v.x, v.y, v.z);
+        let on_off = |on: bool| if on { "ON" } else { "OFF" };
+        Some(vec![
+            format!("EYE {}", vector(self.camera.component.eye.to_vec())),
+            format!("OFFSET {}", vector(self.camera_offset())),
+            format!("FREE CAMERA {}", on_off(self.free_camera)),
+            format!("OBJECTS {}", self.world.len()),
+            format!("SERVER OBJECTS {}", self.world.network_count()),
+            format!(
+                "DRAWN {}/{}",
+                self.assets.models().map(|model| model.borrow().drawn_count()).sum::<usize>(),
+                self.assets.models().map(|model| model.borrow().instance_count()).sum::<usize>(),
+            ),
+            format!("PENDING MOVES {}", self.pending_moves.len()),
+            format!("LAST SEQ {}", self.last_seq.map_or_else(|| "-".to_string(), |seq| seq.to_string())),
+            format!("UDP {}", on_off(self.udp.is_some())),
+            format!("INTERPOLATION {:.1}", self.interpolation_rate),
+            format!("INTERPOLATION DELAY {}MS (-/=)", self.network_config.interpolation_delay.as_millis()),
+        ])
     }
 
+    /// 점수판을 켰으면 화면에 보여줄 줄 (`순위. 이름 점수`, 점수가 높은 순서)
+    pub fn scoreboard_overlay(&self) -> Option<Vec<String>> {
+        if !self.show_scoreboard {
+            return None;
+        }
 
-    fn view_proj(&self) -> cgmath::Matrix4<f32> {
-        self.camera.build_view_projection_matrix()
+        let lines = self.scoreboard.iter()
+            .take(SCOREBOARD_OVERLAY_LINES)
+            .enumerate()
+            .map(|(idx, entry)| format!("{}. {} {}", idx + 1, entry.name, entry.score))
+            .collect();
+        Some(lines)
     }
 
-    fn background_color(&self) -> Color {
-        self.background_color
+    /// 게임이 끝났거나 오류에서 돌아왔을 때 GPU 리소스는 그대로 두고 씬만 초기 상태로 되돌림.
+    /// `rebuild_board`가 `true`이면 보드 타일도 새로 생성 (서버가 바꾼 타일 색상 초기화).
+    /// 이전 세션도 잊으므로 다시 접속하면 `resume`하지 않고 새 플레이어로 시작함
+    pub fn reset(&mut self, rebuild_board: bool) {
+        self.clear_remote_objects();
+
+        for (_, marker) in self.markers.drain() {
+            self.world.despawn(marker);
+        }
+        self.names.clear();
+        self.update_name_tags();
+        self.colors.clear();
+        self.kinds.clear();
+        self.scoreboard.clear();
+
+        self.player_id = None;
+        self.session = None;
+        self.last_seq = None;
+        self.update_pending = false;
+        self.last_update_request = None;
+        self.spectate_target = None;
+        self.camera_focus = None;
+        self.followed_last = None;
+        self.selected = None;
+        self.background_color = DEFAULT_BACKGROUND_COLOR;
+        self.background_transition = None;
+        self.held_keys.clear();
+        self.gamepad_direction = None;
+        self.text_input.unfocus();
+        self.input_actions.clear();
+        self.move_batch.clear();
+        self.batch_ticks = 0;
+        self.move_seq = 0;
+        self.pending_moves.clear();
+        self.server_cell = None;
+        self.chat.clear();
+        self.packet_parser.clear();
+
+        if rebuild_board {
+            self.build_objects();
+        }
     }
-    
 
-    fn models(&self) -> impl Iterator<Item = &Rc<RefCell<Model>>> {
-        self.models.iter()
+    fn update_background(&mut self, now: Instant) {
+        if let Some((from, to, started)) = self.background_transition {
+            let t = now.saturating_duration_since(started).as_secs_f32() 
+                / BACKGROUND_TRANSITION.as_secs_f32();
+
+            self.background_color = from.lerp(&to, easing::ease_in_out_quad(t));
+            if t >= 1.0 {
+                self.background_transition = None;
+            }
+        }
+    }
+
+    /// 사라지는 중인 오브젝트의 투명도와 크기를 갱신하고, 다 사라진 오브젝트는 삭제
+    fn update_despawning(&mut self, now: Instant) {
+        let fade = self.despawn_fade;
+
+        let world = &mut self.world;
+        self.despawning.retain(|&(_, entity, started)| {
+            let t = match fade {
+                Some(fade) if !fade.is_zero() => {
+                    now.saturating_duration_since(started).as_secs_f32() / fade.as_secs_f32()
+                },
+                _ => 1.0,
+            };
+
+            if t >= 1.0 {
+                world.despawn(entity);
+                return false;
+            }
+
+            if let Some(renderable) = world.renderables.get_mut(entity) {
+                renderable.alpha = 1.0 - easing::ease_in_quad(t);
+            }
+            if let Some(transform) = world.transforms.get_mut(entity) {
+                transform.scale = 1.0 - easing::ease_in_quad(t);
+            }
+            true
+        });
+    }
+
+    /// 주운 아이템을 떠오르며 돌고 투명해지게 하고, 끝난 효과는 삭제
+    fn update_pickups(&mut self, now: Instant) {
+        let world = &mut self.world;
+        self.pickups.retain(|&(entity, start, started)| {
+            let t = now.saturating_duration_since(started).as_secs_f32() / PICKUP_EFFECT.as_secs_f32();
+            if t >= 1.0 {
+                world.despawn(entity);
+                return false;
+            }
+
+            if let Some(transform) = world.transforms.get_mut(entity) {
+                transform.position = start + Vector3::unit_y() * PICKUP_RISE * easing::ease_out_quad(t);
+                transform.set_yaw(PICKUP_SPIN_DEGREES * t);
+            }
+            if let Some(renderable) = world.renderables.get_mut(entity) {
+                renderable.alpha = 1.0 - easing::ease_in_quad(t);
+            }
+            true
+        });
+    }
+
+    pub fn selected(&self) -> Option<u32> {
+        self.selected
+    }
+
+    /// 서버 오브젝트 선택. 선택된 오브젝트에는 별도의 표시가 그려짐
+    pub fn select(&mut self, id: Option<u32>) {
+        self.selected = id;
+    }
+
+    /// 표시를 따라갈 오브젝트 위로 옮김. 따라갈 오브젝트가 없으면 표시를 숨김
+    fn update_markers(&mut self) {
+        let targets = [
+            (Marker::LocalPlayer, self.player()),
+            (Marker::Selection, self.selected.and_then(|id| self.world.network_entity(id))),
+        ];
+
+        for (marker, target) in targets {
+            let Some(position) = target.and_then(|target| self.world.transforms.get(target)).map(|t| t.position) else {
+                if let Some(entity) = self.markers.remove(&marker) {
+                    self.world.despawn(entity);
+                }
+                continue;
+            };
+
+            let world = &mut self.world;
+            let entity = *self.markers.entry(marker)
+                .or_insert_with(|| world.spawn_renderable(Renderable::new(MARKER_MODEL), Transform::default()));
+            if let Some(transform) = world.transforms.get_mut(entity) {
+                transform.position = position + Vector3::new(0.0, 0.01, 0.0);
+            }
+        }
     }
 
-    fn objects(&self) -> impl Iterator<Item = &Rc<RefCell<Object>>> {
-        self.objects.iter().chain(self.objects_from_server.values())
+    /// 서버가 알려준 플레이어 `id`의 이름
+    pub fn name_of(&self, id: u32) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    /// 이름이 바뀌거나 사라진 이름표를 지우고, 남은 이름표를 오브젝트 위에 카메라를 향하도록 놓음
+    fn update_name_tags(&mut self) {
+        let stale = self.name_tags.iter()
+            .filter(|(id, tag)| {
+                self.names.get(id) != Some(&tag.text) || self.world.network_entity(**id).is_none()
+            })
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+        for id in stale {
+            if let Some(tag) = self.name_tags.remove(&id) {
+                for (_, pixel) in tag.pixels {
+                    self.world.despawn(pixel);
+                }
+            }
+        }
+
+        // 카메라 방향의 수평 오른쪽. 바로 위에서 내려다보면 +x
+        let forward = self.camera.component.target - self.camera.component.eye;
+        let right = Vector3::new(-forward.z, 0.0, forward.x);
+        let right = match right.magnitude2() > f32::EPSILON {
+            true => right.normalize(),
+            false => Vector3::unit_x(),
+        };
+        let up = Vector3::unit_y();
+
+        let tagged = self.world.network_entities()
+            .filter_map(|(id, entity)| Some((id, self.world.transforms.get(entity)?.position)))
+            .collect::<Vec<_>>();
+        for (id, position) in tagged {
+            let Some(name) = self.names.get(&id) else {
+                continue;
+            };
+            let world = &mut self.world;
+            let tag = self.name_tags.entry(id).or_insert_with(|| NameTag {
+                text: name.clone(),
+                pixels: name_tag::layout(name).into_iter()
+                    .map(|offset| {
+                        let renderable = Renderable { color: Some(NAME_TAG_COLOR), ..Renderable::new(NAME_TAG_MODEL) };
+                        let transform = Transform { scale: NAME_TAG_PIXEL, ..Transform::default() };
+                        (offset, world.spawn_renderable(renderable, transform))
+                    })
+                    .collect(),
+            });
+
+            let base = position + up * NAME_TAG_HEIGHT;
+            for &(offset, pixel) in tag.pixels.iter() {
+                if let Some(transform) = world.transforms.get_mut(pixel) {
+                    transform.position = base + (right * offset.x + up * offset.y) * NAME_TAG_PIXEL;
+                }
+            }
+        }
+    }
+
+    /// 창 크기 (픽셀)
+    pub fn viewport(&self) -> (f32, f32) {
+        self.viewport
+    }
+
+    pub fn camera_offset(&self) -> Vector3<f32> {
+        self.camera_offset
+    }
+
+    /// 플레이어 기준 카메라 위치 설정.  
+    /// 거리는 `MIN_CAMERA_DISTANCE`..=`MAX_CAMERA_DISTANCE`로 제한되고, 
+    /// 길이가 0이거나 유효하지 않은 값은 무시함.
+    pub fn set_camera_offset(&mut self, offset: Vector3<f32>) {
+        let distance = offset.magnitude();
+        if !distance.is_normal() {
+            return;
+        }
+
+        let clamped = distance.clamp(MIN_CAMERA_DISTANCE, MAX_CAMERA_DISTANCE);
+        self.camera_offset = offset * (clamped / distance);
+        self.camera_distance = clamped;
+        self.camera_yaw = offset.x.atan2(offset.z);
+        self.camera_pitch = (offset.y / distance).asin().clamp(MIN_CAMERA_PITCH, MAX_CAMERA_PITCH);
+    }
+
+    /// 카메라를 플레이어 주위로 회전. pitch는 `MIN_CAMERA_PITCH`..=`MAX_CAMERA_PITCH`로 제한
+    pub fn orbit_camera(&mut self, yaw: f32, pitch: f32) {
+        self.camera_yaw += yaw;
+        self.camera_pitch = (self.camera_pitch + pitch).clamp(MIN_CAMERA_PITCH, MAX_CAMERA_PITCH);
+    }
+
+    /// 양수면 가까워짐. 거리는 `MIN_CAMERA_DISTANCE`..=`MAX_CAMERA_DISTANCE`로 제한
+    pub fn zoom_camera(&mut self, amount: f32) {
+        self.camera_distance = (self.camera_distance - amount).clamp(MIN_CAMERA_DISTANCE, MAX_CAMERA_DISTANCE);
+    }
+
+    /// 회전과 확대를 취소하고 설정의 카메라 위치로 되돌림
+    pub fn reset_camera(&mut self) {
+        self.set_camera_offset(self.camera_config.offset);
+    }
+
+    pub fn spectate_target(&self) -> Option<u32> {
+        self.spectate_target
+    }
+
+    /// 관전 대상을 다음 플레이어(id 오름차순, 마지막 다음은 처음)로 변경.
+    /// 관전중이 아니면 첫번째 플레이어부터 관전 시작.
+    pub fn cycle_spectate_target(&mut self) {
+        let mut ids = self.world.network_entities()
+            .map(|(id, _)| id)
+            .filter(|&id| Some(id) != self.player_id)
+            .collect::<Vec<u32>>();
+        ids.sort_unstable();
+
+        self.spectate_target = match self.spectate_target {
+            Some(current) => ids.iter()
+                .find(|&&id| id > current)
+                .or(ids.first())
+                .copied(),
+            None => ids.first().copied(),
+        };
+    }
+
+    pub fn stop_spectating(&mut self) {
+        self.spectate_target = None;
+    }
+
+    /// 끄면 다음 `update`에서 다시 따라가던 대상 위치로 돌아감
+    pub fn toggle_free_camera(&mut self) {
+        self.free_camera = !self.free_camera;
+    }
+
+    /// 직접 켰거나, 플레이어가 없거나 관전 중이고 따라갈 대상도 없으면 자유 카메라
+    pub fn is_free_camera(&self) -> bool {
+        self.free_camera || ((self.player_id.is_none() || self.spectating) && self.spectate_target.is_none())
+    }
+
+    pub fn is_spectating(&self) -> bool {
+        self.spectating
+    }
+
+    /// 누르고 있는 이동키 방향으로 카메라를 바닥과 평행하게 이동
+    fn move_free_camera(&mut self, dt: Duration) {
+        if !self.is_free_camera() {
+            return;
+        }
+        let held = self.held_direction();
+        if held == Vector2::new(0, 0) {
+            return;
+        }
+
+        let direction = self.camera_relative(held.cast::<f32>().unwrap()).normalize()
+            * FREE_CAMERA_SPEED * dt.as_secs_f32();
+        let delta = Vector3::new(direction.x, 0.0, direction.y);
+        self.camera.component.eye += delta;
+        self.camera.component.target += delta;
+    }
+
+    /// `dt`가 있으면 `follow_speed`에 맞춰 대상 쪽으로 다가가고, 없으면 바로 붙음.
+    /// 회전과 확대는 부드럽게 하지 않고 바로 적용함
+    fn follow_camera(&mut self, dt: Option<Duration>) {
+        // 관전 대상이 사라지면 다음 플레이어로 넘어감
+        if let Some(target) = self.spectate_target {
+            if self.world.network_entity(target).is_none() {
+                self.cycle_spectate_target();
+            }
+        }
+
+        let (yaw_sin, yaw_cos) = self.camera_yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.camera_pitch.sin_cos();
+        self.camera_offset = self.camera_distance 
+            * Vector3::new(pitch_cos * yaw_sin, pitch_sin, pitch_cos * yaw_cos);
+
+        // 자유 카메라는 위치를 유지하고 바라보는 방향만 바꿈. 돌아오면 대상에 바로 붙음
+        if self.is_free_camera() {
+            self.camera.component.target = self.camera.component.eye - self.camera_offset;
+            self.camera_focus = None;
+            self.followed_last = None;
+            return;
+        }
+
+        let followed = self.spectate_target.or(self.player_id)
+            .and_then(|id| Some((id, self.world.network_position(id)?)));
+        let Some((id, position)) = followed else {
+            return;
+        };
+
+        // 바닥에서 움직인 방향을 기억해서 멈춘 뒤에도 그 방향을 바라봄. 대상이 바뀌면 처음부터
+        match self.followed_last {
+            Some((last_id, last)) if last_id == id => {
+                let moved = Vector3::new(position.x - last.x, 0.0, position.z - last.z);
+                if moved.magnitude2() > f32::EPSILON {
+                    self.look_ahead_direction = moved.normalize();
+                }
+            }
+            _ => self.look_ahead_direction = Vector3::new(0.0, 0.0, 0.0),
+        }
+        self.followed_last = Some((id, position));
+
+        let goal = Point3::from_vec(position + self.look_ahead_direction * self.camera_config.look_ahead);
+        let focus = match (self.camera_focus, self.camera_config.follow_speed, dt) {
+            (Some(focus), Some(speed), Some(dt)) => {
+                // 프레임 간격과 상관없이 같은 시간에 같은 비율만큼 다가감
+                let t = 1.0 - (-speed * dt.as_secs_f32()).exp();
+                focus + (goal - focus) * t
+            }
+            _ => goal,
+        };
+
+        self.camera_focus = Some(focus);
+        self.camera.component.target = focus;
+        self.camera.component.eye = focus + self.camera_offset;
+    }
+
+    /// 현재 월드 상태의 복사본. 위치는 보간 중인 현재 값
+    pub fn world_snapshot(&self) -> WorldSnapshot {
+        let mut objects = Vec::with_capacity(self.world.network_count());
+        objects.extend(self.world.network_entities().filter_map(|(id, entity)| Some(ObjectSnapshot {
+            id,
+            position: self.world.transforms.get(entity)?.position,
+            is_local: Some(id) == self.player_id,
+        })));
+        objects.sort_unstable_by_key(|object| object.id);
+
+        WorldSnapshot { player_id: self.player_id, objects, connection: self.connection_state }
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection_state
+    }
+
+    fn set_connection_state(&mut self, state: ConnectionState) {
+        if self.connection_state != state {
+            log::debug!(target: logging::NET, "Connection state: {:?} -> {:?}", self.connection_state, state);
+            self.connection_state = state;
+        }
+    }
+
+    /// 연결을 닫고 `Disconnected`로 바꿈. 다음 `try_reconnect`에서 다시 접속함
+    fn close_connection(&mut self) {
+        let _ = self.transport.close();
+        self.set_connection_state(ConnectionState::Disconnected);
+    }
+
+    /// 연결 후 handshake에 실패했을 때의 오류 메세지
+    pub fn handshake_error(&self) -> Option<&str> {
+        self.handshake_error.as_deref()
+    }
+
+    /// 연결은 됐지만 `handshake_timeout` 안에 `init`이 오지 않으면 
+    /// (응답이 없는 서버, 다른 프로토콜의 서버) 오류를 남기고 다시 연결.
+    fn check_handshake(&mut self, now: Instant) {
+        if self.player_id.is_some() || self.replay.is_some() || self.connection_state == ConnectionState::Closed {
+            return;
+        }
+
+        let timeout = self.network_config.handshake_timeout;
+        if now.saturating_duration_since(self.handshake_started) < timeout {
+            return;
+        }
+
+        let error = match self.server_addr {
+            Some(addr) => format!("Handshake timed out: no init from server {} within {:?}", addr, timeout),
+            None => format!("Handshake timed out: no init from server within {:?}", timeout),
+        };
+        log::error!(target: logging::NET, "{}", error);
+        self.handshake_error = Some(error);
+
+        self.reconnect();
+        self.handshake_started = now;
+    }
+
+    fn reconnect(&mut self) {
+        let Some(addr) = self.server_addr else {
+            return;
+        };
+        if self.connection_state == ConnectionState::Closed {
+            return;
+        }
+
+        // 이미 접속 중이면 결과만 확인함
+        if !self.transport.is_connecting() {
+            self.set_connection_state(ConnectionState::Reconnecting { attempt: self.retry_attempts + 1 });
+        }
+        match self.transport.reconnect(addr, RECONNECT_TIMEOUT) {
+            Ok(()) => {
+                log::info!(target: logging::NET, "Reconnected to {}", addr);
+                self.set_connection_state(ConnectionState::Connecting);
+                self.retry_attempts = 0;
+                self.handshake_started = Instant::now();
+                self.last_server_seen = Instant::now();
+                // 새 연결의 서버는 일련번호를 0부터 셈
+                self.move_seq = 0;
+                self.pending_moves.clear();
+                self.packet_parser.clear();
+                self.outgoing.clear();
+                self.last_update_request = None;
+                self.update_pending = false;
+                self.server_pushes_updates = false;
+                self.last_seq = None;
+                self.udp = None;
+                if let Some((id, token)) = self.session {
+                    self.send(&ClientMessage::Resume { id, token });
+                }
+                self.request_resync();
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {},
+            Err(e) => {
+                log::warn!(target: logging::NET, "Failed to reconnect to {}: {}", addr, e);
+            }
+        }
+    }
+
+    /// 움직이지 않거나 프레임이 늦어져도 서버가 연결을 끊지 않도록 
+    /// `heartbeat_interval`마다 `ping`을 보냄
+    fn send_heartbeat(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_heartbeat) < self.network_config.heartbeat_interval {
+            return;
+        }
+
+        let stamp = now.saturating_duration_since(self.ping_epoch).as_millis() as u64;
+        self.send(&ClientMessage::Ping { stamp: Some(stamp) });
+        self.last_heartbeat = now;
+    }
+
+    /// `update_request_interval`마다 서버에 `update`를 요청.
+    /// 앞의 요청에 응답이 없으면 `update_request_timeout`이 지난 뒤에 다시 요청함.
+    /// 서버가 위치를 알아서 보내면 UDP로 받을 때만 요청함
+    fn request_update(&mut self, now: Instant) {
+        if self.server_pushes_updates && self.udp.is_none() {
+            return;
+        }
+        if let Some(last) = self.last_update_request {
+            let wait = match self.update_pending {
+                true => self.network_config.update_request_timeout,
+                false => self.network_config.update_request_interval,
+            };
+            if now.saturating_duration_since(last) < wait {
+                return;
+            }
+            if self.update_pending {
+                log::debug!(target: logging::NET, "No reply to update request, requesting again");
+            }
+        }
+        if !self.transport.is_connected() {
+            return;
+        }
+
+        match (&self.udp, self.session) {
+            (Some(udp), Some((id, token))) => {
+                // 보내지 못한 요청은 응답이 없는 요청과 같이 다시 보냄
+                if let Err(e) = udp.send(ClientMessage::DatagramUpdate { id, token }.encode().as_bytes()) {
+                    log::debug!(target: logging::NET, "Failed to send update datagram: {}", e);
+                }
+            }
+            _ => self.send(&ClientMessage::Update),
+        }
+        self.last_update_request = Some(now);
+        self.update_pending = true;
+    }
+
+    /// `stamp`(ms)를 담아 보낸 `ping`의 응답을 `now`에 받음
+    fn record_round_trip(&mut self, stamp: u64, now: Instant) {
+        let sent = self.ping_epoch + Duration::from_millis(stamp);
+        self.round_trip = Some(now.saturating_duration_since(sent));
+    }
+
+    /// 화면 왼쪽 위에 보여주는 FPS, 서버 왕복 시간, 플레이어 id와 연결 상태
+    pub fn hud_lines(&self) -> Vec<String> {
+        let fps = self.frame_stats.summary()
+            .map_or_else(|| "FPS -".to_string(), |summary| format!("FPS {:.0}", summary.fps));
+        let id = match (self.spectating, self.player_id) {
+            (true, _) => "SPECTATING".to_string(),
+            (false, Some(id)) => format!("ID {}", id),
+            (false, None) => "ID -".to_string(),
+        };
+        let connection = match (self.replay.is_some(), self.connection_state) {
+            (true, _) => "REPLAY".to_string(),
+            (false, ConnectionState::Connecting) => "CONNECTING".to_string(),
+            (false, ConnectionState::Connected) => "CONNECTED".to_string(),
+            (false, ConnectionState::Reconnecting { attempt }) => format!("RECONNECTING {}", attempt),
+            (false, ConnectionState::Disconnected) => "DISCONNECTED".to_string(),
+            (false, ConnectionState::Closed) => "SERVER CLOSED".to_string(),
+        };
+
+        let mut lines = vec![fps];
+        if self.replay.is_none() {
+            lines.push(self.ping_ms().map_or_else(|| "PING -".to_string(), |ms| format!("PING {} MS", ms)));
+        }
+        lines.push(id);
+        lines.push(connection);
+        lines
+    }
+
+    /// 마지막 `ping`의 왕복 시간(ms). 아직 `pong`을 받지 못했으면 `None`
+    pub fn ping_ms(&self) -> Option<u32> {
+        self.round_trip.map(|round_trip| round_trip.as_millis() as u32)
+    }
+
+    /// `stale_timeout` 동안 서버에서 `pong`이 오지 않았으면 `true`
+    pub fn is_stale(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_server_seen) > self.network_config.stale_timeout
+    }
+
+    /// 연결된 서버가 `stale_timeout` 동안 응답이 없으면 연결을 닫고 서버 오브젝트를 지움.
+    /// 다음 `try_reconnect`에서 다시 접속함
+    fn check_stale(&mut self, now: Instant) {
+        if self.connection_state != ConnectionState::Connected || !self.is_stale(now) {
+            return;
+        }
+
+        log::warn!(
+            target: logging::NET, 
+            "No response from server for {:?}, disconnecting", 
+            self.network_config.stale_timeout
+        );
+        self.close_connection();
+        self.clear_remote_objects();
+    }
+
+    /// 연결이 없으면 다시 접속. 접속하는 동안은 매번 결과를 확인하고,
+    /// 실패하면 `reconnect_backoff`만큼 기다린 뒤 다시 시도함. 그동안에도 씬은 그대로 갱신됨.
+    fn try_reconnect(&mut self, now: Instant) {
+        if self.transport.is_connected() || now < self.next_retry {
+            return;
+        }
+
+        self.reconnect();
+        if !self.transport.is_connected() && !self.transport.is_connecting() {
+            self.next_retry = now + reconnect_backoff(self.retry_attempts);
+            self.retry_attempts = self.retry_attempts.saturating_add(1);
+        }
+    }
+
+    /// 보낼 메세지를 `outgoing`에 추가. 실제 전송은 `flush_outgoing`에서 함.
+    /// 연결이 없으면 버림
+    fn send(&mut self, msg: &ClientMessage) {
+        if !self.transport.is_connected() {
+            return;
+        }
+
+        self.outgoing.extend_from_slice(msg.encode().as_bytes());
+    }
+
+    /// 쌓인 메세지를 보낼 수 있는 만큼 보내고, 남은 바이트는 다음 `flush_outgoing`에서 이어서 보냄.
+    /// 전송에 실패하면 연결을 끊은 것으로 처리
+    fn flush_outgoing(&mut self) {
+        while !self.outgoing.is_empty() && self.transport.is_connected() {
+            match self.transport.write(&self.outgoing) {
+                Ok(0) => {
+                    log::warn!(target: logging::NET, "Server stopped accepting data");
+                    self.close_connection();
+                }
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {},
+                Err(e) => {
+                    log::warn!(target: logging::NET, "Failed to send to server: {}", e);
+                    self.close_connection();
+                }
+            }
+        }
+
+        // 끊긴 연결에 보내던 메세지를 새 연결에 이어 보내면 메세지가 깨짐
+        if !self.transport.is_connected() {
+            self.outgoing.clear();
+        }
+    }
+
+    /// 지금 읽을 수 있는 데이터를 모두 읽어서 `packet_parser`에 쌓음.  
+    /// TCP는 메세지 경계를 지키지 않으므로 잘린 줄은 다음 읽기와 이어붙여짐 (`PacketParser` 참고).
+    fn pull_messages(&mut self) {
+        let mut buf = [0; 1024];
+
+        while self.transport.is_connected() {
+            match self.transport.read(&mut buf) {
+                Ok(0) => {
+                    log::info!(target: logging::NET, "Connection closed");
+                    self.close_connection();
+                    break;
+                },
+                Ok(n) => {
+                    log::trace!(target: logging::NET, "Received {} bytes", n);
+                    self.packet_parser.push(&buf[..n]);
+                },
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {},
+                Err(e) => {
+                    log::warn!(target: logging::NET, "Failed to read from server: {}", e);
+                    self.close_connection();
+                    break;
+                }
+            }
+        }
+    }
+
+    /// UDP로 받은 `update`를 처리. 데이터그램 하나가 메세지 하나임
+    fn pull_datagrams(&mut self) {
+        let mut buf = [0; 65536];
+
+        while let Some(udp) = &self.udp {
+            let n = match udp.recv(&mut buf) {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    log::debug!(target: logging::NET, "Failed to receive datagram: {}", e);
+                    break;
+                }
+            };
+
+            let msg = String::from_utf8_lossy(&buf[..n]).trim_end().to_string();
+            if let Err(e) = self.process_message(&msg) {
+                self.report_malformed(&msg, &e, Instant::now());
+            }
+        }
+    }
+
+    /// 재생 위치까지의 리플레이 메세지를 서버에서 받은 것처럼 `packet_parser`에 넣음
+    fn advance_replay(&mut self, dt: Duration) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+
+        for message in replay.advance(dt) {
+            self.packet_parser.push(message.as_bytes());
+            self.packet_parser.push(b"\n");
+        }
+    }
+
+    /// 리플레이 재생 위치를 `offset`만큼 옮김. 뒤로 가면 씬을 비우고 처음부터 다시 적용함
+    fn seek_replay(&mut self, offset: Duration, forward: bool) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+
+        let to = match forward {
+            true => replay.position().saturating_add(offset),
+            false => replay.position().saturating_sub(offset),
+        };
+        if replay.seek(to) {
+            self.reset(false);
+        }
+    }
+
+    /// 리플레이 재생 상태 (`12.3s / 60.0s x2 paused`). 리플레이가 아니면 `None`
+    pub fn replay_status(&self) -> Option<String> {
+        self.replay.as_ref().map(|replay| replay.to_string())
+    }
+
+    /// 완성된 줄만 처리. 마지막 잘린 줄은 다음 `pull_messages`까지 남겨둠
+    fn process_messages(&mut self) {
+        while let Some(msg) = self.packet_parser.pop() {
+            // 읽을 수 없는 서버의 나머지 메세지는 버림
+            if self.incompatible_server.is_some() {
+                continue;
+            }
+            let msg = String::from_utf8_lossy(&msg);
+            match decompress(&msg) {
+                Some(Ok(messages)) => {
+                    for line in messages.lines() {
+                        if let Err(e) = self.process_message(line) {
+                            self.report_malformed(line, &e, Instant::now());
+                        }
+                    }
+                }
+                Some(Err(e)) => self.report_malformed(&msg, &e, Instant::now()),
+                None => {
+                    if let Err(e) = self.process_message(&msg) {
+                        self.report_malformed(&msg, &e, Instant::now());
+                    }
+                }
+            }
+        }
+    }
+
+    /// 형식이 잘못된 메세지는 버리고 경고. 경고는 `PARSE_WARNING_INTERVAL`에 한 번만 출력
+    fn report_malformed(&mut self, msg: &str, error: &ProtocolError, now: Instant) {
+        if let Some(suppressed) = self.parse_warnings.hit(now) {
+            if suppressed > 0 {
+                log::warn!(
+                    target: logging::NET, 
+                    "Dropped malformed message {:?}: {} ({} more suppressed)", 
+                    msg, 
+                    error,
+                    suppressed
+                );
+            } else {
+                log::warn!(target: logging::NET, "Dropped malformed message {:?}: {}", msg, error);
+            }
+        }
+    }
+    
+    /// 새 오브젝트 `id`를 `position`에 추가할 수 있는지 확인.  
+    /// 최대 개수에 도달했으면 `overflow_policy`에 따라 거절하거나
+    /// 카메라에서 가장 먼 오브젝트(플레이어 제외)를 바로 제거함.
+    fn make_room_for(&mut self, id: u32, position: Vector3<f32>) -> bool {
+        if self.world.network_count() < self.network_config.max_remote_objects {
+            return true;
+        }
+
+        if self.network_config.overflow_policy == OverflowPolicy::RejectNew {
+            return false;
+        }
+
+        let eye = self.camera.component.eye;
+        let distance = |p: Vector3<f32>| Point3::from_vec(p).distance2(eye);
+
+        let furthest = self.world.network_entities()
+            .filter(|&(k, _)| Some(k) != self.player_id)
+            .filter_map(|(_, entity)| Some((entity, distance(self.world.transforms.get(entity)?.position))))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match furthest {
+            // 플레이어 자신은 항상 추가
+            Some((furthest, d)) if d > distance(position) || Some(id) == self.player_id => {
+                self.world.despawn(furthest);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 형식이 잘못된 메세지는 아무것도 바꾸지 않고 오류를 반환
+    fn process_message(&mut self, msg: &str) -> Result<(), ProtocolError> {
+        log::trace!(target: logging::NET, "Received: {:?}", msg);
+
+        if msg.trim().is_empty() {
+            return Ok(());
+        }
+
+        match ServerMessage::decode(msg)? {
+            // 더 높은 버전의 서버는 모르는 형식을 보낼 수 있으므로 연결을 닫고 다시 접속하지 않음
+            ServerMessage::Init { version: Some(version), .. } if !is_supported_version(Some(version)) => {
+                log::error!(
+                    target: logging::NET, 
+                    "Server protocol version {} is newer than client version {}, disconnecting", 
+                    version, 
+                    PROTOCOL_VERSION
+                );
+                let _ = self.transport.close();
+                self.set_connection_state(ConnectionState::Closed);
+                self.incompatible_server = Some(version);
+            }
+            ServerMessage::Init { id, version, token } => {
+                if let Some(version) = version.filter(|&version| version != PROTOCOL_VERSION) {
+                    log::warn!(
+                        target: logging::NET, 
+                        "Server protocol version {} differs from client version {}", 
+                        version, 
+                        PROTOCOL_VERSION
+                    );
+                }
+
+                self.player_id = Some(id);
+                self.session = token.map(|token| (id, token));
+                self.name_key = self.name_key.or(token);
+                self.server_pushes_updates = version.is_some_and(|version| version >= PUSHED_UPDATES_VERSION);
+                // 새로 만들어진 플레이어는 로비에서 시작함. `resume`한 경우 서버가 이어서 `joined`를 보냄
+                self.rooms_supported = version.is_some_and(|version| version >= ROOMS_VERSION);
+                self.room = self.rooms_supported.then_some(LOBBY_ROOM);
+                // 새 연결은 대기열에 없음
+                self.matchmaking_supported = version.is_some_and(|version| version >= MATCHMAKING_VERSION);
+                self.match_search = None;
+                // 이후 서버가 보내는 긴 메세지는 `deflate`로 압축됨
+                if self.network_config.compression && version.is_some_and(|version| version >= COMPRESSION_VERSION) {
+                    self.send(&ClientMessage::Compress);
+                }
+                // 이름은 플레이어마다 등록함. `resume`하면 서버가 끊기기 전의 이름을 유지함
+                self.names.clear();
+                if let Some(name) = self.network_config.player_name.clone() {
+                    if version.is_some_and(|version| version >= NAMES_VERSION) {
+                        let key = self.name_key.filter(|_| version.is_some_and(|version| version >= NAME_KEY_VERSION));
+                        self.send(&ClientMessage::Name { name, key });
+                    }
+                }
+                self.colors.clear();
+                if version.is_some_and(|version| version >= COLORS_VERSION) {
+                    self.send(&ClientMessage::Colors);
+                }
+                self.kinds.clear();
+                if version.is_some_and(|version| version >= KINDS_VERSION) {
+                    self.send(&ClientMessage::Kinds);
+                }
+                self.scoreboard.clear();
+                if version.is_some_and(|version| version >= SCOREBOARD_VERSION) {
+                    self.send(&ClientMessage::Scoreboard);
+                }
+                // 관전자는 서버가 UDP로 보내는 관심 영역이 없으므로 TCP로 받음
+                self.spectating = self.network_config.spectator
+                    && version.is_some_and(|version| version >= SPECTATOR_VERSION);
+                if self.spectating {
+                    self.send(&ClientMessage::Spectate);
+                }
+                if self.network_config.udp_updates && self.session.is_some() && !self.spectating {
+                    self.udp = self.server_addr.and_then(open_udp);
+                }
+                self.handshake_error = None;
+                self.set_connection_state(ConnectionState::Connected);
+            }
+
+            // keyframe은 기존 오브젝트를 모두 지우고 새로 만듦.
+            // 회전이 없는 메세지는 기존 회전을 유지.
+            // 일련번호가 있으면 마지막으로 적용한 것보다 큰 것만 적용함
+            ServerMessage::Update { kind, seq, objects } => {
+                if !self.accept_seq(seq) {
+                    return Ok(());
+                }
+
+                if kind.is_keyframe() {
+                    self.clear_remote_objects();
+                }
+                self.update_pending = false;
+
+                // 기존에 있던 id가 안보이면 삭제
+                let valid_ids = objects.iter().map(|object| object.id).collect::<Vec<u32>>();
+                self.despawn_remote_objects(|id| !valid_ids.contains(&id));
+                self.apply_object_states(objects);
+            }
+
+            // 사라진 id만 삭제하고 바뀐 오브젝트만 갱신
+            ServerMessage::Delta { seq, changed, removed } => {
+                if !self.accept_seq(Some(seq)) {
+                    return Ok(());
+                }
+                self.update_pending = false;
+
+                self.despawn_remote_objects(|id| removed.contains(&id));
+                self.apply_object_states(changed);
+            }
+
+            ServerMessage::Chat { sender, name, text } => {
+                let message = ChatMessage {
+                    sender,
+                    name,
+                    timestamp: Instant::now(),
+                    text,
+                };
+                log::info!(target: logging::NET, "[chat] {}", message.line());
+                self.chat.push(message);
+            }
+
+            ServerMessage::BgColor(hex) => {
+                let color = Color::from_hex(&hex)
+                    .ok_or(ProtocolError::BadColor(hex))?;
+                self.background_transition = Some((self.background_color, color, Instant::now()));
+            }
+
+            // 지정된 타일만 색상을 바꾸고, 나머지는 기본 체크무늬 유지
+            ServerMessage::Tiles(tiles) => {
+                for (idx, hex) in tiles {
+                    let tile = self.tiles.get(idx).and_then(|&tile| self.world.renderables.get_mut(tile));
+                    if let (Some(tile), Some(color)) = (tile, Color::from_hex(&hex)) {
+                        tile.color = Some(color);
+                    }
+                }
+            }
+
+            // 서버가 seq 전까지의 이동을 모두 처리함
+            ServerMessage::Ack { seq } => {
+                self.pending_moves.retain(|&(pending, _)| pending >= seq);
+            }
+
+            ServerMessage::Rooms(rooms) => self.rooms = rooms,
+
+            ServerMessage::Names(names) => self.names = names.into_iter().collect(),
+
+            ServerMessage::Scoreboard(entries) => self.scoreboard = entries,
+
+            // 아이템은 다음 위치 메세지에서 사라지지만, 그 전에 효과로 옮겨서 바로 지워지지 않게 함
+            ServerMessage::Pickup { player, item } => {
+                log::info!(target: logging::NET, "{} picked up item {}", self.display_name(player), item);
+                if let Some(entity) = self.world.network_entity(item) {
+                    self.world.remove_network_id(entity);
+                    let start = self.world.transforms.get(entity).map_or(Vector3::new(0.0, 0.0, 0.0), |t| t.position);
+                    self.particles.emit(Effect::Pickup, start, None);
+                    self.pickups.push((entity, start, Instant::now()));
+                }
+                self.kinds.remove(&item);
+            }
+
+            // 잡힌 오브젝트의 새 위치는 다음 위치 메세지로 옴. 채팅 기록에 알림으로 남김
+            ServerMessage::Capture { by, target } => {
+                let message = ChatMessage {
+                    sender: by,
+                    name: Some(CAPTURE_SENDER.to_string()),
+                    timestamp: Instant::now(),
+                    text: format!("{} captured {}", self.display_name(by), self.display_name(target)),
+                };
+                log::info!(target: logging::NET, "{}", message.text);
+                self.chat.push(message);
+                if let Some(position) = self.world.network_position(target) {
+                    self.particles.emit(Effect::Capture, position, self.object_color(by));
+                }
+            }
+
+            // `init` 바로 뒤에 옴. 이전 서버는 보내지 않으므로 기본 8x8 보드
+            ServerMessage::Map { width, height, tiles } => self.apply_map(width, height, &tiles),
+
+            // 보통 위치보다 먼저 오지만, 이미 있는 오브젝트는 모델을 바꿈
+            ServerMessage::Spawn(entities) => {
+                for (id, kind) in entities {
+                    self.kinds.insert(id, kind);
+                    let Some(entity) = self.world.network_entity(id) else {
+                        continue;
+                    };
+                    let model = self.model_for(id);
+                    let animator = self.animator_for(model);
+                    if let Some(renderable) = self.world.renderables.get_mut(entity).filter(|r| r.model != model) {
+                        renderable.model = model;
+                        match animator {
+                            Some(animator) => self.world.animators.insert(entity, animator),
+                            None => self.world.animators.remove(entity),
+                        };
+                    }
+                }
+            }
+
+            // 잘못된 색은 무시하고, 이미 있는 오브젝트에도 바로 적용
+            ServerMessage::Colors(colors) => {
+                self.colors = colors.into_iter()
+                    .filter_map(|(id, hex)| Some((id, Color::from_hex(&hex)?)))
+                    .collect();
+                let objects = self.world.network_entities().collect::<Vec<_>>();
+                for (id, entity) in objects {
+                    let color = self.object_color(id);
+                    if let Some(renderable) = self.world.renderables.get_mut(entity) {
+                        renderable.color = color;
+                    }
+                }
+            }
+
+            // 이전 방의 오브젝트는 새 방의 위치 메세지를 기다리지 않고 바로 지움
+            ServerMessage::Joined { room } => {
+                if self.room != Some(room) {
+                    self.clear_remote_objects();
+                }
+                self.room = Some(room);
+                self.room_error = None;
+            }
+
+            ServerMessage::JoinFailed { reason } => {
+                log::warn!(target: logging::NET, "Failed to join room: {}", reason);
+                self.room_error = Some(reason);
+                self.match_search = None;
+            }
+
+            ServerMessage::Queued { waiting, size } => {
+                if self.match_search.is_some() {
+                    self.match_search = Some((waiting, size));
+                }
+            }
+
+            // 방은 앞서 온 `joined`로 이미 옮김
+            ServerMessage::MatchStart { room, players } => {
+                log::info!(target: logging::NET, "Match started in room {} with {} players", room, players.len());
+                self.match_search = None;
+                self.match_players = players;
+            }
+
+            // 서버는 이어서 연결을 닫음. 먼저 닫혔어도 (`Disconnected`) 다시 접속하지 않도록 바꿈
+            ServerMessage::Closing => {
+                log::info!(target: logging::NET, "Server shut down");
+                let _ = self.transport.close();
+                self.clear_remote_objects();
+                self.set_connection_state(ConnectionState::Closed);
+            }
+
+            // ping 응답. 이전 서버는 stamp를 돌려주지 않음
+            ServerMessage::Pong { stamp } => {
+                let now = Instant::now();
+                self.last_server_seen = now;
+                if let Some(stamp) = stamp {
+                    self.record_round_trip(stamp, now);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 일련번호가 없거나 마지막으로 적용한 것보다 크면 기록하고 `true`
+    fn accept_seq(&mut self, seq: Option<u32>) -> bool {
+        let Some(seq) = seq else {
+            return true;
+        };
+        if self.last_seq.is_some_and(|last| seq <= last) {
+            log::debug!(target: logging::NET, "Dropped out-of-order position message (seq {})", seq);
+            return false;
+        }
+
+        self.last_seq = Some(seq);
+        true
+    }
+
+    /// `remove`가 `true`인 서버 오브젝트를 삭제. `despawn_fade`가 있으면 사라지는 효과 후 삭제.
+    /// 새 오브젝트보다 먼저 지워서 같은 메세지의 새 id가 빈 자리를 사용할 수 있음
+    fn despawn_remote_objects(&mut self, remove: impl Fn(u32) -> bool) {
+        let removed = self.world.network_entities()
+            .filter(|&(k, _)| remove(k))
+            .collect::<Vec<_>>();
+
+        let now = Instant::now();
+        for (id, entity) in removed {
+            // 다시 보이면 서버가 `spawn`을 다시 보냄
+            self.kinds.remove(&id);
+            match self.despawn_fade {
+                Some(_) => {
+                    self.world.remove_network_id(entity);
+                    self.despawning.push((id, entity, now));
+                }
+                None => {
+                    self.world.despawn(entity);
+                }
+            }
+        }
+    }
+
+    /// 서버가 보낸 위치를 적용. 없는 오브젝트는 최대 개수 안에서 새로 만듦
+    fn apply_object_states(&mut self, objects: Vec<ObjectState>) {
+        let mut dropped = 0;
+
+        for ObjectState { id, x, y, z, yaw } in objects {
+            let mut position = self.board.cell_to_world(x, z);
+            position.y = y as f32;
+
+            // 플레이어 자신은 서버 위치에 아직 처리되지 않은 이동을 다시 적용한 위치
+            let is_player = Some(id) == self.player_id;
+            if is_player {
+                self.server_cell = Some(Vector2::new(x, z));
+                position = self.predicted_position(position.y).unwrap_or(position);
+            }
+
+            if self.world.network_entity(id).is_none() {
+                self.restore_despawning(id);
+            }
+            let buffered = self.buffers_snapshots() && !is_player;
+            let now = Instant::now();
+
+            // 이미 있는 오브젝트는 목표 위치와 회전으로 보간.
+            // 플레이어 자신은 예측이 틀렸을 때만 보정하고, 예측한 이동이 남아 있으면 그 방향을 유지함
+            let existing = self.world.network_entity(id).and_then(|entity| {
+                let transform = self.world.transforms.get_mut(entity)?;
+                let interpolated = self.world.interpolated.get_mut(entity)?;
+                Some((transform, interpolated, self.world.animators.get_mut(entity), self.world.snapshots.get_mut(entity)))
+            });
+            if let Some((transform, interpolated, animator, snapshots)) = existing {
+                match yaw {
+                    Some(_) if is_player && !self.pending_moves.is_empty() => {}
+                    // 버퍼로 움직이면 회전도 스냅샷으로 보간
+                    Some(_) if buffered && snapshots.is_some() => {}
+                    Some(yaw) if self.interpolation_rate > 0.0 => {
+                        interpolated.target_rotation = Some(Quaternion::from_angle_y(Deg(yaw)));
+                    }
+                    Some(yaw) => {
+                        transform.set_yaw(yaw);
+                        interpolated.target_rotation = None;
+                    }
+                    None => {}
+                }
+                if is_player {
+                    let error = (position - transform.position).magnitude();
+                    if error > PREDICTION_TOLERANCE 
+                        && error <= PREDICTION_SNAP_DISTANCE 
+                        && self.interpolation_rate > 0.0 
+                    {
+                        interpolated.target = Some(position);
+                    } else {
+                        transform.position = position;
+                        interpolated.target = None;
+                    }
+                    continue;
+                }
+
+                // 다른 칸으로 가면 떠나는 칸에 먼지
+                let from = match snapshots.as_deref().and_then(SnapshotBuffer::latest) {
+                    Some(latest) => latest.position,
+                    None => interpolated.target.unwrap_or(transform.position),
+                };
+                if (position - from).magnitude() > PREDICTION_TOLERANCE {
+                    self.particles.emit(Effect::MoveDust, from, None);
+                    if let Some(animator) = animator {
+                        animator.play(WALK);
+                    }
+                }
+                match snapshots {
+                    Some(snapshots) if buffered => {
+                        let rotation = yaw.map(|yaw| Quaternion::from_angle_y(Deg(yaw)))
+                            .or(snapshots.latest().map(|latest| latest.rotation))
+                            .unwrap_or(transform.rotation);
+                        snapshots.push(Snapshot::new(now, position, rotation));
+                    }
+                    _ if self.interpolation_rate <= 0.0 => {
+                        transform.position = position;
+                        interpolated.target = None;
+                    }
+                    _ => interpolated.target = Some(position),
+                }
+                continue;
+            }
+
+            if !self.make_room_for(id, position) {
+                dropped += 1;
+                continue;
+            }
+
+            let model = self.model_for(id);
+            let mut transform = Transform { position, ..Transform::default() };
+            if let Some(yaw) = yaw {
+                transform.set_yaw(yaw);
+            }
+            let renderable = Renderable { color: self.object_color(id), ..Renderable::new(model) };
+            let entity = self.world.spawn_renderable(renderable, transform);
+            self.world.interpolated.insert(entity, Interpolated::default());
+            if buffered {
+                let snapshot = Snapshot::new(now, transform.position, transform.rotation);
+                self.world.snapshots.insert(entity, SnapshotBuffer::new(snapshot));
+            }
+            if let Some(animator) = self.animator_for(model) {
+                self.world.animators.insert(entity, animator);
+            }
+            self.world.set_network_id(entity, id);
+        }
+
+        if dropped > 0 {
+            log::warn!(
+                target: logging::NET, 
+                "Remote object limit ({}) exceeded, dropped {} object(s)", 
+                self.network_config.max_remote_objects, 
+                dropped
+            );
+        }
+    }
+
+    /// 서버 오브젝트를 목표 위치와 회전으로 이동하고 애니메이션을 진행.  
+    /// 경과 시간은 `MAX_FRAME_TIME`까지만 반영하고, 한 프레임에 목표를 지나치지 않음.
+    /// 스냅샷 버퍼가 있는 오브젝트는 `interpolation_delay` 전의 위치로 둠.
+    fn update_positions(&mut self, dt: Duration) {
+        let dt = dt.min(MAX_FRAME_TIME);
+
+        let t = (dt.as_secs_f32() * self.interpolation_rate).min(1.0);
+        let now = Instant::now();
+        let render_time = now.checked_sub(self.network_config.interpolation_delay).unwrap_or(now);
+        // 플레이어 자신은 예측한 위치를 그대로 씀
+        let player = self.player_id.and_then(|id| self.world.network_entity(id));
+        let objects = self.world.network_entities().map(|(_, entity)| entity).collect::<Vec<_>>();
+        for entity in objects {
+            if let Some(animator) = self.world.animators.get_mut(entity) {
+                animator.advance(dt);
+            }
+            if let (Some(transform), Some(snapshots)) = 
+                (self.world.transforms.get_mut(entity), self.world.snapshots.get_mut(entity).filter(|_| player != Some(entity))) 
+            {
+                if let Some((position, rotation)) = snapshots.sample(render_time) {
+                    transform.position = position;
+                    transform.rotation = rotation;
+                }
+                snapshots.discard_before(render_time);
+                continue;
+            }
+            let (Some(transform), Some(interpolated)) = 
+                (self.world.transforms.get_mut(entity), self.world.interpolated.get_mut(entity)) 
+            else {
+                continue;
+            };
+            if let Some(target) = interpolated.target_rotation {
+                if t >= 1.0 {
+                    transform.rotation = target;
+                    interpolated.target_rotation = None;
+                } else {
+                    transform.rotation = transform.rotation.slerp(target, t);
+                }
+            }
+
+            let Some(target) = interpolated.target else {
+                continue;
+            };
+
+            if t >= 1.0 {
+                transform.position = target;
+                interpolated.target = None;
+            } else {
+                let position = transform.position;
+                transform.position = position + (target - position) * t;
+            }
+        }
+    }
+
+    /// 현재 누르고 있는 이동키들의 방향 합
+    fn held_direction(&self) -> Vector2<i32> {
+        self.held_keys.iter()
+            .filter_map(|keycode| self.input_map.action(*keycode)?.direction())
+            .fold(Vector2::new(0, 0), |acc, direction| acc + direction)
+    }
+
+    /// 카메라 기준 방향(W가 `(0, -1)`)을 월드 방향으로 바꿈.
+    /// 카메라 앞 방향을 바닥에 투영해서 쓰고, 거의 수직이면 `camera_yaw`만으로 계산
+    fn camera_relative(&self, direction: Vector2<f32>) -> Vector2<f32> {
+        let (yaw_sin, yaw_cos) = self.camera_yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.camera_pitch.sin_cos();
+        let forward = -Vector3::new(pitch_cos * yaw_sin, pitch_sin, pitch_cos * yaw_cos);
+
+        let ground = Vector2::new(forward.x, forward.z);
+        let forward = if ground.magnitude2() > 1e-6 {
+            ground.normalize()
+        } else {
+            Vector2::new(-yaw_sin, -yaw_cos)
+        };
+        let right = Vector2::new(-forward.y, forward.x);
+
+        right * direction.x - forward * direction.y
+    }
+
+    /// 누르고 있는 이동키의 월드 방향. 보드는 칸 단위이므로 각 축을 반올림함
+    fn held_move(&self) -> Option<Vector2<f32>> {
+        let held = self.held_direction();
+        if held == Vector2::new(0, 0) {
+            return None;
+        }
+
+        let direction = self.camera_relative(held.cast::<f32>().unwrap());
+        let direction = Vector2::new(direction.x.round(), direction.y.round());
+        (direction != Vector2::new(0.0, 0.0)).then_some(direction)
+    }
+
+    /// 아직 플레이어 id를 받지 못했으면 보내지 않음
+    fn send_move(&mut self, direction: Vector2<f32>) {
+        let Some(id) = self.player_id.filter(|_| !self.spectating) else {
+            return;
+        };
+
+        log::debug!(target: logging::INPUT, "Move ({} {})", direction.x, direction.y);
+
+        let seq = self.move_seq.wrapping_add(self.move_batch.len() as u32);
+        self.predict_move(seq, direction);
+
+        if self.network_config.move_batch_ticks.is_some() {
+            self.move_batch.push((direction.x, direction.y));
+            return;
+        }
+
+        self.send(&ClientMessage::Move {
+            id,
+            x: direction.x,
+            y: direction.y,
+        });
+        self.move_seq = self.move_seq.wrapping_add(1);
+
+        log::debug!(target: logging::NET, "Sent ok");
+    }
+
+    /// 서버 응답을 기다리지 않고 플레이어를 바로 이동.
+    /// 서버 위치를 아직 모르면 예측하지 않음
+    fn predict_move(&mut self, seq: u32, direction: Vector2<f32>) {
+        if self.server_cell.is_none() {
+            return;
+        }
+
+        // 서버와 같이 반올림
+        let delta = Vector2::new(direction.x.round() as i32, direction.y.round() as i32);
+        let from = self.predicted_cell();
+        self.pending_moves.push_back((seq, delta));
+        if self.pending_moves.len() > MAX_PENDING_MOVES {
+            self.pending_moves.pop_front();
+        }
+
+        let Some(player) = self.player() else {
+            return;
+        };
+        let Some(height) = self.world.transforms.get(player).map(|transform| transform.position.y) else {
+            return;
+        };
+        let predicted = self.predicted_position(height);
+        let to = self.predicted_cell();
+        let (Some(transform), Some(interpolated)) = 
+            (self.world.transforms.get_mut(player), self.world.interpolated.get_mut(player)) 
+        else {
+            return;
+        };
+        // 서버와 같이 실제로 움직인 방향을 바라봄
+        if let (Some(from), Some(to)) = (from, to) {
+            if from != to {
+                let yaw = facing_yaw(to.x - from.x, to.y - from.y);
+                interpolated.target_rotation = Some(Quaternion::from_angle_y(Deg(yaw)));
+                self.particles.emit(Effect::MoveDust, transform.position, None);
+                if let Some(animator) = self.world.animators.get_mut(player) {
+                    animator.play(WALK);
+                }
+            }
+        }
+        if let Some(position) = predicted {
+            transform.position = position;
+            interpolated.target = None;
+        }
+    }
+
+    /// 마지막 서버 칸에 `ack`받지 않은 이동을 순서대로 적용한 칸. 서버처럼 벽으로는 움직이지 않음
+    fn predicted_cell(&self) -> Option<Vector2<i32>> {
+        let cell = self.server_cell?;
+        Some(self.pending_moves.iter().fold(cell, |cell, &(_, delta)| {
+            let next = self.board.step(cell, delta);
+            match self.walls.contains(&self.board.tile_index(next.x, next.y)) {
+                true => cell,
+                false => next,
+            }
+        }))
+    }
+
+    fn predicted_position(&self, height: f32) -> Option<Vector3<f32>> {
+        let cell = self.predicted_cell()?;
+        let mut position = self.board.cell_to_world(cell.x, cell.y);
+        position.y = height;
+        Some(position)
+    }
+
+    fn push_move(&mut self, direction: Vector2<f32>) {
+        self.input_actions.push_back(InputAction::Move(direction));
+        self.last_resend = Instant::now();
+    }
+
+    /// 쌓인 입력 동작을 처리 (이동은 서버로 `move` 전송)
+    fn process_input_actions(&mut self) {
+        while let Some(action) = self.input_actions.pop_front() {
+            match action {
+                InputAction::Move(direction) => self.send_move(direction),
+                InputAction::CycleSpectateTarget => self.cycle_spectate_target(),
+                InputAction::SubmitText(text) => self.send_chat(text),
+            }
+        }
+    }
+
+    /// 빈 메세지나 `init`을 받기 전의 메세지는 보내지 않음
+    fn send_chat(&mut self, text: String) {
+        let text = text.trim();
+        let Some(id) = self.player_id.filter(|_| !text.is_empty()) else {
+            log::debug!(target: logging::INPUT, "Chat not sent: {:?}", text);
+            return;
+        };
+
+        self.send(&ClientMessage::Chat { id, text: text.to_string() });
+    }
+
+    /// 누르고 있는 이동키들의 방향 합을 이번 tick의 이동으로 추가. 이동키가 없으면 기울인 스틱이나 방향 버튼의 방향.
+    /// 마지막으로 보낸 뒤 `move_interval`이 지나지 않았거나,
+    /// 누르고 있는 키가 없거나 서로 상쇄되면 아무것도 하지 않음.
+    fn queue_held_move(&mut self, now: Instant) {
+        // 플레이어가 없어서 자유 카메라일 때는 어차피 `send_move`에서 버려짐
+        if self.free_camera || self.spectating {
+            return;
+        }
+        let gamepad = self.gamepad_direction.map(|direction| self.camera_relative(direction));
+        let Some(direction) = self.held_move().or(gamepad) else {
+            return;
+        };
+
+        let interval = self.network_config.move_interval;
+        if self.last_move_sent.is_some_and(|sent| now.saturating_duration_since(sent) < interval) {
+            return;
+        }
+
+        self.last_move_sent = Some(now);
+        self.push_move(direction);
+    }
+
+    /// `move_batch_ticks`마다 모은 이동을 한 번에 보냄. `MAX_MOVE_BATCH`개씩 나눠서 보냄
+    fn tick_move_batch(&mut self) {
+        let Some(ticks) = self.network_config.move_batch_ticks else {
+            return;
+        };
+
+        self.batch_ticks += 1;
+        if self.batch_ticks < ticks {
+            return;
+        }
+        self.batch_ticks = 0;
+
+        let Some(id) = self.player_id else {
+            return;
+        };
+        if self.move_batch.is_empty() {
+            return;
+        }
+
+        let batch = std::mem::take(&mut self.move_batch);
+        for moves in batch.chunks(MAX_MOVE_BATCH) {
+            let seq = self.move_seq;
+            self.move_seq = self.move_seq.wrapping_add(moves.len() as u32);
+            self.send(&ClientMessage::MoveBatch { id, seq, moves: moves.to_vec() });
+        }
+    }
+
+    /// 현재 누르고 있는 이동방향을 변경 여부와 상관없이 다음 `update`에서 서버로 다시 전송.  
+    /// 누르고 있는 이동키가 없으면 아무것도 하지 않음.
+    pub fn resend_input(&mut self) {
+        let direction = if let Some(direction) = self.held_move().filter(|_| !self.free_camera) {
+            direction
+        } else if let Some(direction) = self.gamepad_direction {
+            self.camera_relative(direction)
+        } else {
+            return;
+        };
+
+        self.push_move(direction);
+    }
+
+    /// 텍스트 입력창에 포커스를 줌.  
+    /// 포커스가 있는 동안 키 입력은 이동 대신 입력창으로 전달됨.
+    pub fn focus_text_input(&mut self) {
+        self.held_keys.clear();
+        self.text_input.focus();
+    }
+
+    pub fn text_input(&self) -> &TextInput {
+        &self.text_input
+    }
+
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+
+    /// 실행 중에 키를 바꿈. 누르고 있던 키는 놓은 것으로 봄
+    pub fn set_input_map(&mut self, input_map: InputMap) {
+        self.held_keys.clear();
+        self.input_map = input_map;
+    }
+
+    /// 게임을 나가면 돌아갈 첫 화면. 접속했던 서버 주소를 채워둠
+    fn title_scene(&self) -> TitleScene {
+        let address = self.server_addr.map_or_else(TitleScene::default_address, |addr| addr.to_string());
+        TitleScene::new(address, self.network_config.clone())
+    }
+
+    fn process_key(
+        &mut self, 
+        state: &ElementState, 
+        keycode: &KeyCode, 
+        repeat: bool, 
+        text: Option<&str>
+    ) -> bool {
+        if self.text_input.is_focused() {
+            match self.text_input.handle_key(state, keycode, text) {
+                TextInputEvent::Submitted(text) => {
+                    self.input_actions.push_back(InputAction::SubmitText(text))
+                },
+                TextInputEvent::Cancelled | TextInputEvent::Consumed => {},
+            }
+            return true;
+        }
+
+        if repeat {
+            return false;
+        }
+
+        self.process_keyboard_input(state, keycode)
+    }
+
+    /// 스틱이나 방향 버튼을 기울이고 있는 동안은 이동키를 누르고 있는 것과 같이
+    /// `queue_held_move`가 `move_interval`마다 `move`를 보냄. 중립에서 기울이는 순간 바로 보냄.
+    pub fn process_gamepad_input(&mut self, input: &GamepadInput) {
+        let direction = input.direction.filter(|_| !self.text_input.is_focused());
+        if self.gamepad_direction.is_none() && direction.is_some() && self.held_keys.is_empty() {
+            self.last_move_sent = None;
+        }
+        self.gamepad_direction = direction;
+
+        // 입력창에 포커스가 있으면 키보드처럼 동작하지 않음
+        if self.text_input.is_focused() {
+            return;
+        }
+        for button in input.actions.iter() {
+            let Some(action) = self.input_map.button_action(*button) else {
+                continue;
+            };
+            log::debug!(target: logging::INPUT, "Gamepad {:?}: {:?}", button, action);
+            self.perform_action(action);
+        }
+    }
+
+    /// 리플레이 재생 키: Space 멈춤, ←/→ 이동, [/] 속도. 리플레이가 아니거나 다른 키면 `false`
+    fn control_replay(&mut self, keycode: &KeyCode) -> bool {
+        let Some(replay) = &mut self.replay else {
+            return false;
+        };
+
+        match keycode {
+            KeyCode::Space => replay.toggle_pause(),
+            KeyCode::BracketLeft => replay.slower(),
+            KeyCode::BracketRight => replay.faster(),
+            KeyCode::ArrowLeft => self.seek_replay(REPLAY_SEEK_STEP, false),
+            KeyCode::ArrowRight => self.seek_replay(REPLAY_SEEK_STEP, true),
+            _ => return false,
+        }
+        true
+    }
+
+    /// 디버그 패널을 켰을 때 -/=로 `interpolation_delay`를 줄이고 늘림. 패널이 꺼졌거나 다른 키면 `false`
+    fn control_debug_panel(&mut self, keycode: &KeyCode) -> bool {
+        if !self.show_debug_panel {
+            return false;
+        }
+
+        let delay = self.network_config.interpolation_delay;
+        let delay = match keycode {
+            KeyCode::Minus => delay.saturating_sub(INTERPOLATION_DELAY_STEP),
+            KeyCode::Equal => (delay + INTERPOLATION_DELAY_STEP).min(MAX_INTERPOLATION_DELAY),
+            _ => return false,
+        };
+        self.set_interpolation_delay(delay);
+        true
+    }
+
+    /// 키는 `input_map`으로 동작으로 바꿔서 처리함.
+    /// 이동키는 누르고 있는 상태만 기록하고, 실제 `move`는 `queue_held_move`에서 보냄
+    fn process_keyboard_input(&mut self, state: &ElementState, keycode: &KeyCode) -> bool {
+        match state {
+            ElementState::Pressed if self.control_replay(keycode) => true,
+            ElementState::Pressed if self.control_debug_panel(keycode) => true,
+            ElementState::Pressed => {
+                let Some(action) = self.input_map.action(*keycode) else {
+                    return false;
+                };
+                if action.direction().is_some() {
+                    if self.held_keys.is_empty() {
+                        self.last_move_sent = None;
+                    }
+                    self.held_keys.insert(*keycode);
+                } else {
+                    self.perform_action(action);
+                }
+
+                true
+            }
+            ElementState::Released => {
+                self.held_keys.remove(keycode);
+                false
+            }
+        }
+    }
+
+    /// 키나 게임패드 버튼에 연결된 이동 외의 동작. 이동은 누르고 있는 동안 `queue_held_move`가 보냄
+    fn perform_action(&mut self, action: Action) {
+        match action {
+            Action::MoveUp | Action::MoveDown | Action::MoveLeft | Action::MoveRight => {},
+            Action::CycleSpectateTarget => {
+                self.input_actions.push_back(InputAction::CycleSpectateTarget);
+            }
+            Action::ToggleFreeCamera => self.toggle_free_camera(),
+            Action::ToggleScoreboard => self.toggle_scoreboard(),
+            Action::ResetCamera => self.reset_camera(),
+            Action::ToggleDebugPanel => self.toggle_debug_panel(),
+            Action::ToggleMinimap => self.toggle_minimap(),
+            // 채팅 입력 시작. 입력 중에는 process_key에서 입력창으로 전달됨
+            Action::OpenChat => self.focus_text_input(),
+            Action::Pause => self.pause_requested = true,
+        }
+    }
+}
+
+impl Scene for GameScene {
+    fn init(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<()> {
+        self.load_models(device, queue)?;
+        self.build_objects();
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state,
+                    physical_key: PhysicalKey::Code(keycode),
+                    repeat,
+                    text,
+                    ..
+                },
+                ..
+            } => self.process_key(state, keycode, *repeat, text.as_deref()),
+            WindowEvent::CursorMoved { position, .. } => {
+                let (x, y) = (position.x as f32, position.y as f32);
+                let orbited = match self.cursor {
+                    Some((last_x, last_y)) if self.orbiting => {
+                        self.orbit_camera((last_x - x) * ORBIT_SENSITIVITY, (y - last_y) * ORBIT_SENSITIVITY);
+                        true
+                    }
+                    _ => false,
+                };
+                self.cursor = Some((x, y));
+                orbited
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Right, .. } => {
+                self.orbiting = *state == ElementState::Pressed;
+                true
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 100.0,
+                };
+                self.zoom_camera(lines * ZOOM_STEP);
+                true
+            }
+            WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
+                match self.cursor {
+                    Some((x, y)) => {
+                        self.process_click(x, y);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// 모은 이동은 프레임 속도와 상관없이 같은 간격으로 보냄
+    fn fixed_update(&mut self) {
+        self.tick_move_batch();
+    }
+
+    /// 크기가 0이면 (창 최소화) 무시
+    fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.viewport = (width as f32, height as f32);
+        self.camera.component.aspect = width as f32 / height as f32;
+    }
+
+    /// 서버에 `disconnect`를 보내고 연결을 정리. 이미 끊긴 연결이면 그냥 넘어감
+    fn on_exit(&mut self) {
+        if !self.transport.is_connected() {
+            return;
+        }
+
+        if let Some(id) = self.player_id {
+            self.send(&ClientMessage::Disconnect { id });
+        }
+        self.flush_outgoing();
+        if !self.outgoing.is_empty() {
+            log::warn!(target: logging::NET, "Closing with {} unsent byte(s)", self.outgoing.len());
+        }
+
+        if let Err(e) = self.transport.close() {
+            log::debug!(target: logging::NET, "Failed to shut down connection: {}", e);
+        }
+        self.set_connection_state(ConnectionState::Disconnected);
+    }
+
+    fn update(&mut self, dt: Duration) -> Option<SceneTransition> {
+        self.clock += dt;
+        self.frame_stats.push(dt);
+        self.try_reconnect(Instant::now());
+        self.send_heartbeat(Instant::now());
+        self.request_update(self.clock);
+
+        if let Some(interval) = self.network_config.resend_interval {
+            if self.last_resend.elapsed() >= interval {
+                self.resend_input();
+            }
+        }
+
+        self.queue_held_move(self.clock);
+        self.process_input_actions();
+        self.pull_messages();
+        self.advance_replay(dt);
+        self.process_messages();
+        self.pull_datagrams();
+        if let Some(version) = self.incompatible_server {
+            return Some(SceneTransition::ReplaceAll(ActiveScene::Error(ErrorScene::new(client_out_of_date(version)))));
+        }
+
+        let now = Instant::now();
+        self.check_handshake(now);
+        self.check_stale(now);
+        self.update_background(now);
+        self.update_positions(dt);
+        self.update_despawning(now);
+        self.update_pickups(now);
+        self.particles.update(dt.min(MAX_FRAME_TIME));
+        self.update_markers();
+        self.move_free_camera(dt);
+        self.follow_camera(Some(dt));
+        self.update_name_tags();
+        self.flush_outgoing();
+
+        std::mem::take(&mut self.pause_requested)
+            .then(|| SceneTransition::Push(ActiveScene::Pause(PauseScene::new(self.title_scene()))))
+    }
+
+    /// 멈춤 화면에서는 입력을 받지 못하므로 누르고 있던 키와 스틱, 드래그를 놓은 것으로 봄
+    fn suspend(&mut self) {
+        self.held_keys.clear();
+        self.gamepad_direction = None;
+        self.orbiting = false;
+        self.cursor = None;
+    }
+
+
+    fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        self.camera.build_view_projection_matrix()
+    }
+
+    fn camera_eye(&self) -> Point3<f32> {
+        self.camera.component.eye
+    }
+
+    fn background_color(&self) -> Color {
+        self.background_color
+    }
+
+    fn skybox(&self) -> Option<&str> {
+        let server_background = self.background_transition.is_some() || self.background_color != DEFAULT_BACKGROUND_COLOR;
+        self.skybox.as_deref().filter(|_| !server_background)
+    }
+
+    fn particles(&self) -> Option<&ParticleSystem> {
+        Some(&self.particles)
+    }
+
+    /// 그림자는 보드 전체를 덮음
+    fn light(&self) -> DirectionalLight {
+        let board = &self.board;
+        let last = board.cell_to_world(board.width - 1, board.height - 1);
+        let center = (board.origin + last) / 2.0;
+        let size = Vector2::new(board.width as f32 * board.spacing_x, board.height as f32 * board.spacing_z);
+
+        DirectionalLight {
+            center: Point3::from_vec(center),
+            radius: size.magnitude() / 2.0 + SHADOW_MARGIN,
+            ..Default::default()
+        }
+    }
+    
+
+    fn hud(&self, hud: &mut Hud) {
+        for (idx, line) in self.hud_lines().iter().enumerate() {
+            let y = HUD_MARGIN + idx as f32 * (DEFAULT_TEXT_SIZE + HUD_LINE_SPACING);
+            hud.draw_text((HUD_MARGIN, y), DEFAULT_TEXT_SIZE, HUD_COLOR, line);
+        }
+
+        // 오른쪽 정렬
+        for (idx, line) in self.debug_panel().unwrap_or_default().iter().enumerate() {
+            let x = self.viewport.0 - HUD_MARGIN - Hud::text_width(DEFAULT_TEXT_SIZE, line);
+            let y = HUD_MARGIN + idx as f32 * (DEFAULT_TEXT_SIZE + HUD_LINE_SPACING);
+            hud.draw_text((x, y), DEFAULT_TEXT_SIZE, HUD_COLOR, line);
+        }
+
+        if let Some(minimap) = self.minimap() {
+            minimap.draw_background(hud, MINIMAP_BACKGROUND);
+            for &tile in &self.walls {
+                let (x, z) = self.board.tile_cell(tile);
+                minimap.draw_cell(hud, x, z, MINIMAP_WALL_COLOR);
+            }
+            for (position, color) in self.minimap_dots() {
+                minimap.draw_dot(hud, position, MINIMAP_DOT_SCALE, color);
+            }
+        }
+    }
+
+    /// 보드 격자와 서버 오브젝트마다 서버 위치 (노란 십자)와 그리는 위치 (흰 십자). 둘이 다르면 선으로 이음
+    fn debug_lines(&self, lines: &mut DebugLines) {
+        lines.board_grid(&self.board, DEBUG_GRID_LIFT, DEBUG_GRID_COLOR);
+
+        for (id, entity) in self.world.network_entities() {
+            let Some(transform) = self.world.transforms.get(entity) else {
+                continue;
+            };
+            let rendered = transform.position;
+            let server = match self.server_cell {
+                // 플레이어 자신은 예측하기 전 칸
+                Some(cell) if Some(id) == self.player_id => {
+                    Some(self.board.cell_to_world(cell.x, cell.y) + Vector3::new(0.0, rendered.y, 0.0))
+                }
+                _ => self.world.snapshots.get(entity)
+                    .and_then(SnapshotBuffer::latest)
+                    .map(|latest| latest.position)
+                    .or_else(|| self.world.interpolated.get(entity).and_then(|interpolated| interpolated.target)),
+            }.unwrap_or(rendered);
+
+            lines.cross(server, DEBUG_SERVER_MARKER, DEBUG_SERVER_COLOR);
+            lines.cross(rendered, DEBUG_RENDERED_MARKER, DEBUG_RENDERED_COLOR);
+            if (server - rendered).magnitude() > PREDICTION_TOLERANCE {
+                lines.line(server, rendered, DEBUG_SERVER_COLOR);
+            }
+        }
+    }
+
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        self.assets.named_models()
+    }
+
+    fn world(&self) -> Option<&World> {
+        Some(&self.world)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{SCREEN_WIDTH, SCREEN_HEIGHT, skybox::DEFAULT_SKYBOX, gamepad::GamepadAction};
+    use std::{io::{Read, Write}, net::TcpListener, time::Duration};
+    use cgmath::Vector4;
+
+    fn connected_scene(network_config: NetworkConfig) -> (GameScene, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        server.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+
+        (GameScene::with_stream(stream, network_config), server)
+    }
+
+    /// 씬에 쌓인 메세지를 보낸 뒤 서버가 `expected`를 받았는지 확인
+    fn assert_received(scene: &mut GameScene, server: &mut TcpStream, expected: &str) {
+        scene.flush_outgoing();
+        let mut buf = vec![0; expected.len()];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+    }
+
+    /// 서버 오브젝트 `id`의 엔티티
+    fn entity(scene: &GameScene, id: u32) -> Entity {
+        scene.world.network_entity(id).unwrap()
+    }
+
+    fn transform(scene: &GameScene, id: u32) -> Transform {
+        *scene.world.transforms.get(entity(scene, id)).unwrap()
+    }
+
+    fn renderable(scene: &GameScene, id: u32) -> &Renderable {
+        scene.world.renderables.get(entity(scene, id)).unwrap()
+    }
+
+    fn interpolated(scene: &GameScene, id: u32) -> Interpolated {
+        *scene.world.interpolated.get(entity(scene, id)).unwrap()
+    }
+
+    /// 서버 오브젝트 id (오름차순)
+    fn server_ids(scene: &GameScene) -> Vec<u32> {
+        let mut ids = scene.world.network_entities().map(|(id, _)| id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn tile_transform(scene: &GameScene, idx: usize) -> Transform {
+        *scene.world.transforms.get(scene.tiles[idx]).unwrap()
+    }
+
+    fn tile_color(scene: &GameScene, idx: usize) -> Option<Color> {
+        scene.world.renderables.get(scene.tiles[idx]).unwrap().color
+    }
+
+    /// `done`이 참이 될 때까지 서버 데이터를 읽고 처리
+    fn pull_until(scene: &mut GameScene, done: impl Fn(&GameScene) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while !done(scene) && Instant::now() < deadline {
+            scene.pull_messages();
+            scene.process_messages();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// 다른 스레드의 접속이 끝날 때까지 `try_reconnect`로 결과를 확인
+    fn reconnect_until_done(scene: &mut GameScene, now: Instant) {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        scene.try_reconnect(now);
+        while scene.transport.is_connecting() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+            scene.try_reconnect(now);
+        }
+    }
+
+    #[test]
+    fn test_split_messages() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+
+        server.write_all(b"GAMESERVER upd").unwrap();
+        pull_until(&mut scene, |scene| !scene.packet_parser.is_empty());
+        assert!(scene.world.network_count() == 0);
+
+        // 나머지 + 완성된 메세지 + 다음 메세지 일부
+        server.write_all(b"ate 1 4 2 3\nGAMESERVER bgcolor #ffffff\nGAMESERVER ini").unwrap();
+        pull_until(&mut scene, |scene| scene.background_transition.is_some());
+        assert_eq!(transform(&scene, 4).position, Vector3::new(2.0, 0.0, 3.0));
+        assert_eq!(scene.player_id, None);
+
+        server.write_all(b"t 4\n").unwrap();
+        pull_until(&mut scene, |scene| scene.player_id.is_some());
+        assert_eq!(scene.player_id, Some(4));
+    }
+
+    #[test]
+    fn test_start_without_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let server = ServerConfig { ip: "127.0.0.1".to_string(), port };
+        let mut scene = GameScene::new(NetworkConfig::default(), Some(server)).unwrap();
+        assert!(!scene.transport.is_connected());
+        // 접속을 기다리지 않음
+        assert_eq!(scene.connection_state(), ConnectionState::Connecting);
+
+        // 연결이 없어도 전송은 무시됨
+        scene.process_message("GAMESERVER init 0").unwrap();
+        scene.send_move(Vector2::new(1.0, 0.0));
+        scene.pull_messages();
+
+        // 실패하면 다음 시도까지 기다림
+        let now = Instant::now();
+        reconnect_until_done(&mut scene, now);
+        assert!(!scene.transport.is_connected());
+        assert_eq!(scene.retry_attempts, 1);
+        scene.try_reconnect(now + Duration::from_millis(50));
+        assert!(!scene.transport.is_connecting());
+        assert_eq!(scene.retry_attempts, 1);
+
+        let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        scene.try_reconnect(now + reconnect_backoff(0));
+        assert_eq!(scene.connection_state(), ConnectionState::Reconnecting { attempt: 2 });
+        reconnect_until_done(&mut scene, now + reconnect_backoff(0));
+        assert!(scene.transport.is_connected());
+        assert_eq!(scene.retry_attempts, 0);
+        drop(listener);
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let schedule = (0..8).map(|attempt| reconnect_backoff(attempt).as_millis()).collect::<Vec<_>>();
+        assert_eq!(schedule, [100, 200, 400, 800, 1600, 3200, 5000, 5000]);
+        assert_eq!(reconnect_backoff(u32::MAX), RECONNECT_BACKOFF_MAX);
+    }
+
+    #[test]
+    fn test_set_camera_offset() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 3 4").unwrap();
+
+        scene.set_camera_offset(Vector3::new(0.0, 3.0, 4.0));
+        scene.follow_camera(None);
+        assert_eq!(scene.camera.component.eye, Point3::new(3.0, 3.0, 8.0));
+        
+        scene.set_camera_offset(Vector3::new(0.0, 0.0, 100.0));
+        assert_eq!(scene.camera_offset(), Vector3::new(0.0, 0.0, MAX_CAMERA_DISTANCE));
+
+        scene.set_camera_offset(Vector3::new(0.0, 0.0, 0.0));
+        assert_eq!(scene.camera_offset(), Vector3::new(0.0, 0.0, MAX_CAMERA_DISTANCE));
+    }
+
+    #[test]
+    fn test_orbit_and_zoom_camera() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        scene.set_camera_offset(Vector3::new(0.0, 0.0, 4.0));
+
+        // 수평으로 90도 회전하면 +x쪽에서 바라봄
+        scene.orbit_camera(std::f32::consts::FRAC_PI_2, 0.0);
+        scene.follow_camera(None);
+        assert!((scene.camera_offset() - Vector3::new(4.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert_eq!(scene.camera.component.target, Point3::new(0.0, 0.0, 0.0));
+
+        // 머리 위를 넘어가지 않음
+        scene.orbit_camera(0.0, 10.0);
+        scene.follow_camera(None);
+        assert_eq!(scene.camera_pitch, MAX_CAMERA_PITCH);
+        assert!(scene.camera_offset().y < 4.0);
+
+        scene.zoom_camera(2.0);
+        scene.follow_camera(None);
+        assert!((scene.camera_offset().magnitude() - 2.0).abs() < 1e-5);
+        scene.zoom_camera(100.0);
+        assert_eq!(scene.camera_distance, MIN_CAMERA_DISTANCE);
+
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyR);
+        scene.follow_camera(None);
+        assert!((scene.camera_offset() - CameraConfig::default().offset).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_smooth_follow_camera() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.set_camera_config(&CameraConfig {
+            offset: Vector3::new(0.0, 0.0, 4.0),
+            follow_speed: Some(10.0),
+            look_ahead: 1.0,
+            ..Default::default()
+        });
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        scene.follow_camera(Some(Duration::from_millis(16)));
+        assert_eq!(scene.camera.component.target, Point3::new(0.0, 0.0, 0.0));
+
+        // 움직이면 바로 붙지 않고 다가감
+        scene.world.transforms.get_mut(entity(&scene, 1)).unwrap().position = Vector3::new(2.0, 0.0, 0.0);
+        scene.follow_camera(Some(Duration::from_millis(100)));
+        let target = scene.camera.component.target;
+        assert!(target.x > 0.0 && target.x < 2.0, "{:?}", target);
+        assert_eq!(scene.camera.component.eye, target + Vector3::new(0.0, 0.0, 4.0));
+
+        // 충분히 지나면 움직인 방향으로 `look_ahead`만큼 앞을 봄
+        for _ in 0..100 {
+            scene.follow_camera(Some(Duration::from_millis(100)));
+        }
+        assert!((scene.camera.component.target - Point3::new(3.0, 0.0, 0.0)).magnitude() < 1e-3);
+
+        // 따라가는 방식을 끄면 바로 붙음
+        scene.set_camera_config(&CameraConfig { offset: Vector3::new(0.0, 0.0, 4.0), follow_speed: None, ..Default::default() });
+        scene.follow_camera(Some(Duration::from_millis(16)));
+        assert_eq!(scene.camera.component.target, Point3::new(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_free_camera() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.set_camera_offset(Vector3::new(0.0, 0.0, 4.0));
+        // 플레이어가 없으면 자유 카메라
+        assert!(scene.is_free_camera());
+
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 2 2").unwrap();
+        scene.follow_camera(None);
+        assert!(!scene.is_free_camera());
+        let followed = scene.camera.component.eye;
+
+        assert!(scene.process_key(&ElementState::Pressed, &KeyCode::KeyF, false, Some("f")));
+        assert!(scene.is_free_camera());
+
+        // W로 1초에 FREE_CAMERA_SPEED만큼 카메라 앞쪽(-z)으로 이동하고, 서버에는 보내지 않음
+        scene.process_key(&ElementState::Pressed, &KeyCode::KeyW, false, Some("w"));
+        scene.queue_held_move(Instant::now());
+        scene.process_input_actions();
+        scene.move_free_camera(Duration::from_millis(500));
+        scene.move_free_camera(Duration::from_millis(500));
+        scene.follow_camera(None);
+        let eye = scene.camera.component.eye;
+        assert!((eye - (followed - Vector3::new(0.0, 0.0, FREE_CAMERA_SPEED))).magnitude() < 1e-4);
+        assert!((scene.camera.component.target - (eye - Vector3::new(0.0, 0.0, 4.0))).magnitude() < 1e-4);
+
+        // 다시 누르면 플레이어를 따라감
+        scene.process_key(&ElementState::Pressed, &KeyCode::KeyF, false, Some("f"));
+        scene.follow_camera(None);
+        assert_eq!(scene.camera.component.eye, followed);
+
+        scene.send_chat("free".to_string());
+        assert_received(&mut scene, &mut server, "chat 1 free\n");
+    }
+
+    #[test]
+    fn test_spectator() {
+        let config = NetworkConfig { spectator: true, ..Default::default() };
+        let (mut scene, mut server) = connected_scene(config);
+        scene.process_message("GAMESERVER init 1 14 9").unwrap();
+        assert_received(&mut scene, &mut server, "colors\nkinds\nscoreboard\nspectate\n");
+        assert!(scene.is_spectating());
+
+        // 다른 플레이어가 보여도 자유 카메라이고, 이동키는 서버로 보내지 않음
+        scene.process_message("GAMESERVER update 1 2 2 2").unwrap();
+        assert!(scene.is_free_camera());
+        scene.process_key(&ElementState::Pressed, &KeyCode::KeyW, false, Some("w"));
+        scene.queue_held_move(Instant::now());
+        scene.process_input_actions();
+        scene.send_chat("hi".to_string());
+        assert_received(&mut scene, &mut server, "chat 1 hi\n");
+
+        // 관전을 지원하지 않는 서버에서는 보통 플레이어
+        scene.process_message("GAMESERVER init 1 13 9").unwrap();
+        assert!(!scene.is_spectating());
+    }
+
+    #[test]
+    fn test_replay() {
+        let replay = "0 GAMESERVER map 2 1 ..\n50 GAMESERVER update 1 1 0 0 0\n1000 GAMESERVER update 2 2 0 1 0 7 0 0\n";
+        let mut scene = GameScene::with_transport(Box::new(TcpTransport::new(None)), None, NetworkConfig::default());
+        scene.replay = Some(replay.parse().unwrap());
+
+        scene.update(Duration::from_millis(100));
+        assert_eq!(scene.world.network_count(), 1);
+        assert!(scene.is_free_camera());
+        assert_eq!(scene.replay_status().as_deref(), Some("0.1s / 1.0s x1"));
+
+        assert!(scene.process_key(&ElementState::Pressed, &KeyCode::ArrowRight, false, None));
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.world.network_count(), 2);
+        assert_eq!(scene.last_seq, Some(2));
+
+        // 뒤로 가면 처음부터 다시 적용
+        assert!(scene.process_key(&ElementState::Pressed, &KeyCode::ArrowLeft, false, None));
+        scene.update(Duration::ZERO);
+        assert!(scene.world.network_count() == 0);
+        assert_eq!(scene.last_seq, None);
+
+        scene.process_key(&ElementState::Pressed, &KeyCode::Space, false, Some(" "));
+        scene.process_key(&ElementState::Pressed, &KeyCode::BracketRight, false, None);
+        scene.update(Duration::from_secs(1));
+        assert_eq!(scene.replay_status().as_deref(), Some("0.0s / 1.0s x2 paused"));
+    }
+
+    #[test]
+    fn test_camera_relative_move() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.set_camera_offset(Vector3::new(0.0, 4.0, 4.0));
+        assert!((scene.camera_relative(Vector2::new(0.0, -1.0)) - Vector2::new(0.0, -1.0)).magnitude() < 1e-5);
+
+        // +x쪽에서 바라보면 W는 -x, D는 -z
+        scene.orbit_camera(std::f32::consts::FRAC_PI_2, 0.0);
+        assert!((scene.camera_relative(Vector2::new(0.0, -1.0)) - Vector2::new(-1.0, 0.0)).magnitude() < 1e-5);
+        assert!((scene.camera_relative(Vector2::new(1.0, 0.0)) - Vector2::new(0.0, -1.0)).magnitude() < 1e-5);
+
+        scene.process_key(&ElementState::Pressed, &KeyCode::KeyW, false, Some("w"));
+        scene.queue_held_move(Instant::now());
+        scene.process_input_actions();
+        assert_received(&mut scene, &mut server, "move 1 -1 0\n");
+
+        // 바로 위에서 내려다봐도 yaw 방향으로 이동
+        scene.camera_pitch = std::f32::consts::FRAC_PI_2;
+        assert!((scene.camera_relative(Vector2::new(0.0, -1.0)) - Vector2::new(-1.0, 0.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_server_object_on_tile() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.set_interpolation_rate(0.0);
+        scene.set_board_layout(BoardLayout { 
+            width: 10,
+            height: 10,
+            spacing_x: 1.5, 
+            spacing_z: 2.0, 
+            origin: Vector3::new(-4.0, 0.0, 3.0),
+        });
+        scene.build_objects();
+        assert_eq!(scene.tiles.len(), 100);
+
+        scene.process_message("GAMESERVER update 1 4 3 5").unwrap();
+        let piece = transform(&scene, 4).position;
+        let tile = tile_transform(&scene, scene.board.tile_index(3, 5)).position;
+        assert_eq!(piece, Vector3::new(0.5, 0.0, 13.0));
+        assert_eq!((piece.x, piece.z), (tile.x, tile.z));
+
+        // 크기가 바뀌면 타일을 다시 만듦
+        scene.set_board_layout(BoardLayout::default());
+        assert_eq!(scene.tiles.len(), 64);
+    }
+
+    #[test]
+    fn test_server_map() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.set_interpolation_rate(0.0);
+        scene.build_objects();
+        assert_eq!(scene.tiles.len(), 64);
+
+        scene.process_message("GAMESERVER init 0").unwrap();
+        scene.process_message("GAMESERVER map 5 2 ..#.. .....").unwrap();
+        assert_eq!((scene.board.width, scene.board.height), (5, 2));
+        assert_eq!(scene.tiles.len(), 10);
+        let wall = tile_transform(&scene, 2).position;
+        let floor = tile_transform(&scene, 7).position;
+        assert_eq!(wall, scene.board.cell_to_world(2, 0) + Vector3::new(0.0, 0.5, 0.0));
+        assert_eq!(floor, scene.board.cell_to_world(2, 1) + Vector3::new(0.0, -0.5, 0.0));
+
+        // 벽으로는 움직인다고 예측하지 않음
+        scene.process_message("GAMESERVER update 1 0 1 0").unwrap();
+        scene.pending_moves.push_back((1, Vector2::new(1, 0)));
+        assert_eq!(scene.predicted_cell(), Some(Vector2::new(1, 0)));
+
+        // 크기가 맞지 않는 맵은 무시
+        scene.apply_map(3, 3, &[Tile::Floor; 4]);
+        scene.apply_map(i32::MAX, 2, &[Tile::Floor; 2]);
+        assert_eq!(scene.tiles.len(), 10);
+    }
+
+    #[test]
+    fn test_tile_colors() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.build_objects();
+
+        scene.process_message("GAMESERVER tiles 0 #ff0000 63 00ff00").unwrap();
+        assert_eq!(tile_color(&scene, 0), Some(Color::RED));
+        assert_eq!(tile_color(&scene, 63), Some(Color::GREEN));
+        assert_eq!(tile_color(&scene, 1), None);
+    }
+
+    #[test]
+    fn test_key_bindings() {
+        let mut keys = InputMap::default();
+        keys.rebind(Action::MoveUp, &[KeyCode::ArrowUp]);
+        ClientConfig::install(ClientConfig { keys, ..ClientConfig::default() });
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        ClientConfig::install(ClientConfig::default());
+
+        assert!(!scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyW));
+        assert_eq!(scene.held_direction(), Vector2::new(0, 0));
+        assert!(scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::ArrowUp));
+        assert_eq!(scene.held_direction(), Vector2::new(0, -1));
+
+        // 실행 중에 바꾸면 누르고 있던 키는 놓은 것으로 봄
+        let mut keys = scene.input_map().clone();
+        keys.bind(KeyCode::KeyY, Action::ToggleScoreboard);
+        scene.set_input_map(keys);
+        assert_eq!(scene.held_direction(), Vector2::new(0, 0));
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyY);
+        assert!(scene.scoreboard_overlay().is_some());
+    }
+
+    #[test]
+    fn test_escape_pauses() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyW);
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::Escape);
+        assert!(matches!(scene.update(Duration::ZERO), Some(SceneTransition::Push(ActiveScene::Pause(_)))));
+        assert!(scene.update(Duration::ZERO).is_none());
+
+        // 멈춤 화면에서는 키를 놓았다는 입력을 받지 못함
+        scene.suspend();
+        assert!(scene.held_keys.is_empty());
+
+        // 채팅 입력 중의 Escape는 입력만 취소함
+        scene.focus_text_input();
+        scene.process_key(&ElementState::Pressed, &KeyCode::Escape, false, None);
+        assert!(!scene.text_input.is_focused());
+        assert!(scene.update(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_text_input_suppresses_movement() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 0").unwrap();
+
+        scene.focus_text_input();
+        assert!(scene.process_key(&ElementState::Pressed, &KeyCode::KeyW, false, Some("w")));
+        assert!(scene.process_key(&ElementState::Released, &KeyCode::KeyW, false, None));
+        assert_eq!(scene.text_input().text(), "w");
+        assert!(scene.held_keys.is_empty());
+
+        assert!(scene.process_key(&ElementState::Pressed, &KeyCode::Enter, false, Some("\r")));
+        assert!(!scene.text_input().is_focused());
+
+        scene.process_key(&ElementState::Pressed, &KeyCode::KeyA, false, Some("a"));
+        scene.queue_held_move(Instant::now());
+        scene.process_input_actions();
+        assert_received(&mut scene, &mut server, "chat 0 w\n");
+        assert_received(&mut scene, &mut server, "move 0 -1 0\n");
+    }
+
+    #[test]
+    fn test_send_chat() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 3").unwrap();
+
+        // T로 입력을 시작하면 T는 입력되지 않음
+        assert!(scene.process_key(&ElementState::Pressed, &KeyCode::KeyT, false, Some("t")));
+        assert!(scene.text_input().is_focused());
+        assert_eq!(scene.text_input().text(), "");
+
+        for (keycode, text) in [(KeyCode::KeyH, "h"), (KeyCode::KeyI, "i"), (KeyCode::Space, " "), (KeyCode::KeyD, "d")] {
+            scene.process_key(&ElementState::Pressed, &keycode, false, Some(text));
+        }
+        scene.process_key(&ElementState::Pressed, &KeyCode::Enter, false, Some("\r"));
+        scene.process_input_actions();
+        assert!(scene.held_keys.is_empty());
+        assert_received(&mut scene, &mut server, "chat 3 hi d\n");
+
+        // 빈 메세지는 보내지 않음
+        scene.process_key(&ElementState::Pressed, &KeyCode::Enter, false, Some("\r"));
+        scene.process_key(&ElementState::Pressed, &KeyCode::Enter, false, Some("\r"));
+        scene.push_move(Vector2::new(1.0, 0.0));
+        scene.process_input_actions();
+        assert_received(&mut scene, &mut server, "move 3 1 0\n");
+    }
+
+    #[test]
+    fn test_gamepad_movement() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 0").unwrap();
+
+        let input = GamepadInput {
+            direction: Some(Vector2::new(0.5, -1.0)),
+            stick: (0.5, 1.0),
+            actions: Vec::new(),
+        };
+        // 기울인 채로 있으면 이동키를 누르고 있는 것처럼 `move_interval`마다 보냄
+        let start = Instant::now();
+        let interval = scene.network_config.move_interval;
+        for tick in 0..5 {
+            scene.process_gamepad_input(&input);
+            scene.queue_held_move(start + interval / 2 * tick);
+        }
+        scene.process_input_actions();
+        assert_received(&mut scene, &mut server, "move 0 0.5 -1\nmove 0 0.5 -1\nmove 0 0.5 -1\n");
+
+        // 놓으면 멈춤
+        scene.process_gamepad_input(&GamepadInput::default());
+        scene.queue_held_move(start + interval * 4);
+        assert!(scene.input_actions.is_empty());
+    }
+
+    #[test]
+    fn test_gamepad_buttons() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 0").unwrap();
+
+        let press = |button| GamepadInput { actions: vec![button], ..Default::default() };
+        scene.process_gamepad_input(&press(GamepadAction::Select));
+        assert!(matches!(scene.input_actions.pop_front(), Some(InputAction::CycleSpectateTarget)));
+
+        scene.process_gamepad_input(&press(GamepadAction::Cancel));
+        assert!(scene.pause_requested);
+
+        scene.process_gamepad_input(&press(GamepadAction::Confirm));
+        assert!(scene.text_input.is_focused());
+        // 입력 중에는 버튼이 동작을 일으키지 않음
+        scene.process_gamepad_input(&press(GamepadAction::Select));
+        assert!(scene.input_actions.is_empty());
+    }
+
+    #[test]
+    fn test_cycle_spectate_target() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 3 1 0 0 5 2 2 9 4 4").unwrap();
+
+        scene.cycle_spectate_target();
+        assert_eq!(scene.spectate_target(), Some(5));
+        scene.follow_camera(None);
+        assert_eq!(scene.camera.component.target, Point3::new(2.0, 0.0, 2.0));
+
+        scene.cycle_spectate_target();
+        assert_eq!(scene.spectate_target(), Some(9));
+        scene.follow_camera(None);
+        assert_eq!(scene.camera.component.target, Point3::new(4.0, 0.0, 4.0));
+
+        // 관전 대상이 사라지면 다음 플레이어로
+        scene.process_message("GAMESERVER update 2 1 0 0 5 2 2").unwrap();
+        scene.follow_camera(None);
+        assert_eq!(scene.spectate_target(), Some(5));
+
+        scene.stop_spectating();
+        scene.follow_camera(None);
+        assert_eq!(scene.camera.component.target, Point3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_despawn_fade() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.set_despawn_fade(Some(Duration::from_secs(1)));
+        scene.process_message("GAMESERVER update 2 1 0 0 2 3 3").unwrap();
+
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        let start = scene.despawning[0].2;
+        assert_eq!(scene.world.network_count(), 1);
+        assert_eq!(scene.world.len(), 2);
+
+        scene.update_despawning(start + Duration::from_millis(500));
+        let fading = scene.despawning[0].1;
+        let alpha = scene.world.renderables.get(fading).unwrap().alpha;
+        assert!(alpha > 0.0 && alpha < 1.0);
+        let scale = scene.world.transforms.get(fading).unwrap().scale;
+        assert!(scale > 0.0 && scale < 1.0);
+        assert_eq!(scene.world.len(), 2);
+
+        scene.update_despawning(start + Duration::from_secs(1));
+        assert_eq!(scene.world.len(), 1);
+    }
+
+    #[test]
+    fn test_despawn_restored_on_reappear() {
+        let (mut scene, _server) = connected_scene(NetworkConfig { interpolation_delay: Duration::ZERO, ..Default::default() });
+        scene.set_despawn_fade(Some(Duration::from_secs(1)));
+        scene.process_message("GAMESERVER update 2 1 0 0 2 3 3").unwrap();
+        let object = entity(&scene, 2);
+
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        let start = scene.despawning[0].2;
+        scene.update_despawning(start + Duration::from_millis(500));
+
+        // 같은 오브젝트가 다시 쓰이고, 사라지는 중인 목록에서 빠짐
+        scene.process_message("GAMESERVER update 2 1 0 0 2 3 4").unwrap();
+        assert!(scene.despawning.is_empty());
+        assert_eq!(entity(&scene, 2), object);
+        assert_eq!(renderable(&scene, 2).alpha, 1.0);
+        assert_eq!(transform(&scene, 2).scale, 1.0);
+        assert_eq!(interpolated(&scene, 2).target, Some(scene.board.cell_to_world(3, 4)));
+
+        scene.update_despawning(start + Duration::from_secs(2));
+        assert_eq!(scene.world.len(), 2);
+    }
+
+    #[test]
+    fn test_remote_object_limit() {
+        let config = NetworkConfig { max_remote_objects: 2, ..Default::default() };
+        let (mut scene, _server) = connected_scene(config);
+        let logs = logging::capture::capture(|| {
+            scene.process_message("GAMESERVER update 3 1 0 0 2 1 1 3 2 2").unwrap();
+        });
+        let mut ids = server_ids(&scene);
+        ids.sort();
+        assert_eq!(ids, [1, 2]);
+        assert!(logs.iter().any(|r| r.target == logging::NET && r.level == log::Level::Warn));
+
+        let config = NetworkConfig { 
+            max_remote_objects: 2, 
+            overflow_policy: OverflowPolicy::EvictFurthest, 
+            ..Default::default() 
+        };
+        let (mut scene, _server) = connected_scene(config);
+        scene.camera.component.eye = Point3::new(0.0, 0.0, 0.0);
+        scene.process_message("GAMESERVER update 2 1 0 0 2 7 7").unwrap();
+        scene.process_message("GAMESERVER update 3 1 0 0 2 7 7 3 1 1").unwrap();
+        let mut ids = server_ids(&scene);
+        ids.sort();
+        assert_eq!(ids, [1, 3]);
+    }
+
+    #[test]
+    fn test_remote_object_limit_churn() {
+        let config = NetworkConfig { max_remote_objects: 4, interpolation_delay: Duration::ZERO, ..Default::default() };
+        let (mut scene, _server) = connected_scene(config);
+
+        // 매번 새 id를 잔뜩 보내도 최대 개수를 넘지 않음
+        for round in 0..10u32 {
+            let ids = (0..50).map(|i| round * 100 + i);
+            let entries = ids.map(|id| format!("{} 0 0", id)).collect::<Vec<_>>();
+            let message = format!("GAMESERVER update {} {}", entries.len(), entries.join(" "));
+            logging::capture::capture(|| scene.process_message(&message).unwrap());
+            assert!(scene.world.network_count() <= 4);
+        }
+
+        // 가득 찬 상태에서도 기존 오브젝트는 갱신하고, 사라진 자리는 새 id가 사용함
+        scene.process_message("GAMESERVER update 4 900 0 0 901 0 0 902 0 0 903 0 0").unwrap();
+        scene.process_message("GAMESERVER update 4 900 3 3 901 0 0 904 0 0 905 0 0").unwrap();
+        let mut ids = server_ids(&scene);
+        ids.sort();
+        assert_eq!(ids, [900, 901, 904, 905]);
+        assert!(interpolated(&scene, 900).target.is_some());
+    }
+
+    #[test]
+    fn test_interpolation_clamp() {
+        let (mut scene, _server) = connected_scene(NetworkConfig { interpolation_delay: Duration::ZERO, ..Default::default() });
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        scene.process_message("GAMESERVER update 1 1 4 2").unwrap();
+
+        scene.update_positions(Duration::from_millis(10));
+        let position = transform(&scene, 1).position;
+        assert!(position.x > 0.0 && position.x < 4.0);
+
+        scene.update_positions(Duration::from_secs(30));
+        assert_eq!(transform(&scene, 1).position, Vector3::new(4.0, 0.0, 2.0));
+        assert_eq!(interpolated(&scene, 1).target, None);
+    }
+
+    #[test]
+    fn test_resync_keyframe() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.set_despawn_fade(Some(Duration::from_secs(1)));
+        scene.process_message("GAMESERVER update 2 1 0 0 2 3 3").unwrap();
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        assert_eq!(scene.despawning.len(), 1);
+
+        scene.request_resync();
+        assert_received(&mut scene, &mut server, "resync\n");
+
+        scene.process_message("GAMESERVER keyframe 2 1 5 5 3 1 1").unwrap();
+        let mut ids = server_ids(&scene);
+        ids.sort();
+        assert_eq!(ids, [1, 3]);
+        assert!(scene.despawning.is_empty());
+        // 보간 없이 바로 새 위치
+        assert_eq!(transform(&scene, 1).position, Vector3::new(5.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn test_board_spacing() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.build_objects();
+        scene.set_board_layout(BoardLayout::uniform(2.0));
+        assert_eq!(tile_transform(&scene, 9).position, Vector3::new(2.0, -0.5, 2.0));
+
+        scene.process_message("GAMESERVER update 1 7 3 2").unwrap();
+        assert_eq!(transform(&scene, 7).position, Vector3::new(6.0, 0.0, 4.0));
+
+        // 타일 윗면 중심을 화면에 투영한 좌표를 다시 고르면 같은 타일
+        scene.camera.component.eye = Point3::new(7.0, 12.0, 14.0);
+        scene.camera.component.target = Point3::new(7.0, 0.0, 7.0);
+        let clip = scene.view_proj() * Vector4::new(6.0, 0.0, 4.0, 1.0);
+        let x = (clip.x / clip.w + 1.0) / 2.0 * SCREEN_WIDTH as f32;
+        let y = (1.0 - clip.y / clip.w) / 2.0 * SCREEN_HEIGHT as f32;
+        assert_eq!(scene.pick_tile(x, y), Some(scene.board.tile_index(3, 2)));
+
+        scene.process_click(x, y);
+        assert_eq!(scene.selected(), Some(7));
+
+        // 플레이어가 있으면 클릭한 칸으로 이동을 요청
+        scene.player_id = Some(1);
+        scene.process_click(x, y);
+        assert_received(&mut scene, &mut server, "move_to 3 2\n");
+    }
+
+    #[test]
+    fn test_resize() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        let point = Vector4::new(1.0, 0.0, 0.0, 1.0);
+        let project = |scene: &GameScene| {
+            let clip = scene.view_proj() * point;
+            Vector2::new(clip.x / clip.w, clip.y / clip.w)
+        };
+
+        let before = project(&scene);
+        scene.resize(SCREEN_WIDTH * 2, SCREEN_HEIGHT);
+        let after = project(&scene);
+        // 가로로 두 배 늘어나면 같은 점이 화면 가로 비율로 절반 위치에 보임
+        assert!((after.x - before.x / 2.0).abs() < 1e-5);
+        assert!((after.y - before.y).abs() < 1e-5);
+
+        scene.resize(SCREEN_WIDTH, 0);
+        assert!(scene.view_proj().x.x.is_finite());
+        assert_eq!(project(&scene), after);
+    }
+
+    #[test]
+    fn test_light_covers_board() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER map 4 2 .... ....").unwrap();
+
+        let light = scene.light();
+        let center = scene.board.cell_to_world(0, 0) + (scene.board.cell_to_world(3, 1) - scene.board.cell_to_world(0, 0)) / 2.0;
+        assert!((light.center.to_vec() - center).magnitude() < 1e-5);
+        for (x, z) in [(0, 0), (3, 0), (0, 1), (3, 1)] {
+            assert!(light.center.distance(Point3::from_vec(scene.board.cell_to_world(x, z))) < light.radius);
+        }
+    }
+
+    #[test]
+    fn test_malformed_update() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+
+        assert_eq!(
+            scene.process_message("GAMESERVER update 3 1 2"),
+            Err(ProtocolError::CountMismatch { expected: 3, found: 0 })
+        );
+        assert_eq!(
+            scene.process_message("GAMESERVER update abc"),
+            Err(ProtocolError::BadInteger("abc".to_string()))
+        );
+        assert_eq!(
+            scene.process_message("GAMESERVER update 1 2 x 0"),
+            Err(ProtocolError::BadInteger("x".to_string()))
+        );
+        assert_eq!(scene.process_message("GAMESERVER init"), Err(ProtocolError::MissingField("id")));
+        assert_eq!(
+            scene.process_message("GAMESERVER jump"),
+            Err(ProtocolError::UnknownCommand("jump".to_string()))
+        );
+
+        assert_eq!(server_ids(&scene), [1]);
+        assert_eq!(scene.player_id, None);
+    }
+
+    #[test]
+    fn test_malformed_message_warning() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        let logs = logging::capture::capture(|| {
+            for _ in 0..1000 {
+                scene.packet_parser.push(b"GAMESERVER update 2 1 0\n");
+            }
+            scene.process_messages();
+        });
+        assert!(scene.world.network_count() == 0);
+        let warnings = logs.iter()
+            .filter(|r| r.target == logging::NET && r.level == log::Level::Warn)
+            .collect::<Vec<_>>();
+        assert_eq!(warnings.len(), 1);
+
+        let later = Instant::now() + PARSE_WARNING_INTERVAL;
+        let error = ProtocolError::BadInteger("x".to_string());
+        let logs = logging::capture::capture(|| scene.report_malformed("GAMESERVER init x", &error, later));
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].message.contains("999 more suppressed"));
+    }
+
+    #[test]
+    fn test_hostile_packets() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.build_objects();
+        let packets: [&[u8]; 12] = [
+            b"GAMESERVER update 99999999999999999999 1 0 0\n",
+            b"GAMESERVER update -1 1 0 0\n",
+            b"GAMESERVER update 1 4294967295 2147483647 -2147483648\n",
+            b"GAMESERVER map 2147483647 2 .. ..\n",
+            b"GAMESERVER map -3 -3\n",
+            b"GAMESERVER delta 18446744073709551615 1 1\n",
+            b"GAMESERVER ack -5\n",
+            b"GAMESERVER color 1 nan nan nan\n",
+            b"GAMESERVER \xff\xfe\n",
+            b"GAMESERVER\n",
+            b"\n",
+            b"garbage\n",
+        ];
+        logging::capture::capture(|| {
+            for packet in packets {
+                scene.packet_parser.push(packet);
+            }
+            scene.update(Duration::ZERO);
+        });
+
+        // 잘못된 메세지는 버리고 다음 메세지는 그대로 처리함
+        scene.packet_parser.push(b"GAMESERVER init 7\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.player_id, Some(7));
+        assert_eq!(scene.tiles.len(), 64);
+    }
+
+    #[test]
+    fn test_chat_message() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER chat 2 alice hello  there").unwrap();
+        scene.process_message("GAMESERVER chat 3 - hi").unwrap();
+
+        let chat = scene.recent_chat(1).collect::<Vec<_>>();
+        assert_eq!(chat.len(), 1);
+        assert_eq!((chat[0].sender, chat[0].name.as_deref(), chat[0].text.as_str()), (3, None, "hi"));
+        let first = scene.recent_chat(2).next().unwrap();
+        assert_eq!((first.name.as_deref(), first.text.as_str()), (Some("alice"), "hello there"));
+
+        // 오래된 채팅은 사라지지만 입력창을 열면 입력 중인 줄과 함께 다시 보임
+        let now = Instant::now();
+        assert_eq!(scene.chat_overlay(now), ["alice: hello there", "3: hi"]);
+        assert!(scene.chat_overlay(now + CHAT_OVERLAY_DURATION).is_empty());
+        scene.focus_text_input();
+        scene.process_key(&ElementState::Pressed, &KeyCode::KeyA, false, Some("a"));
+        assert_eq!(scene.chat_overlay(now + CHAT_OVERLAY_DURATION), ["alice: hello there", "3: hi", "> a_"]);
+    }
+
+    #[test]
+    fn test_move_batch() {
+        let config = NetworkConfig { move_batch_ticks: Some(3), ..Default::default() };
+        let (mut scene, mut server) = connected_scene(config);
+        scene.process_message("GAMESERVER init 0").unwrap();
+
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyD);
+        scene.queue_held_move(Instant::now());
+        scene.process_input_actions();
+        scene.tick_move_batch();
+        scene.process_keyboard_input(&ElementState::Released, &KeyCode::KeyD);
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyW);
+        scene.queue_held_move(Instant::now());
+        scene.process_input_actions();
+        scene.tick_move_batch();
+        scene.process_keyboard_input(&ElementState::Released, &KeyCode::KeyW);
+        scene.queue_held_move(Instant::now());
+        scene.tick_move_batch();
+        assert_received(&mut scene, &mut server, "moves 0 0 2 1 0 0 -1\n");
+        assert!(scene.move_batch.is_empty());
+        assert_eq!(scene.move_seq, 2);
+    }
+
+    #[test]
+    fn test_interpolation_approaches_target() {
+        let (mut scene, _server) = connected_scene(NetworkConfig { interpolation_delay: Duration::ZERO, ..Default::default() });
+        scene.set_interpolation_rate(5.0);
+        scene.process_message("GAMESERVER init 2").unwrap();
+        scene.process_message("GAMESERVER update 2 1 0 0 2 0 0").unwrap();
+        scene.process_message("GAMESERVER update 2 1 6 0 2 6 0").unwrap();
+
+        // 플레이어 자신은 바로 이동
+        assert_eq!(transform(&scene, 2).position.x, 6.0);
+
+        let mut prev = 0.0;
+        for _ in 1..=10 {
+            scene.update_positions(Duration::from_millis(16));
+            let x = transform(&scene, 1).position.x;
+            assert!(x > prev && x <= 6.0);
+            prev = x;
+        }
+    }
+
+    #[test]
+    fn test_snapshot_delay() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 2").unwrap();
+        scene.process_message("GAMESERVER update 2 1 0 0 2 0 0").unwrap();
+        scene.process_message("GAMESERVER update 2 1 6 0 2 6 0").unwrap();
+
+        // 다른 오브젝트는 지연 시간이 지나기 전까지 이전 스냅샷 위치. 플레이어 자신은 버퍼 없이 바로 이동
+        scene.update_positions(Duration::from_millis(16));
+        let object = entity(&scene, 1);
+        assert_eq!(transform(&scene, 1).position.x, 0.0);
+        assert_eq!(scene.world.snapshots.get(object).map(SnapshotBuffer::len), Some(2));
+        assert_eq!(transform(&scene, 2).position.x, 6.0);
+        assert!(!scene.world.snapshots.contains(entity(&scene, 2)));
+
+        // 디버그 패널을 켰을 때만 -/=로 지연 시간을 바꿈
+        assert!(!scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::Equal));
+        scene.toggle_debug_panel();
+        assert!(scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::Equal));
+        assert_eq!(scene.network_config.interpolation_delay, Duration::from_millis(110));
+
+        // 0이면 버퍼를 버리고 목표 위치를 따라감
+        scene.set_interpolation_delay(Duration::ZERO);
+        assert!(scene.world.snapshots.is_empty());
+        scene.process_message("GAMESERVER update 1 1 8 0").unwrap();
+        assert_eq!(interpolated(&scene, 1).target.map(|target| target.x), Some(8.0));
+    }
+
+    #[test]
+    fn test_remove_instance_from_model() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        let count = |scene: &GameScene, name: &str| scene.world.count_model(name);
+
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 3 1 0 0 2 1 1 3 2 2").unwrap();
+        assert_eq!(count(&scene, PLAYER_MODEL), 1);
+        assert_eq!(count(&scene, "pawn"), 2);
+
+        let removed = entity(&scene, 2);
+        scene.process_message("GAMESERVER update 2 1 0 0 3 2 2").unwrap();
+        assert_eq!(count(&scene, PLAYER_MODEL), 1);
+        assert_eq!(count(&scene, "pawn"), 1);
+        assert!(!scene.world.is_alive(removed));
+        assert!(scene.world.renderables.get(removed).is_none());
+    }
+
+    #[test]
+    fn test_reload_models() {
+        let Some((device, queue)) = super::super::super::test_device() else {
+            return;
+        };
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.init(&device, &queue).unwrap();
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 2 1 0 0 2 1 1").unwrap();
+        let counts = |scene: &GameScene| scene.assets.named_models().map(|(name, _)| scene.world.count_model(name)).collect::<Vec<_>>();
+        let before = counts(&scene);
+        let old_player = scene.assets.model(PLAYER_MODEL).unwrap().clone();
+
+        // 엔티티는 그대로 새 모델로 그려짐
+        scene.reload_models(&device, &queue).unwrap();
+        assert_eq!(counts(&scene), before);
+        assert!(!Rc::ptr_eq(scene.assets.model(PLAYER_MODEL).unwrap(), &old_player));
+        assert!(scene.world.animators.get(entity(&scene, 1)).is_some());
+    }
+
+    #[test]
+    fn test_disconnect_on_exit() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 3").unwrap();
+
+        scene.on_exit();
+        assert_received(&mut scene, &mut server, "disconnect 3\n");
+        let mut buf = [0; 8];
+        assert_eq!(server.read(&mut buf).unwrap(), 0);
+        assert!(!scene.transport.is_connected());
+
+        // 서버가 이미 끊겼어도 종료는 계속됨
+        let (mut scene, server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 3").unwrap();
+        drop(server);
+        scene.on_exit();
+        scene.on_exit();
+    }
+
+    #[test]
+    fn test_pushed_updates() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        transport.push_incoming(format!("GAMESERVER init 1 {} 42\n", PUSHED_UPDATES_VERSION).as_bytes());
+        scene.update(Duration::ZERO);
+        transport.take_outgoing();
+
+        // 요청하지 않아도 서버가 보낸 위치를 적용
+        for _ in 0..10 {
+            scene.update(Duration::from_secs(2));
+        }
+        assert!(!transport.take_outgoing().contains("update"));
+        transport.push_incoming(b"GAMESERVER update 1 1 1 3 4\n");
+        scene.update(Duration::ZERO);
+        assert!(scene.world.network_entity(1).is_some());
+
+        // 이전 서버에는 계속 요청
+        transport.push_incoming(b"GAMESERVER init 1 3 42\n");
+        scene.update(Duration::ZERO);
+        scene.update(Duration::from_secs(2));
+        assert!(transport.take_outgoing().contains("update\n"));
+    }
+
+    #[test]
+    fn test_update_request_interval() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let config = NetworkConfig { 
+            update_request_interval: Duration::from_millis(50), 
+            update_request_timeout: Duration::from_millis(500),
+            ..Default::default() 
+        };
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, config);
+        let requests = |transport: &MockTransport| transport.take_outgoing().matches("update\n").count();
+
+        // 응답이 오면 50ms마다 요청: 1초 동안 10ms 프레임 100개
+        for _ in 0..100 {
+            transport.push_incoming(b"GAMESERVER update 0\n");
+            scene.update(Duration::from_millis(10));
+        }
+        assert_eq!(requests(&transport), 20);
+
+        // 응답이 없으면 timeout마다 다시 요청
+        scene.update(Duration::from_millis(50));
+        for _ in 0..100 {
+            scene.update(Duration::from_millis(10));
+        }
+        assert_eq!(requests(&transport), 3);
+    }
+
+    #[test]
+    fn test_partial_writes() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        scene.process_message("GAMESERVER init 2").unwrap();
+        transport.limit_writes(Some(0));
+
+        scene.send_chat("hello there".to_string());
+        scene.update(Duration::ZERO);
+        assert_eq!(transport.take_outgoing(), "");
+
+        // 한 번에 3바이트씩만 받아도 쌓인 메세지가 순서대로, 깨지지 않고 모두 전송됨
+        transport.limit_writes(Some(3));
+        scene.push_move(Vector2::new(1.0, 0.0));
+        scene.update(Duration::ZERO);
+        transport.limit_writes(None);
+        scene.update(Duration::ZERO);
+        assert_eq!(
+            transport.take_outgoing(), 
+            "chat 2 hello there\nupdate\nmove 2 1 0\n"
+        );
+        assert!(scene.outgoing.is_empty());
+    }
+
+    #[test]
+    fn test_connection_state() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let addr = "127.0.0.1:7878".parse().unwrap();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), Some(addr), NetworkConfig::default());
+        assert_eq!(scene.connection_state(), ConnectionState::Connecting);
+
+        transport.push_incoming(b"GAMESERVER init 1\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.connection_state(), ConnectionState::Connected);
+
+        // 서버가 연결을 닫고, 두 번 다시 접속에 실패한 뒤 성공
+        transport.close_by_peer();
+        transport.fail_reconnects(2);
+        scene.pull_messages();
+        assert_eq!(scene.connection_state(), ConnectionState::Disconnected);
+
+        let mut states = Vec::new();
+        for i in 0..3 {
+            scene.try_reconnect(Instant::now() + Duration::from_secs(60 * (i + 1)));
+            states.push(scene.connection_state());
+        }
+        assert_eq!(states, [
+            ConnectionState::Reconnecting { attempt: 1 },
+            ConnectionState::Reconnecting { attempt: 2 },
+            ConnectionState::Connecting,
+        ]);
+
+        transport.push_incoming(b"GAMESERVER init 1\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.connection_state(), ConnectionState::Connected);
+
+        scene.on_exit();
+        assert_eq!(scene.connection_state(), ConnectionState::Disconnected);
+    }
+
+    #[test]
+    fn test_compressed_messages() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        transport.push_incoming(format!("GAMESERVER init 1 {}\n", PROTOCOL_VERSION).as_bytes());
+        scene.update(Duration::ZERO);
+        assert!(transport.take_outgoing().lines().any(|line| line == "compress"));
+
+        // 압축한 한 줄에 든 메세지를 모두 처리
+        let objects = (1..=40).map(|id| format!("{} {} {}", id, id % 8, id / 8)).collect::<Vec<_>>().join(" ");
+        let line = network::compress(&format!("GAMESERVER update 40 {}\nGAMESERVER names 0\n", objects)).unwrap();
+        transport.push_incoming(line.as_bytes());
+        transport.push_incoming(b"GAMESERVER deflate 10 AAAA\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.world.network_count(), 40);
+    }
+
+    #[test]
+    fn test_newer_server_version() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let addr = "127.0.0.1:7878".parse().unwrap();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), Some(addr), NetworkConfig::default());
+        let init = format!("GAMESERVER init 1 {}\nGAMESERVER update 1 1 1 1\n", PROTOCOL_VERSION + 1);
+        transport.push_incoming(init.as_bytes());
+
+        // 뒤따르는 메세지는 읽지 않고, 다시 접속하지 않고 오류 화면으로
+        let Some(SceneTransition::ReplaceAll(ActiveScene::Error(error))) = scene.update(Duration::ZERO) else {
+            panic!("expected error scene");
+        };
+        assert!(error.message().contains("Client out of date"));
+        assert_eq!((scene.player_id, scene.world.network_count()), (None, 0));
+        assert_eq!(scene.connection_state(), ConnectionState::Closed);
+        scene.try_reconnect(Instant::now() + Duration::from_secs(60));
+        assert_eq!(transport.reconnects(), 0);
+    }
+
+    #[test]
+    fn test_server_closing() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let addr = "127.0.0.1:7878".parse().unwrap();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), Some(addr), NetworkConfig::default());
+        transport.push_incoming(b"GAMESERVER init 1\nGAMESERVER update 1 1 1 1\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.world_snapshot().objects.len(), 1);
+
+        // 연결이 닫힌 것을 먼저 알아도 종료 메세지를 받으면 다시 접속하지 않음
+        transport.push_incoming(b"GAMESERVER server_closing\n");
+        transport.close_by_peer();
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.connection_state(), ConnectionState::Closed);
+        assert!(scene.world_snapshot().objects.is_empty());
+
+        scene.try_reconnect(Instant::now() + Duration::from_secs(60));
+        assert_eq!(transport.reconnects(), 0);
+        assert_eq!(scene.connection_state(), ConnectionState::Closed);
+    }
+
+    #[test]
+    fn test_udp_updates() {
+        use std::net::UdpSocket;
+        use super::super::super::transport::MockTransport;
+
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let transport = MockTransport::connected();
+        let config = NetworkConfig { udp_updates: true, ..Default::default() };
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), Some(server.local_addr().unwrap()), config);
+
+        // init 전에는 TCP로 요청
+        scene.update(Duration::from_millis(20));
+        assert!(transport.take_outgoing().contains("update\n"));
+
+        transport.push_incoming(b"GAMESERVER init 1 3 42\n");
+        scene.update(Duration::ZERO);
+        scene.update(Duration::from_secs(2));
+        assert!(!transport.take_outgoing().contains("update"));
+
+        let mut buf = [0; 64];
+        let (n, client) = server.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"update 1 42\n");
+
+        // 늦게 도착한 이전 응답은 버림
+        server.send_to(b"GAMESERVER update 2 1 1 3 4\n", client).unwrap();
+        server.send_to(b"GAMESERVER update 1 2 1 0 0 2 0 0\n", client).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while scene.last_seq != Some(2) && Instant::now() < deadline {
+            scene.pull_datagrams();
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        scene.pull_datagrams();
+        assert_eq!(server_ids(&scene), [1]);
+    }
+
+    #[test]
+    fn test_resume_session() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let addr = "127.0.0.1:7878".parse().unwrap();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), Some(addr), NetworkConfig::default());
+        transport.push_incoming(b"GAMESERVER init 1 3 42\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.session, Some((1, 42)));
+        transport.take_outgoing();
+
+        // 다시 접속하면 새 id를 받은 뒤 끊기기 전의 id로 돌아감
+        transport.close_by_peer();
+        scene.pull_messages();
+        scene.try_reconnect(Instant::now() + Duration::from_secs(60));
+        scene.flush_outgoing();
+        assert!(transport.take_outgoing().starts_with("resume 1 42\nresync\n"));
+
+        transport.push_incoming(b"GAMESERVER init 5 3 7\nGAMESERVER init 1 3 42\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.player_id, Some(1));
+        assert_eq!(scene.session, Some((1, 42)));
+
+        // 토큰을 보내지 않는 서버
+        scene.process_message("GAMESERVER init 2").unwrap();
+        assert_eq!(scene.session, None);
+    }
+
+    #[test]
+    fn test_join_room() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        transport.push_incoming(b"GAMESERVER init 1 5 9\nGAMESERVER update 1 2 1 3 3 2 4 4\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.room(), Some(LOBBY_ROOM));
+        assert_eq!(scene.world.network_count(), 2);
+
+        // 다른 방의 위치를 받기 전에 이전 방의 오브젝트를 지움
+        scene.join_room(3);
+        transport.push_incoming(b"GAMESERVER joined 3\n");
+        scene.update(Duration::ZERO);
+        assert!(transport.take_outgoing().contains("join 3\n"));
+        assert_eq!(scene.room(), Some(3));
+        assert!(scene.world.network_count() == 0);
+
+        scene.process_message("GAMESERVER join_failed room is full").unwrap();
+        assert_eq!(scene.room(), Some(3));
+        assert_eq!(scene.room_error(), Some("room is full"));
+
+        // 방이 없는 서버
+        scene.process_message("GAMESERVER init 1 4").unwrap();
+        assert!(!scene.supports_rooms());
+        assert_eq!(scene.room(), None);
+    }
+
+    #[test]
+    fn test_hud_lines() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        assert_eq!(scene.hud_lines(), ["FPS -", "PING -", "ID -", "CONNECTING"]);
+
+        transport.push_incoming(b"GAMESERVER init 3\n");
+        scene.update(Duration::from_millis(20));
+        assert_eq!(scene.hud_lines(), ["FPS 50", "PING -", "ID 3", "CONNECTED"]);
+
+        let mut hud = Hud::new();
+        scene.hud(&mut hud);
+        assert!(!hud.is_empty());
+    }
+
+    #[test]
+    fn test_debug_panel() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        assert_eq!(scene.debug_panel(), None);
+
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::F3);
+        transport.push_incoming(b"GAMESERVER init 1\nGAMESERVER update 2 1 3 3 2 4 4\n");
+        scene.update(Duration::ZERO);
+        let lines = scene.debug_panel().unwrap();
+        assert!(lines.contains(&"SERVER OBJECTS 2".to_string()));
+        assert!(lines.contains(&"FREE CAMERA OFF".to_string()));
+
+        scene.toggle_debug_panel();
+        assert_eq!(scene.debug_panel(), None);
+    }
+
+    #[test]
+    fn test_minimap() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        transport.push_incoming(b"GAMESERVER init 1 8 9\nGAMESERVER spawn 2 1 player 5 npc\nGAMESERVER update 1 2 1 3 3 5 4 4\n");
+        scene.update(Duration::ZERO);
+
+        // 자기 플레이어가 마지막
+        let dots = scene.minimap_dots();
+        assert_eq!(dots, [
+            (scene.board.cell_to_world(4, 4), Color::RED),
+            (scene.board.cell_to_world(3, 3), PLAYER_COLOR),
+        ]);
+        let minimap = scene.minimap().unwrap();
+        assert!(dots.iter().all(|(position, _)| minimap.to_screen(*position).is_some()));
+
+        let mut with_minimap = Hud::new();
+        scene.hud(&mut with_minimap);
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyM);
+        assert_eq!(scene.minimap(), None);
+        let mut without_minimap = Hud::new();
+        scene.hud(&mut without_minimap);
+        assert!(without_minimap.vertices(800, 600).len() < with_minimap.vertices(800, 600).len());
+    }
+
+    #[test]
+    fn test_name_tags() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let config = NetworkConfig { player_name: Some("my name".to_string()), ..NetworkConfig::default() };
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, config);
+        transport.push_incoming(b"GAMESERVER init 1 6 9\nGAMESERVER update 1 2 1 3 3 2 4 4\n");
+        scene.update(Duration::ZERO);
+        assert!(transport.take_outgoing().contains("name my_name\n"));
+
+        transport.push_incoming(b"GAMESERVER names 2 1 my_name 2 B\n");
+        scene.update(Duration::ZERO);
+        assert_eq!(scene.name_of(1), Some("my_name"));
+        assert_eq!(scene.name_tags[&2].text, "B");
+        assert_eq!(scene.name_tags[&2].pixels.len(), name_tag::layout("B").len());
+
+        // 이름이 바뀌면 새로 만들고, 이름이 없어지면 지움
+        transport.push_incoming(b"GAMESERVER names 1 2 C\n");
+        scene.update(Duration::ZERO);
+        assert!(!scene.name_tags.contains_key(&1));
+        assert_eq!(scene.name_tags[&2].text, "C");
+
+        // 이름을 지원하지 않는 서버에는 보내지 않음
+        scene.process_message("GAMESERVER init 1 5 9").unwrap();
+        assert!(scene.names.is_empty());
+        scene.update(Duration::ZERO);
+        assert!(!transport.take_outgoing().contains("name "));
+
+        // 다른 플레이어로 접속해도 처음 받은 토큰을 열쇠로 보냄
+        scene.process_message(&format!("GAMESERVER init 3 {} 11", NAME_KEY_VERSION)).unwrap();
+        scene.update(Duration::ZERO);
+        assert!(transport.take_outgoing().contains("name my_name 9\n"));
+    }
+
+    #[test]
+    fn test_world_snapshot() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        scene.set_interpolation_rate(0.0);
+        assert_eq!(scene.world_snapshot().connection, ConnectionState::Connecting);
+
+        transport.push_incoming(b"GAMESERVER init 7\nGAMESERVER update 2 8 3 4 7 1 2\n");
+        scene.update(Duration::ZERO);
+
+        let snapshot = scene.world_snapshot();
+        assert_eq!(snapshot.player_id, Some(7));
+        assert_eq!(snapshot.connection, ConnectionState::Connected);
+        assert_eq!(snapshot.objects, [
+            ObjectSnapshot { id: 7, position: scene.board.cell_to_world(1, 2), is_local: true },
+            ObjectSnapshot { id: 8, position: scene.board.cell_to_world(3, 4), is_local: false },
+        ]);
+
+        scene.close_connection();
+        assert_eq!(scene.world_snapshot().connection, ConnectionState::Disconnected);
+    }
+
+    #[test]
+    fn test_mock_transport() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let addr = "127.0.0.1:7878".parse().unwrap();
+        let mut scene = GameScene::with_transport(
+            Box::new(transport.clone()), 
+            Some(addr), 
+            NetworkConfig::default()
+        );
+
+        // 한 메세지가 두 번에 나뉘어 도착해도 처리됨
+        transport.push_incoming(b"GAMESERVER init 7\nGAMESERVER upd");
+        transport.push_incoming(b"ate 2 7 1 2 8 3 4\n");
+        scene.update(Duration::ZERO);
+
+        assert_eq!(scene.player_id, Some(7));
+        let mut ids = server_ids(&scene);
+        ids.sort();
+        assert_eq!(ids, [7, 8]);
+        assert_eq!(transport.take_outgoing(), "update\n");
+
+        // 연결이 끊기면 다시 접속하고 resync 요청
+        transport.push_incoming(b"GAMESERVER update 1 7 1 2\n");
+        scene.transport.close().unwrap();
+        scene.update(Duration::ZERO);
+        assert_eq!(transport.reconnects(), 1);
+        assert_eq!(transport.take_outgoing(), "resync\nupdate\n");
+    }
+
+    #[test]
+    fn test_update_dt() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig { interpolation_delay: Duration::ZERO, ..Default::default() });
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 2 0 0").unwrap();
+        scene.process_message("GAMESERVER update 1 2 4 0").unwrap();
+
+        let interval = scene.network_config.move_interval;
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyW);
+        let mut moves = Vec::new();
+        for dt in [Duration::ZERO, interval / 2, interval / 2, interval / 4] {
+            scene.update(dt);
+            moves.push(transport.take_outgoing().matches("move ").count());
+        }
+        // 실제 시각이 아니라 누적된 dt 기준으로 간격을 지킴
+        assert_eq!(moves, [1, 0, 1, 0]);
+
+        // 경과 시간이 길수록 목표에 더 가까이 이동
+        let x = transform(&scene, 2).position.x;
+        scene.update(Duration::from_millis(16));
+        let small_step = transform(&scene, 2).position.x - x;
+        scene.update(Duration::from_millis(64));
+        let large_step = transform(&scene, 2).position.x - x - small_step;
+        assert!(large_step > small_step);
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        let interval = scene.network_config.heartbeat_interval;
+        let start = scene.last_heartbeat;
+
+        scene.send_heartbeat(start + interval / 2);
+        scene.flush_outgoing();
+        assert_eq!(transport.take_outgoing(), "");
+        let epoch = scene.ping_epoch;
+        let stamp = |time: Instant| time.saturating_duration_since(epoch).as_millis();
+        let first = stamp(start + interval);
+        scene.send_heartbeat(start + interval);
+        scene.flush_outgoing();
+        assert_eq!(transport.take_outgoing(), format!("ping {}\n", first));
+        scene.send_heartbeat(start + interval * 3 / 2);
+        scene.flush_outgoing();
+        assert_eq!(transport.take_outgoing(), "");
+        let second = stamp(start + interval * 2);
+        scene.send_heartbeat(start + interval * 2);
+        scene.flush_outgoing();
+        assert_eq!(transport.take_outgoing(), format!("ping {}\n", second));
+    }
+
+    #[test]
+    fn test_ping_ms() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        assert_eq!(scene.ping_ms(), None);
+
+        let sent = scene.ping_epoch + Duration::from_millis(2000);
+        scene.record_round_trip(2000, sent + Duration::from_millis(35));
+        assert_eq!(scene.ping_ms(), Some(35));
+
+        // stamp 없는 pong은 왕복 시간을 바꾸지 않음
+        scene.process_message("GAMESERVER pong").unwrap();
+        assert_eq!(scene.ping_ms(), Some(35));
+        scene.process_message("GAMESERVER pong 0").unwrap();
+        assert!(scene.ping_ms().is_some_and(|ms| ms != 35));
+    }
+
+    #[test]
+    fn test_stale_connection() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        let timeout = scene.network_config.stale_timeout;
+        let seen = scene.last_server_seen;
+        assert!(!scene.is_stale(seen + timeout));
+        assert!(scene.is_stale(seen + timeout + Duration::from_millis(1)));
+
+        scene.process_message("GAMESERVER pong").unwrap();
+        assert!(scene.last_server_seen >= seen);
+        assert!(!scene.is_stale(scene.last_server_seen + timeout / 2));
+
+        // 연결된 서버가 응답이 없으면 연결을 닫고 오브젝트를 지움
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        scene.check_stale(scene.last_server_seen + timeout / 2);
+        assert_eq!(scene.connection_state(), ConnectionState::Connected);
+
+        logging::capture::capture(|| scene.check_stale(scene.last_server_seen + timeout * 2));
+        assert_eq!(scene.connection_state(), ConnectionState::Disconnected);
+        assert!(!scene.transport.is_connected());
+        assert!(scene.world.network_count() == 0);
+    }
+
+    #[test]
+    fn test_update_with_height() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        let position = |scene: &GameScene, id| transform(scene, id).position;
+
+        scene.process_message("GAMESERVER update3 2 1 2 5 3 4 0 0 1").unwrap();
+        assert_eq!(position(&scene, 1), scene.board.cell_to_world(2, 3) + Vector3::new(0.0, 5.0, 0.0));
+        assert_eq!(position(&scene, 4), scene.board.cell_to_world(0, 1));
+
+        // 기존 형식은 y가 0
+        scene.set_interpolation_rate(0.0);
+        scene.process_message("GAMESERVER update 2 1 2 3 4 0 1").unwrap();
+        assert_eq!(position(&scene, 1), scene.board.cell_to_world(2, 3));
+
+        // update3은 한 오브젝트에 4개 필드
+        assert_eq!(
+            scene.process_message("GAMESERVER update3 2 1 2 3 4 0 1"),
+            Err(ProtocolError::CountMismatch { expected: 2, found: 1 })
+        );
+    }
+
+    #[test]
+    fn test_update_with_rotation() {
+        let (mut scene, _server) = connected_scene(NetworkConfig { interpolation_delay: Duration::ZERO, ..Default::default() });
+        let rotation = |scene: &GameScene, id| transform(scene, id).rotation;
+        let yaw = |degrees: f32| cgmath::Quaternion::from_angle_y(cgmath::Deg(degrees));
+
+        scene.process_message("GAMESERVER update_rot 2 1 2 3 90 4 0 1 -45.5").unwrap();
+        assert_eq!(rotation(&scene, 1), yaw(90.0));
+        assert_eq!(rotation(&scene, 4), yaw(-45.5));
+        assert_eq!(transform(&scene, 1).position, scene.board.cell_to_world(2, 3));
+
+        // 회전이 없는 update는 기존 회전을 유지하고, 새 오브젝트는 회전 없음
+        scene.process_message("GAMESERVER update 3 1 2 3 4 0 1 5 1 1").unwrap();
+        assert_eq!(rotation(&scene, 1), yaw(90.0));
+        assert_eq!(rotation(&scene, 5), cgmath::Quaternion::new(1.0, 0.0, 0.0, 0.0));
+
+        assert_eq!(
+            scene.process_message("GAMESERVER update_rot 1 1 2 3 left"),
+            Err(ProtocolError::BadNumber("left".to_string()))
+        );
+        assert_eq!(
+            scene.process_message("GAMESERVER update_rot 1 1 2 3 NaN"),
+            Err(ProtocolError::BadNumber("NaN".to_string()))
+        );
+        assert_eq!(rotation(&scene, 1), yaw(90.0));
+
+        // 이미 있는 오브젝트는 새 방향으로 천천히 돌아감
+        scene.process_message("GAMESERVER update_rot 4 1 1 2 3 180").unwrap();
+        assert_eq!(rotation(&scene, 1), yaw(90.0));
+        scene.update_positions(Duration::from_millis(10));
+        let turning = rotation(&scene, 1);
+        assert!(turning != yaw(90.0) && turning != yaw(180.0));
+        scene.update_positions(Duration::from_secs(1));
+        assert_eq!(rotation(&scene, 1), yaw(180.0));
+    }
+
+    #[test]
+    fn test_despawn_without_fade() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER update 2 1 0 0 2 3 3").unwrap();
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        assert_eq!(scene.world.len(), 1);
+    }
+
+    #[test]
+    fn test_reset() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.build_objects();
+        // 위치를 요청해서 받는 서버
+        scene.process_message("GAMESERVER init 1 3 42").unwrap();
+        scene.process_message("GAMESERVER update 5 2 1 0 0 2 3 3").unwrap();
+        scene.process_message("GAMESERVER tiles 0 #ff0000").unwrap();
+        scene.request_update(Instant::now());
+        assert_eq!(scene.last_seq, Some(5));
+        assert!(scene.update_pending);
+
+        scene.reset(true);
+        assert!(scene.world.network_count() == 0);
+        assert_eq!(scene.player_id, None);
+        assert_eq!(scene.session, None);
+        assert_eq!(scene.last_seq, None);
+        assert!(!scene.update_pending);
+        assert_eq!(scene.tiles.len(), 64);
+        assert_eq!(tile_color(&scene, 0), None);
+    }
+
+    #[test]
+    fn test_network_log_target() {
+        let (mut scene, server) = connected_scene(NetworkConfig::default());
+        drop(server);
+        
+        let records = logging::capture::capture(|| scene.pull_messages());
+        assert!(!records.is_empty());
+        assert!(records.iter().all(|record| record.target == logging::NET));
+        assert!(records.iter().any(|record| {
+            record.level == log::Level::Info && record.message == "Connection closed"
+        }));
+    }
+
+    #[test]
+    fn test_background_color_message() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        assert_eq!(scene.skybox(), Some(DEFAULT_SKYBOX));
+
+        scene.process_message("GAMESERVER bgcolor #ff0000").unwrap();
+        assert_eq!(scene.skybox(), None);
+        let (_, _, started) = scene.background_transition.unwrap();
+
+        scene.update_background(started + BACKGROUND_TRANSITION / 2);
+        assert_ne!(scene.background_color(), DEFAULT_BACKGROUND_COLOR);
+        assert_ne!(scene.background_color(), Color::RED);
+
+        scene.update_background(started + BACKGROUND_TRANSITION);
+        assert_eq!(scene.background_color(), Color::RED);
+
+        scene.reset(false);
+        assert_eq!(scene.background_color(), DEFAULT_BACKGROUND_COLOR);
+        assert_eq!(scene.skybox(), Some(DEFAULT_SKYBOX));
+    }
+
+    #[test]
+    fn test_handshake_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let _silent = listener.accept().unwrap();
+
+        let network_config = NetworkConfig {
+            handshake_timeout: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let mut scene = GameScene::with_stream(stream, network_config);
+        let started = scene.handshake_started;
+
+        scene.check_handshake(started + Duration::from_millis(50));
+        assert_eq!(scene.handshake_error(), None);
+
+        scene.check_handshake(started + Duration::from_millis(100));
+        assert!(scene.handshake_error().unwrap().contains("Handshake timed out"));
+        // 다시 연결함
+        reconnect_until_done(&mut scene, started + Duration::from_millis(100));
+        assert!(scene.transport.is_connected());
+        assert!(listener.accept().is_ok());
+
+        scene.process_message("GAMESERVER init 3").unwrap();
+        assert_eq!(scene.handshake_error(), None);
+    }
+
+    #[test]
+    fn test_prediction_without_error() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 2 2").unwrap();
+
+        scene.send_move(Vector2::new(1.0, 0.0));
+        let predicted = scene.board.cell_to_world(3, 2);
+        assert_eq!(transform(&scene, 1).position, predicted);
+
+        // 이동을 처리하기 전의 update는 아직 처리되지 않은 이동을 다시 적용
+        scene.process_message("GAMESERVER update 1 1 2 2").unwrap();
+        scene.process_message("GAMESERVER ack 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 3 2").unwrap();
+        assert!(scene.pending_moves.is_empty());
+
+        assert_eq!(transform(&scene, 1).position, predicted);
+        assert_eq!(interpolated(&scene, 1).target, None);
+    }
+
+    #[test]
+    fn test_prediction_correction() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 2 2").unwrap();
+
+        scene.send_move(Vector2::new(1.0, 0.0));
+        scene.send_move(Vector2::new(1.0, 0.0));
+        assert_eq!(transform(&scene, 1).position, scene.board.cell_to_world(4, 2));
+
+        // 서버가 첫 이동만 처리하고 두번째 이동은 막음
+        scene.process_message("GAMESERVER ack 2").unwrap();
+        scene.process_message("GAMESERVER update 1 1 3 2").unwrap();
+        let server = scene.board.cell_to_world(3, 2);
+        assert_eq!(interpolated(&scene, 1).target, Some(server));
+
+        let mut prev = f32::MAX;
+        for _ in 0..10 {
+            scene.update_positions(Duration::from_millis(16));
+            let error = (transform(&scene, 1).position - server).magnitude();
+            assert!(error < prev);
+            prev = error;
+        }
+    }
+
+    #[test]
+    fn test_player_color() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER update 2 1 0 0 2 1 1").unwrap();
+        assert_eq!(renderable(&scene, 1).color, Some(PLAYER_COLOR));
+        assert_eq!(renderable(&scene, 2).color, None);
+
+        scene.set_player_color(None);
+        scene.process_message("GAMESERVER keyframe 1 1 0 0").unwrap();
+        assert_eq!(renderable(&scene, 1).color, None);
+    }
+
+    #[test]
+    fn test_server_colors() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        transport.push_incoming(b"GAMESERVER init 1 7 9\nGAMESERVER update 1 2 1 3 3 2 4 4\n");
+        scene.update(Duration::ZERO);
+        assert!(transport.take_outgoing().contains("colors\n"));
+        assert_eq!(renderable(&scene, 2).color, None);
+
+        // 자기 플레이어는 `player_color`를 유지
+        scene.process_message("GAMESERVER colors 3 1 #00ff00 2 #ff0000 3 nope").unwrap();
+        assert_eq!(renderable(&scene, 1).color, Some(PLAYER_COLOR));
+        assert_eq!(renderable(&scene, 2).color, Some(Color::RED));
+
+        // 색을 받은 뒤 새로 생긴 오브젝트
+        scene.process_message("GAMESERVER delta 2 1 3 0 0 0").unwrap();
+        assert_eq!(renderable(&scene, 3).color, None);
+        scene.set_player_color(None);
+        scene.process_message("GAMESERVER colors 2 1 #00ff00 3 #ff0000").unwrap();
+        assert_eq!(renderable(&scene, 1).color, Some(Color::GREEN));
+        assert_eq!(renderable(&scene, 2).color, None);
+        assert_eq!(renderable(&scene, 3).color, Some(Color::RED));
+    }
+
+    #[test]
+    fn test_entity_kinds() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        transport.push_incoming(b"GAMESERVER init 1 8 9\nGAMESERVER spawn 3 1 player 5 npc 6 obstacle\n");
+        scene.update(Duration::ZERO);
+        assert!(transport.take_outgoing().contains("kinds\n"));
+        assert_eq!(scene.kind_of(5), EntityKind::Npc);
+        assert_eq!(scene.model_for(1), PLAYER_MODEL);
+        assert_eq!(scene.model_for(5), "npc");
+        assert_eq!(scene.model_for(6), "obstacle");
+        // 종류를 모르면 다른 플레이어
+        assert_eq!(scene.model_for(2), "pawn");
+
+        // 사라지면 종류도 잊음
+        scene.process_message("GAMESERVER update 2 1 0 0 5 1 1").unwrap();
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        assert_eq!(scene.kind_of(5), EntityKind::Player);
+        assert_eq!(scene.kind_of(6), EntityKind::Obstacle);
+    }
+
+    #[test]
+    fn test_capture_message() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER names 1 1 alice").unwrap();
+        scene.process_message("GAMESERVER spawn 1 7 npc").unwrap();
+
+        scene.process_message("GAMESERVER capture 1 7").unwrap();
+        scene.process_message("GAMESERVER capture 7 2").unwrap();
+        let lines = scene.chat.recent(2).map(ChatMessage::line).collect::<Vec<_>>();
+        assert_eq!(lines, ["*: alice captured npc", "*: npc captured 2"]);
+    }
+
+    #[test]
+    fn test_pawn_animation() {
+        use super::super::super::animation::IDLE;
+
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.animations = Some(Rc::new("[idle]\n0 0 0 0 0 0 0 1\n[walk]\nloop = false\n0 0 0 0 0 0 0 1\n0.2 0 1 0 0 0 0 1".parse().unwrap()));
+        scene.process_message("GAMESERVER spawn 2 7 npc 8 item").unwrap();
+        scene.process_message("GAMESERVER update 2 7 1 0 8 2 0").unwrap();
+        assert!(scene.world.animators.get(entity(&scene, 8)).is_none());
+        let animator = |scene: &GameScene| scene.world.animators.get(entity(scene, 7)).unwrap().current().to_string();
+        assert_eq!(animator(&scene), IDLE);
+
+        scene.process_message("GAMESERVER update 2 7 2 0 8 2 0").unwrap();
+        assert_eq!(animator(&scene), WALK);
+        scene.update_positions(Duration::from_millis(100));
+        // 자세가 인스턴스 행렬에 들어감
+        let posed = super::super::super::object::Instance::new(&transform(&scene, 7), renderable(&scene, 7), scene.world.animators.get(entity(&scene, 7)));
+        assert_ne!(bytemuck::bytes_of(&posed.raw)[..64], *bytemuck::bytes_of(&transform(&scene, 7).to_raw()));
+        scene.update_positions(Duration::from_millis(100));
+        assert_eq!(animator(&scene), IDLE);
+    }
+
+    #[test]
+    fn test_scoreboard() {
+        use super::super::super::transport::MockTransport;
+
+        let transport = MockTransport::connected();
+        let mut scene = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        transport.push_incoming(b"GAMESERVER init 1 10 9\n");
+        scene.update(Duration::ZERO);
+        assert!(transport.take_outgoing().contains("scoreboard\n"));
+
+        scene.process_message("GAMESERVER scoreboard 2 4 25 2 1 42 alice 1 0 0 0 3 player1").unwrap();
+        assert_eq!(scene.scoreboard().len(), 2);
+        assert_eq!(scene.scoreboard_overlay(), None);
+
+        assert!(scene.process_key(&ElementState::Pressed, &KeyCode::Tab, false, None));
+        assert_eq!(scene.scoreboard_overlay().unwrap(), ["1. alice 25", "2. player1 0"]);
+        assert!(!scene.is_free_camera());
+    }
+
+    #[test]
+    fn test_pickup_effect() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        scene.process_message("GAMESERVER spawn 1 9 item").unwrap();
+        scene.process_message("GAMESERVER update 2 1 0 0 9 1 0").unwrap();
+        let start = transform(&scene, 9).position;
+
+        scene.process_message("GAMESERVER pickup 1 9").unwrap();
+        assert!(scene.world.network_entity(9).is_none());
+        let started = scene.pickups[0].2;
+
+        // 다음 위치 메세지에서 아이템이 빠져도 효과는 계속됨
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        scene.update_pickups(started + PICKUP_EFFECT / 2);
+        let effect = scene.pickups[0].0;
+        assert!(scene.world.transforms.get(effect).unwrap().position.y > start.y);
+        assert!(scene.world.renderables.get(effect).unwrap().alpha < 1.0);
+
+        scene.update_pickups(started + PICKUP_EFFECT);
+        assert!(scene.pickups.is_empty());
+    }
+
+    #[test]
+    fn test_selection_marker() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER update 2 1 0 0 5 2 3").unwrap();
+
+        scene.select(Some(5));
+        scene.update_markers();
+        let marker = scene.world.transforms.get(scene.markers[&Marker::Selection]).unwrap().position;
+        assert_eq!((marker.x, marker.z), (2.0, 3.0));
+        assert_eq!(renderable(&scene, 5).color, None);
+
+        scene.select(None);
+        scene.update_markers();
+        assert!(scene.markers.is_empty());
+    }
+
+    #[test]
+    fn test_diagonal_movement() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+
+        let start = Instant::now();
+        let interval = scene.network_config.move_interval;
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyW);
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyD);
+        scene.queue_held_move(start);
+        // 한 쪽을 떼도 나머지는 유지
+        scene.process_keyboard_input(&ElementState::Released, &KeyCode::KeyW);
+        scene.queue_held_move(start + interval);
+        scene.process_keyboard_input(&ElementState::Released, &KeyCode::KeyD);
+        scene.queue_held_move(start + interval * 2);
+
+        assert_eq!(
+            scene.input_actions.iter().collect::<Vec<_>>(),
+            [
+                &InputAction::Move(Vector2::new(1.0, -1.0)),
+                &InputAction::Move(Vector2::new(1.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_held_key_move_interval() {
+        let config = NetworkConfig { move_interval: Duration::from_millis(50), ..Default::default() };
+        let (mut scene, _server) = connected_scene(config);
+
+        let start = Instant::now();
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyS);
+        for ms in [0, 20, 40, 60, 100, 110, 130] {
+            scene.queue_held_move(start + Duration::from_millis(ms));
+        }
+        // 0, 60, 110ms
+        assert_eq!(scene.input_actions.len(), 3);
+
+        // 다시 누르면 바로 보냄
+        scene.process_keyboard_input(&ElementState::Released, &KeyCode::KeyS);
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyS);
+        scene.queue_held_move(start + Duration::from_millis(140));
+        assert_eq!(scene.input_actions.len(), 4);
+    }
+
+    #[test]
+    fn test_key_press_enqueues_action() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyW);
+        assert!(scene.input_actions.is_empty());
+
+        scene.queue_held_move(Instant::now());
+        assert_eq!(
+            scene.input_actions.iter().collect::<Vec<_>>(), 
+            [&InputAction::Move(Vector2::new(0.0, -1.0))]
+        );
+
+        scene.process_input_actions();
+        assert!(scene.input_actions.is_empty());
+    }
+
+    #[test]
+    fn test_resend_input() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 0").unwrap();
+
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyW);
+        scene.queue_held_move(Instant::now());
+        scene.process_input_actions();
+        assert_received(&mut scene, &mut server, "move 0 0 -1\n");
+
+        scene.resend_input();
+        scene.process_input_actions();
+        assert_received(&mut scene, &mut server, "move 0 0 -1\n");
+    }
+
+    #[test]
+    fn test_resend_input_without_held_key() {
+        let (mut scene, mut server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 0").unwrap();
+
+        scene.process_keyboard_input(&ElementState::Pressed, &KeyCode::KeyD);
+        scene.queue_held_move(Instant::now());
+        scene.process_keyboard_input(&ElementState::Released, &KeyCode::KeyD);
+        scene.process_input_actions();
+        assert_received(&mut scene, &mut server, "move 0 1 0\n");
+
+        scene.resend_input();
+        scene.process_input_actions();
+        server.set_nonblocking(true).unwrap();
+        let mut buf = [0; 16];
+        assert_eq!(
+            server.read(&mut buf).map_err(|e| e.kind()), 
+            Err(std::io::ErrorKind::WouldBlock)
+        );
+    }
+
+    #[test]
+    fn test_delta_update() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.set_interpolation_rate(0.0);
+        let position = |scene: &GameScene, id| transform(scene, id).position;
+
+        scene.process_message("GAMESERVER update 1 3 1 0 0 2 1 1 3 2 2").unwrap();
+
+        // 2는 이동, 4는 새로 생김, 3은 삭제, 1은 그대로
+        scene.process_message("GAMESERVER delta 2 2 2 5 5 4 0 3 1 3").unwrap();
+        let mut ids = server_ids(&scene);
+        ids.sort();
+        assert_eq!(ids, [1, 2, 4]);
+        assert_eq!(position(&scene, 1), scene.board.cell_to_world(0, 0));
+        assert_eq!(position(&scene, 2), scene.board.cell_to_world(5, 5));
+        assert_eq!(position(&scene, 4), scene.board.cell_to_world(0, 3));
+
+        // 오래된 delta는 무시
+        scene.process_message("GAMESERVER delta 2 0 1 1").unwrap();
+        assert!(scene.world.network_entity(1).is_some());
+    }
+
+    #[test]
+    fn test_update_sequence() {
+        let (mut scene, _server) = connected_scene(NetworkConfig::default());
+        scene.process_message("GAMESERVER init 1").unwrap();
+        let count = |scene: &GameScene| scene.world.network_count();
+
+        // 일련번호가 커지면 적용
+        scene.process_message("GAMESERVER update 1 1 1 0 0").unwrap();
+        scene.process_message("GAMESERVER update 2 2 1 0 0 2 1 1").unwrap();
+        assert_eq!(scene.last_seq, Some(2));
+        assert_eq!(count(&scene), 2);
+
+        // 같은 일련번호와 이전 일련번호는 무시
+        scene.process_message("GAMESERVER update 2 1 1 0 0").unwrap();
+        scene.process_message("GAMESERVER update3 1 3 1 0 0 0 2 1 0 1 3 2 0 2").unwrap();
+        assert_eq!(count(&scene), 2);
+
+        scene.process_message("GAMESERVER update3 5 3 1 0 0 0 2 1 0 1 3 2 0 2").unwrap();
+        assert_eq!(scene.last_seq, Some(5));
+        assert_eq!(count(&scene), 3);
+
+        // 일련번호가 없는 메세지는 항상 적용
+        scene.process_message("GAMESERVER update 1 1 0 0").unwrap();
+        assert_eq!(count(&scene), 1);
+        assert_eq!(scene.last_seq, Some(5));
     }
 }
@@ -0,0 +1,204 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::Duration,
+};
+use cgmath::{Point3, SquareMatrix};
+use futures::executor::block_on;
+
+use super::super::{
+    model::{self, MeshData, Model},
+    color::Color,
+    hud::{Hud, DEFAULT_TEXT_SIZE},
+    config::ClientConfig,
+    logging,
+};
+use super::{ActiveScene, GameScene, LobbyScene, Scene, SceneTransition};
+
+
+const LOADING_BACKGROUND_COLOR: Color = Color::from_rgb(0.05, 0.05, 0.1);
+/// 진행 막대의 크기 (화면 픽셀)와 색
+const PROGRESS_BAR_SIZE: (f32, f32) = (300.0, 12.0);
+const PROGRESS_BAR_BACKGROUND: Color = Color::DARK_GRAY;
+const PROGRESS_BAR_COLOR: Color = Color::from_rgb(0.3, 0.7, 1.0);
+
+
+/// 게임 씬이 쓸 메쉬와 텍스처를 다른 스레드에서 읽는 동안 진행 막대를 보여주는 씬.
+/// 다 읽으면 `next`의 `mesh_cache`에 넣고 게임 씬(`with_lobby`이면 로비)으로 전환함.
+pub struct LoadingScene {
+    next: Option<Box<GameScene>>,
+    lobby: bool,
+    receiver: Receiver<(String, anyhow::Result<MeshData>)>,
+    remaining: usize,
+    total: usize,
+    /// 창 크기 (픽셀). 진행 막대를 가운데에 그림
+    viewport: (f32, f32),
+}
+
+impl LoadingScene {
+    pub fn new(next: GameScene, files: &[&str]) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let files = files.iter().map(|file| file.to_string()).collect::<Vec<_>>();
+        let remaining = files.len();
+
+        std::thread::spawn(move || {
+            for file in files {
+                let result = block_on(model::parse_mesh(&file));
+                if sender.send((file, result)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let window = &ClientConfig::current().window;
+        Self {
+            next: Some(Box::new(next)),
+            lobby: false,
+            receiver,
+            remaining,
+            total: remaining,
+            viewport: (window.width as f32, window.height as f32),
+        }
+    }
+
+    /// 다 읽으면 게임 씬 대신 로비로 전환
+    pub fn with_lobby(mut self) -> Self {
+        self.lobby = true;
+        self
+    }
+
+    /// 아직 읽지 못한 파일 수
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// 읽은 파일의 비율 (0..=1)
+    pub fn progress(&self) -> f32 {
+        match self.total {
+            0 => 1.0,
+            total => (total - self.remaining) as f32 / total as f32,
+        }
+    }
+
+    fn receive(&mut self) {
+        while self.remaining > 0 {
+            let (file, result) = match self.receiver.try_recv() {
+                Ok(loaded) => loaded,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    log::error!(target: logging::SCENE, "Loading thread stopped with {} file(s) left", self.remaining);
+                    self.remaining = 0;
+                    return;
+                }
+            };
+            self.remaining -= 1;
+
+            // 실패한 파일은 게임 씬의 init에서 다시 읽음
+            match result {
+                Ok(data) => {
+                    if let Some(next) = self.next.as_mut() {
+                        next.mesh_cache_mut().insert(&file, data);
+                    }
+                }
+                Err(e) => log::error!(target: logging::SCENE, "Failed to load {:?}: {}", file, e),
+            }
+        }
+    }
+}
+
+impl Scene for LoadingScene {
+    fn init(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn handle_event(&mut self, _event: &winit::event::WindowEvent) -> bool {
+        false
+    }
+
+    fn update(&mut self, _dt: Duration) -> Option<SceneTransition> {
+        self.receive();
+        if self.remaining > 0 {
+            return None;
+        }
+
+        let next = self.next.take()?;
+        match self.lobby {
+            true => Some(SceneTransition::Replace(ActiveScene::Lobby(Box::new(LobbyScene::new(next))))),
+            false => Some(SceneTransition::Replace(ActiveScene::Game(next))),
+        }
+    }
+
+    fn on_exit(&mut self) {
+        if let Some(next) = self.next.as_mut() {
+            next.on_exit();
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.viewport = (width as f32, height as f32);
+        if let Some(next) = self.next.as_mut() {
+            next.resize(width, height);
+        }
+    }
+
+    fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::identity()
+    }
+
+    fn camera_eye(&self) -> Point3<f32> {
+        Point3::new(0.0, 0.0, 0.0)
+    }
+
+    fn background_color(&self) -> Color {
+        LOADING_BACKGROUND_COLOR
+    }
+
+    /// 가운데에 `LOADING 1/2`와 진행 막대
+    fn hud(&self, hud: &mut Hud) {
+        let (width, height) = PROGRESS_BAR_SIZE;
+        let left = (self.viewport.0 - width) / 2.0;
+        let top = (self.viewport.1 - height) / 2.0;
+
+        let text = format!("LOADING {}/{}", self.total - self.remaining, self.total);
+        let text_left = (self.viewport.0 - Hud::text_width(DEFAULT_TEXT_SIZE, &text)) / 2.0;
+        hud.draw_text((text_left, top - DEFAULT_TEXT_SIZE * 2.0), DEFAULT_TEXT_SIZE, Color::WHITE, &text);
+        hud.fill_rect((left, top), PROGRESS_BAR_SIZE, PROGRESS_BAR_BACKGROUND);
+        hud.fill_rect((left, top), (width * self.progress(), height), PROGRESS_BAR_COLOR);
+    }
+
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        std::iter::empty()
+    }
+
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{config::NetworkConfig, transport::MockTransport};
+
+    #[test]
+    fn test_progress() {
+        let game = GameScene::with_transport(Box::new(MockTransport::connected()), None, NetworkConfig::default());
+        let mut loading = LoadingScene::new(game, &["cube.obj", "pawn.obj"]);
+        assert_eq!(loading.progress(), 0.0);
+        let mut hud = Hud::new();
+        loading.hud(&mut hud);
+        assert!(!hud.is_empty());
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while loading.remaining() > 0 && std::time::Instant::now() < deadline {
+            loading.receive();
+        }
+        assert_eq!(loading.progress(), 1.0);
+
+        // 텍스처도 로딩 스레드에서 디코딩해둠
+        let next = loading.next.as_mut().unwrap();
+        let cube = block_on(next.mesh_cache_mut().get("cube.obj")).unwrap();
+        assert!(cube.textures.contains_key("cube-diffuse.jpg"));
+        assert_eq!(next.mesh_cache().misses(), 0);
+    }
+}
@@ -0,0 +1,399 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+use cgmath::{Point3, SquareMatrix};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+use network::RoomInfo;
+
+use super::super::{
+    model::Model,
+    color::Color,
+    text_input::{TextInput, TextInputEvent},
+    logging,
+};
+use super::{ActiveScene, ConnectionState, GameScene, Scene, SceneTransition};
+
+
+const LOBBY_BACKGROUND_COLOR: Color = Color::from_rgb(0.1, 0.1, 0.15);
+/// 방 목록을 다시 요청하는 간격
+const ROOM_LIST_INTERVAL: Duration = Duration::from_secs(1);
+/// 로비에서 만드는 방의 정원
+pub const DEFAULT_ROOM_CAPACITY: usize = 8;
+
+
+/// 서버의 응답을 기다리는 요청
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoomRequest {
+    Join(u32),
+    /// 서버가 새 방의 id를 `joined`로 알려줌
+    Create,
+    /// 매치 대기열. 매치가 잡히면 서버가 매치 방의 id를 `joined`로 알려줌
+    Match,
+}
+
+
+/// 서버의 방 목록을 보여주고, 방에 들어가면 게임 씬으로 전환하는 씬.
+/// 글자를 그릴 수 없으므로 목록은 로그와 창 제목(`title`)으로 보여줌.
+///
+/// 위/아래로 방을 고르고 Enter로 들어감. N을 누르고 이름을 입력한 뒤 Enter로 방을 만듦.
+/// M으로 매치 대기열에 들어가고, 다시 누르면 나옴.
+/// 서버에 방이 없거나 서버에 접속하지 못했으면 바로 게임 씬으로 넘어감
+pub struct LobbyScene {
+    /// 로비에 있는 동안에도 연결을 유지하기 위해 매 프레임 `update`함
+    game: Option<Box<GameScene>>,
+    /// `rooms` 안의 선택된 방
+    selected: usize,
+    /// 서버가 `joined`로 알려주면 전환하고, 실패하면 지움
+    request: Option<RoomRequest>,
+    last_list_request: Option<Instant>,
+    /// 마지막으로 로그에 출력한 목록
+    shown_rooms: Vec<RoomInfo>,
+    /// 새 방 이름 입력창
+    text_input: TextInput,
+}
+
+impl LobbyScene {
+    pub fn new(game: Box<GameScene>) -> Self {
+        Self {
+            game: Some(game),
+            selected: 0,
+            request: None,
+            last_list_request: None,
+            shown_rooms: Vec::new(),
+            text_input: TextInput::new(),
+        }
+    }
+
+    pub fn rooms(&self) -> &[RoomInfo] {
+        self.game.as_ref().map_or(&[], |game| game.rooms())
+    }
+
+    pub fn selected(&self) -> Option<&RoomInfo> {
+        self.rooms().get(self.selected)
+    }
+
+    /// 창 제목에 붙일 선택된 방
+    pub fn title(&self) -> String {
+        if self.text_input.is_focused() {
+            return format!("new room: {}_", self.text_input.text());
+        }
+
+        if let Some((waiting, size)) = self.game.as_ref().and_then(|game| game.match_search()) {
+            return format!("searching… {}/{}", waiting, size);
+        }
+
+        let error = self.game.as_ref()
+            .and_then(|game| game.room_error())
+            .map(|error| format!(" ({})", error))
+            .unwrap_or_default();
+        match self.selected() {
+            Some(room) => format!(
+                "lobby [{}/{}] {} ({}/{}){}",
+                self.selected + 1,
+                self.rooms().len(),
+                room.name,
+                room.players,
+                room.capacity,
+                error
+            ),
+            None => format!("lobby{}", error),
+        }
+    }
+
+    fn join_selected(&mut self) {
+        let Some(room) = self.selected().map(|room| room.id) else {
+            return;
+        };
+        if let Some(game) = self.game.as_mut() {
+            game.join_room(room);
+        }
+        self.request = Some(RoomRequest::Join(room));
+    }
+
+    fn create_room(&mut self, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+
+        if let Some(game) = self.game.as_mut() {
+            game.create_room(name, DEFAULT_ROOM_CAPACITY);
+        }
+        self.request = Some(RoomRequest::Create);
+    }
+
+    fn toggle_match(&mut self) {
+        let Some(game) = self.game.as_mut().filter(|game| game.supports_matchmaking()) else {
+            return;
+        };
+        if game.match_search().is_some() {
+            game.cancel_match();
+            self.request = None;
+        } else {
+            game.find_match();
+            self.request = Some(RoomRequest::Match);
+        }
+    }
+
+    fn process_key(&mut self, state: &ElementState, keycode: &KeyCode, text: Option<&str>) -> bool {
+        if self.text_input.is_focused() {
+            if let TextInputEvent::Submitted(name) = self.text_input.handle_key(state, keycode, text) {
+                self.create_room(&name);
+            }
+            return true;
+        }
+
+        if *state != ElementState::Pressed {
+            return false;
+        }
+
+        let num_rooms = self.rooms().len();
+        match keycode {
+            KeyCode::ArrowUp if num_rooms > 0 => {
+                self.selected = (self.selected.min(num_rooms - 1) + num_rooms - 1) % num_rooms;
+            }
+            KeyCode::ArrowDown if num_rooms > 0 => {
+                self.selected = (self.selected.min(num_rooms - 1) + 1) % num_rooms;
+            }
+            KeyCode::Enter | KeyCode::NumpadEnter => self.join_selected(),
+            KeyCode::KeyN => self.text_input.focus(),
+            KeyCode::KeyR => self.last_list_request = None,
+            KeyCode::KeyM => self.toggle_match(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn request_rooms(&mut self, now: Instant) {
+        if self.last_list_request.is_some_and(|last| now - last < ROOM_LIST_INTERVAL) {
+            return;
+        }
+        if let Some(game) = self.game.as_mut() {
+            game.request_rooms();
+        }
+        self.last_list_request = Some(now);
+    }
+
+    fn log_rooms(&mut self) {
+        if self.rooms() == self.shown_rooms {
+            return;
+        }
+
+        self.shown_rooms = self.rooms().to_vec();
+        for (idx, room) in self.shown_rooms.iter().enumerate() {
+            log::info!(target: logging::SCENE, "[{}] {} ({}/{})", idx + 1, room.name, room.players, room.capacity);
+        }
+    }
+
+    /// 게임 씬으로 넘어갈지
+    fn joined(&self, game: &GameScene) -> bool {
+        match game.connection_state() {
+            ConnectionState::Connecting => return false,
+            ConnectionState::Connected => {}
+            // 서버 없이도 게임 씬은 보드를 보여주고 다시 접속을 시도함. 서버가 종료했으면 종료 화면을 보여줌
+            ConnectionState::Reconnecting { .. } | ConnectionState::Disconnected | ConnectionState::Closed => return true,
+        }
+        if !game.supports_rooms() {
+            return true;
+        }
+
+        match (self.request, game.room()) {
+            (Some(RoomRequest::Join(requested)), Some(room)) => requested == room,
+            (Some(RoomRequest::Create | RoomRequest::Match), Some(room)) => room != network::LOBBY_ROOM,
+            _ => false,
+        }
+    }
+}
+
+impl Scene for LobbyScene {
+    fn init(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state,
+                    physical_key: PhysicalKey::Code(keycode),
+                    text,
+                    ..
+                },
+                ..
+            } => self.process_key(state, keycode, text.as_deref()),
+            _ => false,
+        }
+    }
+
+    /// 보낼 메세지는 게임 씬의 `update`에서 한꺼번에 보냄
+    fn update(&mut self, dt: Duration) -> Option<SceneTransition> {
+        let game = self.game.as_ref()?;
+        if game.connection_state() == ConnectionState::Connected && game.supports_rooms() {
+            self.request_rooms(Instant::now());
+        }
+        self.game.as_mut()?.update(dt);
+
+        self.log_rooms();
+        if self.game.as_ref()?.room_error().is_some() {
+            self.request = None;
+        }
+
+        if !self.joined(self.game.as_ref()?) {
+            return None;
+        }
+        self.game.take().map(|game| SceneTransition::Replace(ActiveScene::Game(game)))
+    }
+
+    fn on_exit(&mut self) {
+        if let Some(game) = self.game.as_mut() {
+            game.on_exit();
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        if let Some(game) = self.game.as_mut() {
+            game.resize(width, height);
+        }
+    }
+
+    fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::identity()
+    }
+
+    fn camera_eye(&self) -> Point3<f32> {
+        Point3::new(0.0, 0.0, 0.0)
+    }
+
+    fn background_color(&self) -> Color {
+        LOBBY_BACKGROUND_COLOR
+    }
+
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        std::iter::empty()
+    }
+
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{
+        config::NetworkConfig,
+        transport::MockTransport,
+    };
+
+    fn lobby(init: &[u8]) -> (LobbyScene, MockTransport) {
+        let transport = MockTransport::connected();
+        let game = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        let mut lobby = LobbyScene::new(Box::new(game));
+        transport.push_incoming(init);
+        assert!(lobby.update(Duration::ZERO).is_none());
+        (lobby, transport)
+    }
+
+    fn press(lobby: &mut LobbyScene, keycode: KeyCode, text: Option<&str>) {
+        lobby.process_key(&ElementState::Pressed, &keycode, text);
+        lobby.process_key(&ElementState::Released, &keycode, None);
+    }
+
+    #[test]
+    fn test_join_room() {
+        let (mut lobby, transport) = lobby(b"GAMESERVER init 1 5 9\n");
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert!(transport.take_outgoing().contains("rooms\n"));
+
+        transport.push_incoming(b"GAMESERVER rooms 2 0 1 100 lobby 4 0 2 arena\n");
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert_eq!(lobby.rooms().len(), 2);
+
+        press(&mut lobby, KeyCode::ArrowDown, None);
+        assert_eq!(lobby.title(), "lobby [2/2] arena (0/2)");
+        press(&mut lobby, KeyCode::Enter, Some("\r"));
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert!(transport.take_outgoing().contains("join 4\n"));
+
+        transport.push_incoming(b"GAMESERVER joined 4\n");
+        let Some(SceneTransition::Replace(ActiveScene::Game(game))) = lobby.update(Duration::ZERO) else {
+            panic!("expected game scene");
+        };
+        assert_eq!(game.room(), Some(4));
+    }
+
+    #[test]
+    fn test_create_room_failed() {
+        let (mut lobby, transport) = lobby(b"GAMESERVER init 1 5 9\n");
+
+        press(&mut lobby, KeyCode::KeyN, Some("n"));
+        press(&mut lobby, KeyCode::KeyA, Some("a"));
+        assert_eq!(lobby.title(), "new room: a_");
+        press(&mut lobby, KeyCode::Enter, Some("\r"));
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert!(transport.take_outgoing().contains(&format!("create {} a\n", DEFAULT_ROOM_CAPACITY)));
+
+        transport.push_incoming(b"GAMESERVER join_failed too many rooms\n");
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert!(lobby.title().ends_with("(too many rooms)"));
+        assert_eq!(lobby.request, None);
+    }
+
+    #[test]
+    fn test_matchmaking() {
+        let (mut lobby, transport) = lobby(b"GAMESERVER init 1 13 9\n");
+
+        press(&mut lobby, KeyCode::KeyM, Some("m"));
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert!(transport.take_outgoing().contains("queue\n"));
+        assert_eq!(lobby.title(), "searching… 0/0");
+
+        transport.push_incoming(b"GAMESERVER queued 1 2\n");
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert_eq!(lobby.title(), "searching… 1/2");
+
+        transport.push_incoming(b"GAMESERVER joined 3\nGAMESERVER match_start 3 2 1 4\n");
+        let Some(SceneTransition::Replace(ActiveScene::Game(game))) = lobby.update(Duration::ZERO) else {
+            panic!("expected game scene");
+        };
+        assert_eq!(game.room(), Some(3));
+        assert_eq!(game.match_players(), [1, 4]);
+        assert_eq!(game.match_search(), None);
+    }
+
+    #[test]
+    fn test_cancel_matchmaking() {
+        let (mut lobby, transport) = lobby(b"GAMESERVER init 1 13 9\n");
+        press(&mut lobby, KeyCode::KeyM, Some("m"));
+        press(&mut lobby, KeyCode::KeyM, Some("m"));
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert!(transport.take_outgoing().contains("queue\nunqueue\n"));
+        assert_eq!(lobby.request, None);
+        assert!(lobby.title().starts_with("lobby"));
+    }
+
+    #[test]
+    fn test_server_without_matchmaking() {
+        let (mut lobby, transport) = lobby(b"GAMESERVER init 1 12 9\n");
+        press(&mut lobby, KeyCode::KeyM, Some("m"));
+        assert!(lobby.update(Duration::ZERO).is_none());
+        assert!(!transport.take_outgoing().contains("queue"));
+    }
+
+    #[test]
+    fn test_server_without_rooms() {
+        let transport = MockTransport::connected();
+        let game = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        let mut lobby = LobbyScene::new(Box::new(game));
+        assert!(lobby.update(Duration::ZERO).is_none());
+
+        transport.push_incoming(b"GAMESERVER init 1 4 9\n");
+        assert!(matches!(lobby.update(Duration::ZERO), Some(SceneTransition::Replace(ActiveScene::Game(_)))));
+        assert!(!transport.take_outgoing().contains("rooms"));
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    time::Duration,
+};
+
+use super::super::{
+    ecs::World,
+    model::Model,
+    color::Color,
+    light::DirectionalLight,
+    hud::Hud,
+    particles::ParticleSystem,
+    debug_draw::DebugLines,
+    timestep::FixedTimestep,
+    logging,
+};
+use super::{
+    ConnectingScene, ErrorScene, GameScene, LoadingScene, LobbyScene, PauseScene, Scene, SceneTransition, TitleScene,
+};
+
+
+pub enum ActiveScene {
+    Title(TitleScene),
+    Connecting(ConnectingScene),
+    Loading(LoadingScene),
+    Lobby(Box<LobbyScene>),
+    Game(Box<GameScene>),
+    Pause(PauseScene),
+    Error(ErrorScene),
+}
+
+/// 모든 종류의 씬에 같은 식을 적용
+macro_rules! each_scene {
+    ($active:expr, $scene:ident => $body:expr) => {
+        match $active {
+            ActiveScene::Title($scene) => $body,
+            ActiveScene::Connecting($scene) => $body,
+            ActiveScene::Loading($scene) => $body,
+            ActiveScene::Lobby($scene) => $body,
+            ActiveScene::Game($scene) => $body,
+            ActiveScene::Pause($scene) => $body,
+            ActiveScene::Error($scene) => $body,
+        }
+    };
+}
+
+impl ActiveScene {
+    fn init(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<()> {
+        each_scene!(self, scene => scene.init(device, queue))
+    }
+
+    fn is_overlay(&self) -> bool {
+        each_scene!(self, scene => scene.is_overlay())
+    }
+}
+
+
+/// 씬을 쌓아두고 맨 위의 씬만 입력을 받음. 씬이 요청하면 (`SceneTransition`) 쌓거나 빼거나 바꿈.
+/// 오버레이 (`Scene::is_overlay`) 아래의 씬은 입력 없이 계속 `update`되고 화면에 그려짐
+pub struct SceneManager {
+    /// 비어있지 않음. 마지막이 맨 위
+    stack: Vec<ActiveScene>,
+    /// 마지막으로 받은 창 크기. 나중에 올라오는 씬에도 알려줌
+    size: Option<(u32, u32)>,
+    timestep: FixedTimestep,
+}
+
+impl SceneManager {
+    /// `scene`의 `init`은 호출하지 않으므로 장치가 필요없는 씬이어야 함
+    pub fn new(scene: ActiveScene) -> Self {
+        Self { stack: vec![scene], size: None, timestep: FixedTimestep::default() }
+    }
+
+    /// 입력을 받는 맨 위의 씬
+    pub fn active(&self) -> &ActiveScene {
+        self.stack.last().expect("scene stack is never empty")
+    }
+
+    /// 쌓인 씬 수
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// 게임 씬이 맨 위에 있지 않으면 (멈춤 화면 등) `None`
+    pub fn game_mut(&mut self) -> Option<&mut GameScene> {
+        match self.stack.last_mut()? {
+            ActiveScene::Game(game) => Some(game),
+            _ => None,
+        }
+    }
+
+    /// 맨 위의 씬과 그 아래로 오버레이에 가려진 씬들. 맨 위부터
+    fn updated(&mut self) -> impl Iterator<Item = &mut ActiveScene> {
+        let count = self.stack.iter().rev()
+            .position(|scene| !scene.is_overlay())
+            .map_or(self.stack.len(), |idx| idx + 1);
+        self.stack.iter_mut().rev().take(count)
+    }
+
+    /// 화면에 그리는 씬. 맨 위에서부터 오버레이가 아닌 첫 씬
+    fn drawn(&self) -> &ActiveScene {
+        self.stack.iter().rev()
+            .find(|scene| !scene.is_overlay())
+            .unwrap_or_else(|| self.active())
+    }
+
+    /// 새로 올라온 씬의 `init`을 호출하므로 `device`와 `queue`가 필요함.
+    /// `init`이 실패하면 오류 씬으로 바꿈. 맨 위의 씬이 요청한 전환만 적용함
+    pub fn update(&mut self, dt: Duration, device: &wgpu::Device, queue: &wgpu::Queue) {
+        for _ in 0..self.timestep.advance(dt) {
+            for scene in self.updated() {
+                each_scene!(scene, scene => scene.fixed_update());
+            }
+        }
+
+        let top = self.stack.len() - 1;
+        let transition = each_scene!(&mut self.stack[top], scene => scene.update(dt));
+        for scene in self.updated().skip(1) {
+            each_scene!(scene, scene => scene.update(dt));
+        }
+
+        match transition {
+            Some(SceneTransition::Push(scene)) => {
+                each_scene!(self.stack.last_mut().unwrap(), scene => scene.suspend());
+                self.push(scene, device, queue);
+            }
+            Some(SceneTransition::Pop) if self.stack.len() > 1 => {
+                self.pop();
+            }
+            Some(SceneTransition::Pop) => {
+                log::warn!(target: logging::SCENE, "Ignoring pop of the last scene");
+            }
+            Some(SceneTransition::Replace(scene)) => {
+                self.pop();
+                self.push(scene, device, queue);
+            }
+            Some(SceneTransition::ReplaceAll(scene)) => {
+                while !self.stack.is_empty() {
+                    self.pop();
+                }
+                self.push(scene, device, queue);
+            }
+            None => {}
+        }
+    }
+
+    fn push(&mut self, mut scene: ActiveScene, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if let Err(e) = scene.init(device, queue) {
+            log::error!(target: logging::SCENE, "Failed to initialize scene: {:#}", e);
+            // 준비하지 못한 씬은 바로 끝냄 (서버에 `disconnect`를 보냄)
+            each_scene!(&mut scene, scene => scene.on_exit());
+            scene = ActiveScene::Error(ErrorScene::from_error(&e));
+        }
+        // 씬이 만들어진 뒤에 창 크기가 바뀌었을 수 있음
+        if let Some((width, height)) = self.size {
+            each_scene!(&mut scene, scene => scene.resize(width, height));
+        }
+        self.stack.push(scene);
+    }
+
+    /// 뺀 씬은 끝냄
+    fn pop(&mut self) {
+        if let Some(mut scene) = self.stack.pop() {
+            each_scene!(&mut scene, scene => scene.on_exit());
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &winit::event::WindowEvent) -> bool {
+        each_scene!(self.stack.last_mut().unwrap(), scene => scene.handle_event(event))
+    }
+
+    pub fn on_exit(&mut self) {
+        for scene in self.stack.iter_mut().rev() {
+            each_scene!(scene, scene => scene.on_exit());
+        }
+    }
+
+    /// 크기가 0이면 (창 최소화) 기억하지 않음
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.size = Some((width, height));
+        }
+        for scene in self.stack.iter_mut() {
+            each_scene!(scene, scene => scene.resize(width, height));
+        }
+    }
+
+    pub fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        each_scene!(self.drawn(), scene => scene.view_proj())
+    }
+
+    pub fn camera_eye(&self) -> cgmath::Point3<f32> {
+        each_scene!(self.drawn(), scene => scene.camera_eye())
+    }
+
+    pub fn background_color(&self) -> Color {
+        each_scene!(self.drawn(), scene => scene.background_color())
+    }
+
+    pub fn light(&self) -> DirectionalLight {
+        each_scene!(self.drawn(), scene => scene.light())
+    }
+
+    pub fn skybox(&self) -> Option<&str> {
+        each_scene!(self.drawn(), scene => scene.skybox())
+    }
+
+    pub fn particles(&self) -> Option<&ParticleSystem> {
+        each_scene!(self.drawn(), scene => scene.particles())
+    }
+
+    pub fn debug_lines(&self, lines: &mut DebugLines) {
+        each_scene!(self.drawn(), scene => scene.debug_lines(lines))
+    }
+
+    /// 그려지는 씬과 그 위의 오버레이가 아래부터 차례로 글자를 그림
+    pub fn hud(&self, hud: &mut Hud) {
+        let drawn = self.stack.iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(self.stack.len() - 1);
+        for scene in &self.stack[drawn..] {
+            each_scene!(scene, scene => scene.hud(hud));
+        }
+    }
+
+    pub fn models(&self) -> Box<dyn Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> + '_> {
+        each_scene!(self.drawn(), scene => Box::new(scene.models()))
+    }
+
+    pub fn world(&self) -> Option<&World> {
+        each_scene!(self.drawn(), scene => scene.world())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{
+        config::NetworkConfig,
+        transport::MockTransport,
+    };
+    use std::time::Instant;
+
+    #[test]
+    fn test_loading_to_game() {
+        let Some((device, queue)) = super::super::super::test_device() else {
+            return;
+        };
+
+        let game = GameScene::with_transport(Box::new(MockTransport::connected()), None, NetworkConfig::default());
+        let mut manager = SceneManager::new(ActiveScene::Loading(LoadingScene::new(game, GameScene::MODEL_FILES)));
+        assert!(matches!(manager.active(), ActiveScene::Loading(_)));
+        assert_eq!(manager.models().count(), 0);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while manager.game_mut().is_none() && Instant::now() < deadline {
+            manager.update(Duration::ZERO, &device, &queue);
+        }
+
+        // 로딩 스레드가 읽어둔 메쉬를 사용하므로 게임 씬은 파일을 다시 읽지 않음
+        let game = manager.game_mut().unwrap();
+        assert_eq!(game.mesh_cache().misses(), 0);
+        assert!(manager.models().count() > 0);
+    }
+
+    #[test]
+    fn test_pause_overlay() {
+        let Some((device, queue)) = super::super::super::test_device() else {
+            return;
+        };
+
+        let transport = MockTransport::connected();
+        let game = GameScene::with_transport(Box::new(transport.clone()), None, NetworkConfig::default());
+        let title = TitleScene::new("localhost:7878", NetworkConfig::default());
+        let mut manager = SceneManager::new(ActiveScene::Title(title));
+        manager.push(ActiveScene::Game(Box::new(game)), &device, &queue);
+        let models = manager.models().count();
+        assert!(models > 0);
+
+        // 멈춤 화면이 입력을 가져가지만 게임 씬은 계속 그려지고 메세지를 받음
+        let title = TitleScene::new("localhost:7878", NetworkConfig::default());
+        manager.push(ActiveScene::Pause(PauseScene::new(title)), &device, &queue);
+        assert!(matches!(manager.active(), ActiveScene::Pause(_)));
+        assert!(manager.game_mut().is_none());
+        assert_eq!(manager.models().count(), models);
+        transport.push_incoming(b"GAMESERVER init 3\n");
+        manager.update(Duration::ZERO, &device, &queue);
+
+        manager.pop();
+        assert_eq!(manager.depth(), 2);
+        assert_eq!(manager.game_mut().unwrap().world_snapshot().player_id, Some(3));
+    }
+
+    #[test]
+    fn test_resize_before_push() {
+        let Some((device, queue)) = super::super::super::test_device() else {
+            return;
+        };
+
+        let title = TitleScene::new("localhost:7878", NetworkConfig::default());
+        let mut manager = SceneManager::new(ActiveScene::Title(title));
+        manager.resize(1600, 600);
+        manager.resize(0, 0);
+
+        let game = GameScene::with_transport(Box::new(MockTransport::connected()), None, NetworkConfig::default());
+        manager.push(ActiveScene::Game(Box::new(game)), &device, &queue);
+        let game = manager.game_mut().unwrap();
+        assert_eq!(game.viewport(), (1600.0, 600.0));
+    }
+}
@@ -1,11 +1,30 @@
+pub mod connecting_scene;
+pub mod error_scene;
 pub mod game_scene;
+pub mod loading_scene;
+pub mod lobby_scene;
+pub mod manager;
+pub mod pause_scene;
+pub mod title_scene;
 
-pub use game_scene::GameScene;
+pub use connecting_scene::ConnectingScene;
+pub use error_scene::ErrorScene;
+pub use game_scene::{ConnectionState, GameScene, ObjectSnapshot, WorldSnapshot};
+pub use loading_scene::LoadingScene;
+pub use lobby_scene::LobbyScene;
+pub use manager::{ActiveScene, SceneManager};
+pub use pause_scene::PauseScene;
+pub use title_scene::TitleScene;
+pub use network::ProtocolError;
 
 use super::{
     model::Model, 
-    object::Object,
+    ecs::World,
     color,
+    light::DirectionalLight,
+    hud::Hud,
+    particles::ParticleSystem,
+    debug_draw::DebugLines,
 };
 
 use std::{
@@ -15,16 +34,76 @@ use std::{
 };
 
 
+/// 씬이 `update`에서 요청하는 씬 스택의 변화. 새로 올라오는 씬은 `init`이 호출되고, 빠지는 씬은 `on_exit`이 호출됨
+pub enum SceneTransition {
+    /// 지금 씬 위에 쌓음. 지금 씬은 `suspend`가 호출됨
+    Push(ActiveScene),
+    /// 지금 씬을 빼고 아래 씬으로 돌아감
+    Pop,
+    /// 지금 씬을 뺀 자리에 쌓음
+    Replace(ActiveScene),
+    /// 쌓인 씬을 모두 빼고 새로 시작함
+    ReplaceAll(ActiveScene),
+}
+
+
 pub trait Scene {
-    fn init(&mut self, device: &wgpu::Device);
+    /// 실패하면 씬을 보여줄 수 없으므로 `SceneManager`가 오류 씬으로 바꿈
+    fn init(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<()>;
 
     fn handle_event(&mut self, event: &winit::event::WindowEvent) -> bool;
 
-    fn update(&mut self);
+    /// 다른 씬이 위에 쌓여서 입력을 받지 못하게 될 때 호출. 누르고 있던 키는 잊어야 함
+    fn suspend(&mut self) {}
+
+    /// 오버레이는 아래 씬을 가리지 않음. 아래 씬이 그대로 그려지고 입력 없이 `update`됨
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// `dt`는 이전 `update` 호출 이후 지난 시간.
+    /// 다른 씬으로 넘어가야 하면 `SceneTransition`을 반환
+    fn update(&mut self, dt: std::time::Duration) -> Option<SceneTransition>;
+
+    /// 프레임 속도와 상관없이 `timestep::FIXED_TIMESTEP`마다 한 번씩, 그 프레임의 `update` 전에 호출
+    fn fixed_update(&mut self) {}
+
+    /// 프로그램이 끝나기 직전에 한 번 호출
+    fn on_exit(&mut self) {}
+
+    /// 창 크기가 바뀌었을 때 호출 (픽셀 단위)
+    fn resize(&mut self, _width: u32, _height: u32) {}
 
     fn view_proj(&self) -> cgmath::Matrix4<f32>;
+    /// 반투명 오브젝트 정렬에 사용하는 카메라 위치
+    fn camera_eye(&self) -> cgmath::Point3<f32>;
     fn background_color(&self) -> color::Color;
 
-    fn models(&self) -> impl Iterator<Item = &Rc<RefCell<Model>>>;
-    fn objects(&self) -> impl Iterator<Item = &Rc<RefCell<Object>>>;
+    fn light(&self) -> DirectionalLight {
+        DirectionalLight::default()
+    }
+
+    /// 배경으로 그릴 스카이박스 (`skybox::face_files`). `None`이면 `background_color`로 지운 화면 그대로
+    fn skybox(&self) -> Option<&str> {
+        None
+    }
+
+    /// 반투명 모델 뒤에 그릴 파티클
+    fn particles(&self) -> Option<&ParticleSystem> {
+        None
+    }
+
+    /// 3D 장면 위에 그릴 글자. 매 프레임 그리기 전에 호출
+    fn hud(&self, _hud: &mut Hud) {}
+
+    /// 디버그 렌더 모드 (F5)에서 장면 위에 그릴 선. 켜져 있을 때만 매 프레임 호출
+    fn debug_lines(&self, _lines: &mut DebugLines) {}
+
+    /// 그릴 모델과 그 이름. `world`의 `Renderable::model`이 같은 모델로 그림
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)>;
+
+    /// 그릴 엔티티. `None`이면 모델에 인스턴스가 없음
+    fn world(&self) -> Option<&World> {
+        None
+    }
 }
\ No newline at end of file
@@ -0,0 +1,137 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    time::Duration,
+};
+use cgmath::{Point3, SquareMatrix};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use super::super::{
+    model::Model,
+    color::Color,
+};
+use super::{ActiveScene, Scene, SceneTransition, TitleScene};
+
+
+/// 오버레이이므로 그리지 않음 (아래 게임 씬을 그림)
+const PAUSE_BACKGROUND_COLOR: Color = Color::from_rgb(0.0, 0.0, 0.0);
+
+
+/// 게임 씬 위에 쌓는 멈춤 화면. 게임은 서버와 계속 주고받으며 화면에 그려지지만 입력은 이 씬이 모두 가져감.
+///
+/// Escape로 게임으로 돌아가고, Q로 게임을 끝낸 뒤 첫 화면으로 돌아감
+pub struct PauseScene {
+    /// Q로 돌아갈 첫 화면
+    title: Option<TitleScene>,
+    resume: bool,
+    leave: bool,
+}
+
+impl PauseScene {
+    pub fn new(title: TitleScene) -> Self {
+        Self { title: Some(title), resume: false, leave: false }
+    }
+
+    /// 창 제목에 붙일 안내
+    pub fn title(&self) -> &'static str {
+        "paused - Esc to resume, Q to leave"
+    }
+
+    /// 모든 키를 가져가서 게임 씬에 전달되지 않게 함
+    fn process_key(&mut self, state: &ElementState, keycode: &KeyCode) -> bool {
+        if *state == ElementState::Pressed {
+            match keycode {
+                KeyCode::Escape => self.resume = true,
+                KeyCode::KeyQ => self.leave = true,
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+impl Scene for PauseScene {
+    fn init(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 마우스 등 다른 입력도 모두 가져감
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state,
+                    physical_key: PhysicalKey::Code(keycode),
+                    ..
+                },
+                ..
+            } => self.process_key(state, keycode),
+            WindowEvent::CursorMoved { .. } | WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn update(&mut self, _dt: Duration) -> Option<SceneTransition> {
+        if self.leave {
+            return self.title.take().map(|title| SceneTransition::ReplaceAll(ActiveScene::Title(title)));
+        }
+        std::mem::take(&mut self.resume).then_some(SceneTransition::Pop)
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::identity()
+    }
+
+    fn camera_eye(&self) -> Point3<f32> {
+        Point3::new(0.0, 0.0, 0.0)
+    }
+
+    fn background_color(&self) -> Color {
+        PAUSE_BACKGROUND_COLOR
+    }
+
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        std::iter::empty()
+    }
+
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::config::NetworkConfig;
+
+    fn pause() -> PauseScene {
+        PauseScene::new(TitleScene::new("localhost:7878", NetworkConfig::default()))
+    }
+
+    #[test]
+    fn test_resume() {
+        let mut pause = pause();
+        assert!(pause.process_key(&ElementState::Pressed, &KeyCode::KeyW));
+        assert!(pause.update(Duration::ZERO).is_none());
+
+        pause.process_key(&ElementState::Pressed, &KeyCode::Escape);
+        assert!(matches!(pause.update(Duration::ZERO), Some(SceneTransition::Pop)));
+        assert!(pause.update(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_leave() {
+        let mut pause = pause();
+        pause.process_key(&ElementState::Pressed, &KeyCode::KeyQ);
+        let Some(SceneTransition::ReplaceAll(ActiveScene::Title(title))) = pause.update(Duration::ZERO) else {
+            panic!("expected title scene");
+        };
+        assert_eq!(title.address(), "localhost:7878");
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    time::Duration,
+};
+use cgmath::{Point3, SquareMatrix};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+use get_addr::get_addr;
+
+use super::super::{
+    model::Model,
+    color::Color,
+    config::{NetworkConfig, ServerConfig},
+    text_input::{TextInput, TextInputEvent},
+};
+use super::{ActiveScene, ConnectingScene, Scene, SceneTransition};
+
+
+const TITLE_BACKGROUND_COLOR: Color = Color::from_rgb(0.05, 0.1, 0.1);
+/// 실행 인자에서 주소를 가져오지 못했을 때 입력창에 채워두는 주소
+pub const DEFAULT_SERVER_ADDRESS: &str = "localhost:7878";
+
+
+/// 서버 주소를 입력받는 첫 화면. 글자를 그릴 수 없으므로 입력 중인 주소는 창 제목(`title`)으로 보여줌.
+///
+/// Enter로 접속을 시작함. 주소가 잘못됐거나 접속하지 못하면 이 화면으로 돌아와서 오류를 보여줌.
+/// Escape는 처리하지 않으므로 프로그램이 끝남
+pub struct TitleScene {
+    text_input: TextInput,
+    network_config: NetworkConfig,
+    /// 마지막으로 접속하지 못한 이유
+    error: Option<String>,
+    /// Enter로 고른 서버. 다음 `update`에서 접속 화면으로 넘어감
+    connect: Option<ServerConfig>,
+}
+
+impl TitleScene {
+    /// 입력창에 `address`를 채워둠
+    pub fn new(address: impl Into<String>, network_config: NetworkConfig) -> Self {
+        let mut text_input = TextInput::new();
+        text_input.set_text(address);
+        text_input.focus();
+        Self { text_input, network_config, error: None, connect: None }
+    }
+
+    /// 처음 보여줄 오류
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// 실행 인자의 `ip:port` (`get_addr`). 없거나 잘못됐으면 `DEFAULT_SERVER_ADDRESS`
+    pub fn default_address() -> String {
+        get_addr()
+            .map(|(ip, port)| format!("{}:{}", ip, port))
+            .unwrap_or_else(|_| DEFAULT_SERVER_ADDRESS.to_string())
+    }
+
+    pub fn address(&self) -> &str {
+        self.text_input.text()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// 창 제목에 붙일 입력 중인 주소
+    pub fn title(&self) -> String {
+        let error = self.error.as_ref()
+            .map(|error| format!(" ({})", error))
+            .unwrap_or_default();
+        format!("server: {}_ - Enter to connect{}", self.address(), error)
+    }
+
+    fn process_key(&mut self, state: &ElementState, keycode: &KeyCode, text: Option<&str>) -> bool {
+        if *keycode == KeyCode::Escape {
+            return false;
+        }
+
+        if let TextInputEvent::Submitted(address) = self.text_input.handle_key(state, keycode, text) {
+            match address.trim().parse::<ServerConfig>() {
+                Ok(server) => {
+                    self.error = None;
+                    self.connect = Some(server);
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            }
+            // 입력창은 Enter로 비워지므로 다시 채움 (돌아왔을 때 고쳐서 입력할 수 있도록)
+            self.text_input.set_text(address);
+            self.text_input.focus();
+        }
+        true
+    }
+}
+
+impl Scene for TitleScene {
+    fn init(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state,
+                    physical_key: PhysicalKey::Code(keycode),
+                    text,
+                    ..
+                },
+                ..
+            } => self.process_key(state, keycode, text.as_deref()),
+            _ => false,
+        }
+    }
+
+    fn update(&mut self, _dt: Duration) -> Option<SceneTransition> {
+        let server = self.connect.take()?;
+        let connecting = ConnectingScene::new(server, self.network_config.clone());
+        Some(SceneTransition::Replace(ActiveScene::Connecting(connecting)))
+    }
+
+    fn view_proj(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::identity()
+    }
+
+    fn camera_eye(&self) -> Point3<f32> {
+        Point3::new(0.0, 0.0, 0.0)
+    }
+
+    fn background_color(&self) -> Color {
+        TITLE_BACKGROUND_COLOR
+    }
+
+    fn models(&self) -> impl Iterator<Item = (&'static str, &Rc<RefCell<Model>>)> {
+        std::iter::empty()
+    }
+
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(title: &mut TitleScene, keycode: KeyCode, text: Option<&str>) -> bool {
+        let handled = title.process_key(&ElementState::Pressed, &keycode, text);
+        title.process_key(&ElementState::Released, &keycode, None);
+        handled
+    }
+
+    #[test]
+    fn test_enter_address() {
+        let mut title = TitleScene::new("localhost:78", NetworkConfig::default());
+        assert_eq!(title.title(), "server: localhost:78_ - Enter to connect");
+
+        press(&mut title, KeyCode::Digit7, Some("7"));
+        press(&mut title, KeyCode::Digit8, Some("8"));
+        assert_eq!(title.address(), "localhost:7878");
+        press(&mut title, KeyCode::Enter, Some("\r"));
+        assert_eq!(title.address(), "localhost:7878");
+
+        let Some(SceneTransition::Replace(ActiveScene::Connecting(connecting))) = title.update(Duration::ZERO) else {
+            panic!("expected connecting scene");
+        };
+        assert_eq!(connecting.server(), &ServerConfig { ip: "localhost".to_string(), port: 7878 });
+        assert!(title.update(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        let mut title = TitleScene::new("localhost", NetworkConfig::default());
+        press(&mut title, KeyCode::Enter, Some("\r"));
+        assert!(title.update(Duration::ZERO).is_none());
+        assert!(title.error().unwrap().contains("expected ip:port"));
+        assert_eq!(title.address(), "localhost");
+
+        // Escape는 프로그램 종료로 넘김
+        assert!(!press(&mut title, KeyCode::Escape, None));
+        assert_eq!(title.address(), "localhost");
+    }
+}
@@ -0,0 +1,140 @@
+//! 그림자 맵.
+//! 주 렌더 패스 전에 빛에서 본 불투명 오브젝트의 깊이를 그림자 맵에 그리고,
+//! 주 패스의 셰이더가 그 맵과 비교해서 (PCF) 그림자를 그림.
+
+use super::{
+    model::{Model, ModelVertex, Vertex},
+    object::InstanceRaw,
+    texture::Texture,
+};
+
+
+/// 그림자 맵 한 변의 텍셀 수
+pub const SHADOW_MAP_SIZE: u32 = 2048;
+
+
+pub struct ShadowPass {
+    map: Texture,
+    pipeline: wgpu::RenderPipeline,
+    /// 셰이더에서 `LightUniform`의 `view_proj`를 씀
+    bind_group: wgpu::BindGroup,
+}
+
+impl ShadowPass {
+    /// `light_buffer`는 `LightUniform`을 담은 버퍼. 그림자를 끄면 `size`를 1로 줘서 메모리를 아낌
+    pub fn new(device: &wgpu::Device, light_buffer: &wgpu::Buffer, size: u32) -> Self {
+        let map = Texture::create_shadow_map(device, size, "shadow_map");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shadow.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("shadow_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }
+                ]
+            }
+        );
+
+        let bind_group = device.create_bind_group(
+            &wgpu::BindGroupDescriptor {
+                label: Some("shadow_bind_group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: light_buffer.as_entire_binding()
+                    }
+                ]
+            }
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    ModelVertex::desc(),
+                    InstanceRaw::desc(),
+                ],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            // 깊이만 그림
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                // 자기 자신에게 그림자가 지는 줄무늬 (shadow acne) 방지
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self { map, pipeline, bind_group }
+    }
+
+    /// 주 패스에서 샘플링할 그림자 맵
+    pub fn map(&self) -> &Texture {
+        &self.map
+    }
+
+    /// `models`의 불투명 인스턴스를 그림자 맵에 그림. 인스턴스 버퍼는 이미 갱신되어 있어야 함
+    pub fn render<'a>(&'a self, encoder: &mut wgpu::CommandEncoder, models: impl Iterator<Item = &'a Model>) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.map.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        for model in models {
+            model.draw_depth(&mut render_pass);
+        }
+    }
+}
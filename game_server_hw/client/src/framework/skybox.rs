@@ -0,0 +1,294 @@
+//! 스카이박스.
+//! 리소스 폴더의 큐브맵 (`face_files`)을 주 렌더 패스에서 다른 오브젝트보다 먼저,
+//! 깊이를 쓰지 않고 화면 전체에 그림. 씬마다 `Scene::skybox`로 고름.
+
+use cgmath::{Matrix4, Point3, SquareMatrix};
+use wgpu::util::DeviceExt;
+
+use super::{
+    texture::Texture,
+    resources,
+    logging,
+};
+
+
+/// 기본 스카이박스 (`sky_px.png` ...)
+pub const DEFAULT_SKYBOX: &str = "sky";
+/// 큐브맵 면 이름. `Texture::cubemap`의 순서
+const FACES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+
+/// `name` 스카이박스의 면 파일 이름 (`<name>_px.png` ...)
+pub fn face_files(name: &str) -> [String; 6] {
+    FACES.map(|face| format!("{}_{}.png", name, face))
+}
+
+/// 리소스 폴더에서 `name`의 면 6장을 읽어서 큐브맵을 만듦
+pub async fn load_cubemap(name: &str, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<Texture> {
+    let mut faces = Vec::with_capacity(FACES.len());
+    for file_name in face_files(name) {
+        let data = resources::load_binary(&file_name).await?;
+        let image = image::load_from_memory(&data)
+            .map_err(|e| anyhow::anyhow!("failed to decode {:?}: {}", file_name, e))?;
+        faces.push(image.to_rgba8());
+    }
+    Texture::cubemap(device, queue, &faces, name)
+}
+
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkyboxUniform {
+    /// 화면 좌표에서 월드 좌표로
+    inv_view_proj: [[f32; 4]; 4],
+    eye: [f32; 4],
+}
+
+impl SkyboxUniform {
+    /// `view_proj`의 역행렬이 없으면 단위 행렬
+    fn new(view_proj: Matrix4<f32>, eye: Point3<f32>) -> Self {
+        Self {
+            inv_view_proj: view_proj.invert().unwrap_or_else(Matrix4::identity).into(),
+            eye: [eye.x, eye.y, eye.z, 1.0],
+        }
+    }
+}
+
+
+pub struct SkyboxPass {
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    /// 마지막으로 고른 스카이박스 이름과 그 bind group. 불러오지 못했으면 bind group이 `None`
+    current: Option<(String, Option<wgpu::BindGroup>)>,
+}
+
+impl SkyboxPass {
+    /// `format`은 그릴 화면의 텍스처 형식, `sample_count`는 주 렌더 패스의 MSAA 샘플 수
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skybox Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../skybox.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Skybox Buffer"),
+                contents: bytemuck::cast_slice(&[SkyboxUniform::new(Matrix4::identity(), Point3::new(0.0, 0.0, 0.0))]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("skybox_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ]
+            }
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = create_pipeline(device, &pipeline_layout, &shader, format, sample_count);
+
+        Self { shader, pipeline_layout, format, sample_count, pipeline, bind_group_layout, uniform_buffer, current: None }
+    }
+
+    /// 주 렌더 패스의 MSAA 샘플 수가 바뀌면 파이프라인을 다시 만듦
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        if sample_count != self.sample_count {
+            self.sample_count = sample_count;
+            self.pipeline = create_pipeline(device, &self.pipeline_layout, &self.shader, self.format, sample_count);
+        }
+    }
+
+    /// 스카이박스를 `name`으로 바꿈. 이름이 같으면 아무것도 하지 않으므로 매 프레임 호출해도 됨.
+    /// 불러오지 못하면 경고를 남기고 다른 이름으로 바뀔 때까지 다시 시도하지 않음
+    pub fn set_skybox(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, name: Option<&str>) {
+        if self.current.as_ref().map(|(current, _)| current.as_str()) == name {
+            return;
+        }
+        let Some(name) = name else {
+            self.current = None;
+            return;
+        };
+
+        let bind_group = match futures::executor::block_on(load_cubemap(name, device, queue)) {
+            Ok(cubemap) => Some(self.create_bind_group(device, &cubemap)),
+            Err(e) => {
+                log::warn!(target: logging::RENDER, "Failed to load skybox {:?}: {:#}", name, e);
+                None
+            }
+        };
+        self.current = Some((name.to_string(), bind_group));
+    }
+
+    /// 다음 `set_skybox`에서 같은 이름이어도 큐브맵을 다시 읽음 (면 파일이 바뀌었을 때)
+    pub fn reload(&mut self) {
+        self.current = None;
+    }
+
+    fn create_bind_group(&self, device: &wgpu::Device, cubemap: &Texture) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("skybox_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&cubemap.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&cubemap.sampler),
+                },
+            ],
+        })
+    }
+
+    /// 그릴 스카이박스가 있음. 없으면 씬의 배경색으로 지운 화면 그대로
+    pub fn is_active(&self) -> bool {
+        matches!(self.current, Some((_, Some(_))))
+    }
+
+    /// 카메라가 바뀌면 호출
+    pub fn update(&self, queue: &wgpu::Queue, view_proj: Matrix4<f32>, eye: Point3<f32>) {
+        if self.is_active() {
+            queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[SkyboxUniform::new(view_proj, eye)]));
+        }
+    }
+
+    /// 주 렌더 패스에서 다른 오브젝트보다 먼저 호출. 파이프라인을 바꾸므로 다음에 다시 설정해야 함
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        let Some((_, Some(bind_group))) = &self.current else {
+            return;
+        };
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Skybox Pipeline"),
+        layout: Some(pipeline_layout),
+        // 정점 버퍼 없이 화면을 덮는 삼각형 하나
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        // 주 패스의 깊이 버퍼를 같이 쓰지만, 가장 먼저 그리고 깊이를 남기지 않으므로 모든 오브젝트 뒤에 보임
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_face_files() {
+        assert_eq!(face_files("sky")[0], "sky_px.png");
+        assert_eq!(face_files("sky")[5], "sky_nz.png");
+    }
+
+    #[test]
+    fn test_load_cubemap() {
+        let Some((device, queue)) = super::super::test_device() else {
+            return;
+        };
+
+        let cubemap = futures::executor::block_on(load_cubemap(DEFAULT_SKYBOX, &device, &queue)).unwrap();
+        assert_eq!(cubemap.texture.depth_or_array_layers(), 6);
+        assert!(futures::executor::block_on(load_cubemap("missing", &device, &queue)).is_err());
+
+        let mut pass = SkyboxPass::new(&device, wgpu::TextureFormat::Rgba8UnormSrgb, 1);
+        pass.set_skybox(&device, &queue, Some("missing"));
+        assert!(!pass.is_active());
+        pass.set_skybox(&device, &queue, Some(DEFAULT_SKYBOX));
+        assert!(pass.is_active());
+        pass.set_sample_count(&device, 4);
+        assert!(pass.is_active());
+        pass.set_skybox(&device, &queue, None);
+        assert!(!pass.is_active());
+    }
+}
@@ -0,0 +1,145 @@
+//! 서버가 보낸 오브젝트 상태를 받은 시간과 함께 모아두는 버퍼 (jitter buffer).
+//! 받은 그대로 그리지 않고 `interpolation_delay`만큼 늦은 시간을 앞뒤 스냅샷 사이로 보간해서,
+//! 업데이트가 고르지 않게 와도 오브젝트가 일정한 속도로 움직임.
+
+use std::{collections::VecDeque, time::Instant};
+use cgmath::{InnerSpace, Quaternion, Vector3};
+
+
+/// 오브젝트마다 유지하는 최대 스냅샷 수. 넘으면 오래된 것부터 버림
+pub const MAX_SNAPSHOTS: usize = 32;
+
+
+/// `time`에 받은 오브젝트 위치와 회전
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot {
+    pub time: Instant,
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+}
+
+impl Snapshot {
+    pub fn new(time: Instant, position: Vector3<f32>, rotation: Quaternion<f32>) -> Self {
+        Self { time, position, rotation }
+    }
+}
+
+
+/// 받은 시간 순서대로 쌓인 스냅샷
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl SnapshotBuffer {
+    pub fn new(snapshot: Snapshot) -> Self {
+        Self { snapshots: VecDeque::from([snapshot]) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.back()
+    }
+
+    /// 마지막 스냅샷보다 이르지 않은 시간만 받음. 같은 시간이면 마지막 스냅샷을 바꿈
+    pub fn push(&mut self, snapshot: Snapshot) {
+        match self.snapshots.back_mut() {
+            Some(latest) if snapshot.time < latest.time => return,
+            Some(latest) if snapshot.time == latest.time => *latest = snapshot,
+            _ => self.snapshots.push_back(snapshot),
+        }
+        while self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// `time`의 위치와 회전. 첫 스냅샷보다 이르면 첫 스냅샷, 마지막보다 늦으면 마지막 스냅샷 (예측하지 않음)
+    pub fn sample(&self, time: Instant) -> Option<(Vector3<f32>, Quaternion<f32>)> {
+        let next = self.snapshots.iter().position(|snapshot| snapshot.time > time);
+        let (before, after) = match next {
+            Some(0) => return self.snapshots.front().map(|first| (first.position, first.rotation)),
+            Some(next) => (&self.snapshots[next - 1], &self.snapshots[next]),
+            None => return self.snapshots.back().map(|last| (last.position, last.rotation)),
+        };
+
+        let span = (after.time - before.time).as_secs_f32();
+        let t = (time - before.time).as_secs_f32() / span;
+        let position = before.position + (after.position - before.position) * t;
+        let rotation = match before.rotation.dot(after.rotation) < 0.0 {
+            // 짧은 쪽으로 돌도록 부호를 맞춤
+            true => before.rotation.slerp(-after.rotation, t),
+            false => before.rotation.slerp(after.rotation, t),
+        };
+        Some((position, rotation))
+    }
+
+    /// `time`을 보간하는 데 더 이상 필요하지 않은 스냅샷을 버림. `time` 이전 스냅샷은 하나만 남김
+    pub fn discard_before(&mut self, time: Instant) {
+        while self.snapshots.get(1).is_some_and(|second| second.time <= time) {
+            self.snapshots.pop_front();
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use cgmath::{Deg, Rotation3};
+
+    fn at(start: Instant, ms: u64, x: f32) -> Snapshot {
+        Snapshot::new(start + Duration::from_millis(ms), Vector3::new(x, 0.0, 0.0), Quaternion::from_angle_y(Deg(0.0)))
+    }
+
+    #[test]
+    fn test_sample_between_snapshots() {
+        let start = Instant::now();
+        let mut buffer = SnapshotBuffer::new(at(start, 0, 0.0));
+        buffer.push(at(start, 100, 1.0));
+        buffer.push(at(start, 300, 3.0));
+
+        let x = |ms| buffer.sample(start + Duration::from_millis(ms)).unwrap().0.x;
+        assert!((x(50) - 0.5).abs() < 1e-4);
+        assert!((x(200) - 2.0).abs() < 1e-4);
+        // 범위 밖은 양 끝 스냅샷
+        assert_eq!((x(400), buffer.sample(start).unwrap().0.x), (3.0, 0.0));
+    }
+
+    #[test]
+    fn test_push_keeps_order() {
+        let start = Instant::now();
+        let mut buffer = SnapshotBuffer::new(at(start, 100, 1.0));
+        buffer.push(at(start, 50, 5.0));
+        buffer.push(at(start, 100, 2.0));
+        assert_eq!((buffer.len(), buffer.latest().unwrap().position.x), (1, 2.0));
+
+        for ms in 0..MAX_SNAPSHOTS as u64 * 2 {
+            buffer.push(at(start, 200 + ms, 0.0));
+        }
+        assert_eq!(buffer.len(), MAX_SNAPSHOTS);
+    }
+
+    #[test]
+    fn test_discard_before() {
+        let start = Instant::now();
+        let mut buffer = SnapshotBuffer::new(at(start, 0, 0.0));
+        buffer.push(at(start, 100, 1.0));
+        buffer.push(at(start, 200, 2.0));
+
+        // 150을 보간하려면 100과 200이 필요함
+        buffer.discard_before(start + Duration::from_millis(150));
+        assert_eq!(buffer.len(), 2);
+        buffer.discard_before(start + Duration::from_millis(500));
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.sample(start).unwrap().0.x, 2.0);
+    }
+}
@@ -0,0 +1,87 @@
+use winit::{
+    event::ElementState,
+    keyboard::KeyCode,
+};
+
+
+/// 포커스된 동안 키 입력을 받은 텍스트 입력창의 처리 결과
+#[derive(Debug, PartialEq)]
+pub enum TextInputEvent {
+    /// 입력창이 키를 소비함 (문자 입력, 지우기 등)
+    Consumed,
+    /// Enter로 입력 완료. 포커스 해제됨
+    Submitted(String),
+    /// Escape로 입력 취소. 포커스 해제됨
+    Cancelled,
+}
+
+
+/// 채팅/콘솔 등에서 사용하는 한 줄 텍스트 입력창.
+/// 포커스가 있는 동안에는 모든 키 입력을 가져감.
+#[derive(Default)]
+pub struct TextInput {
+    buffer: String,
+    focused: bool,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn focus(&mut self) {
+        self.focused = true;
+    }
+
+    pub fn unfocus(&mut self) {
+        self.focused = false;
+        self.buffer.clear();
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    /// 입력 중인 내용을 `text`로 바꿈
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.buffer = text.into();
+    }
+
+    /// `text`는 키 입력으로 만들어진 문자 (winit의 `KeyEvent::text`)
+    pub fn handle_key(
+        &mut self,
+        state: &ElementState,
+        keycode: &KeyCode,
+        text: Option<&str>
+    ) -> TextInputEvent {
+        if *state == ElementState::Released {
+            return TextInputEvent::Consumed;
+        }
+
+        match keycode {
+            KeyCode::Enter | KeyCode::NumpadEnter => {
+                let text = std::mem::take(&mut self.buffer);
+                self.unfocus();
+                TextInputEvent::Submitted(text)
+            }
+            KeyCode::Escape => {
+                self.unfocus();
+                TextInputEvent::Cancelled
+            }
+            KeyCode::Backspace => {
+                self.buffer.pop();
+                TextInputEvent::Consumed
+            }
+            _ => {
+                if let Some(text) = text {
+                    self.buffer.extend(text.chars().filter(|c| !c.is_control()));
+                }
+                TextInputEvent::Consumed
+            }
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use super::color::Color;
+
+
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
@@ -6,18 +9,199 @@ pub struct Texture {
 
 impl Texture {
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float; // 1.
+
+    /// 텍스처 샘플러 설정.  
+    /// `filter`는 확대/축소시 사용할 필터 (도트 그림은 `Nearest`, 그 외는 `Linear`)
+    pub fn sampler_descriptor(filter: wgpu::FilterMode) -> wgpu::SamplerDescriptor<'static> {
+        wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+        filter: wgpu::FilterMode,
+    ) -> anyhow::Result<Self> {
+        let img = image::load_from_memory(bytes)?;
+        Ok(Self::from_image(device, queue, &img, Some(label), filter))
+    }
+
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::DynamicImage,
+        label: Option<&str>,
+        filter: wgpu::FilterMode,
+    ) -> Self {
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            &rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&Self::sampler_descriptor(filter));
+
+        Self { texture, view, sampler }
+    }
     
-    pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, label: &str) -> Self {
+    /// 큐브맵. `faces`는 +x, -x, +y, -y, +z, -z 순서의 같은 크기 정사각형 이미지 6장
+    pub fn cubemap(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: &[image::RgbaImage],
+        label: &str,
+    ) -> anyhow::Result<Self> {
+        let Some(first) = faces.first().filter(|_| faces.len() == 6) else {
+            anyhow::bail!("cubemap {:?} needs 6 faces, got {}", label, faces.len());
+        };
+        let (width, height) = first.dimensions();
+        if width != height || faces.iter().any(|face| face.dimensions() != (width, height)) {
+            anyhow::bail!("cubemap {:?} faces must be squares of the same size", label);
+        }
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 6,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (layer, face) in faces.iter().enumerate() {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: layer as u32 },
+                },
+                face,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d { depth_or_array_layers: 1, ..size },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&Self::sampler_descriptor(wgpu::FilterMode::Linear));
+
+        Ok(Self { texture, view, sampler })
+    }
+
+    /// 1x1 흰색 텍스처. 텍스처가 없는 재질에 사용
+    pub fn white(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self::solid(device, queue, Color::WHITE)
+    }
+
+    /// `color` 한 색으로 채운 1x1 텍스처
+    pub fn solid(device: &wgpu::Device, queue: &wgpu::Queue, color: Color) -> Self {
+        let rgba = [color.r(), color.g(), color.b(), color.a()].map(|c| (c * 255.0).round() as u8);
+        let img = image::RgbaImage::from_pixel(1, 1, image::Rgba(rgba));
+        Self::from_image(device, queue, &image::DynamicImage::ImageRgba8(img), Some("solid"), wgpu::FilterMode::Nearest)
+    }
+
+    /// 화면과 크기가 같은 깊이 버퍼. `sample_count`는 주 렌더 패스의 색 타깃과 같아야 함
+    pub fn create_depth_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32, label: &str) -> Self {
+        Self::create_depth(device, config.width, config.height, sample_count, label)
+    }
+
+    /// 한 변이 `size`인 그림자 맵. 샘플러는 깊이 비교용
+    pub fn create_shadow_map(device: &wgpu::Device, size: u32, label: &str) -> Self {
+        Self::create_depth(device, size, size, 1, label)
+    }
+
+    /// MSAA에서 주 렌더 패스가 그리는 화면 크기의 색 타깃. 패스가 끝나면 화면 텍스처로 합쳐짐 (resolve).
+    /// `sample_count`가 1이면 화면 텍스처에 바로 그리므로 `None`
+    pub fn create_msaa_target(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32, label: &str) -> Option<Self> {
+        if sample_count <= 1 {
+            return None;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: config.width.max(1),
+                height: config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // 샘플링하지 않지만 `Texture`를 채우기 위함
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        Some(Self { texture, view, sampler })
+    }
+
+    fn create_depth(device: &wgpu::Device, width: u32, height: u32, sample_count: u32, label: &str) -> Self {
         let size = wgpu::Extent3d { // 2.
-            width: config.width,
-            height: config.height,
+            width: width.max(1),
+            height: height.max(1),
             depth_or_array_layers: 1,
         };
         let desc = wgpu::TextureDescriptor {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT // 3.
@@ -44,4 +228,22 @@ impl Texture {
 
         Self { texture, view, sampler }
     }
-}
\ No newline at end of file
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampler_filter() {
+        let desc = Texture::sampler_descriptor(wgpu::FilterMode::Nearest);
+        assert_eq!(desc.mag_filter, wgpu::FilterMode::Nearest);
+        assert_eq!(desc.min_filter, wgpu::FilterMode::Nearest);
+
+        let desc = Texture::sampler_descriptor(wgpu::FilterMode::Linear);
+        assert_eq!(desc.mag_filter, wgpu::FilterMode::Linear);
+        assert_eq!(desc.min_filter, wgpu::FilterMode::Linear);
+    }
+}
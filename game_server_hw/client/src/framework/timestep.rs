@@ -0,0 +1,78 @@
+//! 고정 간격 갱신.
+//! 프레임마다 지난 시간을 쌓아두고, `step`만큼 쌓일 때마다 한 번씩 갱신하도록 알려줌.
+//! 프레임 속도와 상관없이 같은 시간에 같은 횟수만큼 갱신해야 하는 로직에 사용.
+
+use std::time::Duration;
+
+
+/// 기본 고정 갱신 간격 (60Hz)
+pub const FIXED_TIMESTEP: Duration = Duration::from_nanos(16_666_667);
+/// 한 프레임에 최대 몇 번까지 갱신할지. 넘는 시간은 버림 (창을 끌거나 멈췄다가 돌아왔을 때)
+pub const MAX_FIXED_STEPS: u32 = 8;
+
+
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulated: Duration,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(FIXED_TIMESTEP, MAX_FIXED_STEPS)
+    }
+}
+
+impl FixedTimestep {
+    /// `step`이 0이면 1ns, `max_steps`가 0이면 1로 바꿈
+    pub fn new(step: Duration, max_steps: u32) -> Self {
+        Self { step: step.max(Duration::from_nanos(1)), max_steps: max_steps.max(1), accumulated: Duration::ZERO }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// `dt`를 쌓고 이번 프레임에 갱신할 횟수를 반환. 남은 시간은 다음 프레임으로 넘김
+    pub fn advance(&mut self, dt: Duration) -> u32 {
+        self.accumulated += dt;
+
+        let mut steps = 0;
+        while self.accumulated >= self.step {
+            if steps == self.max_steps {
+                self.accumulated = Duration::ZERO;
+                break;
+            }
+            self.accumulated -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// 다음 갱신까지 진행한 비율 (0..1). 두 갱신 결과 사이를 보간할 때 사용
+    pub fn alpha(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        let mut timestep = FixedTimestep::new(Duration::from_millis(10), 4);
+        assert_eq!(timestep.advance(Duration::from_millis(5)), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance(Duration::from_millis(7)), 1);
+        assert_eq!(timestep.advance(Duration::from_millis(28)), 3);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        // 너무 오래 멈췄으면 최대 횟수만 갱신하고 나머지는 버림
+        assert_eq!(timestep.advance(Duration::from_secs(1)), 4);
+        assert_eq!(timestep.advance(Duration::ZERO), 0);
+    }
+}
@@ -1,7 +1,12 @@
+use cgmath::Rotation3;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Transform {
     pub position: cgmath::Vector3<f32>,
     pub rotation: cgmath::Quaternion<f32>,
-    // pub scale: f32,
+    /// 균일 크기 배율
+    pub scale: f32,
 }
 
 #[repr(C)]
@@ -12,10 +17,19 @@ pub struct TransformRaw {
 
 
 impl Transform {
+    /// y축 기준 회전(도)으로 `rotation`을 설정
+    pub fn set_yaw(&mut self, degrees: f32) {
+        self.rotation = cgmath::Quaternion::from_angle_y(cgmath::Deg(degrees));
+    }
+
+    pub fn matrix(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::from_translation(self.position) 
+            * cgmath::Matrix4::from(self.rotation) 
+            * cgmath::Matrix4::from_scale(self.scale)
+    }
+
     pub fn to_raw(&self) -> TransformRaw {
-        TransformRaw {
-            transform_matrix: (cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)).into()
-        }
+        TransformRaw::from(self.matrix())
     }
 }
 
@@ -24,12 +38,18 @@ impl Default for Transform {
         Self {
             position: cgmath::Vector3::new(0.0, 0.0, 0.0),
             rotation: cgmath::Quaternion::new(1.0, 0.0, 0.0, 0.0),
-            // scale: 1.0,
+            scale: 1.0,
         }
     }
 }
 
 
+impl From<cgmath::Matrix4<f32>> for TransformRaw {
+    fn from(matrix: cgmath::Matrix4<f32>) -> Self {
+        Self { transform_matrix: matrix.into() }
+    }
+}
+
 impl TransformRaw {
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         use std::mem;
@@ -61,4 +81,40 @@ impl TransformRaw {
             ],
         }
     }
-}
\ No newline at end of file
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+
+    #[test]
+    fn test_matrix_with_yaw() {
+        let mut transform = Transform {
+            position: cgmath::Vector3::new(1.0, 2.0, 3.0),
+            ..Default::default()
+        };
+        transform.set_yaw(90.0);
+
+        // 회전 후 이동: +x 방향은 y축 90도 회전으로 -z가 됨
+        let matrix = cgmath::Matrix4::from(transform.to_raw().transform_matrix);
+        let p = matrix * cgmath::Vector4::new(1.0, 0.0, 0.0, 1.0);
+        assert!((p - cgmath::Vector4::new(1.0, 2.0, 2.0, 1.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_matrix_with_scale() {
+        let transform = Transform {
+            position: cgmath::Vector3::new(1.0, 0.0, 0.0),
+            scale: 0.5,
+            ..Default::default()
+        };
+
+        // 위치는 배율에 영향을 받지 않음
+        let matrix = cgmath::Matrix4::from(transform.to_raw().transform_matrix);
+        let p = matrix * cgmath::Vector4::new(2.0, 2.0, 0.0, 1.0);
+        assert!((p - cgmath::Vector4::new(2.0, 1.0, 0.0, 1.0)).magnitude() < 1e-5);
+    }
+}
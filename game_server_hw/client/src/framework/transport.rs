@@ -0,0 +1,257 @@
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::Duration,
+};
+
+use super::logging;
+
+
+/// 서버와 바이트를 주고받는 연결.  
+/// `GameScene`은 이 trait만 사용하므로 테스트에서는 `MockTransport`로 바꿀 수 있음.
+pub trait Transport {
+    fn is_connected(&self) -> bool;
+
+    /// 읽을 데이터가 없으면 `WouldBlock`, 상대가 연결을 닫았으면 `Ok(0)`
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// 보낸 바이트 수를 반환. 일부만 보낼 수 있고, 하나도 보낼 수 없으면 `WouldBlock`
+    fn write(&mut self, data: &[u8]) -> io::Result<usize>;
+
+    /// 기존 연결을 버리고 `addr`에 새로 접속. 기다리지 않으며, 접속 중이면 `WouldBlock`.  
+    /// 접속 중에 다시 부르면 새로 접속하지 않고 결과만 확인함
+    fn reconnect(&mut self, addr: SocketAddr, timeout: Duration) -> io::Result<()>;
+
+    /// `reconnect`로 시작한 접속을 기다리는 중이면 `true`
+    fn is_connecting(&self) -> bool {
+        false
+    }
+
+    /// 보내던 데이터를 마저 보내고 연결을 닫음. 닫힌 뒤 `is_connected`는 `false`
+    fn close(&mut self) -> io::Result<()>;
+}
+
+
+/// non-blocking `TcpStream` 연결. 접속은 다른 스레드에서 하므로 화면이 멈추지 않음
+#[derive(Default)]
+pub struct TcpTransport {
+    stream: Option<TcpStream>,
+    /// 접속하는 스레드의 결과
+    connecting: Option<Receiver<io::Result<TcpStream>>>,
+}
+
+impl TcpTransport {
+    /// non-blocking으로 바꾸지 못한 연결은 버림 (끊긴 것으로 보고 다시 접속함)
+    pub fn new(stream: Option<TcpStream>) -> Self {
+        let stream = stream.filter(|stream| match stream.set_nonblocking(true) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!(target: logging::NET, "Failed to make connection non-blocking: {}", e);
+                false
+            }
+        });
+        Self { stream, connecting: None }
+    }
+
+    /// 다른 스레드에서 `addr`에 접속을 시작함. 결과는 `reconnect`로 확인
+    pub fn connect(addr: SocketAddr, timeout: Duration) -> Self {
+        let mut transport = Self::default();
+        transport.start_connect(addr, timeout);
+        transport
+    }
+
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.as_ref()?.peer_addr().ok()
+    }
+
+    fn start_connect(&mut self, addr: SocketAddr, timeout: Duration) {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            // 기다리는 동안 연결을 닫았으면 받는 쪽이 없음
+            let _ = sender.send(TcpStream::connect_timeout(&addr, timeout));
+        });
+        self.connecting = Some(receiver);
+    }
+
+    fn stream(&mut self) -> io::Result<&mut TcpStream> {
+        self.stream.as_mut().ok_or_else(|| io::ErrorKind::NotConnected.into())
+    }
+}
+
+impl Transport for TcpTransport {
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream()?.read(buf)
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.stream()?.write(data)
+    }
+
+    fn reconnect(&mut self, addr: SocketAddr, timeout: Duration) -> io::Result<()> {
+        let Some(receiver) = &self.connecting else {
+            self.stream = None;
+            self.start_connect(addr, timeout);
+            return Err(io::ErrorKind::WouldBlock.into());
+        };
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return Err(io::ErrorKind::WouldBlock.into()),
+            Err(TryRecvError::Disconnected) => Err(io::Error::other("connecting thread stopped")),
+        };
+        self.connecting = None;
+
+        let stream = result?;
+        stream.set_nonblocking(true)?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn is_connecting(&self) -> bool {
+        self.connecting.is_some()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.connecting = None;
+        let Some(mut stream) = self.stream.take() else {
+            return Ok(());
+        };
+        stream.flush()?;
+        stream.shutdown(Shutdown::Write)
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) use mock::MockTransport;
+
+#[cfg(test)]
+mod mock {
+    use std::{
+        cell::RefCell,
+        collections::VecDeque,
+        rc::Rc,
+    };
+    use super::*;
+
+    #[derive(Default)]
+    struct MockState {
+        incoming: VecDeque<u8>,
+        outgoing: Vec<u8>,
+        connected: bool,
+        reconnects: usize,
+        /// 한 번의 `write`에서 받는 최대 바이트 수
+        write_limit: Option<usize>,
+        /// 받은 데이터를 다 읽으면 `read`가 `Ok(0)` (서버가 연결을 닫음)
+        closed_by_peer: bool,
+        /// 남은 횟수만큼 `reconnect`가 실패함
+        failing_reconnects: usize,
+    }
+
+    /// 메모리 큐로 동작하는 테스트용 연결.
+    /// 복제본끼리 상태를 공유하므로 씬에 넘긴 뒤에도 복제본으로 주고받은 데이터를 확인할 수 있음.
+    #[derive(Clone, Default)]
+    pub struct MockTransport {
+        state: Rc<RefCell<MockState>>,
+    }
+
+    impl MockTransport {
+        pub fn connected() -> Self {
+            let transport = Self::default();
+            transport.state.borrow_mut().connected = true;
+            transport
+        }
+
+        /// 서버가 보낸 것처럼 데이터를 추가
+        pub fn push_incoming(&self, data: &[u8]) {
+            self.state.borrow_mut().incoming.extend(data);
+        }
+
+        /// 지금까지 보낸 데이터를 가져오고 비움
+        pub fn take_outgoing(&self) -> String {
+            let data = std::mem::take(&mut self.state.borrow_mut().outgoing);
+            String::from_utf8(data).unwrap()
+        }
+
+        pub fn reconnects(&self) -> usize {
+            self.state.borrow().reconnects
+        }
+
+        /// 서버가 연결을 닫은 것처럼 동작
+        pub fn close_by_peer(&self) {
+            self.state.borrow_mut().closed_by_peer = true;
+        }
+
+        /// 다음 `count`번의 `reconnect`를 실패시킴
+        pub fn fail_reconnects(&self, count: usize) {
+            self.state.borrow_mut().failing_reconnects = count;
+        }
+
+        /// `write`마다 최대 `limit` 바이트만 받음. 0이면 항상 `WouldBlock`
+        pub fn limit_writes(&self, limit: Option<usize>) {
+            self.state.borrow_mut().write_limit = limit;
+        }
+    }
+
+    impl Transport for MockTransport {
+        fn is_connected(&self) -> bool {
+            self.state.borrow().connected
+        }
+
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut state = self.state.borrow_mut();
+            if !state.connected {
+                return Err(io::ErrorKind::NotConnected.into());
+            }
+            if state.incoming.is_empty() {
+                return match state.closed_by_peer {
+                    true => Ok(0),
+                    false => Err(io::ErrorKind::WouldBlock.into()),
+                };
+            }
+
+            let n = buf.len().min(state.incoming.len());
+            for (dst, src) in buf.iter_mut().zip(state.incoming.drain(..n)) {
+                *dst = src;
+            }
+            Ok(n)
+        }
+
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            let mut state = self.state.borrow_mut();
+            if !state.connected {
+                return Err(io::ErrorKind::NotConnected.into());
+            }
+
+            let n = state.write_limit.map_or(data.len(), |limit| limit.min(data.len()));
+            if n == 0 && !data.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            state.outgoing.extend_from_slice(&data[..n]);
+            Ok(n)
+        }
+
+        fn reconnect(&mut self, _addr: SocketAddr, _timeout: Duration) -> io::Result<()> {
+            let mut state = self.state.borrow_mut();
+            state.connected = false;
+            if state.failing_reconnects > 0 {
+                state.failing_reconnects -= 1;
+                return Err(io::ErrorKind::ConnectionRefused.into());
+            }
+            state.connected = true;
+            state.closed_by_peer = false;
+            state.reconnects += 1;
+            Ok(())
+        }
+
+        fn close(&mut self) -> io::Result<()> {
+            self.state.borrow_mut().connected = false;
+            Ok(())
+        }
+    }
+}
@@ -5,7 +5,7 @@ use winit::{
     event::*,
     event_loop::EventLoop,
     keyboard::{KeyCode, PhysicalKey},
-    window::WindowBuilder,
+    window::{Fullscreen, WindowBuilder},
 };
 
 use framework::*;
@@ -14,20 +14,81 @@ use framework::*;
 /// 이벤트루프 시작 및 윈도우 생성
 #[tokio::main]
 pub async fn run() {
-    env_logger::init();
+    // 로그 설정이 잘못됐다는 오류도 로그로 남기기 위해 먼저 기본 설정으로 시작함
+    let log_filter = config::log_filter_from_args(std::env::args().skip(1));
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Ok(Some(filter)) = &log_filter {
+        logger.parse_filters(filter);
+    }
+    logger.init();
+    if let Err(e) = log_filter {
+        log::error!(target: logging::SCENE, "{}", e);
+        return;
+    }
+
+    let server = match config::ServerConfig::from_args(std::env::args().skip(1)) {
+        Ok(server) => server,
+        Err(e) => {
+            log::error!(target: logging::SCENE, "{}", e);
+            return;
+        }
+    };
+    let network = match config::NetworkConfig::default().with_args(std::env::args().skip(1)) {
+        Ok(network) => network,
+        Err(e) => {
+            log::error!(target: logging::SCENE, "{}", e);
+            return;
+        }
+    };
+
+    let replay = config::replay_from_args(std::env::args().skip(1))
+        .and_then(|path| path.map(|path| replay::Replay::load(&path)).transpose());
+    let replay = match replay {
+        Ok(replay) => replay,
+        Err(e) => {
+            log::error!(target: logging::SCENE, "{}", e);
+            return;
+        }
+    };
+
+    let config_path = config::config_path_from_args(std::env::args().skip(1));
+    let client_config = match config_path {
+        Ok(path) => config::ClientConfig::load(path.as_deref()).await
+            .and_then(|config| config.with_args(std::env::args().skip(1))),
+        Err(e) => Err(e),
+    };
+    let client_config = match client_config {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!(target: logging::SCENE, "{}", e);
+            return;
+        }
+    };
+    config::ClientConfig::install(client_config.clone());
+
     let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let window = WindowBuilder::new()
+        .with_title(WINDOW_TITLE)
+        .with_fullscreen(client_config.window.fullscreen.then_some(Fullscreen::Borderless(None)))
+        .build(&event_loop)
+        .unwrap();
 
-    let mut state = State::new(&window).await;
+    let mut state = match State::new(&window, &client_config, server, network, replay).await {
+        Ok(state) => state,
+        Err(e) => {
+            log::error!(target: logging::SCENE, "Failed to start: {}", e);
+            return;
+        }
+    };
 
-    println!("start");
+    log::info!(target: logging::SCENE, "start");
 
     let _ = event_loop.run(|event, control_flow| {
         match event {
             Event::WindowEvent {
                 ref event,
                 window_id,
-            } if window_id == state.window().id() => if !state.handle_event(event) {
+            } if window_id == state.window().id() && !state.handle_event(event) => {
                 match event {
                     WindowEvent::CloseRequested => control_flow.exit(),
 
@@ -57,7 +118,7 @@ pub async fn run() {
                             // The system is out of memory, we should probably quit
                             Err(wgpu::SurfaceError::OutOfMemory) => control_flow.exit(),
                             // All other errors (Outdated, Timeout) should be resolved by the next frame
-                            Err(e) => eprintln!("{:?}", e),
+                            Err(e) => log::error!(target: logging::RENDER, "{:?}", e),
                         }
                     },
 
@@ -65,6 +126,8 @@ pub async fn run() {
                 }
             },
 
+            Event::LoopExiting => state.on_exit(),
+
             _ => {}
         }
     });
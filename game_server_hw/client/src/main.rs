@@ -1,5 +1,3 @@
-use client;
-
 fn main() {
     client::run();
 }
\ No newline at end of file
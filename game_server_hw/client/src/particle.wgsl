@@ -0,0 +1,56 @@
+// 파티클 셰이더. 인스턴스마다 카메라를 향하는 사각형을 그리고 가장자리로 갈수록 투명하게 함
+
+struct ParticleUniform {
+    view_proj: mat4x4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> particle: ParticleUniform;
+
+struct InstanceInput {
+    // xyz는 중심, w는 크기
+    @location(0) position_size: vec4<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 사각형 안의 위치 (-1..=1)
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> VertexOutput {
+    // 삼각형 두 개: (0, 1, 2), (2, 1, 3)
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[index];
+    let half_size = instance.position_size.w * 0.5;
+    let world = instance.position_size.xyz
+        + (particle.right.xyz * corner.x + particle.up.xyz * corner.y) * half_size;
+
+    var out: VertexOutput;
+    out.clip_position = particle.view_proj * vec4<f32>(world, 1.0);
+    out.corner = corner;
+    out.color = instance.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let falloff = 1.0 - smoothstep(0.5, 1.0, length(in.corner));
+    if falloff <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, in.color.a * falloff);
+}
@@ -3,23 +3,60 @@ struct InstanceInput {
     @location(6) model_matrix_1: vec4<f32>,
     @location(7) model_matrix_2: vec4<f32>,
     @location(8) model_matrix_3: vec4<f32>,
+    @location(9) color: vec4<f32>,
+    @location(10) alpha: f32,
 }
 
 struct CameraUniform {
+    view_position: vec4<f32>,
     view_proj: mat4x4<f32>,
 };
 
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+struct LightUniform {
+    // 빛이 나아가는 방향 (정규화됨)
+    direction: vec3<f32>,
+    ambient: f32,
+    color: vec3<f32>,
+    // 1이면 그림자 맵을 사용
+    shadows: f32,
+    // 그림자 맵을 그릴 때의 변환
+    view_proj: mat4x4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> light: LightUniform;
+@group(2) @binding(1)
+var t_shadow: texture_depth_2d;
+@group(2) @binding(2)
+var s_shadow: sampler_comparison;
+
+// Blinn-Phong 정반사광의 세기와 날카로움
+const SPECULAR_STRENGTH: f32 = 0.3;
+const SHININESS: f32 = 32.0;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+    @location(3) normal: vec3<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
+    @location(1) alpha: f32,
+    @location(2) tex_coords: vec2<f32>,
+    @location(3) world_normal: vec3<f32>,
+    @location(4) world_position: vec3<f32>,
+    @location(5) light_position: vec4<f32>,
 };
 
 
@@ -36,8 +73,19 @@ fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
 
     var out: VertexOutput;
 
-    out.color = model.color;
-    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+    // instance.color.a == 1.0 이면 모델 색상 대신 인스턴스 색상 사용
+    out.color = mix(model.color, instance.color.rgb, instance.color.a);
+    out.alpha = instance.alpha;
+    out.tex_coords = model.tex_coords;
+
+    // 크기는 모든 축이 같으므로 회전 부분으로 법선을 돌리고 fs_main에서 정규화
+    let normal_matrix = mat3x3<f32>(model_matrix[0].xyz, model_matrix[1].xyz, model_matrix[2].xyz);
+    out.world_normal = normal_matrix * model.normal;
+
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+    out.world_position = world_position.xyz;
+    out.light_position = light.view_proj * world_position;
+    out.clip_position = camera.view_proj * world_position;
 
     return out;
 }
@@ -46,7 +94,45 @@ fn vs_main(model: VertexInput, instance: InstanceInput) -> VertexOutput {
 
 // ---------------------------- Fragment Shader ---------------------------- //
 
+// 빛을 받는 비율 (0..=1). 주변 3x3 텍셀을 비교해서 (PCF) 그림자 경계를 부드럽게 함.
+// 그림자 맵 밖은 항상 빛을 받음
+fn shadow_factor(light_position: vec4<f32>) -> f32 {
+    if light.shadows == 0.0 {
+        return 1.0;
+    }
+
+    let ndc = light_position.xyz / light_position.w;
+    let uv = ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5, 0.5);
+    if any(uv < vec2<f32>(0.0, 0.0)) || any(uv > vec2<f32>(1.0, 1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_shadow));
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(t_shadow, s_shadow, uv + offset, ndc.z);
+        }
+    }
+    return lit / 9.0;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    // 텍스처가 없는 재질은 흰색 텍스처이므로 색상 그대로
+    let texel = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let base = in.color * texel.rgb;
+
+    let normal = normalize(in.world_normal);
+    let to_light = -light.direction;
+    let to_camera = normalize(camera.view_position.xyz - in.world_position);
+    let half_dir = normalize(to_light + to_camera);
+
+    let lit = shadow_factor(in.light_position);
+    let ambient = light.color * light.ambient;
+    let diffuse = light.color * max(dot(normal, to_light), 0.0) * lit;
+    let specular = light.color * SPECULAR_STRENGTH * pow(max(dot(normal, half_dir), 0.0), SHININESS) * lit;
+
+    return vec4<f32>(base * (ambient + diffuse) + specular, in.alpha * texel.a);
 }
\ No newline at end of file
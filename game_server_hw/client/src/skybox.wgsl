@@ -0,0 +1,38 @@
+// 스카이박스 셰이더. 화면을 덮는 삼각형의 각 픽셀에서 카메라가 보는 방향으로 큐브맵을 샘플링
+
+struct SkyboxUniform {
+    inv_view_proj: mat4x4<f32>,
+    eye: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> skybox: SkyboxUniform;
+@group(0) @binding(1)
+var t_sky: texture_cube<f32>;
+@group(0) @binding(2)
+var s_sky: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 화면 좌표 (-1..=1)
+    @location(0) ndc: vec2<f32>,
+};
+
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // (-1, -1), (3, -1), (-1, 3)
+    let ndc = vec2<f32>(f32(index == 1u) * 4.0 - 1.0, f32(index == 2u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let far = skybox.inv_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = far.xyz / far.w - skybox.eye.xyz;
+    return textureSample(t_sky, s_sky, direction);
+}
@@ -11,7 +11,7 @@ use tokio::{
 };
 use rand::Rng;
 use get_addr::get_addr;
-use network::PacketParser;
+use network::{ObjectState, PacketParser, ServerMessage, UpdateKind};
 
 
 struct Player {
@@ -73,50 +73,22 @@ impl Server {
     }
     
     fn process_message(&mut self, msg: &str) {
-        let msg = msg.trim().split_whitespace()
-            .map(|s| s.trim())
-            .collect::<Vec<&str>>();
-
         // println!("Received: {:?}", msg);
 
-        if msg.len() == 0 {
-            return;
-        }
-
-        if msg[0] != "GAMESERVER" {
-            return;
-        }
-
-        let msg = &msg[1..];
-
-        match msg[0] {
-            "init" => {
-                if msg.len() < 2 {
-                    return;
-                }
-
-                self.player_id = msg[1].parse::<u32>().unwrap();
+        match ServerMessage::decode(msg) {
+            Ok(ServerMessage::Init { id, .. }) => {
+                self.player_id = id;
             }
 
-            "update" => {
-                if msg.len() < 2 {
-                    return;
-                }
-
+            Ok(ServerMessage::Update { kind: UpdateKind::Flat | UpdateKind::Rotation, objects, .. }) => {
                 // let time = std::time::Instant::now();
                 // let elapsed = time.duration_since(self.prev_update);
                 // println!("Elapsed(id: {}): {:?}", self.player_id, elapsed);
                 // self.prev_update = time;
 
-                let num_objects = msg[1].parse::<usize>().unwrap();
                 let mut valid_ids: Vec<u32> = Vec::new();
 
-                for i in 0..num_objects {
-                    let idx = 2 + i * 3;
-                    let id = msg[idx].parse::<u32>().unwrap();
-                    let x = msg[idx+1].parse::<i32>().unwrap();
-                    let z = msg[idx+2].parse::<i32>().unwrap();
-
+                for ObjectState { id, x, z, .. } in objects {
                     self.players.entry(id)
                         .or_insert_with(|| {
                             Rc::new(RefCell::new(Player { x, z }))
@@ -128,6 +100,18 @@ impl Server {
                 // 기존에 있던 id가 안보이면 삭제
                 self.players.retain(|k, _| valid_ids.contains(k));
             }
+
+            Ok(ServerMessage::Delta { changed, removed, .. }) => {
+                for ObjectState { id, x, z, .. } in changed {
+                    let player = self.players.entry(id)
+                        .or_insert_with(|| {
+                            Rc::new(RefCell::new(Player { x, z }))
+                        });
+                    *player.borrow_mut() = Player { x, z };
+                }
+
+                self.players.retain(|k, _| !removed.contains(k));
+            }
             _ => {}
         }
     }
@@ -34,4 +34,12 @@ pub fn get_addr() -> Result<(String, u16), String> {
     };
 
     Ok((ip.to_string(), port))
+}
+
+/// `public`이면 이 컴퓨터의 로컬 네트워크 주소, 아니면 (`localhost`, ip) 그대로
+pub fn resolve_host(host: &str) -> String {
+    match host {
+        "public" => local_ip().unwrap().to_string(),
+        _ => host.to_string(),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,285 @@
+//! 서버를 같은 프로세스의 임의 포트에 띄우고, 스크립트대로 움직이는 클라이언트로
+//! 프로토콜을 확인하는 통합 테스트 도구.
+//!
+//! 서버는 전역 상태를 쓰므로 프로세스마다 한 번만 띄우며, 모든 테스트가 같은 서버의 로비를 같이 씀.
+//! 다른 테스트의 플레이어가 보일 수 있으므로 확인은 자기 id를 기준으로 해야 함.
+
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+use network::{ClientMessage, PacketParser, ServerMessage, Tile, UpdateKind};
+use server::server::{config::ServerConfig, tcp_server};
+
+
+/// `expect`가 메세지를 기다리는 최대 시간
+pub const TIMEOUT: Duration = Duration::from_secs(2);
+
+
+static SERVER: OnceLock<SocketAddr> = OnceLock::new();
+
+/// 처음 부르면 서버를 띄움. NPC는 플레이어의 이동을 막을 수 있으므로 두지 않음
+pub fn server_addr() -> SocketAddr {
+    *SERVER.get_or_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind test server");
+        let addr = listener.local_addr().unwrap();
+        listener.set_nonblocking(true).unwrap();
+
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().expect("Failed to start test server runtime");
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                tcp_server::serve(listener, ServerConfig { npcs: 0, ..ServerConfig::default() }).await;
+            });
+        });
+
+        addr
+    })
+}
+
+
+/// 서버에 접속한 테스트 클라이언트. 받은 위치 메세지로 보이는 오브젝트의 위치를 계속 갱신함
+pub struct MockClient {
+    stream: TcpStream,
+    packet_parser: PacketParser,
+    pub id: u32,
+    pub token: Option<u64>,
+    /// `map`에서 받은 (너비, 높이, 칸)
+    pub map: Option<(i32, i32, Vec<Tile>)>,
+    /// 보이는 오브젝트의 보드 칸 (x, z)
+    pub positions: HashMap<u32, (i32, i32)>,
+}
+
+impl MockClient {
+    /// 서버에 접속해서 `init`을 기다림
+    pub fn connect() -> Self {
+        let stream = TcpStream::connect(server_addr()).expect("Failed to connect to test server");
+        stream.set_nodelay(true).unwrap();
+
+        let mut client = Self {
+            stream,
+            packet_parser: PacketParser::new(),
+            id: 0,
+            token: None,
+            map: None,
+            positions: HashMap::new(),
+        };
+        let (id, token) = client.expect("init", |message| match message {
+            ServerMessage::Init { id, token, .. } => Some((*id, *token)),
+            _ => None,
+        });
+        client.id = id;
+        client.token = token;
+        client
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) {
+        self.stream.write_all(message.encode().as_bytes()).expect("Failed to send to test server");
+    }
+
+    /// `TIMEOUT` 안에 받은 다음 메세지. 서버가 연결을 닫았거나 시간이 지나면 `None`.
+    /// 형식이 잘못된 메세지를 받으면 실패
+    pub fn recv(&mut self) -> Option<ServerMessage> {
+        let deadline = Instant::now() + TIMEOUT;
+        let mut buf = [0; 4096];
+
+        while self.packet_parser.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return None;
+            }
+            self.stream.set_read_timeout(Some(remaining)).unwrap();
+            match self.stream.read(&mut buf) {
+                Ok(0) => return None,
+                Ok(n) => self.packet_parser.push(&buf[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return None,
+                Err(e) => panic!("Failed to read from test server: {}", e),
+            }
+        }
+
+        let packet = self.packet_parser.pop()?;
+        let msg = String::from_utf8_lossy(&packet);
+        let message = ServerMessage::decode(&msg)
+            .unwrap_or_else(|e| panic!("Malformed message from server {:?}: {}", msg, e));
+        self.apply(&message);
+        Some(message)
+    }
+
+    /// `TIMEOUT` 안에 `matches`가 `Some`을 반환하는 메세지가 올 때까지 다른 메세지는 건너뜀. 오지 않으면 실패
+    pub fn expect<T>(&mut self, what: &str, mut matches: impl FnMut(&ServerMessage) -> Option<T>) -> T {
+        let deadline = Instant::now() + TIMEOUT;
+        while Instant::now() < deadline {
+            let Some(message) = self.recv() else {
+                break;
+            };
+            if let Some(value) = matches(&message) {
+                return value;
+            }
+        }
+        panic!("Client {} did not receive {} within {:?}", self.id, what, TIMEOUT);
+    }
+
+    /// 위치 메세지를 받으면서 `TIMEOUT` 안에 `done`이 참이 되기를 기다림. 되지 않으면 실패
+    pub fn wait_until(&mut self, what: &str, done: impl Fn(&Self) -> bool) {
+        let deadline = Instant::now() + TIMEOUT;
+        while !done(self) {
+            if Instant::now() >= deadline || self.recv().is_none() {
+                panic!("Client {} did not see {} within {:?}", self.id, what, TIMEOUT);
+            }
+        }
+    }
+
+    pub fn position(&self, id: u32) -> Option<(i32, i32)> {
+        self.positions.get(&id).copied()
+    }
+
+    /// 플레이어가 움직일 수 있는 방향 (보드 안이고 벽이나 다른 오브젝트가 없는 칸)
+    pub fn free_direction(&self) -> Option<(i32, i32)> {
+        let (width, height, tiles) = self.map.as_ref()?;
+        let (x, z) = self.position(self.id)?;
+
+        [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter().find(|(dx, dz)| {
+            let (x, z) = (x + dx, z + dz);
+            (0..*width).contains(&x)
+                && (0..*height).contains(&z)
+                && tiles[(z * width + x) as usize] != Tile::Wall
+                && !self.positions.values().any(|position| *position == (x, z))
+        })
+    }
+
+    /// `disconnect`를 보내고 서버가 닫을 때까지 기다림.
+    /// 읽지 않은 데이터가 남은 채 닫으면 RST가 가서 서버가 `disconnect`를 받지 못할 수 있음
+    pub fn disconnect(mut self) {
+        let id = self.id;
+        self.send(&ClientMessage::Disconnect { id });
+        let _ = self.stream.shutdown(Shutdown::Write);
+        while self.recv().is_some() {}
+    }
+
+    fn apply(&mut self, message: &ServerMessage) {
+        match message {
+            ServerMessage::Map { width, height, tiles } => self.map = Some((*width, *height, tiles.clone())),
+            // 높이를 담은 메세지는 위치를 바꾸지 않음
+            ServerMessage::Update { kind, objects, .. } if kind != &UpdateKind::Height => {
+                self.positions = objects.iter().map(|object| (object.id, (object.x, object.z))).collect();
+            },
+            ServerMessage::Delta { changed, removed, .. } => {
+                for object in changed {
+                    self.positions.insert(object.id, (object.x, object.z));
+                }
+                for id in removed {
+                    self.positions.remove(id);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_assigns_unique_ids() {
+        let clients = (0..4).map(|_| MockClient::connect()).collect::<Vec<_>>();
+        let mut ids = clients.iter().map(|client| client.id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), clients.len());
+        assert!(clients.iter().all(|client| client.token.is_some()));
+
+        for client in clients {
+            client.disconnect();
+        }
+    }
+
+    #[test]
+    fn test_ping() {
+        let mut client = MockClient::connect();
+        client.send(&ClientMessage::Ping { stamp: Some(42) });
+        client.expect("pong", |message| (*message == ServerMessage::Pong { stamp: Some(42) }).then_some(()));
+        client.disconnect();
+    }
+
+    #[test]
+    fn test_move_is_reflected_in_next_snapshot() {
+        let mut client = MockClient::connect();
+        let id = client.id;
+        client.wait_until("its own player", |client| client.map.is_some() && client.position(id).is_some());
+
+        let start = client.position(id).unwrap();
+        let (dx, dz) = client.free_direction().expect("player is boxed in");
+        client.send(&ClientMessage::Move { id, x: dx as f32, y: dz as f32 });
+        client.expect("ack", |message| matches!(message, ServerMessage::Ack { seq: 1 }).then_some(()));
+        client.wait_until("the move", |client| client.position(id) == Some((start.0 + dx, start.1 + dz)));
+
+        client.disconnect();
+    }
+
+    #[test]
+    fn test_disconnect_despawns_player() {
+        let mut observer = MockClient::connect();
+        let leaving = MockClient::connect();
+        let id = leaving.id;
+        observer.wait_until("the other player", |observer| observer.position(id).is_some());
+
+        leaving.disconnect();
+        observer.wait_until("the other player leave", |observer| observer.position(id).is_none());
+        observer.disconnect();
+    }
+
+    /// 로비가 아닌 방은 마지막 플레이어가 나가면 닫힘
+    #[test]
+    fn test_empty_room_closes() {
+        let mut client = MockClient::connect();
+        client.send(&ClientMessage::CreateRoom { name: format!("closing-{}", client.id), capacity: 1 });
+        let room = client.expect("joined", |message| match message {
+            ServerMessage::Joined { room } => Some(*room),
+            _ => None,
+        });
+
+        client.send(&ClientMessage::JoinRoom { room: network::LOBBY_ROOM });
+        client.expect("back in the lobby", |message| {
+            (*message == ServerMessage::Joined { room: network::LOBBY_ROOM }).then_some(())
+        });
+        client.send(&ClientMessage::Rooms);
+        let rooms = client.expect("rooms", |message| match message {
+            ServerMessage::Rooms(rooms) => Some(rooms.clone()),
+            _ => None,
+        });
+        assert!(rooms.iter().all(|info| info.id != room));
+
+        client.disconnect();
+    }
+
+    /// 끝난 매치의 방은 닫히므로 `MAX_ROOMS`보다 많은 매치를 이어서 할 수 있음
+    #[test]
+    fn test_back_to_back_matches() {
+        fn match_start(client: &mut MockClient) -> Result<u32, String> {
+            client.expect("match start", |message| match message {
+                ServerMessage::MatchStart { room, .. } => Some(Ok(*room)),
+                ServerMessage::JoinFailed { reason } => Some(Err(reason.clone())),
+                _ => None,
+            })
+        }
+
+        let mut first = MockClient::connect();
+        let mut second = MockClient::connect();
+        for _ in 0..=server::server::room::MAX_ROOMS {
+            // 다음 매치 방에 들어가면 지난 매치 방은 비어서 닫힘
+            first.send(&ClientMessage::Queue);
+            second.send(&ClientMessage::Queue);
+            let room = match_start(&mut first).unwrap();
+            assert_eq!(match_start(&mut second), Ok(room));
+        }
+
+        first.disconnect();
+        second.disconnect();
+    }
+}
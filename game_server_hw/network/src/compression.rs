@@ -0,0 +1,150 @@
+//! 긴 서버 메세지의 deflate 압축.
+//! 압축한 메세지도 한 줄이어야 하므로 `GAMESERVER deflate <원래 길이> <base64>` 형식으로 보냄.
+//! 원래 메세지는 `\n`으로 끝나는 한 줄 이상이며, 풀면 그대로 다시 나옴.
+
+use std::io::{Read, Write};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use super::server_message::{ProtocolError, HEADER};
+
+
+/// 압축한 줄의 명령
+pub const DEFLATE_COMMAND: &str = "deflate";
+
+/// 이 바이트 수보다 긴 메세지만 압축함. 짧은 메세지는 base64로 늘어나는 만큼 손해
+pub const COMPRESSION_THRESHOLD: usize = 256;
+
+/// 풀었을 때 이보다 크다고 알려주면 풀지 않음
+pub const MAX_DECOMPRESSED_LEN: usize = 1 << 20;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+
+/// `encoded`가 `COMPRESSION_THRESHOLD`보다 길고 압축해서 줄어들면 압축한 한 줄, 아니면 `None`
+pub fn compress(encoded: &str) -> Option<String> {
+    if encoded.len() <= COMPRESSION_THRESHOLD {
+        return None;
+    }
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(encoded.as_bytes()).ok()?;
+    let compressed = encoder.finish().ok()?;
+
+    let line = format!("{} {} {} {}\n", HEADER, DEFLATE_COMMAND, encoded.len(), base64_encode(&compressed));
+    (line.len() < encoded.len()).then_some(line)
+}
+
+/// `GAMESERVER deflate`로 시작하는 줄 (줄바꿈 제외)이면 원래 메세지, 아니면 `None`
+pub fn decompress(line: &str) -> Option<Result<String, ProtocolError>> {
+    let payload = line.strip_prefix(HEADER)?.trim_start().strip_prefix(DEFLATE_COMMAND)?;
+    if !payload.is_empty() && !payload.starts_with(' ') {
+        return None;
+    }
+
+    let bad = || ProtocolError::BadCompressed(line.to_string());
+    let mut fields = payload.split_whitespace();
+    let result = (|| {
+        let len = fields.next().ok_or(ProtocolError::MissingField("length"))?;
+        let len = len.parse::<usize>().map_err(|_| ProtocolError::BadInteger(len.to_string()))?;
+        let data = fields.next().ok_or(ProtocolError::MissingField("data"))?;
+        if len > MAX_DECOMPRESSED_LEN {
+            return Err(bad());
+        }
+
+        let compressed = base64_decode(data).ok_or_else(bad)?;
+        let mut decoded = Vec::with_capacity(len);
+        DeflateDecoder::new(compressed.as_slice())
+            .take(len as u64 + 1)
+            .read_to_end(&mut decoded)
+            .map_err(|_| bad())?;
+        if decoded.len() != len {
+            return Err(bad());
+        }
+        String::from_utf8(decoded).map_err(|_| bad())
+    })();
+    Some(result)
+}
+
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => text.push(BASE64[(bits >> (18 - i * 6)) as usize & 0x3f] as char),
+                false => text.push('='),
+            }
+        }
+    }
+    text
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut data = Vec::with_capacity(text.len() / 4 * 3);
+    for (n, chunk) in text.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && n + 1 != text.len() / 4) {
+            return None;
+        }
+
+        let mut bits = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64.iter().position(|&b| b == c)? as u32;
+            bits = bits << 6 | value;
+        }
+        bits <<= 6 * padding as u32;
+        data.extend_from_slice(&bits.to_be_bytes()[1..4 - padding]);
+    }
+    Some(data)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar"] {
+            assert_eq!(base64_decode(&base64_encode(data)).as_deref(), Some(data));
+        }
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_decode("Zm8"), None);
+        assert_eq!(base64_decode("Z=8="), None);
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let objects = (0..100).map(|id| format!("{} 1 {}", id, id % 8)).collect::<Vec<_>>().join(" ");
+        let encoded = format!("GAMESERVER update 1 100 {}\nGAMESERVER names 0\n", objects);
+        let line = compress(&encoded).unwrap();
+        assert!(line.len() < encoded.len());
+        assert_eq!(line.matches('\n').count(), 1);
+        assert_eq!(decompress(line.trim_end()), Some(Ok(encoded)));
+
+        // 짧은 메세지는 그대로
+        assert_eq!(compress("GAMESERVER init 1 16\n"), None);
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        assert_eq!(decompress("GAMESERVER update 1 1 0 0"), None);
+        assert_eq!(decompress("GAMESERVER deflated 1"), None);
+        assert_eq!(decompress("GAMESERVER deflate"), Some(Err(ProtocolError::MissingField("length"))));
+        assert!(matches!(decompress("GAMESERVER deflate 3 !!!!"), Some(Err(ProtocolError::BadCompressed(_)))));
+
+        // 알려준 길이와 다르면 버림
+        let line = compress(&"GAMESERVER chat 1 x\n".repeat(20)).unwrap();
+        let wrong = line.trim_end().replacen(" 400 ", " 399 ", 1);
+        assert!(matches!(decompress(&wrong), Some(Err(ProtocolError::BadCompressed(_)))));
+    }
+}
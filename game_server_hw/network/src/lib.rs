@@ -1,52 +1,100 @@
 mod packet;
-
+pub mod compression;
+pub mod map;
+pub mod message;
+pub mod server_message;
+
+pub use compression::{compress, decompress, COMPRESSION_THRESHOLD};
+pub use map::{GameMap, MapError, Tile};
+pub use message::{ClientMessage, MAX_MOVE_BATCH};
+pub use server_message::{
+    facing_yaw, is_supported_version, EntityKind, ObjectState, ProtocolError, RoomInfo, ScoreEntry, ServerMessage, UpdateKind,
+    COLORS_VERSION, COMPRESSION_VERSION, FACING_VERSION, KINDS_VERSION, LOBBY_ROOM, MAP_VERSION, MATCHMAKING_VERSION,
+    NAME_KEY_VERSION, NAMES_VERSION, PROTOCOL_VERSION, PUSHED_UPDATES_VERSION, ROOMS_VERSION, SCOREBOARD_VERSION, SPECTATOR_VERSION,
+};
 use packet::Packet::{self, *};
 use std::collections::VecDeque;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+
 
+/// `PacketParser::new`의 한 줄 최대 길이 (`\n` 제외)
+pub const MAX_LINE_LEN: usize = 1 << 20;
 
-/// 뭉쳐온 패킷 분리 및 잘린 패킷 이어붙이기를 수행하는 큐 형태의 Parser
+
+/// 뭉쳐온 패킷 분리 및 잘린 패킷 이어붙이기를 수행하는 큐 형태의 Parser.  
+/// `max_line_len`보다 긴 줄은 버리고 다음 `\n`부터 다시 읽음
 pub struct PacketParser {
     queue: VecDeque<Packet>,
+    max_line_len: usize,
+    /// 버리는 줄의 나머지를 다음 `\n`까지 건너뜀
+    discarding: bool,
+    /// 지금까지 버린 줄 수
+    dropped: usize,
+}
+
+impl Default for PacketParser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PacketParser {
     pub fn new() -> Self {
+        Self::with_max_line_len(MAX_LINE_LEN)
+    }
+
+    pub fn with_max_line_len(max_line_len: usize) -> Self {
         Self {
             queue: VecDeque::new(),
+            max_line_len,
+            discarding: false,
+            dropped: 0,
         }
     }
 
+    /// 잘린 줄은 이전에 받은 부분 뒤에 이어붙임 (복사하지 않고 그 버퍼에 추가)
     pub fn push(&mut self, data: &[u8]) {
-        if data.len() == 0 {
-            return;
-        }
+        for piece in data.split_inclusive(|&x| x == b'\n') {
+            let complete = piece.last() == Some(&b'\n');
+            let line = match complete {
+                true => &piece[..piece.len() - 1],
+                false => piece,
+            };
+            if self.discarding {
+                self.discarding = !complete;
+                continue;
+            }
 
-        let mut data: Vec<Bytes> = data.split_inclusive(|&x| x == b'\n')
-            .map(|x| Bytes::copy_from_slice(x))
-            .collect();
+            let mut buf = self.take_incomplete();
+            if buf.len() + line.len() > self.max_line_len {
+                self.dropped += 1;
+                self.discarding = !complete;
+                continue;
+            }
+            buf.extend_from_slice(line);
 
-        if let Some(Incomplete(prev)) = self.queue.back() {
-            data[0] = prev.iter()
-                .chain(data[0].iter())
-                .copied()
-                .collect();
-            self.queue.pop_back();
+            let packet = match complete {
+                true => Complete(buf.freeze()),
+                false => Incomplete(buf.freeze()),
+            };
+            self.queue.push_back(packet);
         }
+    }
 
-        for data in data {
-            self.queue.push_back(self.parse(data));
+    /// 마지막 패킷이 잘린 줄이면 꺼내서 이어붙일 버퍼로 바꿈
+    fn take_incomplete(&mut self) -> BytesMut {
+        if !matches!(self.queue.back(), Some(Incomplete(_))) {
+            return BytesMut::new();
+        }
+        match self.queue.pop_back() {
+            Some(Incomplete(prev)) => BytesMut::from(prev),
+            _ => BytesMut::new(),
         }
     }
 
-    fn parse(&self, data: Bytes) -> Packet {
-        let len = data.len();
-
-        if data[len - 1] != b'\n' {
-            return Incomplete(data);
-        }
-        
-        Complete(data.slice(..len - 1))
+    /// 너무 길어서 버린 줄 수
+    pub fn dropped(&self) -> usize {
+        self.dropped
     }
 
     /// 한개 남았을 때 Incomplete이면 아직 완성 안된것이므로 pop하지 않음.  
@@ -84,7 +132,7 @@ impl PacketParser {
         self.queue.len()
     }
 
-    pub fn iter(&self) -> std::collections::vec_deque::Iter<Packet> {
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, Packet> {
         self.queue.iter()
     }
 }
@@ -132,7 +180,7 @@ mod tests {
         assert_eq!(parser.pop(), Some(b"remove".as_slice().into()));
 
         parser.push(b"update\nupdate\nre");
-        let mut quess = vec![
+        let mut quess = [
             Complete(b"update".as_slice().into()),
             Complete(b"update".as_slice().into()),
             Incomplete(b"re".as_slice().into()),
@@ -160,6 +208,26 @@ mod tests {
         assert_eq!(parser.pop(), Some(b"".as_slice().into()));
     }
 
+    #[test]
+    fn test_long_line() {
+        let mut parser = PacketParser::with_max_line_len(8);
+
+        parser.push(b"update\n0123");
+        parser.push(b"45678");
+        assert_eq!(parser.pop(), Some(b"update".as_slice().into()));
+        assert_eq!(parser.pop(), None);
+        assert_eq!(parser.dropped(), 1);
+
+        // 버린 줄의 나머지는 `\n`까지 건너뜀
+        parser.push(b"9abc\nremove\n");
+        assert_eq!(parser.pop(), Some(b"remove".as_slice().into()));
+        assert!(parser.is_empty());
+
+        parser.push(b"012345678\n01234567\n");
+        assert_eq!(parser.pop(), Some(b"01234567".as_slice().into()));
+        assert_eq!(parser.dropped(), 2);
+    }
+
     #[test]
     fn test_incomplete_pop() {
         let mut parser = PacketParser::new();
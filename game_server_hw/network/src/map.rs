@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+
+use super::server_message::EntityKind;
+
+
+/// 맵 한 변의 최대 칸 수. `map` 메세지가 너무 길어지지 않도록 제한함
+pub const MAX_MAP_SIZE: i32 = 64;
+
+
+/// 보드 칸의 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Floor,
+    /// 아무것도 들어갈 수 없는 칸
+    Wall,
+}
+
+impl Tile {
+    /// `map` 메세지와 맵 파일에서 쓰는 글자
+    pub fn symbol(self) -> char {
+        match self {
+            Tile::Floor => '.',
+            Tile::Wall => '#',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            '.' => Some(Tile::Floor),
+            '#' => Some(Tile::Wall),
+            _ => None,
+        }
+    }
+}
+
+
+/// 맵 파일 형식 오류. 줄과 칸은 0부터
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapError {
+    Empty,
+    TooLarge { width: usize, height: usize },
+    /// 첫 줄과 길이가 다른 줄
+    Ragged { row: usize },
+    BadSymbol { row: usize, col: usize, symbol: char },
+    /// `S`가 두 개 이상
+    MultipleSpawns,
+}
+
+impl std::fmt::Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::Empty => write!(f, "map is empty"),
+            MapError::TooLarge { width, height } => {
+                write!(f, "map is {}x{}, at most {}x{} is allowed", width, height, MAX_MAP_SIZE, MAX_MAP_SIZE)
+            }
+            MapError::Ragged { row } => write!(f, "row {} has a different length", row + 1),
+            MapError::BadSymbol { row, col, symbol } => {
+                write!(f, "unknown symbol {:?} at row {}, column {}", symbol, row + 1, col + 1)
+            }
+            MapError::MultipleSpawns => write!(f, "more than one spawn tile"),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+
+/// 보드의 크기와 칸, 처음 놓는 오브젝트, 플레이어가 생기는 칸.
+///
+/// 맵 파일은 한 줄이 보드의 한 줄(`y`)이고 한 글자가 한 칸(`x`)이며, 빈 줄은 무시함.
+/// `.` 바닥, `#` 벽, `S` 플레이어가 생기는 바닥, `o` 장애물이 있는 바닥, `*` 아이템이 있는 바닥.
+/// `S`가 없으면 가운데 칸에서 생김
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameMap {
+    pub width: i32,
+    pub height: i32,
+    /// `y * width + x` 순서
+    pub tiles: Vec<Tile>,
+    pub spawn: (i32, i32),
+    pub entities: Vec<(EntityKind, i32, i32)>,
+}
+
+impl GameMap {
+    pub fn parse(text: &str) -> Result<Self, MapError> {
+        let rows = text.lines()
+            .map(str::trim_end)
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>();
+        let height = rows.len();
+        let width = rows.first().ok_or(MapError::Empty)?.chars().count();
+        if width > MAX_MAP_SIZE as usize || height > MAX_MAP_SIZE as usize {
+            return Err(MapError::TooLarge { width, height });
+        }
+
+        let mut tiles = Vec::with_capacity(width * height);
+        let mut spawn = None;
+        let mut entities = Vec::new();
+        for (row, line) in rows.iter().enumerate() {
+            if line.chars().count() != width {
+                return Err(MapError::Ragged { row });
+            }
+
+            for (col, symbol) in line.chars().enumerate() {
+                let (x, y) = (col as i32, row as i32);
+                let tile = match symbol {
+                    'S' if spawn.is_some() => return Err(MapError::MultipleSpawns),
+                    'S' => {
+                        spawn = Some((x, y));
+                        Tile::Floor
+                    }
+                    'o' => {
+                        entities.push((EntityKind::Obstacle, x, y));
+                        Tile::Floor
+                    }
+                    '*' => {
+                        entities.push((EntityKind::Item, x, y));
+                        Tile::Floor
+                    }
+                    symbol => Tile::from_symbol(symbol).ok_or(MapError::BadSymbol { row, col, symbol })?,
+                };
+                tiles.push(tile);
+            }
+        }
+
+        let (width, height) = (width as i32, height as i32);
+        Ok(Self {
+            width,
+            height,
+            tiles,
+            spawn: spawn.unwrap_or(((width - 1) / 2, (height - 1) / 2)),
+            entities,
+        })
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (0..self.width).contains(&x) && (0..self.height).contains(&y)
+    }
+
+    /// 보드 밖이면 `None`
+    pub fn tile(&self, x: i32, y: i32) -> Option<Tile> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        self.tiles.get((y * self.width + x) as usize).copied()
+    }
+
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        self.tile(x, y) == Some(Tile::Wall)
+    }
+
+    /// `from`에서 `to`까지 가장 짧은 길의 첫 한 칸 (각 축으로 -1..=1, 대각선 포함).
+    /// 벽과 `blocked`인 칸은 지나가지 않지만 `to`는 `blocked`여도 됨. 이미 도착했거나 길이 없으면 `None`
+    pub fn next_step(&self, from: (i32, i32), to: (i32, i32), blocked: impl Fn((i32, i32)) -> bool) -> Option<(i32, i32)> {
+        if from == to || !self.contains(to.0, to.1) || self.is_wall(to.0, to.1) {
+            return None;
+        }
+
+        // `to`에서 거꾸로 넓혀가며 각 칸에서 `to`까지의 거리를 잼
+        let mut distance = vec![u32::MAX; self.tiles.len()];
+        let index = |(x, y): (i32, i32)| (y * self.width + x) as usize;
+        distance[index(to)] = 0;
+        let mut queue = VecDeque::from([to]);
+        while let Some(tile) = queue.pop_front() {
+            let next = distance[index(tile)] + 1;
+            for neighbour in neighbours(tile) {
+                if neighbour == from {
+                    return Some((tile.0 - from.0, tile.1 - from.1));
+                }
+                let passable = self.contains(neighbour.0, neighbour.1)
+                    && !self.is_wall(neighbour.0, neighbour.1)
+                    && !blocked(neighbour);
+                if passable && distance[index(neighbour)] == u32::MAX {
+                    distance[index(neighbour)] = next;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// 가로세로를 먼저, 대각선을 나중에. 거리가 같으면 곧은 길을 고름
+fn neighbours((x, y): (i32, i32)) -> [(i32, i32); 8] {
+    [
+        (x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1),
+        (x + 1, y + 1), (x - 1, y + 1), (x + 1, y - 1), (x - 1, y - 1),
+    ]
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let map = GameMap::parse("\n..#\n.S*\n\no..\n").unwrap();
+        assert_eq!((map.width, map.height), (3, 3));
+        assert_eq!(map.spawn, (1, 1));
+        assert_eq!(map.tile(2, 0), Some(Tile::Wall));
+        assert_eq!(map.tile(2, 1), Some(Tile::Floor));
+        assert_eq!(map.tile(3, 0), None);
+        assert!(map.is_wall(2, 0) && !map.is_wall(-1, 0));
+        assert_eq!(map.entities, [(EntityKind::Item, 2, 1), (EntityKind::Obstacle, 0, 2)]);
+
+        // 생기는 칸이 없으면 가운데
+        assert_eq!(GameMap::parse("....\n....").unwrap().spawn, (1, 0));
+    }
+
+    #[test]
+    fn test_next_step() {
+        let map = GameMap::parse("\
+.#..
+.#..
+....
+").unwrap();
+        // 벽을 돌아서 감
+        assert_eq!(map.next_step((0, 0), (2, 0), |_| false), Some((0, 1)));
+        assert_eq!(map.next_step((0, 2), (3, 2), |_| false), Some((1, 0)));
+        assert_eq!(map.next_step((1, 2), (1, 2), |_| false), None);
+        assert_eq!(map.next_step((0, 0), (1, 0), |_| false), None);
+
+        // 막힌 칸은 피하고, 막혀서 길이 없으면 `None`
+        assert_eq!(map.next_step((0, 2), (2, 2), |tile| tile == (1, 2)), None);
+        assert_eq!(map.next_step((2, 2), (2, 0), |tile| tile == (2, 1)), Some((1, -1)));
+        assert_eq!(map.next_step((0, 0), (0, 2), |tile| tile == (0, 1)), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(GameMap::parse(" \n"), Err(MapError::Empty));
+        assert_eq!(GameMap::parse("...\n..\n"), Err(MapError::Ragged { row: 1 }));
+        assert_eq!(GameMap::parse("..\n.x"), Err(MapError::BadSymbol { row: 1, col: 1, symbol: 'x' }));
+        assert_eq!(GameMap::parse("S.\n.S"), Err(MapError::MultipleSpawns));
+        assert_eq!(
+            GameMap::parse(&".".repeat(MAX_MAP_SIZE as usize + 1)),
+            Err(MapError::TooLarge { width: MAX_MAP_SIZE as usize + 1, height: 1 })
+        );
+    }
+}
@@ -0,0 +1,272 @@
+/// `moves` 한 번에 담을 수 있는 최대 이동 수. 넘으면 디코딩하지 않음
+pub const MAX_MOVE_BATCH: usize = 64;
+
+
+/// 클라이언트 -> 서버 메세지
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    /// 이동 방향. 각 축의 값은 -1.0..=1.0 크기(아날로그 입력)를 가질 수 있음.
+    /// 키보드 입력은 항상 ±1
+    Move { id: u32, x: f32, y: f32 },
+    /// 여러 tick 동안 모은 이동 방향. 서버는 순서대로 적용함.
+    /// `seq`는 첫 이동의 일련번호. 최대 `MAX_MOVE_BATCH`개
+    MoveBatch { id: u32, seq: u32, moves: Vec<(f32, f32)> },
+    /// 보드 칸 `(x, z)`까지 서버가 길을 찾아 한 칸씩 옮김. 이동 방향을 보내면 멈춤
+    MoveTo { x: i32, z: i32 },
+    Update,
+    /// 연결 확인. `stamp`는 서버가 `pong`에 그대로 돌려주는 값으로, 왕복 시간 측정에 사용
+    Ping { stamp: Option<u64> },
+    /// 전체 상태(`keyframe`) 요청
+    Resync,
+    /// 클라이언트 종료. 서버는 플레이어를 바로 제거함
+    Disconnect { id: u32 },
+    /// 채팅 메세지. `text`에는 공백이 들어갈 수 있음
+    Chat { id: u32, text: String },
+    /// 다시 접속한 뒤 끊기기 전의 플레이어 `id`를 `init`에서 받은 `token`으로 이어서 사용
+    Resume { id: u32, token: u64 },
+    /// UDP로 보내는 `update` 요청. 서버는 `init`에서 준 `token`을 확인하고 UDP로 응답함
+    DatagramUpdate { id: u32, token: u64 },
+    /// 방 목록 요청
+    Rooms,
+    /// 방을 만들고 들어감. `name`의 공백은 `_`로 바뀌어 나감
+    CreateRoom { name: String, capacity: usize },
+    /// 다른 방으로 옮김. 원래 방의 플레이어는 사라지고 새 방에 새로 생김
+    JoinRoom { room: u32 },
+    /// `init`을 받은 뒤 보내는 이름. 서버가 허용하지 않는 글자를 지우고 겹치지 않게 바꿀 수 있음.
+    /// 이 이름의 저장된 상태는 처음 저장할 때와 같은 `key`로만 이어받음 (`NAME_KEY_VERSION`)
+    Name { name: String, key: Option<u64> },
+    /// `init`을 받은 뒤 보내면 서버가 플레이어의 색을 `colors`로 알려줌
+    Colors,
+    /// `init`을 받은 뒤 보내면 서버가 오브젝트의 종류를 `spawn`으로 알려줌
+    Kinds,
+    /// `init`을 받은 뒤 보내면 서버가 점수판을 `scoreboard`로 주기적으로 보냄
+    Scoreboard,
+    /// 매치 대기열에 들어감. 서버가 인원이 모이면 새 방을 만들어 `match_start`로 알려줌
+    Queue,
+    /// 매치 대기열에서 나옴
+    Unqueue,
+    /// `init`을 받은 뒤 보내면 플레이어 없이 관전함. 서버는 관심 영역과 상관없이 방의 모든 오브젝트를 보냄
+    Spectate,
+    /// `init`을 받은 뒤 보내면 서버가 `COMPRESSION_THRESHOLD`보다 긴 메세지를 `deflate`로 압축해서 보냄
+    Compress,
+}
+
+impl ClientMessage {
+    /// `\n`으로 끝나는 한 줄로 인코딩.
+    /// 정수 크기 이동은 기존 형식(`move 0 1 0`) 그대로 나감.
+    pub fn encode(&self) -> String {
+        match self {
+            ClientMessage::Move { id, x, y } => format!("move {} {} {}\n", id, x, y),
+            // moves <id> <seq> <개수> <x> <y> [<x> <y> ...]
+            ClientMessage::MoveBatch { id, seq, moves } => {
+                let mut msg = format!("moves {} {} {}", id, seq, moves.len());
+                for (x, y) in moves {
+                    msg.push_str(&format!(" {} {}", x, y));
+                }
+                msg.push('\n');
+                msg
+            }
+            ClientMessage::MoveTo { x, z } => format!("move_to {} {}\n", x, z),
+            ClientMessage::Update => "update\n".to_string(),
+            ClientMessage::Ping { stamp: Some(stamp) } => format!("ping {}\n", stamp),
+            ClientMessage::Ping { stamp: None } => "ping\n".to_string(),
+            ClientMessage::Resync => "resync\n".to_string(),
+            ClientMessage::Disconnect { id } => format!("disconnect {}\n", id),
+            ClientMessage::Resume { id, token } => format!("resume {} {}\n", id, token),
+            ClientMessage::DatagramUpdate { id, token } => format!("update {} {}\n", id, token),
+            ClientMessage::Rooms => "rooms\n".to_string(),
+            ClientMessage::CreateRoom { name, capacity } => {
+                format!("create {} {}\n", capacity, name.split_whitespace().collect::<Vec<_>>().join("_"))
+            }
+            ClientMessage::JoinRoom { room } => format!("join {}\n", room),
+            ClientMessage::Name { name, key } => {
+                let name = name.split_whitespace().collect::<Vec<_>>().join("_");
+                match key {
+                    Some(key) => format!("name {} {}\n", name, key),
+                    None => format!("name {}\n", name),
+                }
+            }
+            ClientMessage::Colors => "colors\n".to_string(),
+            ClientMessage::Kinds => "kinds\n".to_string(),
+            ClientMessage::Scoreboard => "scoreboard\n".to_string(),
+            ClientMessage::Queue => "queue\n".to_string(),
+            ClientMessage::Unqueue => "unqueue\n".to_string(),
+            ClientMessage::Spectate => "spectate\n".to_string(),
+            ClientMessage::Compress => "compress\n".to_string(),
+            // 줄바꿈은 메세지 구분자이므로 공백으로 바꿈
+            ClientMessage::Chat { id, text } => format!("chat {} {}\n", id, text.replace(['\r', '\n'], " ")),
+        }
+    }
+
+    /// `\n`이 제거된 한 줄을 디코딩. 형식이 맞지 않으면 `None`
+    pub fn decode(msg: &str) -> Option<Self> {
+        // 채팅은 본문의 공백을 그대로 유지
+        if let Some(rest) = msg.strip_prefix("chat ") {
+            let (id, text) = rest.split_once(' ')?;
+            if text.trim().is_empty() {
+                return None;
+            }
+            return Some(ClientMessage::Chat { id: id.parse().ok()?, text: text.to_string() });
+        }
+
+        let msg = msg.split_whitespace()
+            .collect::<Vec<&str>>();
+
+        match msg.as_slice() {
+            ["move", id, x, y] => Some(ClientMessage::Move {
+                id: id.parse().ok()?,
+                x: parse_axis(x)?,
+                y: parse_axis(y)?,
+            }),
+            ["moves", id, seq, count, rest @ ..] => {
+                let count = count.parse::<usize>().ok().filter(|&count| count <= MAX_MOVE_BATCH)?;
+                if Some(rest.len()) != count.checked_mul(2) {
+                    return None;
+                }
+
+                Some(ClientMessage::MoveBatch {
+                    id: id.parse().ok()?,
+                    seq: seq.parse().ok()?,
+                    moves: rest.chunks_exact(2)
+                        .map(|pair| Some((parse_axis(pair[0])?, parse_axis(pair[1])?)))
+                        .collect::<Option<Vec<_>>>()?,
+                })
+            }
+            ["move_to", x, z] => Some(ClientMessage::MoveTo { x: x.parse().ok()?, z: z.parse().ok()? }),
+            ["update"] => Some(ClientMessage::Update),
+            ["update", id, token] => Some(ClientMessage::DatagramUpdate { id: id.parse().ok()?, token: token.parse().ok()? }),
+            ["ping"] => Some(ClientMessage::Ping { stamp: None }),
+            ["ping", stamp] => Some(ClientMessage::Ping { stamp: Some(stamp.parse().ok()?) }),
+            ["resync"] => Some(ClientMessage::Resync),
+            ["disconnect", id] => Some(ClientMessage::Disconnect { id: id.parse().ok()? }),
+            ["resume", id, token] => Some(ClientMessage::Resume { id: id.parse().ok()?, token: token.parse().ok()? }),
+            ["rooms"] => Some(ClientMessage::Rooms),
+            ["create", capacity, name] => Some(ClientMessage::CreateRoom { name: name.to_string(), capacity: capacity.parse().ok()? }),
+            ["join", room] => Some(ClientMessage::JoinRoom { room: room.parse().ok()? }),
+            ["name", name] => Some(ClientMessage::Name { name: name.to_string(), key: None }),
+            ["name", name, key] => Some(ClientMessage::Name { name: name.to_string(), key: Some(key.parse().ok()?) }),
+            ["colors"] => Some(ClientMessage::Colors),
+            ["kinds"] => Some(ClientMessage::Kinds),
+            ["scoreboard"] => Some(ClientMessage::Scoreboard),
+            ["queue"] => Some(ClientMessage::Queue),
+            ["unqueue"] => Some(ClientMessage::Unqueue),
+            ["spectate"] => Some(ClientMessage::Spectate),
+            ["compress"] => Some(ClientMessage::Compress),
+            _ => None,
+        }
+    }
+}
+
+fn parse_axis(s: &str) -> Option<f32> {
+    s.parse::<f32>().ok().filter(|v| v.is_finite())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_move() {
+        let msg = ClientMessage::Move { id: 3, x: 1.0, y: -1.0 };
+        assert_eq!(msg.encode(), "move 3 1 -1\n");
+
+        let msg = ClientMessage::Move { id: 3, x: 0.5, y: -0.25 };
+        assert_eq!(msg.encode(), "move 3 0.5 -0.25\n");
+        assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+    }
+
+    #[test]
+    fn test_encode_resync() {
+        assert_eq!(ClientMessage::Resync.encode(), "resync\n");
+        assert_eq!(ClientMessage::decode("resync"), Some(ClientMessage::Resync));
+        assert_eq!(ClientMessage::decode("disconnect 4"), Some(ClientMessage::Disconnect { id: 4 }));
+
+        let msg = ClientMessage::Resume { id: 4, token: 1 << 40 };
+        assert_eq!(msg.encode(), "resume 4 1099511627776\n");
+        assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+
+        let msg = ClientMessage::DatagramUpdate { id: 4, token: 7 };
+        assert_eq!(msg.encode(), "update 4 7\n");
+        assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+    }
+
+    #[test]
+    fn test_room_round_trip() {
+        for msg in [
+            ClientMessage::Rooms,
+            ClientMessage::CreateRoom { name: "arena".to_string(), capacity: 4 },
+            ClientMessage::JoinRoom { room: 2 },
+            ClientMessage::Name { name: "alice".to_string(), key: None },
+            ClientMessage::Name { name: "alice".to_string(), key: Some(u64::MAX) },
+            ClientMessage::Colors,
+            ClientMessage::Kinds,
+            ClientMessage::Scoreboard,
+            ClientMessage::Queue,
+            ClientMessage::Unqueue,
+            ClientMessage::Spectate,
+            ClientMessage::Compress,
+        ] {
+            assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+        }
+
+        let msg = ClientMessage::CreateRoom { name: " my  room ".to_string(), capacity: 4 };
+        assert_eq!(msg.encode(), "create 4 my_room\n");
+        assert_eq!(ClientMessage::decode("create 4"), None);
+        assert_eq!(ClientMessage::decode("create many arena"), None);
+        assert_eq!(ClientMessage::Name { name: "big bob".to_string(), key: Some(7) }.encode(), "name big_bob 7\n");
+        assert_eq!(ClientMessage::decode("name"), None);
+        assert_eq!(ClientMessage::decode("name alice secret"), None);
+    }
+
+    #[test]
+    fn test_move_batch_round_trip() {
+        let msg = ClientMessage::MoveBatch { 
+            id: 2, 
+            seq: 10, 
+            moves: vec![(1.0, 0.0), (0.0, -1.0), (0.5, 0.5)],
+        };
+        assert_eq!(msg.encode(), "moves 2 10 3 1 0 0 -1 0.5 0.5\n");
+        assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+
+        assert_eq!(ClientMessage::decode("moves 2 10 2 1 0"), None);
+        assert_eq!(ClientMessage::decode("moves 1 1 18446744073709551615"), None);
+        let too_many = format!("moves 1 1 {}{}", MAX_MOVE_BATCH + 1, " 1 0".repeat(MAX_MOVE_BATCH + 1));
+        assert_eq!(ClientMessage::decode(&too_many), None);
+
+        let msg = ClientMessage::MoveTo { x: 3, z: -1 };
+        assert_eq!(msg.encode(), "move_to 3 -1\n");
+        assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+        assert_eq!(ClientMessage::decode("move_to 3 0.5"), None);
+    }
+
+    #[test]
+    fn test_chat_round_trip() {
+        let msg = ClientMessage::Chat { id: 1, text: "hello  there".to_string() };
+        assert_eq!(msg.encode(), "chat 1 hello  there\n");
+        assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+
+        let msg = ClientMessage::Chat { id: 1, text: "two\nlines".to_string() };
+        assert_eq!(msg.encode(), "chat 1 two lines\n");
+
+        assert_eq!(ClientMessage::decode("chat 1"), None);
+        assert_eq!(ClientMessage::decode("chat 1  "), None);
+        assert_eq!(ClientMessage::decode("chat x hi"), None);
+    }
+
+    #[test]
+    fn test_ping_round_trip() {
+        for msg in [ClientMessage::Ping { stamp: Some(1500) }, ClientMessage::Ping { stamp: None }] {
+            assert_eq!(ClientMessage::decode(msg.encode().trim_end()), Some(msg));
+        }
+        assert_eq!(ClientMessage::decode("ping soon"), None);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(ClientMessage::decode("move 3 1"), None);
+        assert_eq!(ClientMessage::decode("move 3 NaN 0"), None);
+        assert_eq!(ClientMessage::decode("jump"), None);
+    }
+}
@@ -0,0 +1,851 @@
+use super::map::{Tile, MAX_MAP_SIZE};
+
+
+/// 서버가 보내는 모든 줄의 첫 단어
+pub const HEADER: &str = "GAMESERVER";
+
+/// 메세지 형식이 바뀌면 올림. 서버는 `init`에 함께 보냄
+pub const PROTOCOL_VERSION: u32 = 17;
+
+/// 이 버전부터 서버가 요청 없이 주기적으로 위치를 보냄
+pub const PUSHED_UPDATES_VERSION: u32 = 4;
+
+/// 이 버전부터 서버에 방(`rooms`, `create`, `join`)이 있음. 접속하면 먼저 로비 방에 들어감
+pub const ROOMS_VERSION: u32 = 5;
+
+/// 로비 방의 id
+pub const LOBBY_ROOM: u32 = 0;
+
+/// 이 버전부터 `init` 뒤에 클라이언트가 `name`으로 이름을 등록하고, 서버가 `names`로 이름을 알려줌
+pub const NAMES_VERSION: u32 = 6;
+
+/// 이 버전부터 클라이언트가 `colors`를 보내면 서버가 플레이어마다 정한 색을 `colors`로 알려줌
+pub const COLORS_VERSION: u32 = 7;
+
+/// 이 버전부터 클라이언트가 `kinds`를 보내면 서버가 새로 보이는 오브젝트의 종류를 `spawn`으로 알려줌
+pub const KINDS_VERSION: u32 = 8;
+
+/// 이 버전부터 클라이언트가 `scoreboard`를 보내면 서버가 방의 점수판을 주기적으로 보냄
+pub const SCOREBOARD_VERSION: u32 = 10;
+
+/// 이 버전부터 서버가 `init` 바로 뒤에 보드의 크기와 칸을 `map`으로 알려줌. 이전 서버의 보드는 8x8 바닥
+pub const MAP_VERSION: u32 = 12;
+
+/// 이 버전부터 클라이언트가 `queue`로 매치 대기열에 들어가고, 서버가 `queued`와 `match_start`로 알려줌
+pub const MATCHMAKING_VERSION: u32 = 13;
+
+/// 이 버전부터 클라이언트가 `spectate`를 보내면 플레이어 없이 방 전체를 관전함
+pub const SPECTATOR_VERSION: u32 = 14;
+
+/// 이 버전부터 서버가 오브젝트가 마지막으로 움직인 방향을 `update_rot`, `keyframe_rot`, `delta_rot`의 yaw로 보냄
+pub const FACING_VERSION: u32 = 15;
+
+/// 이 버전부터 클라이언트가 `compress`를 보내면 서버가 긴 메세지를 `deflate`로 압축해서 보냄 (`compression`)
+pub const COMPRESSION_VERSION: u32 = 16;
+
+/// 이 버전부터 `name`에 열쇠를 붙일 수 있음. 서버는 같은 열쇠로 저장한 이름의 상태만 돌려줌
+pub const NAME_KEY_VERSION: u32 = 17;
+
+/// `init`으로 받은 서버 버전을 이 버전의 클라이언트가 읽을 수 있는지.
+/// 버전을 보내지 않는 이전 서버와 낮은 버전은 위의 버전별 기능으로 맞추지만, 높은 버전은 모르는 형식을 보낼 수 있음
+pub fn is_supported_version(version: Option<u32>) -> bool {
+    version.is_none_or(|version| version <= PROTOCOL_VERSION)
+}
+
+
+/// 서버 메세지 형식 오류
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolError {
+    /// `GAMESERVER`로 시작하지 않음
+    BadHeader(String),
+    /// 필요한 필드가 없음
+    MissingField(&'static str),
+    BadInteger(String),
+    BadNumber(String),
+    BadColor(String),
+    BadKind(String),
+    BadTile(String),
+    /// 알려준 개수와 실제 항목 수가 다름
+    CountMismatch { expected: usize, found: usize },
+    UnknownCommand(String),
+    /// `deflate`를 풀 수 없음
+    BadCompressed(String),
+    /// 맵이 `MAX_MAP_SIZE`보다 큼
+    MapTooLarge { width: i32, height: i32 },
+}
+
+impl std::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::BadHeader(header) => write!(f, "bad header {:?}", header),
+            ProtocolError::MissingField(field) => write!(f, "missing field `{}`", field),
+            ProtocolError::BadInteger(value) => write!(f, "bad integer {:?}", value),
+            ProtocolError::BadNumber(value) => write!(f, "bad number {:?}", value),
+            ProtocolError::BadColor(value) => write!(f, "bad color {:?}", value),
+            ProtocolError::BadKind(value) => write!(f, "bad entity kind {:?}", value),
+            ProtocolError::BadTile(value) => write!(f, "bad map row {:?}", value),
+            ProtocolError::CountMismatch { expected, found } => {
+                write!(f, "expected {} entries, found {}", expected, found)
+            }
+            ProtocolError::UnknownCommand(command) => write!(f, "unknown command {:?}", command),
+            ProtocolError::BadCompressed(line) => write!(f, "bad compressed message {:?}", line),
+            ProtocolError::MapTooLarge { width, height } => {
+                write!(f, "map is {}x{}, at most {}x{} is allowed", width, height, MAX_MAP_SIZE, MAX_MAP_SIZE)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+
+/// 위치 메세지의 종류. 오브젝트 하나의 필드 구성이 다름
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateKind {
+    /// `update`: `id x z`
+    Flat,
+    /// `update3`: `id x y z`
+    Height,
+    /// `update_rot`: `id x z yaw`. yaw는 y축 회전(도)
+    Rotation,
+    /// `keyframe`: `update`와 같은 형식이지만 클라이언트의 오브젝트를 모두 교체함
+    Keyframe,
+    /// `keyframe_rot`: `update_rot`와 같은 형식의 `keyframe`
+    RotationKeyframe,
+}
+
+impl UpdateKind {
+    fn command(self) -> &'static str {
+        match self {
+            UpdateKind::Flat => "update",
+            UpdateKind::Height => "update3",
+            UpdateKind::Rotation => "update_rot",
+            UpdateKind::Keyframe => "keyframe",
+            UpdateKind::RotationKeyframe => "keyframe_rot",
+        }
+    }
+
+    /// 클라이언트의 오브젝트를 모두 교체함
+    pub fn is_keyframe(self) -> bool {
+        matches!(self, UpdateKind::Keyframe | UpdateKind::RotationKeyframe)
+    }
+
+    /// 오브젝트 하나의 필드 수
+    fn stride(self) -> usize {
+        match self {
+            UpdateKind::Height | UpdateKind::Rotation | UpdateKind::RotationKeyframe => 4,
+            UpdateKind::Flat | UpdateKind::Keyframe => 3,
+        }
+    }
+}
+
+
+/// 오브젝트의 종류. `spawn`을 받지 못한 오브젝트는 `Player`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EntityKind {
+    #[default]
+    Player,
+    Npc,
+    Item,
+    Obstacle,
+}
+
+impl EntityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntityKind::Player => "player",
+            EntityKind::Npc => "npc",
+            EntityKind::Item => "item",
+            EntityKind::Obstacle => "obstacle",
+        }
+    }
+}
+
+impl std::str::FromStr for EntityKind {
+    type Err = ProtocolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "player" => Ok(EntityKind::Player),
+            "npc" => Ok(EntityKind::Npc),
+            "item" => Ok(EntityKind::Item),
+            "obstacle" => Ok(EntityKind::Obstacle),
+            _ => Err(ProtocolError::BadKind(s.to_string())),
+        }
+    }
+}
+
+
+/// 위치 메세지의 오브젝트 하나. 형식에 없는 값은 `y`가 0, `yaw`가 `None`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectState {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub yaw: Option<f32>,
+}
+
+/// 보드에서 `(dx, dz)`만큼 움직인 오브젝트의 yaw (도). 0이면 +z 쪽, 90이면 +x 쪽
+pub fn facing_yaw(dx: i32, dz: i32) -> f32 {
+    (dx as f32).atan2(dz as f32).to_degrees().round()
+}
+
+
+/// 점수판의 플레이어 하나. `name`에는 공백이 없음
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreEntry {
+    pub id: u32,
+    pub name: String,
+    pub score: u32,
+    pub captures: u32,
+    pub items: u32,
+    /// 마지막으로 잡힌 (또는 들어온) 뒤 지난 시간 (초)
+    pub survival: u32,
+}
+
+
+/// 방 목록의 방 하나. `name`에는 공백이 없음
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomInfo {
+    pub id: u32,
+    pub name: String,
+    pub players: usize,
+    pub capacity: usize,
+}
+
+
+/// 서버 -> 클라이언트 메세지
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    /// 접속한 클라이언트의 플레이어 id. 이전 서버는 `version`을 보내지 않음.
+    /// `token`은 다시 접속했을 때 `resume`으로 같은 플레이어를 이어서 쓰기 위한 값이며 `version`이 있을 때만 보냄
+    Init { id: u32, version: Option<u32>, token: Option<u64> },
+    /// 오브젝트 위치. `seq`가 있으면 클라이언트는 마지막으로 적용한 것보다 큰 것만 적용함
+    Update { kind: UpdateKind, seq: Option<u32>, objects: Vec<ObjectState> },
+    /// 이전 위치 메세지 이후 바뀐 것만 보냄. `changed`는 새로 생기거나 움직인 오브젝트(`update` 형식),
+    /// `removed`는 사라진 id. 나머지 오브젝트는 그대로 유지
+    Delta { seq: u32, changed: Vec<ObjectState>, removed: Vec<u32> },
+    /// 채팅. `name`이 없으면 `sender` id로 표시
+    Chat { sender: u32, name: Option<String>, text: String },
+    /// 배경색 (16진수 색상 문자열)
+    BgColor(String),
+    /// 색을 바꿀 타일 번호와 16진수 색상 문자열
+    Tiles(Vec<(usize, String)>),
+    /// 서버가 `seq` 전까지의 이동을 모두 처리함
+    Ack { seq: u32 },
+    /// `ping`의 응답. `stamp`는 `ping`에서 받은 값
+    Pong { stamp: Option<u64> },
+    /// `rooms` 요청의 응답
+    Rooms(Vec<RoomInfo>),
+    /// `create`나 `join`으로 방에 들어감. 이후 위치 메세지는 이 방의 오브젝트만 담음
+    Joined { room: u32 },
+    /// 방에 들어가지 못한 이유. 클라이언트는 원래 있던 방에 그대로 있음
+    JoinFailed { reason: String },
+    /// 보이는 플레이어의 id와 이름 전체. 목록이 바뀔 때만 보냄 (`name`을 보낸 클라이언트에만)
+    Names(Vec<(u32, String)>),
+    /// 보이는 플레이어의 id와 16진수 색상 문자열 전체. 목록이 바뀔 때만 보냄 (`colors`를 보낸 클라이언트에만)
+    Colors(Vec<(u32, String)>),
+    /// 새로 보이게 된 오브젝트의 id와 종류 (`kinds`를 보낸 클라이언트에만).
+    /// 같은 틱의 위치 메세지보다 먼저 보내므로 클라이언트는 처음부터 맞는 모델로 만들 수 있음
+    Spawn(Vec<(u32, EntityKind)>),
+    /// `by`가 `target`을 잡음. `target`은 다른 칸으로 옮겨지며 다음 위치 메세지에 반영됨
+    Capture { by: u32, target: u32 },
+    /// `player`가 `item`을 주움. 아이템은 다음 위치 메세지에서 사라짐
+    Pickup { player: u32, item: u32 },
+    /// 방에 있는 플레이어의 점수 (점수가 높은 순서)
+    Scoreboard(Vec<ScoreEntry>),
+    /// 보드의 크기와 칸 (`y * width + x` 순서)
+    Map { width: i32, height: i32, tiles: Vec<Tile> },
+    /// 매치 대기열에서 기다리는 인원과 매치 한 번의 인원. 대기열이 바뀔 때마다 보냄
+    Queued { waiting: usize, size: usize },
+    /// 매치가 잡혀 `room`에 들어감 (`joined` 뒤에 보냄). `players`는 함께 들어간 플레이어 id
+    MatchStart { room: u32, players: Vec<u32> },
+    /// 서버가 곧 종료함. 이어서 연결을 닫으며, 클라이언트는 다시 접속하지 않아도 됨
+    Closing,
+}
+
+impl ServerMessage {
+    /// 헤더를 붙이고 `\n`으로 끝나는 한 줄로 인코딩
+    pub fn encode(&self) -> String {
+        let body = match self {
+            ServerMessage::Init { id, version: Some(version), token: Some(token) } => {
+                format!("init {} {} {}", id, version, token)
+            }
+            ServerMessage::Init { id, version: Some(version), token: None } => format!("init {} {}", id, version),
+            ServerMessage::Init { id, version: None, .. } => format!("init {}", id),
+            // <command> [<seq>] <개수> <오브젝트...>
+            ServerMessage::Update { kind, seq, objects } => {
+                let mut msg = kind.command().to_string();
+                if let Some(seq) = seq {
+                    msg.push_str(&format!(" {}", seq));
+                }
+                msg.push_str(&format!(" {}", objects.len()));
+                for object in objects {
+                    let ObjectState { id, x, y, z, yaw } = object;
+                    match kind {
+                        UpdateKind::Flat | UpdateKind::Keyframe => msg.push_str(&format!(" {} {} {}", id, x, z)),
+                        UpdateKind::Height => msg.push_str(&format!(" {} {} {} {}", id, x, y, z)),
+                        UpdateKind::Rotation | UpdateKind::RotationKeyframe => {
+                            msg.push_str(&format!(" {} {} {} {}", id, x, z, yaw.unwrap_or(0.0)))
+                        }
+                    }
+                }
+                msg
+            }
+            // delta <seq> <개수> <id x z...> <개수> <id...>.
+            // 바뀐 오브젝트에 회전이 있으면 delta_rot <seq> <개수> <id x z yaw...> <개수> <id...>
+            ServerMessage::Delta { seq, changed, removed } => {
+                let rotation = changed.iter().any(|object| object.yaw.is_some());
+                let command = if rotation { "delta_rot" } else { "delta" };
+                let mut msg = format!("{} {} {}", command, seq, changed.len());
+                for ObjectState { id, x, z, yaw, .. } in changed {
+                    match rotation {
+                        true => msg.push_str(&format!(" {} {} {} {}", id, x, z, yaw.unwrap_or(0.0))),
+                        false => msg.push_str(&format!(" {} {} {}", id, x, z)),
+                    }
+                }
+                msg.push_str(&format!(" {}", removed.len()));
+                for id in removed {
+                    msg.push_str(&format!(" {}", id));
+                }
+                msg
+            }
+            // 줄바꿈은 메세지 구분자이므로 공백으로 바꿈
+            ServerMessage::Chat { sender, name, text } => format!(
+                "chat {} {} {}",
+                sender,
+                name.as_deref().unwrap_or("-"),
+                text.replace(['\r', '\n'], " ")
+            ),
+            ServerMessage::BgColor(color) => format!("bgcolor {}", color),
+            ServerMessage::Tiles(tiles) => {
+                let mut msg = "tiles".to_string();
+                for (idx, color) in tiles {
+                    msg.push_str(&format!(" {} {}", idx, color));
+                }
+                msg
+            }
+            ServerMessage::Ack { seq } => format!("ack {}", seq),
+            ServerMessage::Pong { stamp: Some(stamp) } => format!("pong {}", stamp),
+            ServerMessage::Pong { stamp: None } => "pong".to_string(),
+            // rooms <개수> <id players capacity name...>
+            ServerMessage::Rooms(rooms) => {
+                let mut msg = format!("rooms {}", rooms.len());
+                for RoomInfo { id, name, players, capacity } in rooms {
+                    msg.push_str(&format!(" {} {} {} {}", id, players, capacity, name));
+                }
+                msg
+            }
+            ServerMessage::Joined { room } => format!("joined {}", room),
+            ServerMessage::JoinFailed { reason } => format!("join_failed {}", reason.replace(['\r', '\n'], " ")),
+            // names <개수> <id name...>
+            ServerMessage::Names(names) => {
+                let mut msg = format!("names {}", names.len());
+                for (id, name) in names {
+                    msg.push_str(&format!(" {} {}", id, name));
+                }
+                msg
+            }
+            // colors <개수> <id color...>
+            ServerMessage::Colors(colors) => {
+                let mut msg = format!("colors {}", colors.len());
+                for (id, color) in colors {
+                    msg.push_str(&format!(" {} {}", id, color));
+                }
+                msg
+            }
+            ServerMessage::Capture { by, target } => format!("capture {} {}", by, target),
+            ServerMessage::Pickup { player, item } => format!("pickup {} {}", player, item),
+            // scoreboard <개수> <id score captures items survival name...>
+            ServerMessage::Scoreboard(entries) => {
+                let mut msg = format!("scoreboard {}", entries.len());
+                for ScoreEntry { id, name, score, captures, items, survival } in entries {
+                    msg.push_str(&format!(" {} {} {} {} {} {}", id, score, captures, items, survival, name));
+                }
+                msg
+            }
+            // spawn <개수> <id kind...>
+            ServerMessage::Spawn(entities) => {
+                let mut msg = format!("spawn {}", entities.len());
+                for (id, kind) in entities {
+                    msg.push_str(&format!(" {} {}", id, kind.as_str()));
+                }
+                msg
+            }
+            ServerMessage::Queued { waiting, size } => format!("queued {} {}", waiting, size),
+            ServerMessage::Closing => "server_closing".to_string(),
+            // match_start <room> <개수> <id...>
+            ServerMessage::MatchStart { room, players } => {
+                let mut msg = format!("match_start {} {}", room, players.len());
+                for id in players {
+                    msg.push_str(&format!(" {}", id));
+                }
+                msg
+            }
+            // map <width> <height> <줄...>. 한 줄은 칸마다 한 글자
+            ServerMessage::Map { width, height, tiles } => {
+                let mut msg = format!("map {} {}", width, height);
+                for row in tiles.chunks((*width).max(1) as usize) {
+                    msg.push(' ');
+                    msg.extend(row.iter().map(|tile| tile.symbol()));
+                }
+                msg
+            }
+        };
+
+        format!("{} {}\n", HEADER, body)
+    }
+
+    /// `\n`이 제거된 한 줄을 디코딩
+    pub fn decode(msg: &str) -> Result<Self, ProtocolError> {
+        let msg = msg.split_whitespace()
+            .collect::<Vec<&str>>();
+
+        let header = field(&msg, 0, "header")?;
+        if header != HEADER {
+            return Err(ProtocolError::BadHeader(header.to_string()));
+        }
+        let msg = &msg[1..];
+
+        let message = match field(msg, 0, "command")? {
+            "init" => ServerMessage::Init {
+                id: integer(field(msg, 1, "id")?)?,
+                version: msg.get(2).map(|version| integer(version)).transpose()?,
+                token: msg.get(3).map(|token| integer(token)).transpose()?,
+            },
+
+            command @ ("update" | "update3" | "update_rot" | "keyframe" | "keyframe_rot") => {
+                let kind = match command {
+                    "update3" => UpdateKind::Height,
+                    "update_rot" => UpdateKind::Rotation,
+                    "keyframe" => UpdateKind::Keyframe,
+                    "keyframe_rot" => UpdateKind::RotationKeyframe,
+                    _ => UpdateKind::Flat,
+                };
+                let stride = kind.stride();
+
+                // 개수 앞에 일련번호가 있으면 필드 수가 하나 더 많음
+                let (seq, msg) = if msg.len() >= 3 && (msg.len() - 1) % stride == 2 {
+                    (Some(integer(msg[1])?), &msg[1..])
+                } else {
+                    (None, msg)
+                };
+                let num_objects = integer::<usize>(field(msg, 1, "count")?)?;
+                let fields = &msg[2..];
+                if fields.len() != num_objects.saturating_mul(stride) {
+                    return Err(ProtocolError::CountMismatch { expected: num_objects, found: fields.len() / stride });
+                }
+
+                let objects = fields.chunks_exact(stride)
+                    .map(|entry| {
+                        let (y, z, yaw) = match kind {
+                            UpdateKind::Height => (integer(entry[2])?, entry[3], None),
+                            UpdateKind::Rotation | UpdateKind::RotationKeyframe => (0, entry[2], Some(number(entry[3])?)),
+                            UpdateKind::Flat | UpdateKind::Keyframe => (0, entry[2], None),
+                        };
+                        Ok(ObjectState { id: integer(entry[0])?, x: integer(entry[1])?, y, z: integer(z)?, yaw })
+                    })
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::Update { kind, seq, objects }
+            }
+
+            command @ ("delta" | "delta_rot") => {
+                let rotation = command == "delta_rot";
+                let stride = if rotation { 4 } else { 3 };
+                let seq = integer(field(msg, 1, "seq")?)?;
+                let num_changed = integer::<usize>(field(msg, 2, "count")?)?;
+                let fields = msg.get(3..num_changed.saturating_mul(stride).saturating_add(3)).ok_or(
+                    ProtocolError::CountMismatch { expected: num_changed, found: (msg.len() - 3) / stride }
+                )?;
+                let changed = fields.chunks_exact(stride)
+                    .map(|entry| Ok(ObjectState { 
+                        id: integer(entry[0])?, 
+                        x: integer(entry[1])?, 
+                        y: 0, 
+                        z: integer(entry[2])?, 
+                        yaw: rotation.then(|| number(entry[3])).transpose()?,
+                    }))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                let msg = &msg[3 + fields.len()..];
+                let num_removed = integer::<usize>(field(msg, 0, "removed count")?)?;
+                if msg.len() - 1 != num_removed {
+                    return Err(ProtocolError::CountMismatch { expected: num_removed, found: msg.len() - 1 });
+                }
+                let removed = msg[1..].iter()
+                    .map(|id| integer(id))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::Delta { seq, changed, removed }
+            }
+
+            // chat <sender id> <name | -> <text...>
+            "chat" => ServerMessage::Chat {
+                sender: integer(field(msg, 1, "sender")?)?,
+                name: match field(msg, 2, "name")? {
+                    "-" => None,
+                    name => Some(name.to_string()),
+                },
+                text: msg[3..].join(" "),
+            },
+
+            "bgcolor" => ServerMessage::BgColor(field(msg, 1, "color")?.to_string()),
+
+            // tiles <idx> <color> [<idx> <color> ...]. 번호가 잘못된 항목은 무시
+            "tiles" => ServerMessage::Tiles(
+                msg[1..].chunks_exact(2)
+                    .filter_map(|pair| Some((pair[0].parse().ok()?, pair[1].to_string())))
+                    .collect()
+            ),
+
+            "ack" => ServerMessage::Ack { seq: integer(field(msg, 1, "seq")?)? },
+
+            "pong" => ServerMessage::Pong { stamp: msg.get(1).map(|stamp| integer(stamp)).transpose()? },
+
+            "rooms" => {
+                let num_rooms = integer::<usize>(field(msg, 1, "count")?)?;
+                let fields = &msg[2..];
+                if fields.len() != num_rooms.saturating_mul(4) {
+                    return Err(ProtocolError::CountMismatch { expected: num_rooms, found: fields.len() / 4 });
+                }
+
+                let rooms = fields.chunks_exact(4)
+                    .map(|entry| Ok(RoomInfo {
+                        id: integer(entry[0])?,
+                        players: integer(entry[1])?,
+                        capacity: integer(entry[2])?,
+                        name: entry[3].to_string(),
+                    }))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::Rooms(rooms)
+            }
+
+            "joined" => ServerMessage::Joined { room: integer(field(msg, 1, "room")?)? },
+
+            "join_failed" => ServerMessage::JoinFailed { reason: msg[1..].join(" ") },
+
+            "names" => {
+                let num_names = integer::<usize>(field(msg, 1, "count")?)?;
+                let fields = &msg[2..];
+                if fields.len() != num_names.saturating_mul(2) {
+                    return Err(ProtocolError::CountMismatch { expected: num_names, found: fields.len() / 2 });
+                }
+
+                let names = fields.chunks_exact(2)
+                    .map(|entry| Ok((integer(entry[0])?, entry[1].to_string())))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::Names(names)
+            }
+
+            "colors" => {
+                let num_colors = integer::<usize>(field(msg, 1, "count")?)?;
+                let fields = &msg[2..];
+                if fields.len() != num_colors.saturating_mul(2) {
+                    return Err(ProtocolError::CountMismatch { expected: num_colors, found: fields.len() / 2 });
+                }
+
+                let colors = fields.chunks_exact(2)
+                    .map(|entry| Ok((integer(entry[0])?, entry[1].to_string())))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::Colors(colors)
+            }
+
+            "capture" => ServerMessage::Capture {
+                by: integer(field(msg, 1, "by")?)?,
+                target: integer(field(msg, 2, "target")?)?,
+            },
+
+            "pickup" => ServerMessage::Pickup {
+                player: integer(field(msg, 1, "player")?)?,
+                item: integer(field(msg, 2, "item")?)?,
+            },
+
+            "scoreboard" => {
+                let num_entries = integer::<usize>(field(msg, 1, "count")?)?;
+                let fields = &msg[2..];
+                if fields.len() != num_entries.saturating_mul(6) {
+                    return Err(ProtocolError::CountMismatch { expected: num_entries, found: fields.len() / 6 });
+                }
+
+                let entries = fields.chunks_exact(6)
+                    .map(|entry| Ok(ScoreEntry {
+                        id: integer(entry[0])?,
+                        score: integer(entry[1])?,
+                        captures: integer(entry[2])?,
+                        items: integer(entry[3])?,
+                        survival: integer(entry[4])?,
+                        name: entry[5].to_string(),
+                    }))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::Scoreboard(entries)
+            }
+
+            "spawn" => {
+                let num_entities = integer::<usize>(field(msg, 1, "count")?)?;
+                let fields = &msg[2..];
+                if fields.len() != num_entities.saturating_mul(2) {
+                    return Err(ProtocolError::CountMismatch { expected: num_entities, found: fields.len() / 2 });
+                }
+
+                let entities = fields.chunks_exact(2)
+                    .map(|entry| Ok((integer(entry[0])?, entry[1].parse()?)))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::Spawn(entities)
+            }
+
+            "queued" => ServerMessage::Queued {
+                waiting: integer(field(msg, 1, "waiting")?)?,
+                size: integer(field(msg, 2, "size")?)?,
+            },
+
+            "server_closing" => ServerMessage::Closing,
+
+            "match_start" => {
+                let room = integer(field(msg, 1, "room")?)?;
+                let num_players = integer::<usize>(field(msg, 2, "count")?)?;
+                let fields = &msg[3..];
+                if fields.len() != num_players {
+                    return Err(ProtocolError::CountMismatch { expected: num_players, found: fields.len() });
+                }
+                let players = fields.iter()
+                    .map(|id| integer(id))
+                    .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+                ServerMessage::MatchStart { room, players }
+            }
+
+            "map" => {
+                let width = integer::<i32>(field(msg, 1, "width")?)?;
+                let height = integer::<i32>(field(msg, 2, "height")?)?;
+                // 칸을 담을 곳을 만들기 전에 확인
+                if width > MAX_MAP_SIZE || height > MAX_MAP_SIZE {
+                    return Err(ProtocolError::MapTooLarge { width, height });
+                }
+                let rows = &msg[3..];
+                if rows.len() != height.max(0) as usize {
+                    return Err(ProtocolError::CountMismatch { expected: height.max(0) as usize, found: rows.len() });
+                }
+
+                let mut tiles = Vec::with_capacity(rows.len() * width.max(0) as usize);
+                for row in rows {
+                    if row.chars().count() != width.max(0) as usize {
+                        return Err(ProtocolError::BadTile(row.to_string()));
+                    }
+                    for symbol in row.chars() {
+                        tiles.push(Tile::from_symbol(symbol).ok_or(ProtocolError::BadTile(row.to_string()))?);
+                    }
+                }
+
+                ServerMessage::Map { width, height, tiles }
+            }
+
+            command => return Err(ProtocolError::UnknownCommand(command.to_string())),
+        };
+
+        Ok(message)
+    }
+
+    /// `previous`를 받은 클라이언트가 `current`가 되도록 바뀐 것만 담은 `Delta`
+    pub fn delta(seq: u32, previous: &[ObjectState], current: &[ObjectState]) -> Self {
+        let changed = current.iter()
+            .filter(|object| !previous.contains(object))
+            .copied()
+            .collect();
+        let removed = previous.iter()
+            .filter(|object| !current.iter().any(|current| current.id == object.id))
+            .map(|object| object.id)
+            .collect();
+
+        ServerMessage::Delta { seq, changed, removed }
+    }
+}
+
+fn field<'a>(msg: &[&'a str], idx: usize, name: &'static str) -> Result<&'a str, ProtocolError> {
+    msg.get(idx).copied().ok_or(ProtocolError::MissingField(name))
+}
+
+fn integer<T: std::str::FromStr>(value: &str) -> Result<T, ProtocolError> {
+    value.parse().map_err(|_| ProtocolError::BadInteger(value.to_string()))
+}
+
+fn number(value: &str) -> Result<f32, ProtocolError> {
+    value.parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| ProtocolError::BadNumber(value.to_string()))
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(id: u32, x: i32, y: i32, z: i32, yaw: Option<f32>) -> ObjectState {
+        ObjectState { id, x, y, z, yaw }
+    }
+
+    #[test]
+    fn test_supported_version() {
+        assert!(is_supported_version(None));
+        assert!(is_supported_version(Some(PUSHED_UPDATES_VERSION)));
+        assert!(is_supported_version(Some(PROTOCOL_VERSION)));
+        assert!(!is_supported_version(Some(PROTOCOL_VERSION + 1)));
+    }
+
+    #[test]
+    fn test_update_round_trip() {
+        let messages = [
+            ServerMessage::Update {
+                kind: UpdateKind::Flat,
+                seq: Some(7),
+                objects: vec![object(1, 2, 0, 3, None), object(4, -1, 0, 0, None)],
+            },
+            ServerMessage::Update { kind: UpdateKind::Keyframe, seq: None, objects: vec![] },
+            ServerMessage::Update { kind: UpdateKind::Height, seq: None, objects: vec![object(1, 2, 5, 3, None)] },
+            ServerMessage::Update { kind: UpdateKind::Rotation, seq: Some(2), objects: vec![object(1, 2, 0, 3, Some(-45.5))] },
+            ServerMessage::Delta { seq: 3, changed: vec![object(1, 2, 0, 3, None)], removed: vec![4, 5] },
+            ServerMessage::Delta { seq: 4, changed: vec![], removed: vec![] },
+            ServerMessage::Update { kind: UpdateKind::RotationKeyframe, seq: None, objects: vec![object(1, 2, 0, 3, Some(90.0))] },
+            ServerMessage::Delta { seq: 5, changed: vec![object(1, 2, 0, 3, Some(180.0))], removed: vec![4] },
+        ];
+        for msg in messages {
+            assert_eq!(ServerMessage::decode(msg.encode().trim_end()), Ok(msg));
+        }
+
+        assert_eq!(
+            ServerMessage::Update { kind: UpdateKind::Flat, seq: Some(7), objects: vec![object(1, 2, 0, 3, None)] }.encode(),
+            "GAMESERVER update 7 1 1 2 3\n"
+        );
+    }
+
+    #[test]
+    fn test_facing_yaw() {
+        assert_eq!(facing_yaw(0, 1), 0.0);
+        assert_eq!(facing_yaw(1, 0), 90.0);
+        assert_eq!(facing_yaw(-1, -1), -135.0);
+        assert_eq!(facing_yaw(0, -1), 180.0);
+    }
+
+    #[test]
+    fn test_delta() {
+        let previous = [object(1, 0, 0, 0, None), object(2, 1, 0, 1, None), object(3, 2, 0, 2, None)];
+        let current = [object(1, 0, 0, 0, None), object(2, 1, 0, 2, None), object(4, 5, 0, 5, None)];
+        assert_eq!(
+            ServerMessage::delta(9, &previous, &current),
+            ServerMessage::Delta { seq: 9, changed: vec![current[1], current[2]], removed: vec![3] }
+        );
+        assert_eq!(
+            ServerMessage::delta(9, &previous, &current).encode(),
+            "GAMESERVER delta 9 2 2 1 2 4 5 5 1 3\n"
+        );
+
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER delta 1 2 1 0 0 0"),
+            Err(ProtocolError::CountMismatch { expected: 2, found: 1 })
+        );
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER delta 1 0 2 7"),
+            Err(ProtocolError::CountMismatch { expected: 2, found: 1 })
+        );
+        assert_eq!(ServerMessage::decode("GAMESERVER delta 1 0"), Err(ProtocolError::MissingField("removed count")));
+
+        // 바라보는 방향만 바뀌어도 보냄
+        let previous = [object(1, 0, 0, 0, Some(0.0))];
+        let current = [object(1, 0, 0, 0, Some(-90.0))];
+        assert_eq!(ServerMessage::delta(2, &previous, &current).encode(), "GAMESERVER delta_rot 2 1 1 0 0 -90 0\n");
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER delta_rot 1 1 1 0 0"),
+            Err(ProtocolError::CountMismatch { expected: 1, found: 0 })
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let messages = [
+            ServerMessage::Init { id: 3, version: Some(PROTOCOL_VERSION), token: Some(u64::MAX) },
+            ServerMessage::Init { id: 3, version: Some(PROTOCOL_VERSION), token: None },
+            ServerMessage::Init { id: 3, version: None, token: None },
+            ServerMessage::Chat { sender: 1, name: None, text: "hello there".to_string() },
+            ServerMessage::Chat { sender: 1, name: Some("bob".to_string()), text: "hi".to_string() },
+            ServerMessage::BgColor("#ff0000".to_string()),
+            ServerMessage::Tiles(vec![(0, "#ffffff".to_string()), (12, "#000000".to_string())]),
+            ServerMessage::Ack { seq: 5 },
+            ServerMessage::Pong { stamp: Some(1500) },
+            ServerMessage::Pong { stamp: None },
+            ServerMessage::Rooms(vec![
+                RoomInfo { id: 0, name: "lobby".to_string(), players: 3, capacity: 100 },
+                RoomInfo { id: 4, name: "bob's_room".to_string(), players: 0, capacity: 2 },
+            ]),
+            ServerMessage::Rooms(vec![]),
+            ServerMessage::Joined { room: 4 },
+            ServerMessage::JoinFailed { reason: "room is full".to_string() },
+            ServerMessage::Names(vec![(0, "alice".to_string()), (3, "bob_2".to_string())]),
+            ServerMessage::Names(vec![]),
+            ServerMessage::Colors(vec![(0, "#ff0000".to_string()), (3, "#00ff00".to_string())]),
+            ServerMessage::Capture { by: 1, target: 1 << 20 },
+            ServerMessage::Pickup { player: 1, item: (1 << 20) + 3 },
+            ServerMessage::Scoreboard(vec![
+                ScoreEntry { id: 2, name: "alice".to_string(), score: 25, captures: 2, items: 1, survival: 42 },
+                ScoreEntry { id: 0, name: "player0".to_string(), score: 0, captures: 0, items: 0, survival: 3 },
+            ]),
+            ServerMessage::Spawn(vec![(1, EntityKind::Player), (2, EntityKind::Npc), (3, EntityKind::Item), (4, EntityKind::Obstacle)]),
+            ServerMessage::Queued { waiting: 1, size: 2 },
+            ServerMessage::MatchStart { room: 3, players: vec![0, 5] },
+            ServerMessage::Closing,
+            ServerMessage::Map { width: 3, height: 2, tiles: vec![Tile::Floor, Tile::Wall, Tile::Floor, Tile::Floor, Tile::Floor, Tile::Wall] },
+        ];
+        for msg in messages {
+            assert_eq!(ServerMessage::decode(msg.encode().trim_end()), Ok(msg));
+        }
+
+        assert_eq!(
+            ServerMessage::Map { width: 2, height: 1, tiles: vec![Tile::Wall, Tile::Floor] }.encode(),
+            "GAMESERVER map 2 1 #.\n"
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(ServerMessage::decode("SERVER init 1"), Err(ProtocolError::BadHeader("SERVER".to_string())));
+        assert_eq!(ServerMessage::decode("GAMESERVER"), Err(ProtocolError::MissingField("command")));
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER update 3 1 2"),
+            Err(ProtocolError::CountMismatch { expected: 3, found: 0 })
+        );
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER update_rot 1 1 2 3 NaN"),
+            Err(ProtocolError::BadNumber("NaN".to_string()))
+        );
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER rooms 2 0 1 8 lobby"),
+            Err(ProtocolError::CountMismatch { expected: 2, found: 1 })
+        );
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER spawn 1 4 tree"),
+            Err(ProtocolError::BadKind("tree".to_string()))
+        );
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER map 2 2 .."),
+            Err(ProtocolError::CountMismatch { expected: 2, found: 1 })
+        );
+        assert_eq!(
+            ServerMessage::decode("GAMESERVER match_start 3 2 0"),
+            Err(ProtocolError::CountMismatch { expected: 2, found: 1 })
+        );
+        assert_eq!(ServerMessage::decode("GAMESERVER map 2 1 .x"), Err(ProtocolError::BadTile(".x".to_string())));
+        assert_eq!(ServerMessage::decode("GAMESERVER map 2 1 ..."), Err(ProtocolError::BadTile("...".to_string())));
+        assert_eq!(
+            ServerMessage::decode(&format!("GAMESERVER map 2000000000 2000{}", " x".repeat(2000))),
+            Err(ProtocolError::MapTooLarge { width: 2_000_000_000, height: 2000 })
+        );
+        assert_eq!(ServerMessage::decode("GAMESERVER jump"), Err(ProtocolError::UnknownCommand("jump".to_string())));
+    }
+}
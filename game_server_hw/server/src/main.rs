@@ -1,16 +1,18 @@
 use server::server;
-use get_addr::get_addr;
 
 
 #[tokio::main]
 async fn main() {
-    let (ip, port) = match get_addr() {
-        Ok((ip, port)) => (ip, port),
+    server::logging::init();
+
+    let config = match server::config::ServerConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
         Err(e) => {
-            eprintln!("{}", e);
+            log::error!(target: server::logging::SERVER, "{}", e);
             return;
         }
     };
+    log::info!(target: server::logging::SERVER, "Config: {}", config);
 
-    server::tcp_server::run_server(&ip, port).await;
+    server::tcp_server::run_server(config).await;
 }
@@ -0,0 +1,213 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    sync::mpsc,
+};
+use network::ServerMessage;
+
+use super::{
+    logging,
+    room,
+    session,
+    store,
+    tcp_server,
+};
+
+
+/// 관리자가 보낸 채팅의 보낸 사람 id. 플레이어 id와 겹치지 않음
+pub const ADMIN_SENDER: u32 = u32::MAX;
+pub const ADMIN_NAME: &str = "server";
+
+/// 종료할 때 연결들이 `server_closing`을 보내고 닫기를 기다리는 최대 시간
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+
+/// 관리자가 연결에 시키는 일
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Control {
+    /// 연결을 끊고 플레이어를 바로 제거함 (`resume`할 수 없음)
+    Kick,
+    /// `server_closing`을 보내고 연결을 닫음. 플레이어는 바로 제거함
+    Shutdown,
+}
+
+/// 콘솔 한 줄
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Help,
+    List,
+    Kick { id: u32 },
+    Teleport { id: u32, x: i32, y: i32 },
+    Broadcast { text: String },
+    Shutdown,
+}
+
+impl Command {
+    fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args = rest.split_whitespace().collect::<Vec<_>>();
+        let usage = || format!("usage: {}", Self::usage(name));
+
+        match name {
+            "help" => Ok(Command::Help),
+            "list" => Ok(Command::List),
+            "kick" => match args[..] {
+                [id] => Ok(Command::Kick { id: id.parse().map_err(|_| usage())? }),
+                _ => Err(usage()),
+            },
+            "teleport" => match args[..] {
+                [id, x, y] => Ok(Command::Teleport {
+                    id: id.parse().map_err(|_| usage())?,
+                    x: x.parse().map_err(|_| usage())?,
+                    y: y.parse().map_err(|_| usage())?,
+                }),
+                _ => Err(usage()),
+            },
+            "broadcast" if !rest.trim().is_empty() => Ok(Command::Broadcast { text: rest.trim().to_string() }),
+            "broadcast" => Err(usage()),
+            "shutdown" => Ok(Command::Shutdown),
+            _ => Err(format!("unknown command {:?}; try help", name)),
+        }
+    }
+
+    fn usage(name: &str) -> &'static str {
+        match name {
+            "kick" => "kick <id>",
+            "teleport" => "teleport <id> <x> <y>",
+            "broadcast" => "broadcast <message>",
+            _ => "help | list | kick | teleport | broadcast | shutdown",
+        }
+    }
+}
+
+
+/// 관리자의 명령을 받는 연결. `resume`하면 id가 바뀌므로 다시 등록함
+static CONNECTIONS: Mutex<Vec<(u32, mpsc::UnboundedSender<Control>)>> = Mutex::new(Vec::new());
+
+
+/// 연결된 플레이어 `id`가 관리자의 명령을 받는 채널
+pub fn register(id: u32) -> mpsc::UnboundedReceiver<Control> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let mut connections = CONNECTIONS.lock().unwrap();
+    connections.retain(|(connected, _)| *connected != id);
+    connections.push((id, sender));
+
+    receiver
+}
+
+pub fn unregister(id: u32) {
+    CONNECTIONS.lock().unwrap().retain(|(connected, _)| *connected != id);
+}
+
+pub fn connection_count() -> usize {
+    CONNECTIONS.lock().unwrap().len()
+}
+
+fn is_connected(id: u32) -> bool {
+    CONNECTIONS.lock().unwrap().iter().any(|(connected, _)| *connected == id)
+}
+
+fn send(id: u32, control: Control) -> bool {
+    CONNECTIONS.lock().unwrap()
+        .iter()
+        .find(|(connected, _)| *connected == id)
+        .is_some_and(|(_, sender)| sender.send(control).is_ok())
+}
+
+
+/// 표준 입력에서 한 줄씩 명령을 읽어 실행. 입력이 끝나면 (예: `/dev/null`) 콘솔만 끝나고 서버는 계속 돌아감.
+/// 명령의 응답은 로그 설정과 상관없이 표준 출력에 씀
+pub async fn run_console() {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Command::parse(&line) {
+            Ok(command) => execute(command).await,
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+async fn execute(command: Command) {
+    match command {
+        Command::Help => println!("commands: {}", Command::usage("")),
+
+        // <id> <room> <name> <x> <y> <connected|detached>
+        Command::List => {
+            let players = room::players();
+            println!("{} players", players.len());
+            for (id, world) in players {
+                let room = room::room_of(id).map_or(room::LOBBY, |(room, _)| room);
+                let name = session::name(id).unwrap_or_else(|| "-".to_string());
+                let position = world.player_state(id)
+                    .map_or("spectating".to_string(), |state| format!("{} {}", state.x, state.y));
+                let status = if is_connected(id) { "connected" } else { "detached" };
+                println!("{} {} {} {} {}", id, room, name, position, status);
+            }
+        },
+
+        Command::Kick { id } => match send(id, Control::Kick) {
+            true => log::info!(target: logging::ADMIN, "Kicked {}", id),
+            false => println!("No connected player {}", id),
+        },
+
+        // 월드가 벽이나 다른 오브젝트를 피해 가장 가까운 빈 칸으로 옮김
+        Command::Teleport { id, x, y } => {
+            let Some((_, world)) = room::room_of(id) else {
+                println!("No player {}", id);
+                return;
+            };
+            if !world.map().contains(x, y) {
+                println!("({}, {}) is outside the {}x{} map", x, y, world.map().width, world.map().height);
+                return;
+            }
+            world.place_player(id, (x, y)).await;
+            log::info!(target: logging::ADMIN, "Teleported {} to ({}, {})", id, x, y);
+        },
+
+        Command::Broadcast { text } => {
+            for info in room::list() {
+                room::broadcast(info.id, ServerMessage::Chat {
+                    sender: ADMIN_SENDER,
+                    name: Some(ADMIN_NAME.to_string()),
+                    text: text.clone(),
+                });
+            }
+            log::info!(target: logging::ADMIN, "Broadcast to all rooms: {:?}", text);
+        },
+
+        Command::Shutdown => shutdown().await,
+    }
+}
+
+/// 모든 연결에 종료를 알리고 `SHUTDOWN_TIMEOUT`까지 닫기를 기다린 뒤,
+/// 이름이 있는 플레이어의 상태를 파일에 쓰고 서버를 끝냄
+pub async fn shutdown() {
+    let connections = CONNECTIONS.lock().unwrap().len();
+    log::info!(target: logging::ADMIN, "Shutting down; closing {} connections", connections);
+    for (_, sender) in CONNECTIONS.lock().unwrap().iter() {
+        let _ = sender.send(Control::Shutdown);
+    }
+
+    let started = Instant::now();
+    while !CONNECTIONS.lock().unwrap().is_empty() && started.elapsed() < SHUTDOWN_TIMEOUT {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // 닫힌 연결의 플레이어는 방에서 빠질 때 이미 기록됨. 남은 것은 끊긴 채 `resume`을 기다리는 플레이어
+    for (id, world) in room::players() {
+        tcp_server::record_player(id, &world);
+    }
+    if let Err(e) = store::save_async().await {
+        log::error!(target: logging::ADMIN, "Failed to save player state: {}", e);
+    }
+    std::process::exit(0);
+}
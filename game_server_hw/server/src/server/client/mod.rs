@@ -1,85 +1,308 @@
+use std::time::{Duration, Instant};
 use tokio::{
     net::TcpStream,
     io::{AsyncReadExt, AsyncWriteExt},
+    sync::{broadcast, mpsc},
+    time::MissedTickBehavior,
+};
+use super::{
+    admin::{self, Control},
+    logging,
+    matchmaking::{self, Notice},
+    metrics,
+    rate_limit::{RateLimiter, Verdict},
+    room,
+    session,
+    store,
+    tcp_server,
+    world::WorldInterface,
 };
-use super::world::WorldInterface;
 use network::*;
 
 
+/// 이 시간 동안 아무 메세지도 받지 못하면 연결이 끊긴 것으로 보고 플레이어를 제거.
+/// 클라이언트는 움직이지 않아도 1초마다 `ping`을 보냄
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 클라이언트에 위치를 보내는 간격 (20Hz). 클라이언트가 요청하지 않아도 보냄
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// `scoreboard`를 보낸 클라이언트에 점수판을 보내는 간격
+const SCOREBOARD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 채팅 한 줄의 최대 글자 수. 넘는 부분은 잘라서 보냄
+const MAX_CHAT_LEN: usize = 200;
+
+/// 클라이언트가 보내는 한 줄의 최대 바이트 수. 넘는 줄은 처리하지 않고 버림
+const MAX_LINE_LEN: usize = 4096;
+
+
+/// `handle_connection`에서 기다리는 일
+enum Event {
+    Read(std::io::Result<usize>),
+    Snapshot,
+    Scoreboard,
+    /// 같은 방의 연결이 보낸 채팅
+    Chat(Result<ServerMessage, broadcast::error::RecvError>),
+    /// 매치 대기열의 알림
+    Match(Option<Notice>),
+    /// 관리자 콘솔의 명령
+    Control(Option<Control>),
+}
+
+/// 방에 들어가기 전에는 끝나지 않음
+async fn next_chat(chat: &mut Option<broadcast::Receiver<ServerMessage>>) -> Result<ServerMessage, broadcast::error::RecvError> {
+    match chat {
+        Some(chat) => chat.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+
+/// 매치 대기열에 들어가기 전이나 관리자 콘솔에 등록하기 전에는 끝나지 않음
+async fn next_notice<T>(notices: &mut Option<mpsc::UnboundedReceiver<T>>) -> Option<T> {
+    match notices {
+        Some(notices) => notices.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+
 pub struct Client {
     id: u32,
+    /// 다시 접속했을 때 이 플레이어를 이어서 쓰기 위한 토큰 (`session`)
+    token: u64,
     
     stream: TcpStream,
     packet_parser: PacketParser,
+    /// 너무 자주 오는 메세지는 버리고, 계속 넘으면 연결을 끊음
+    rate_limiter: RateLimiter,
 
+    /// 플레이어가 있는 방과 그 방의 월드
+    room: u32,
     world: WorldInterface,
+    /// `room`의 채팅
+    chat: Option<broadcast::Receiver<ServerMessage>>,
+    /// 지금까지 적용한 이동 수. 클라이언트의 이동 일련번호와 같음
+    moves_applied: u32,
+    /// 지금까지 보낸 `update` 수
+    updates_sent: u32,
+    /// 마지막으로 보낸 오브젝트 상태. 다음 `update`는 여기서 바뀐 것만 보냄
+    last_sent: Option<Vec<ObjectState>>,
+    /// 마지막으로 보낸 이름 목록. 클라이언트가 `name`을 보내기 전에는 `None`이며 목록을 보내지 않음
+    last_names: Option<Vec<(u32, String)>>,
+    /// 마지막으로 보낸 색 목록. 클라이언트가 `colors`를 보내기 전에는 `None`이며 목록을 보내지 않음
+    last_colors: Option<Vec<(u32, String)>>,
+    /// 종류를 알려준 보이는 오브젝트 id. 클라이언트가 `kinds`를 보내기 전에는 `None`이며 `spawn`을 보내지 않음
+    last_spawned: Option<Vec<u32>>,
+    /// 클라이언트가 `scoreboard`를 보냄
+    scoreboard: bool,
+    /// 매치 대기열에 있으면 대기열의 알림을 받음
+    matchmaking: Option<mpsc::UnboundedReceiver<Notice>>,
+    /// 관리자 콘솔의 명령
+    control: Option<mpsc::UnboundedReceiver<Control>>,
+    /// 클라이언트가 `spectate`를 보냄. 월드에 플레이어가 없고 방의 모든 오브젝트를 봄
+    spectator: bool,
+    /// 클라이언트가 `compress`를 보냄. `COMPRESSION_THRESHOLD`보다 긴 메세지를 압축해서 보냄
+    compress: bool,
 
     running: bool,
+    /// 클라이언트가 `disconnect`로 직접 종료함. 아니면 연결이 끊겨도 플레이어를 잠시 남겨둠
+    disconnected: bool,
 }
 
 impl Client {
     pub fn new(id: u32, stream: TcpStream, world: WorldInterface) -> Self {
         Self {
             id,
+            token: session::open(id),
             stream,
-            packet_parser: PacketParser::new(),
+            packet_parser: PacketParser::with_max_line_len(MAX_LINE_LEN),
+            rate_limiter: RateLimiter::new(),
+            room: room::LOBBY,
             world,
+            chat: None,
+            moves_applied: 0,
+            updates_sent: 0,
+            last_sent: None,
+            last_names: None,
+            last_colors: None,
+            last_spawned: None,
+            scoreboard: false,
+            matchmaking: None,
+            control: Some(admin::register(id)),
+            spectator: false,
+            compress: false,
             running: true,
+            disconnected: false,
         }
     }
 
+    /// `resume`하면 끊기기 전의 플레이어 id로 바뀜
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// 플레이어는 로비에서 시작함. `world`는 로비의 월드여야 함
     pub async fn handle_connection(&mut self) {
+        if room::join(room::LOBBY, self.id).is_err() {
+            self.running = false;
+            return;
+        }
+        self.chat = room::subscribe(self.room);
         self.world.add_player(self.id).await;
 
-        match self.stream_write(&format!("init {}", self.id)).await {
+        let init = ServerMessage::Init { id: self.id, version: Some(PROTOCOL_VERSION), token: Some(self.token) };
+        let written = match self.stream_write(&init).await {
+            Ok(_) => self.stream_write(&self.map_message()).await,
+            Err(e) => Err(e),
+        };
+        match written {
             Ok(_) => {
-                // println!("Client {} connected", self.id);
+                log::info!(target: logging::NET, "Client {} connected", self.id);
             },
-            Err(_) => {
-                // eprintln!("Failed to init client {}", self.id);
+            Err(e) => {
+                log::warn!(target: logging::NET, "Failed to init client {}: {}", self.id, e);
                 self.running = false;
                 return;
             }
         }
 
         let mut buf = [0; 1024];
+        let mut snapshots = tokio::time::interval(SNAPSHOT_INTERVAL);
+        snapshots.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut scoreboards = tokio::time::interval(SCOREBOARD_INTERVAL);
+        scoreboards.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut last_received = Instant::now();
     
         while self.running {
-            let read = self.stream.read(&mut buf).await;
+            let event = tokio::select! {
+                read = self.stream.read(&mut buf) => Event::Read(read),
+                _ = snapshots.tick() => Event::Snapshot,
+                _ = scoreboards.tick(), if self.scoreboard => Event::Scoreboard,
+                chat = next_chat(&mut self.chat) => Event::Chat(chat),
+                notice = next_notice(&mut self.matchmaking) => Event::Match(notice),
+                control = next_notice(&mut self.control) => Event::Control(control),
+            };
+
+            let read = match event {
+                Event::Read(read) => read,
+                Event::Snapshot => {
+                    if last_received.elapsed() > CLIENT_TIMEOUT {
+                        log::info!(target: logging::NET, "Client {} timed out", self.id);
+                        break;
+                    }
+                    // 새로 보이는 오브젝트의 종류는 위치보다 먼저 보냄
+                    if let Some(message) = self.spawn_message() {
+                        if self.stream_write(&message).await.is_err() {
+                            break;
+                        }
+                    }
+                    if !session::uses_udp(self.id) {
+                        let message = self.update_message();
+                        if self.stream_write(&message).await.is_err() {
+                            break;
+                        }
+                    }
+                    if let Some(message) = self.names_message() {
+                        if self.stream_write(&message).await.is_err() {
+                            break;
+                        }
+                    }
+                    if let Some(message) = self.colors_message() {
+                        if self.stream_write(&message).await.is_err() {
+                            break;
+                        }
+                    }
+                    continue;
+                },
+                Event::Scoreboard => {
+                    if self.stream_write(&self.scoreboard_message()).await.is_err() {
+                        break;
+                    }
+                    continue;
+                },
+                Event::Chat(Ok(message)) => {
+                    if self.stream_write(&message).await.is_err() {
+                        break;
+                    }
+                    continue;
+                },
+                // 너무 느려서 놓친 채팅은 건너뜀
+                Event::Chat(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                Event::Chat(Err(broadcast::error::RecvError::Closed)) => {
+                    self.chat = None;
+                    continue;
+                },
+                Event::Match(notice) => {
+                    if let Some(notice) = notice {
+                        self.process_notice(notice).await;
+                    }
+                    continue;
+                },
+                Event::Control(Some(control)) => {
+                    self.process_control(control).await;
+                    continue;
+                },
+                Event::Control(None) => {
+                    self.control = None;
+                    continue;
+                },
+            };
     
             match read {
                 Ok(0) => {
-                    // println!("Connection closed");
+                    log::debug!(target: logging::NET, "Client {} closed the connection", self.id);
                     break;
                 },
     
                 Ok(n) => {
+                    last_received = Instant::now();
                     self.process_packets(&buf[..n]).await;
                 },
     
-                Err(_) => {
-                    // eprintln!("Failed to read from socket; err = {:?}", e);
+                Err(e) => {
+                    log::debug!(target: logging::NET, "Failed to read from client {}: {}", self.id, e);
                     break;
                 },
             };
         }
 
-        self.world.remove_player(self.id).await;
+        // 다시 접속해도 대기열에는 다시 들어가야 함
+        matchmaking::leave(self.id);
+        admin::unregister(self.id);
+        metrics::forget_client(self.id);
+
+        // 직접 종료하지 않았으면 `tcp_server`가 `RESUME_TIMEOUT` 뒤에 제거함
+        if self.disconnected {
+            room::remove_player(self.id).await;
+            session::close(self.id);
+        }
     }
 
     
     async fn process_packets(&mut self, data: &[u8]) {
+        let dropped = self.packet_parser.dropped();
         self.packet_parser.push(data);
+        metrics::record_bytes_received(data.len());
+        if self.packet_parser.dropped() != dropped {
+            log::debug!(target: logging::NET, "Client {} sent a line longer than {} bytes", self.id, MAX_LINE_LEN);
+        }
 
         while let Some(packet) = self.packet_parser.pop() {
+            metrics::record_message_received();
             let msg = String::from_utf8_lossy(&packet);
 
             if let Some(response) = self.process_message(&msg).await {
                 match self.stream_write(&response).await {
                     Ok(_) => {},
-                    Err(_) => {
+                    Err(e) => {
                         self.running = false;
-                        // eprintln!("Failed to write to socket; err = {:?}", e);
+                        log::debug!(target: logging::NET, "Failed to write to client {}: {}", self.id, e);
                         break;
                     }
                 }
@@ -87,36 +310,387 @@ impl Client {
         }
     }
 
-    async fn process_message(&mut self, msg: &str) -> Option<String> {
-        let msg = msg.trim().split_whitespace()
-            .collect::<Vec<&str>>();
-    
-        if msg.len() == 0 {
-            return None;
+    async fn process_message(&mut self, msg: &str) -> Option<ServerMessage> {
+        let message = ClientMessage::decode(msg)?;
+        match self.rate_limiter.check(&message, Instant::now()) {
+            Verdict::Allow => {},
+            // 버린 이동도 적용한 것으로 셈. 다음 ack가 클라이언트의 일련번호와 맞고, 위치는 다음 update에서 보정됨
+            Verdict::Drop => {
+                match message {
+                    ClientMessage::Move { .. } => self.moves_applied = self.moves_applied.wrapping_add(1),
+                    ClientMessage::MoveBatch { seq, moves, .. } => self.moves_applied = seq.wrapping_add(moves.len() as u32),
+                    _ => {},
+                }
+                return None;
+            },
+            // 다시 접속해도 이어서 쓸 수 없도록 직접 종료한 것처럼 플레이어를 바로 제거함
+            Verdict::Disconnect => {
+                if self.running {
+                    log::warn!(target: logging::NET, "Client {} disconnected for sending too many messages", self.id);
+                }
+                self.running = false;
+                self.disconnected = true;
+                return None;
+            },
         }
+
+        match message {
+            ClientMessage::Ping { stamp } => Some(ServerMessage::Pong { stamp }),
     
-        match msg[0] {
-            "ping" => Some("pong".to_string()),
+            // 보드는 칸 단위이므로 아날로그 크기는 반올림해서 적용.
+            // ack는 다음에 받을 이동의 일련번호 (클라이언트 예측 보정용)
+            ClientMessage::Move { id, x, y } => {
+                if let Some((x, y)) = self.step(id, x, y) {
+                    self.world.move_player(self.id, x, y).await;
+                }
+                self.moves_applied = self.moves_applied.wrapping_add(1);
     
-            "move" if msg.len() == 4 => {
-                let id = msg[1].parse::<u32>().unwrap();
-                let x = msg[2].parse::<i32>().unwrap();
-                let y = msg[3].parse::<i32>().unwrap();
+                Some(ServerMessage::Ack { seq: self.moves_applied })
+            },
     
-                self.world.move_player(id, x, y).await;
+            ClientMessage::MoveBatch { id, seq, moves } => {
+                self.moves_applied = seq.wrapping_add(moves.len() as u32);
+                for (x, y) in moves {
+                    if let Some((x, y)) = self.step(id, x, y) {
+                        self.world.move_player(self.id, x, y).await;
+                    }
+                }
+
+                Some(ServerMessage::Ack { seq: self.moves_applied })
+            },
     
+            // 일련번호가 없으므로 ack를 보내지 않음. 위치는 update로 알게 됨
+            ClientMessage::MoveTo { x, z } => {
+                self.world.move_to(self.id, (x, z)).await;
                 None
             },
-    
-            "update" => Some(self.world.update_message()),
-    
-            _ => None
+
+            // 위치는 `SNAPSHOT_INTERVAL`마다 보내지만, 요청하는 이전 클라이언트에도 응답함
+            ClientMessage::Update => Some(self.update_message()),
+
+            // 클라이언트가 가진 오브젝트를 모두 교체하는 전체 상태
+            ClientMessage::Resync => {
+                let objects = self.visible_objects();
+                self.last_sent = Some(objects.clone());
+
+                Some(ServerMessage::Update { kind: UpdateKind::RotationKeyframe, seq: None, objects })
+            },
+
+            // 보낸 연결을 포함해 같은 방의 모든 연결에 보냄. 보낸 사람은 메세지의 id가 아닌 이 연결의 플레이어
+            ClientMessage::Chat { text, .. } => {
+                let text = text.chars().take(MAX_CHAT_LEN).collect();
+                room::broadcast(self.room, ServerMessage::Chat { sender: self.id, name: session::name(self.id), text });
+
+                None
+            },
+
+            // 끊기기 전의 플레이어를 그 플레이어가 있던 방에서 이어서 사용하고, 이 연결에서 새로 만든 플레이어와 자리는 버림
+            ClientMessage::Resume { id, token } => {
+                if id == self.id {
+                    return None;
+                }
+                let (room, world) = room::room_of(id)?;
+                if !session::resume(id, token) {
+                    return None;
+                }
+                log::info!(target: logging::NET, "Client {} resumed player {}", self.id, id);
+
+                room::remove_player(self.id).await;
+                session::close(self.id);
+                tcp_server::release_slot(self.id);
+                matchmaking::leave(self.id);
+                self.matchmaking = None;
+                admin::unregister(self.id);
+                self.control = Some(admin::register(id));
+                metrics::forget_client(self.id);
+
+                self.room = room;
+                self.world = world;
+                self.chat = room::subscribe(room);
+                self.id = id;
+                self.token = token;
+                self.last_sent = None;
+                if self.last_names.is_some() {
+                    self.last_names = Some(Vec::new());
+                }
+                if self.last_colors.is_some() {
+                    self.last_colors = Some(Vec::new());
+                }
+                if self.last_spawned.is_some() {
+                    self.last_spawned = Some(Vec::new());
+                }
+
+                // init을 받으면 클라이언트는 로비에 있는 것으로 보므로 이어서 방을 알려줌
+                let init = ServerMessage::Init { id, version: Some(PROTOCOL_VERSION), token: Some(token) };
+                if self.stream_write(&init).await.is_err() || self.stream_write(&self.map_message()).await.is_err() {
+                    self.running = false;
+                    return None;
+                }
+                Some(ServerMessage::Joined { room })
+            },
+
+            ClientMessage::Rooms => Some(ServerMessage::Rooms(room::list())),
+
+            ClientMessage::CreateRoom { name, capacity } => match room::create(&name, capacity) {
+                Ok(room) => Some(self.join_room(room).await),
+                Err(e) => Some(ServerMessage::JoinFailed { reason: e.to_string() }),
+            },
+
+            ClientMessage::JoinRoom { room } => Some(self.join_room(room).await),
+
+            // 기다리는 인원은 대기열의 알림으로 보냄
+            ClientMessage::Queue => {
+                self.matchmaking = Some(matchmaking::enter(self.id));
+                None
+            },
+
+            ClientMessage::Unqueue => {
+                matchmaking::leave(self.id);
+                self.matchmaking = None;
+                None
+            },
+
+            // 이미 만든 플레이어는 지우고, 이후 방을 옮겨도 만들지 않음
+            ClientMessage::Spectate => {
+                if !self.spectator {
+                    self.spectator = true;
+                    self.world.remove_player(self.id).await;
+                }
+                None
+            },
+
+            ClientMessage::Compress => {
+                self.compress = true;
+                None
+            },
+
+            // 바로 보이는 플레이어의 이름을 보내고, 이후에는 목록이 바뀔 때 보냄
+            // 처음 이름을 정하면 그 이름으로 저장된 위치와 점수를 이어서 씀. `resume`한 플레이어는 이미 이름이 있음
+            // 저장된 상태는 같은 열쇠로 저장했을 때만 이어받고, 다른 열쇠로 저장된 이름은 숫자를 붙여 줌
+            ClientMessage::Name { name, key } => {
+                let first = session::name(self.id).is_none();
+                session::set_name(self.id, &name, key);
+                let saved = session::saved_name(self.id).and_then(|(name, key)| store::load(&name, key));
+                if let Some(state) = saved.filter(|_| first) {
+                    self.world.restore_player(self.id, state).await;
+                }
+                let names = self.visible_names();
+                self.last_names = Some(names.clone());
+
+                Some(ServerMessage::Names(names))
+            },
+
+            ClientMessage::Colors => {
+                let colors = session::colors(&self.visible_ids());
+                self.last_colors = Some(colors.clone());
+
+                Some(ServerMessage::Colors(colors))
+            },
+
+            ClientMessage::Scoreboard => {
+                self.scoreboard = true;
+
+                Some(self.scoreboard_message())
+            },
+
+            ClientMessage::Kinds => {
+                self.last_spawned = Some(Vec::new());
+
+                Some(self.spawn_message().unwrap_or(ServerMessage::Spawn(Vec::new())))
+            },
+
+            // UDP로만 받는 요청
+            ClientMessage::DatagramUpdate { .. } => None,
+
+            // 연결을 끝내면 handle_connection에서 플레이어를 제거함
+            ClientMessage::Disconnect { .. } => {
+                self.running = false;
+                self.disconnected = true;
+
+                None
+            },
+        }
+    }
+
+    /// 플레이어를 원래 있던 방의 월드에서 빼고 `room`의 월드에 새로 만듦.
+    /// 다음 위치 메세지는 새 방의 전체 상태
+    /// 매치가 잡히면 매치 방에 들어가 정해진 칸으로 옮기고 `joined`, `match_start` 순서로 알림
+    async fn process_notice(&mut self, notice: Notice) {
+        let messages = match notice {
+            Notice::Waiting { waiting, size } => vec![ServerMessage::Queued { waiting, size }],
+            Notice::Matched { room, players, spawn } => {
+                self.matchmaking = None;
+                match self.join_room(room).await {
+                    joined @ ServerMessage::Joined { .. } => {
+                        self.world.place_player(self.id, spawn).await;
+                        vec![joined, ServerMessage::MatchStart { room, players }]
+                    },
+                    failed => vec![failed],
+                }
+            },
+            Notice::Failed(reason) => {
+                self.matchmaking = None;
+                vec![ServerMessage::JoinFailed { reason }]
+            },
+        };
+
+        for message in messages {
+            if self.stream_write(&message).await.is_err() {
+                self.running = false;
+                return;
+            }
         }
     }
 
-    async fn stream_write(&mut self, msg: &str) -> Result<(), std::io::Error> {
-        let msg = format!("GAMESERVER {}\n", msg);
-        
-        self.stream.write_all(msg.as_bytes()).await
+    /// 쫓겨나면 관리자의 채팅으로 알리고 직접 종료한 것처럼 플레이어를 바로 제거함.
+    /// 서버가 종료하면 `server_closing`을 보낸 뒤 보낸 데이터를 모두 내보내고 연결을 닫음
+    async fn process_control(&mut self, control: Control) {
+        match control {
+            Control::Shutdown => {
+                if self.stream_write(&ServerMessage::Closing).await.is_ok() {
+                    let _ = self.stream.shutdown().await;
+                }
+                self.running = false;
+                self.disconnected = true;
+            },
+            Control::Kick => {
+                let message = ServerMessage::Chat {
+                    sender: admin::ADMIN_SENDER,
+                    name: Some(admin::ADMIN_NAME.to_string()),
+                    text: "you were kicked".to_string(),
+                };
+                let _ = self.stream_write(&message).await;
+                self.running = false;
+                self.disconnected = true;
+            },
+        }
+    }
+
+    async fn join_room(&mut self, room: u32) -> ServerMessage {
+        if room == self.room {
+            return ServerMessage::Joined { room };
+        }
+        let (world, previous) = match room::join(room, self.id) {
+            Ok(worlds) => worlds,
+            Err(e) => return ServerMessage::JoinFailed { reason: e.to_string() },
+        };
+
+        if let Some(previous) = previous {
+            previous.remove_player(self.id).await;
+        }
+        self.room = room;
+        self.world = world;
+        self.chat = room::subscribe(room);
+        if !self.spectator {
+            self.world.add_player(self.id).await;
+        }
+        self.last_sent = None;
+        // 방의 오브젝트 id는 방마다 따로 매기므로 새 방의 종류를 모두 다시 보냄
+        if self.last_spawned.is_some() {
+            self.last_spawned = Some(Vec::new());
+        }
+
+        ServerMessage::Joined { room }
+    }
+
+    /// 처음에는 관심 영역 안의 전체 상태, 그 다음부터는 바뀐 것만 보냄.
+    /// TCP는 순서대로 도착하므로 보낸 상태를 클라이언트가 가진 상태로 봄.
+    /// 관심 영역에 들어온 오브젝트는 `changed`, 나간 오브젝트는 `removed`로 전달됨
+    fn update_message(&mut self) -> ServerMessage {
+        self.updates_sent = self.updates_sent.wrapping_add(1);
+        let objects = self.visible_objects();
+        let message = match &self.last_sent {
+            Some(previous) => ServerMessage::delta(self.updates_sent, previous, &objects),
+            None => ServerMessage::Update { kind: UpdateKind::Rotation, seq: Some(self.updates_sent), objects: objects.clone() },
+        };
+        self.last_sent = Some(objects);
+
+        message
+    }
+
+    /// 방의 월드는 모두 로비와 같은 맵이므로 `init` 뒤에 한 번만 보냄
+    fn map_message(&self) -> ServerMessage {
+        let map = self.world.map();
+        ServerMessage::Map { width: map.width, height: map.height, tiles: map.tiles.clone() }
+    }
+
+    /// 관전자는 관심 영역과 상관없이 방의 모든 오브젝트를 봄
+    fn visible_objects(&self) -> Vec<ObjectState> {
+        match self.spectator {
+            true => self.world.objects(),
+            false => self.world.objects_near(self.id),
+        }
+    }
+
+    fn visible_ids(&self) -> Vec<u32> {
+        self.visible_objects()
+            .iter()
+            .map(|object| object.id)
+            .collect()
+    }
+
+    fn visible_names(&self) -> Vec<(u32, String)> {
+        session::names(&self.visible_ids())
+    }
+
+    /// 보이는 플레이어의 이름이 마지막으로 보낸 목록과 다르면 새 목록
+    fn names_message(&mut self) -> Option<ServerMessage> {
+        let names = self.visible_names();
+        if *self.last_names.as_ref()? == names {
+            return None;
+        }
+
+        self.last_names = Some(names.clone());
+        Some(ServerMessage::Names(names))
+    }
+
+    /// 보이는 플레이어의 색이 마지막으로 보낸 목록과 다르면 새 목록
+    fn colors_message(&mut self) -> Option<ServerMessage> {
+        let colors = session::colors(&self.visible_ids());
+        if *self.last_colors.as_ref()? == colors {
+            return None;
+        }
+
+        self.last_colors = Some(colors.clone());
+        Some(ServerMessage::Colors(colors))
+    }
+
+    /// 이름을 등록하지 않은 플레이어는 `player<id>`
+    fn scoreboard_message(&self) -> ServerMessage {
+        let entries = self.world.scoreboard(|id| session::name(id).unwrap_or_else(|| format!("player{}", id)));
+        ServerMessage::Scoreboard(entries)
+    }
+
+    /// 마지막으로 알려준 뒤 새로 보이게 된 오브젝트의 종류.
+    /// 보이지 않게 된 오브젝트는 목록에서 빠지므로 다시 보이면 다시 알려줌
+    fn spawn_message(&mut self) -> Option<ServerMessage> {
+        let visible = self.visible_ids();
+        let last_spawned = self.last_spawned.as_ref()?;
+        let entities = visible.iter()
+            .filter(|id| !last_spawned.contains(id))
+            .filter_map(|&id| Some((id, self.world.kind_of(id)?)))
+            .collect::<Vec<_>>();
+
+        self.last_spawned = Some(visible);
+        (!entities.is_empty()).then_some(ServerMessage::Spawn(entities))
+    }
+
+    /// 이 연결의 플레이어만 움직일 수 있고, 한 번에 각 축으로 한 칸까지만 이동.
+    /// 다른 플레이어의 이동은 적용하지 않지만 ack는 보내므로, 클라이언트 예측은 다음 update에서 보정됨
+    fn step(&self, id: u32, x: f32, y: f32) -> Option<(i32, i32)> {
+        if id != self.id {
+            log::debug!(target: logging::NET, "Client {} tried to move player {}", self.id, id);
+            return None;
+        }
+
+        Some((x.round().clamp(-1.0, 1.0) as i32, y.round().clamp(-1.0, 1.0) as i32))
+    }
+
+    async fn stream_write(&mut self, msg: &ServerMessage) -> Result<(), std::io::Error> {
+        let encoded = msg.encode();
+        let compressed = self.compress.then(|| compress(&encoded)).flatten();
+        let sent = compressed.as_deref().unwrap_or(&encoded);
+        metrics::record_sent(self.id, sent.len(), encoded.len());
+        self.stream.write_all(sent.as_bytes()).await
     }
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+//! 서버 설정. 기본값, `server.toml`, 환경변수, 명령줄 순서로 덮어씀.
+//!
+//! `server.toml`은 `key = value` 줄만 읽음 (`#` 뒤는 주석):
+//! `host`, `port`, `tick_rate`, `map`, `max_players`, `interest_radius`, `npcs`, `capture`, `match_size`
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{
+    matchmaking,
+    tcp_server::MAX_CLIENTS,
+    world::{CAPTURE_ENV, DEFAULT_INTEREST_RADIUS, DEFAULT_NPCS, INTEREST_RADIUS_ENV, MAP_ENV, NPCS_ENV},
+};
+
+
+/// `--config`가 없으면 현재 폴더에서 찾는 설정 파일. 없으면 기본값
+pub const CONFIG_FILE: &str = "server.toml";
+
+/// `public`이면 이 컴퓨터의 로컬 네트워크 주소, `localhost`나 ip면 그대로
+pub const DEFAULT_HOST: &str = "public";
+pub const DEFAULT_PORT: u16 = 7878;
+/// 초당 월드 틱 (NPC 이동) 수
+pub const DEFAULT_TICK_RATE: u32 = 2;
+pub const MAX_TICK_RATE: u32 = 1000;
+
+pub const USAGE: &str = "\
+Usage: server [<mode(or ip)>:<port>] [options]
+  mode: localhost, public (this machine's local network address)
+options:
+  --config <file>       settings file (default: server.toml if it exists)
+  --port <port>         port for TCP and UDP
+  --tick-rate <hz>      world ticks (NPC moves) per second
+  --map <file>          board and entities to load instead of the default map
+  --max-players <n>     connections accepted at once";
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub tick_rate: u32,
+    /// 없으면 기본 맵
+    pub map: Option<PathBuf>,
+    pub max_players: usize,
+    pub interest_radius: i32,
+    /// 방마다 놓는 NPC 수
+    pub npcs: usize,
+    pub capture: bool,
+    pub match_size: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            tick_rate: DEFAULT_TICK_RATE,
+            map: None,
+            max_players: MAX_CLIENTS,
+            interest_radius: DEFAULT_INTEREST_RADIUS,
+            npcs: DEFAULT_NPCS,
+            capture: false,
+            match_size: matchmaking::DEFAULT_MATCH_SIZE,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// 명령줄 `args` (프로그램 이름 제외)의 `--config` 파일이나 `CONFIG_FILE`을 읽고,
+    /// 환경변수와 나머지 옵션으로 덮어씀
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let args = args.into_iter().collect::<Vec<_>>();
+        let path = match args.iter().position(|arg| arg == "--config") {
+            Some(idx) => Some(PathBuf::from(args.get(idx + 1).ok_or("--config requires a file")?)),
+            None => None,
+        };
+
+        Self::load(path.as_deref())?
+            .with_env()
+            .with_args(args)
+    }
+
+    /// `path`가 없으면 `CONFIG_FILE`. 그 파일이 없으면 기본값이지만, 직접 지정한 `path`는 있어야 함
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path, true),
+            None => (Path::new(CONFIG_FILE), false),
+        };
+        match std::fs::read_to_string(path) {
+            Ok(text) => text.parse().map_err(|e| format!("invalid {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(e) => Err(format!("failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// 설정된 환경변수로 덮어씀. 잘못된 값은 무시
+    pub fn with_env(mut self) -> Self {
+        let var = |name: &str| std::env::var(name).ok();
+
+        if let Some(radius) = var(INTEREST_RADIUS_ENV).and_then(|radius| radius.parse::<i32>().ok()).filter(|radius| *radius >= 0) {
+            self.interest_radius = radius;
+        }
+        if let Some(npcs) = var(NPCS_ENV).and_then(|npcs| npcs.parse().ok()) {
+            self.npcs = npcs;
+        }
+        if let Some(capture) = var(CAPTURE_ENV) {
+            self.capture = capture == "1" || capture == "true";
+        }
+        if let Some(size) = var(matchmaking::MATCH_SIZE_ENV).and_then(|size| size.parse().ok()) {
+            self.match_size = size;
+        }
+        if let Some(path) = var(MAP_ENV) {
+            self.map = Some(path.into());
+        }
+        self
+    }
+
+    /// 명령줄 옵션으로 덮어씀. 옵션이 아닌 인자는 `<mode(or ip)>:<port>`
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("{} requires a value", name));
+            match arg.as_str() {
+                "--config" => {
+                    value("--config")?;
+                }
+                "--port" => self.port = parse_port(&value("--port")?)?,
+                "--tick-rate" => self.tick_rate = parse_tick_rate(&value("--tick-rate")?)?,
+                "--map" => self.map = Some(value("--map")?.into()),
+                "--max-players" => self.max_players = parse_max_players(&value("--max-players")?)?,
+                "--help" | "-h" => return Err(USAGE.to_string()),
+                arg if arg.starts_with('-') => return Err(format!("unknown option {}\n{}", arg, USAGE)),
+                addr => {
+                    let (host, port) = addr.rsplit_once(':')
+                        .filter(|(host, _)| !host.is_empty())
+                        .ok_or_else(|| format!("invalid address {:?}\n{}", addr, USAGE))?;
+                    self.host = host.to_string();
+                    self.port = parse_port(port)?;
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// NPC를 움직이는 간격
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.tick_rate as f64)
+    }
+}
+
+impl std::str::FromStr for ServerConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Self::default();
+
+        for (n, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `key = value`, found {:?}", n + 1, line));
+            };
+            let key = key.trim();
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|value| value.strip_suffix('"')).unwrap_or(value);
+
+            let at_line = |e: String| format!("line {}: {}", n + 1, e);
+            let invalid = |expected: &str| format!("line {}: invalid {} {:?} for `{}`", n + 1, expected, value, key);
+            match key {
+                "host" if !value.is_empty() => config.host = value.to_string(),
+                "port" => config.port = parse_port(value).map_err(at_line)?,
+                "tick_rate" => config.tick_rate = parse_tick_rate(value).map_err(at_line)?,
+                "map" => config.map = Some(PathBuf::from(value)).filter(|path| !path.as_os_str().is_empty()),
+                "max_players" => config.max_players = parse_max_players(value).map_err(at_line)?,
+                "interest_radius" => {
+                    config.interest_radius = value.parse().ok().filter(|radius| *radius >= 0).ok_or_else(|| invalid("radius"))?;
+                }
+                "npcs" => config.npcs = value.parse().map_err(|_| invalid("count"))?,
+                "capture" => config.capture = value.parse().map_err(|_| invalid("boolean"))?,
+                "match_size" => config.match_size = value.parse().map_err(|_| invalid("count"))?,
+                "host" => return Err(invalid("host")),
+                key => return Err(format!("line {}: unknown key `{}`", n + 1, key)),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+impl std::fmt::Display for ServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}, {} ticks/s, max {} players",
+            self.host,
+            self.port,
+            self.tick_rate,
+            self.max_players
+        )
+    }
+}
+
+
+fn parse_port(value: &str) -> Result<u16, String> {
+    value.parse().map_err(|_| format!("invalid port {:?}", value))
+}
+
+fn parse_tick_rate(value: &str) -> Result<u32, String> {
+    value.parse().ok()
+        .filter(|rate| (1..=MAX_TICK_RATE).contains(rate))
+        .ok_or_else(|| format!("tick rate must be 1..={}, found {:?}", MAX_TICK_RATE, value))
+}
+
+fn parse_max_players(value: &str) -> Result<usize, String> {
+    value.parse().ok()
+        .filter(|count| (1..=MAX_CLIENTS).contains(count))
+        .ok_or_else(|| format!("max players must be 1..={}, found {:?}", MAX_CLIENTS, value))
+}
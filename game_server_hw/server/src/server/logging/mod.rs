@@ -0,0 +1,57 @@
+//! 서브시스템별 로그 target (클라이언트와 같은 이름).
+//! `GAMESERVER_LOG=gameserver::net=debug`처럼 필요한 부분의 로그만 볼 수 있음.
+
+use std::io::Write;
+
+
+pub const SERVER: &str = "gameserver::server";
+pub const NET: &str = "gameserver::net";
+pub const WORLD: &str = "gameserver::world";
+pub const ADMIN: &str = "gameserver::admin";
+
+/// 로그 필터를 정하는 환경변수 (`RUST_LOG`와 같은 형식). 없으면 `RUST_LOG`, 그것도 없으면 `info`
+pub const LOG_ENV: &str = "GAMESERVER_LOG";
+
+/// `json`이면 한 줄에 하나씩 JSON 객체로 출력 (로그 수집용)
+pub const LOG_FORMAT_ENV: &str = "GAMESERVER_LOG_FORMAT";
+
+
+pub fn init() {
+    let filter = std::env::var(LOG_ENV)
+        .or_else(|_| std::env::var("RUST_LOG"))
+        .unwrap_or_else(|_| "info".to_string());
+
+    let mut builder = env_logger::Builder::new();
+    builder.parse_filters(&filter);
+    if std::env::var(LOG_FORMAT_ENV).is_ok_and(|format| format == "json") {
+        builder.format(|buf, record| {
+            writeln!(
+                buf,
+                "{{\"time\":\"{}\",\"level\":\"{}\",\"target\":{},\"message\":{}}}",
+                buf.timestamp_millis(),
+                record.level(),
+                json_string(record.target()),
+                json_string(&record.args().to_string()),
+            )
+        });
+    }
+    builder.init();
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,138 @@
+use std::{sync::Mutex, time::Duration};
+use tokio::sync::mpsc;
+use network::GameMap;
+
+use super::{
+    replay,
+    room,
+};
+
+
+/// 매치 한 번의 인원을 정하는 환경변수
+pub const MATCH_SIZE_ENV: &str = "GAMESERVER_MATCH_SIZE";
+pub const DEFAULT_MATCH_SIZE: usize = 2;
+
+/// 매치 방에 이 시간 안에 아무도 들어오지 않으면 (모두 그 전에 끊기면) 방을 닫음.
+/// 들어온 뒤에는 마지막 플레이어가 나갈 때 방이 닫힘
+pub const MATCH_JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// 대기열에 있는 연결에 알리는 일
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notice {
+    /// 대기열이 바뀜
+    Waiting { waiting: usize, size: usize },
+    /// 매치가 잡힘. 연결은 `room`에 들어가서 `spawn` 칸으로 옮김
+    Matched { room: u32, players: Vec<u32>, spawn: (i32, i32) },
+    /// 매치 방을 만들지 못함. 연결은 대기열에서 빠짐
+    Failed(String),
+}
+
+struct Queue {
+    size: usize,
+    /// 들어온 순서
+    waiting: Vec<(u32, mpsc::UnboundedSender<Notice>)>,
+    /// 다음 매치 방 이름의 번호
+    next_match: u32,
+}
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    size: DEFAULT_MATCH_SIZE,
+    waiting: Vec::new(),
+    next_match: 1,
+});
+
+
+pub fn set_match_size(size: usize) {
+    QUEUE.lock().unwrap().size = size.clamp(1, room::MAX_ROOM_CAPACITY);
+}
+
+pub fn match_size() -> usize {
+    QUEUE.lock().unwrap().size
+}
+
+/// 플레이어 `id`를 대기열 끝에 넣고 알림을 받는 채널을 반환. 이미 있으면 끝으로 옮김.
+/// 인원이 모이면 먼저 들어온 순서대로 매치 방을 만듦
+pub fn enter(id: u32) -> mpsc::UnboundedReceiver<Notice> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let mut queue = QUEUE.lock().unwrap();
+    queue.waiting.retain(|(waiting, _)| *waiting != id);
+    queue.waiting.push((id, sender));
+    if queue.waiting.len() >= queue.size {
+        start_match(&mut queue);
+    }
+    notify_waiting(&queue);
+
+    receiver
+}
+
+/// 대기열에 없으면 아무것도 하지 않음
+pub fn leave(id: u32) {
+    let mut queue = QUEUE.lock().unwrap();
+    let len = queue.waiting.len();
+    queue.waiting.retain(|(waiting, _)| *waiting != id);
+    if queue.waiting.len() != len {
+        notify_waiting(&queue);
+    }
+}
+
+fn notify_waiting(queue: &Queue) {
+    let notice = Notice::Waiting { waiting: queue.waiting.len(), size: queue.size };
+    for (_, sender) in &queue.waiting {
+        // 받는 연결이 끝났으면 그 연결이 `leave`함
+        let _ = sender.send(notice.clone());
+    }
+}
+
+fn start_match(queue: &mut Queue) {
+    let players = queue.waiting.drain(..queue.size).collect::<Vec<_>>();
+    let name = format!("match-{}", queue.next_match);
+    queue.next_match += 1;
+
+    let room = match room::create(&name, players.len()) {
+        Ok(room) => room,
+        Err(e) => {
+            for (_, sender) in players {
+                let _ = sender.send(Notice::Failed(e.to_string()));
+            }
+            return;
+        }
+    };
+
+    let Some(world) = room::world_of(room) else {
+        return;
+    };
+    replay::start(room, &name);
+    tokio::spawn(async move {
+        tokio::time::sleep(MATCH_JOIN_TIMEOUT).await;
+        room::close_if_empty(room);
+    });
+    let spawns = spawn_points(world.map(), players.len());
+    let ids = players.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    for ((_, sender), spawn) in players.into_iter().zip(spawns) {
+        let _ = sender.send(Notice::Matched { room, players: ids.clone(), spawn });
+    }
+}
+
+/// 서로 멀리 떨어진 `count`개의 칸. 네 모서리, 네 변의 가운데 순서이며 더 필요하면 처음부터 다시 씀.
+/// 벽이나 다른 오브젝트가 있으면 월드가 가장 가까운 빈 칸으로 옮김
+fn spawn_points(map: &GameMap, count: usize) -> Vec<(i32, i32)> {
+    let (left, top) = (1.min(map.width - 1), 1.min(map.height - 1));
+    let (right, bottom) = ((map.width - 2).max(0), (map.height - 2).max(0));
+    let (center_x, center_y) = (map.width / 2, map.height / 2);
+
+    [
+        (left, top),
+        (right, bottom),
+        (right, top),
+        (left, bottom),
+        (center_x, top),
+        (center_x, bottom),
+        (left, center_y),
+        (right, center_y),
+    ].into_iter()
+        .cycle()
+        .take(count)
+        .collect()
+}
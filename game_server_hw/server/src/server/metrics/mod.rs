@@ -0,0 +1,143 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use super::{admin, tcp_server};
+
+
+/// 지표를 HTTP로 보여줄 주소를 정하는 환경변수 (예: `127.0.0.1:9100`). 없으면 열지 않음
+pub const METRICS_ENV: &str = "GAMESERVER_METRICS";
+
+
+static MESSAGES_RECEIVED: AtomicU64 = AtomicU64::new(0);
+static BYTES_RECEIVED: AtomicU64 = AtomicU64::new(0);
+static MESSAGES_SENT: AtomicU64 = AtomicU64::new(0);
+
+/// 연결마다 보낸 바이트 (TCP와 UDP). 연결이 끝나면 지움
+static BYTES_SENT: Mutex<BTreeMap<u32, u64>> = Mutex::new(BTreeMap::new());
+/// 연결마다 압축하기 전 메세지의 바이트. `BYTES_SENT`와 비교하면 압축으로 줄어든 양
+static PAYLOAD_BYTES: Mutex<BTreeMap<u32, u64>> = Mutex::new(BTreeMap::new());
+
+/// 모든 방의 월드 tick (NPC 이동, 아이템 생성)에 걸린 시간
+struct Ticks {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+static TICKS: Mutex<Ticks> = Mutex::new(Ticks { count: 0, total: Duration::ZERO, max: Duration::ZERO });
+
+
+pub fn record_bytes_received(bytes: usize) {
+    BYTES_RECEIVED.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+pub fn record_message_received() {
+    MESSAGES_RECEIVED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 연결 `id`에 메세지 하나를 보냄. `payload`는 압축하기 전 바이트 (압축하지 않았으면 `bytes`와 같음)
+pub fn record_sent(id: u32, bytes: usize, payload: usize) {
+    MESSAGES_SENT.fetch_add(1, Ordering::Relaxed);
+    *BYTES_SENT.lock().unwrap().entry(id).or_default() += bytes as u64;
+    *PAYLOAD_BYTES.lock().unwrap().entry(id).or_default() += payload as u64;
+}
+
+pub fn record_tick(duration: Duration) {
+    let mut ticks = TICKS.lock().unwrap();
+    ticks.count += 1;
+    ticks.total += duration;
+    ticks.max = ticks.max.max(duration);
+}
+
+pub fn forget_client(id: u32) {
+    BYTES_SENT.lock().unwrap().remove(&id);
+    PAYLOAD_BYTES.lock().unwrap().remove(&id);
+}
+
+
+/// Prometheus 텍스트 형식. 초당 메세지 수는 누적 값에서 `rate()`로 구함
+pub fn render() -> String {
+    let mut text = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+        let _ = writeln!(text, "# HELP {} {}", name, help);
+        let _ = writeln!(text, "# TYPE {} {}", name, kind);
+        for (labels, value) in samples {
+            let _ = writeln!(text, "{}{} {}", name, labels, value);
+        }
+    };
+    let value = |value: u64| vec![(String::new(), value.to_string())];
+
+    metric("gameserver_clients", "gauge", "Client slots in use, including players waiting to resume",
+        &value(tcp_server::client_count() as u64));
+    metric("gameserver_connections", "gauge", "Open client connections",
+        &value(admin::connection_count() as u64));
+
+    {
+        let ticks = TICKS.lock().unwrap();
+        metric("gameserver_tick_seconds_total", "counter", "Time spent in world ticks",
+            &[(String::new(), ticks.total.as_secs_f64().to_string())]);
+        metric("gameserver_ticks_total", "counter", "World ticks run", &value(ticks.count));
+        metric("gameserver_tick_seconds_max", "gauge", "Longest world tick",
+            &[(String::new(), ticks.max.as_secs_f64().to_string())]);
+    }
+
+    metric("gameserver_messages_received_total", "counter", "Messages received from clients",
+        &value(MESSAGES_RECEIVED.load(Ordering::Relaxed)));
+    metric("gameserver_bytes_received_total", "counter", "Bytes received from clients",
+        &value(BYTES_RECEIVED.load(Ordering::Relaxed)));
+    metric("gameserver_messages_sent_total", "counter", "Messages sent to clients",
+        &value(MESSAGES_SENT.load(Ordering::Relaxed)));
+
+    let per_client = |bytes: &Mutex<BTreeMap<u32, u64>>| bytes.lock().unwrap()
+        .iter()
+        .map(|(id, bytes)| (format!("{{client=\"{}\"}}", id), bytes.to_string()))
+        .collect::<Vec<_>>();
+    metric("gameserver_client_bytes_sent_total", "counter", "Bytes sent to each connected client", &per_client(&BYTES_SENT));
+    metric("gameserver_client_payload_bytes_total", "counter", "Bytes of messages to each connected client before compression",
+        &per_client(&PAYLOAD_BYTES));
+
+    text
+}
+
+
+/// 어떤 경로를 요청해도 지표를 보냄
+pub async fn serve(listener: TcpListener) {
+    loop {
+        let Ok((stream, _addr)) = listener.accept().await else {
+            continue;
+        };
+        tokio::spawn(respond(stream));
+    }
+}
+
+async fn respond(mut stream: TcpStream) {
+    // 요청 내용은 보지 않으므로 헤더가 끝날 때까지만 읽음
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let body = render();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
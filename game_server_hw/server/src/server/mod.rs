@@ -1,3 +1,13 @@
 pub mod tcp_server;
+pub mod config;
 pub mod world;
-pub mod client;
\ No newline at end of file
+pub mod client;
+pub mod session;
+pub mod room;
+pub mod store;
+pub mod matchmaking;
+pub mod admin;
+pub mod rate_limit;
+pub mod metrics;
+pub mod logging;
+pub mod replay;
\ No newline at end of file
@@ -0,0 +1,308 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use network::ClientMessage;
+
+
+/// 종류마다 초당 허용하는 메세지 수를 정하는 환경변수. 0이면 제한하지 않음 (예: `dummy_client`로 부하 테스트)
+pub const MOVE_RATE_ENV: &str = "GAMESERVER_MOVE_RATE";
+pub const UPDATE_RATE_ENV: &str = "GAMESERVER_UPDATE_RATE";
+pub const CHAT_RATE_ENV: &str = "GAMESERVER_CHAT_RATE";
+pub const MESSAGE_RATE_ENV: &str = "GAMESERVER_MESSAGE_RATE";
+
+/// 클라이언트는 `move_interval`(50ms)마다 이동을 보냄. `move_batch`는 이동 수만큼 셈
+pub const DEFAULT_MOVE_RATE: u32 = 30;
+/// UDP로 받을 때 클라이언트는 16ms마다 `update`를 요청함
+pub const DEFAULT_UPDATE_RATE: u32 = 100;
+pub const DEFAULT_CHAT_RATE: u32 = 2;
+/// 그 밖의 메세지 (`ping`, `rooms`, `name` 등)
+pub const DEFAULT_MESSAGE_RATE: u32 = 20;
+
+/// `DROP_WINDOW` 안에 버린 메세지가 이보다 많으면 연결을 끊는 환경변수
+pub const MAX_DROPPED_ENV: &str = "GAMESERVER_MAX_DROPPED";
+pub const DEFAULT_MAX_DROPPED: u32 = 100;
+pub const DROP_WINDOW: Duration = Duration::from_secs(10);
+
+/// 한 번에 몰려와도 허용하는 양. 초당 수의 이 시간만큼
+const BURST: Duration = Duration::from_secs(2);
+
+
+/// 제한을 따로 세는 메세지 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageClass {
+    Move,
+    Update,
+    Chat,
+    Other,
+}
+
+impl MessageClass {
+    /// 연결을 이어가거나 끝내는 메세지는 제한하지 않음
+    pub fn of(message: &ClientMessage) -> Option<Self> {
+        match message {
+            ClientMessage::Move { .. } | ClientMessage::MoveBatch { .. } | ClientMessage::MoveTo { .. } => Some(MessageClass::Move),
+            ClientMessage::Update | ClientMessage::Resync | ClientMessage::DatagramUpdate { .. } => Some(MessageClass::Update),
+            ClientMessage::Chat { .. } => Some(MessageClass::Chat),
+            ClientMessage::Resume { .. } | ClientMessage::Disconnect { .. } => None,
+            _ => Some(MessageClass::Other),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+
+/// 초당 허용하는 메세지 수. 0이면 제한하지 않음
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub move_rate: u32,
+    pub update_rate: u32,
+    pub chat_rate: u32,
+    pub message_rate: u32,
+    /// 0이면 버리기만 하고 끊지 않음
+    pub max_dropped: u32,
+}
+
+impl Limits {
+    pub const DEFAULT: Self = Self {
+        move_rate: DEFAULT_MOVE_RATE,
+        update_rate: DEFAULT_UPDATE_RATE,
+        chat_rate: DEFAULT_CHAT_RATE,
+        message_rate: DEFAULT_MESSAGE_RATE,
+        max_dropped: DEFAULT_MAX_DROPPED,
+    };
+
+    /// 없거나 잘못된 환경변수는 기본값
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u32| std::env::var(name).ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(default);
+
+        Self {
+            move_rate: var(MOVE_RATE_ENV, DEFAULT_MOVE_RATE),
+            update_rate: var(UPDATE_RATE_ENV, DEFAULT_UPDATE_RATE),
+            chat_rate: var(CHAT_RATE_ENV, DEFAULT_CHAT_RATE),
+            message_rate: var(MESSAGE_RATE_ENV, DEFAULT_MESSAGE_RATE),
+            max_dropped: var(MAX_DROPPED_ENV, DEFAULT_MAX_DROPPED),
+        }
+    }
+
+    fn rate(&self, class: MessageClass) -> u32 {
+        match class {
+            MessageClass::Move => self.move_rate,
+            MessageClass::Update => self.update_rate,
+            MessageClass::Chat => self.chat_rate,
+            MessageClass::Other => self.message_rate,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl std::fmt::Display for Limits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rate = |rate: u32| match rate {
+            0 => "unlimited".to_string(),
+            rate => format!("{}/s", rate),
+        };
+        write!(
+            f,
+            "move {}, update {}, chat {}, other {}, ",
+            rate(self.move_rate),
+            rate(self.update_rate),
+            rate(self.chat_rate),
+            rate(self.message_rate),
+        )?;
+        match self.max_dropped {
+            0 => write!(f, "never disconnect"),
+            max => write!(f, "disconnect after {} dropped in {:?}", max, DROP_WINDOW),
+        }
+    }
+}
+
+/// 새 연결에 적용하는 제한
+static LIMITS: Mutex<Limits> = Mutex::new(Limits::DEFAULT);
+
+pub fn set_limits(limits: Limits) {
+    *LIMITS.lock().unwrap() = limits;
+}
+
+pub fn limits() -> Limits {
+    *LIMITS.lock().unwrap()
+}
+
+
+/// 초당 `rate`개씩 채워지고 `BURST`만큼까지 쌓이는 토큰
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, now: Instant) -> Self {
+        let rate = rate as f64;
+        Self { rate, tokens: rate * BURST.as_secs_f64(), last: now }
+    }
+
+    /// 토큰이 `cost`개 이상 있으면 쓰고 `true`
+    fn take(&mut self, cost: f64, now: Instant) -> bool {
+        if self.rate == 0.0 {
+            return true;
+        }
+
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate * BURST.as_secs_f64());
+        self.last = now;
+        if self.tokens < cost {
+            return false;
+        }
+
+        self.tokens -= cost;
+        true
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    /// 처리하지 않고 버림
+    Drop,
+    /// 너무 자주 넘어서 연결을 끊음
+    Disconnect,
+}
+
+/// 연결 하나의 메세지 종류마다 따로 세는 제한
+pub struct RateLimiter {
+    buckets: [TokenBucket; 4],
+    max_dropped: u32,
+    dropped: u32,
+    window_started: Instant,
+}
+
+impl RateLimiter {
+    /// 지금 설정된 `limits`를 사용
+    pub fn new() -> Self {
+        Self::with_limits(limits(), Instant::now())
+    }
+
+    fn with_limits(limits: Limits, now: Instant) -> Self {
+        let bucket = |class| TokenBucket::new(limits.rate(class), now);
+
+        Self {
+            buckets: [
+                bucket(MessageClass::Move),
+                bucket(MessageClass::Update),
+                bucket(MessageClass::Chat),
+                bucket(MessageClass::Other),
+            ],
+            max_dropped: limits.max_dropped,
+            dropped: 0,
+            window_started: now,
+        }
+    }
+
+    pub fn check(&mut self, message: &ClientMessage, now: Instant) -> Verdict {
+        let Some(class) = MessageClass::of(message) else {
+            return Verdict::Allow;
+        };
+        let cost = match message {
+            ClientMessage::MoveBatch { moves, .. } => moves.len().max(1) as f64,
+            _ => 1.0,
+        };
+        if self.buckets[class.index()].take(cost, now) {
+            return Verdict::Allow;
+        }
+
+        if now.saturating_duration_since(self.window_started) > DROP_WINDOW {
+            self.window_started = now;
+            self.dropped = 0;
+        }
+        self.dropped += 1;
+        match self.max_dropped != 0 && self.dropped > self.max_dropped {
+            true => Verdict::Disconnect,
+            false => Verdict::Drop,
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10, start);
+
+        // 처음에는 `BURST`만큼 쌓여 있음
+        for _ in 0..20 {
+            assert!(bucket.take(1.0, start));
+        }
+        assert!(!bucket.take(1.0, start));
+
+        // 초당 10개씩 채워짐
+        assert!(!bucket.take(2.0, start + Duration::from_millis(100)));
+        assert!(bucket.take(2.0, start + Duration::from_millis(200)));
+
+        // 오래 쉬어도 `BURST`보다 많이 쌓이지 않음
+        let later = start + Duration::from_secs(60);
+        assert!(bucket.take(20.0, later));
+        assert!(!bucket.take(1.0, later));
+
+        let mut unlimited = TokenBucket::new(0, start);
+        assert!(unlimited.take(1000.0, start));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let start = Instant::now();
+        let limits = Limits { chat_rate: 1, max_dropped: 3, ..Limits::DEFAULT };
+        let mut limiter = RateLimiter::with_limits(limits, start);
+        let chat = ClientMessage::Chat { id: 0, text: "hi".to_string() };
+
+        assert_eq!(limiter.check(&chat, start), Verdict::Allow);
+        assert_eq!(limiter.check(&chat, start), Verdict::Allow);
+        // 종류마다 따로 셈
+        assert_eq!(limiter.check(&ClientMessage::Update, start), Verdict::Allow);
+        for _ in 0..3 {
+            assert_eq!(limiter.check(&chat, start), Verdict::Drop);
+        }
+        assert_eq!(limiter.check(&chat, start), Verdict::Disconnect);
+
+        // 끊지 않는 연결은 버리기만 함
+        let mut limiter = RateLimiter::with_limits(Limits { max_dropped: 0, ..limits }, start);
+        for _ in 0..10 {
+            assert_ne!(limiter.check(&chat, start), Verdict::Disconnect);
+        }
+        assert_eq!(limiter.check(&ClientMessage::Disconnect { id: 0 }, start), Verdict::Allow);
+    }
+
+    #[test]
+    fn test_move_batch_cost() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::with_limits(Limits { move_rate: 5, ..Limits::DEFAULT }, start);
+        let batch = |n| ClientMessage::MoveBatch { id: 0, seq: 0, moves: vec![(1.0, 0.0); n] };
+
+        // 이동 수만큼 셈
+        assert_eq!(limiter.check(&batch(8), start), Verdict::Allow);
+        assert_eq!(limiter.check(&batch(3), start), Verdict::Drop);
+        assert_eq!(limiter.check(&batch(2), start), Verdict::Allow);
+    }
+}
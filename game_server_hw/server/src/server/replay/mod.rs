@@ -0,0 +1,176 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::broadcast,
+    time::MissedTickBehavior,
+};
+use network::{ServerMessage, UpdateKind};
+
+use super::{
+    logging,
+    room,
+    session,
+    world::WorldInterface,
+};
+
+
+/// 매치의 리플레이를 저장할 디렉토리를 정하는 환경변수. 없으면 기록하지 않음
+pub const REPLAY_DIR_ENV: &str = "GAMESERVER_REPLAYS";
+
+/// 방 전체 상태를 기록하는 간격 (클라이언트에 보내는 간격과 같음)
+pub const RECORD_INTERVAL: Duration = Duration::from_millis(50);
+const SCOREBOARD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 이 시간이 지나도록 아무도 들어오지 않거나, 기록이 이보다 길어지면 끝냄
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_LENGTH: Duration = Duration::from_secs(60 * 60);
+
+
+static DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub fn set_dir(dir: PathBuf) {
+    *DIR.lock().unwrap() = Some(dir);
+}
+
+pub fn dir() -> Option<PathBuf> {
+    DIR.lock().unwrap().clone()
+}
+
+
+/// `dir`이 설정되어 있으면 `room`을 기록하기 시작함. 방에 있던 플레이어가 모두 나가면 끝남
+pub fn start(room: u32, name: &str) {
+    let Some(dir) = dir() else {
+        return;
+    };
+
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let path = dir.join(format!("{}-{}.replay", name, stamp));
+    tokio::spawn(record(room, path));
+}
+
+/// 한 줄에 메세지 하나이며 `<기록을 시작한 뒤 ms> <메세지>`.
+/// 메세지는 클라이언트에 보내는 것과 같지만 관심 영역 없이 방 전체를 기록함
+async fn record(room: u32, path: PathBuf) {
+    let (Some(world), Some(events)) = (room::world_of(room), room::subscribe(room)) else {
+        return;
+    };
+    let file = match std::fs::File::create(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            log::error!(target: logging::SERVER, "Failed to create replay {}: {}", path.display(), e);
+            return;
+        }
+    };
+    log::info!(target: logging::SERVER, "Recording room {} to {}", room, path.display());
+
+    let mut recorder = Recorder {
+        world,
+        file: std::io::BufWriter::new(file),
+        started: Instant::now(),
+        seq: 0,
+        spawned: Vec::new(),
+        names: Vec::new(),
+        colors: Vec::new(),
+    };
+    let map = recorder.world.map();
+    let map = ServerMessage::Map { width: map.width, height: map.height, tiles: map.tiles.clone() };
+    let written = async {
+        recorder.write(&map)?;
+        recorder.run(room, events).await?;
+        recorder.file.flush()
+    }.await;
+
+    match written {
+        Ok(()) => log::info!(target: logging::SERVER, "Saved replay {}", path.display()),
+        Err(e) => log::error!(target: logging::SERVER, "Failed to write replay {}: {}", path.display(), e),
+    }
+}
+
+
+struct Recorder {
+    world: WorldInterface,
+    file: std::io::BufWriter<std::fs::File>,
+    started: Instant,
+    seq: u32,
+    /// 종류를 기록한 오브젝트
+    spawned: Vec<u32>,
+    names: Vec<(u32, String)>,
+    colors: Vec<(u32, String)>,
+}
+
+impl Recorder {
+    fn write(&mut self, message: &ServerMessage) -> std::io::Result<()> {
+        let elapsed = self.started.elapsed().as_millis();
+        write!(self.file, "{} {}", elapsed, message.encode())
+    }
+
+    /// 방에 있던 플레이어가 모두 나가거나 `MAX_LENGTH`가 지날 때까지 기록
+    async fn run(&mut self, room: u32, mut events: broadcast::Receiver<ServerMessage>) -> std::io::Result<()> {
+        let mut snapshots = tokio::time::interval(RECORD_INTERVAL);
+        snapshots.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut scoreboards = tokio::time::interval(SCOREBOARD_INTERVAL);
+        scoreboards.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut joined = false;
+
+        while self.started.elapsed() < MAX_LENGTH {
+            tokio::select! {
+                _ = snapshots.tick() => {
+                    let players = room::list().into_iter()
+                        .find(|info| info.id == room)
+                        .map_or(0, |info| info.players);
+                    joined |= players > 0;
+                    if (joined && players == 0) || (!joined && self.started.elapsed() > JOIN_TIMEOUT) {
+                        break;
+                    }
+                    self.snapshot()?;
+                },
+                _ = scoreboards.tick() => {
+                    let entries = self.world.scoreboard(|id| session::name(id).unwrap_or_else(|| format!("player{}", id)));
+                    self.write(&ServerMessage::Scoreboard(entries))?;
+                },
+                event = events.recv() => match event {
+                    Ok(message) => self.write(&message)?,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {},
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 새 오브젝트의 종류, 전체 위치, 바뀐 이름과 색
+    fn snapshot(&mut self) -> std::io::Result<()> {
+        let objects = self.world.objects();
+        let ids = objects.iter().map(|object| object.id).collect::<Vec<_>>();
+
+        let entities = ids.iter()
+            .filter(|id| !self.spawned.contains(id))
+            .filter_map(|&id| Some((id, self.world.kind_of(id)?)))
+            .collect::<Vec<_>>();
+        if !entities.is_empty() {
+            self.write(&ServerMessage::Spawn(entities))?;
+        }
+        self.spawned = ids.clone();
+
+        self.seq = self.seq.wrapping_add(1);
+        self.write(&ServerMessage::Update { kind: UpdateKind::Rotation, seq: Some(self.seq), objects })?;
+
+        let names = session::names(&ids);
+        if names != self.names {
+            self.write(&ServerMessage::Names(names.clone()))?;
+            self.names = names;
+        }
+        let colors = session::colors(&ids);
+        if colors != self.colors {
+            self.write(&ServerMessage::Colors(colors.clone()))?;
+            self.colors = colors;
+        }
+
+        Ok(())
+    }
+}
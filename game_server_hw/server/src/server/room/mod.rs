@@ -0,0 +1,247 @@
+use std::{sync::Mutex, time::Duration};
+use tokio::sync::broadcast;
+use network::{GameMap, RoomInfo, ServerMessage};
+
+use super::{
+    logging,
+    tcp_server,
+    world::{default_map, World, WorldInterface, NPC_TICK},
+};
+
+
+/// 접속하면 처음 들어가는 방
+pub const LOBBY: u32 = network::LOBBY_ROOM;
+pub const LOBBY_NAME: &str = "lobby";
+
+/// 동시에 있을 수 있는 방의 최대 개수 (로비 제외).
+/// 방마다 월드의 메세지 루프가 돌아가므로 개수를 제한함. 빈 방은 닫히므로 다시 만들 수 있음
+pub const MAX_ROOMS: usize = 100;
+pub const MAX_ROOM_CAPACITY: usize = 64;
+pub const MAX_ROOM_NAME_LEN: usize = 32;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomError {
+    NotFound,
+    Full,
+    TooManyRooms,
+    BadCapacity,
+    BadName,
+}
+
+impl std::fmt::Display for RoomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoomError::NotFound => write!(f, "no such room"),
+            RoomError::Full => write!(f, "room is full"),
+            RoomError::TooManyRooms => write!(f, "too many rooms"),
+            RoomError::BadCapacity => write!(f, "capacity must be 1..={}", MAX_ROOM_CAPACITY),
+            RoomError::BadName => write!(f, "name must be 1..={} characters", MAX_ROOM_NAME_LEN),
+        }
+    }
+}
+
+
+struct Room {
+    id: u32,
+    name: String,
+    capacity: usize,
+    /// 이 방에 있는 플레이어. 연결이 끊겨 `resume`을 기다리는 플레이어도 포함
+    players: Vec<u32>,
+    world: WorldInterface,
+    /// 방에 있는 연결들에 보내는 채팅과 잡기 이벤트 (월드의 `events`)
+    chat: broadcast::Sender<ServerMessage>,
+}
+
+struct Rooms {
+    rooms: Vec<Room>,
+    next_id: u32,
+    /// 새로 만드는 방의 월드에 적용 (로비와 같음). 로비를 열기 전에는 `None`
+    map: Option<GameMap>,
+    interest_radius: i32,
+    npcs: usize,
+    capture: bool,
+    npc_tick: Duration,
+}
+
+impl Rooms {
+    /// `room`이 로비가 아니고 비었으면 목록에서 빼고 월드의 메세지 루프를 끝냄
+    fn close_if_empty(&mut self, room: u32) {
+        let Some(idx) = self.rooms.iter().position(|r| r.id == room && r.id != LOBBY && r.players.is_empty()) else {
+            return;
+        };
+        let room = self.rooms.remove(idx);
+        log::info!(target: logging::SERVER, "Closed empty room {} ({})", room.id, room.name);
+        tokio::spawn(async move { room.world.close().await });
+    }
+}
+
+static ROOMS: Mutex<Rooms> = Mutex::new(Rooms {
+    rooms: Vec::new(),
+    next_id: LOBBY + 1,
+    map: None,
+    interest_radius: 0,
+    npcs: 0,
+    capture: false,
+    npc_tick: NPC_TICK,
+});
+
+
+/// `world`를 로비로 등록. 새로 만드는 방은 로비와 같은 맵, 관심 반경, NPC 수와 틱, 잡기 규칙을 사용
+pub fn open_lobby(world: &World, capacity: usize) {
+    let mut rooms = ROOMS.lock().unwrap();
+    rooms.map = Some(world.map().clone());
+    rooms.interest_radius = world.interest_radius();
+    rooms.npcs = world.npc_count();
+    rooms.capture = world.capture();
+    rooms.npc_tick = world.npc_tick();
+    rooms.rooms.retain(|room| room.id != LOBBY);
+    rooms.rooms.insert(0, Room {
+        id: LOBBY,
+        name: LOBBY_NAME.to_string(),
+        capacity,
+        players: Vec::new(),
+        world: world.interface(),
+        chat: world.events(),
+    });
+}
+
+pub fn list() -> Vec<RoomInfo> {
+    ROOMS.lock().unwrap().rooms
+        .iter()
+        .map(|room| RoomInfo {
+            id: room.id,
+            name: room.name.clone(),
+            players: room.players.len(),
+            capacity: room.capacity,
+        })
+        .collect()
+}
+
+/// 자기 월드를 가진 빈 방을 만들고 id를 반환.
+/// 월드는 메세지 루프를 돌리는 태스크가 가지며, 마지막 플레이어가 나가면 방을 닫고 루프를 끝냄
+pub fn create(name: &str, capacity: usize) -> Result<u32, RoomError> {
+    if !(1..=MAX_ROOM_CAPACITY).contains(&capacity) {
+        return Err(RoomError::BadCapacity);
+    }
+    if name.is_empty() || name.chars().count() > MAX_ROOM_NAME_LEN || name.contains(char::is_whitespace) {
+        return Err(RoomError::BadName);
+    }
+
+    let mut rooms = ROOMS.lock().unwrap();
+    if rooms.rooms.iter().filter(|room| room.id != LOBBY).count() >= MAX_ROOMS {
+        return Err(RoomError::TooManyRooms);
+    }
+
+    let mut world = World::with_map(
+        rooms.map.clone().unwrap_or_else(default_map),
+        rooms.interest_radius,
+    );
+    world.set_npc_tick(rooms.npc_tick);
+    world.spawn_npcs(rooms.npcs);
+    world.set_capture(rooms.capture);
+    let chat = world.events();
+    let interface = world.interface();
+    tokio::spawn(async move { world.run_message_loop().await });
+
+    let id = rooms.next_id;
+    rooms.next_id += 1;
+    rooms.rooms.push(Room {
+        id,
+        name: name.to_string(),
+        capacity,
+        players: Vec::new(),
+        world: interface,
+        chat,
+    });
+
+    Ok(id)
+}
+
+/// 플레이어 `id`를 `room`으로 옮김. 새 방의 월드와 원래 있던 방의 월드를 반환.
+/// 월드의 플레이어는 호출한 쪽에서 옮김. 원래 있던 방이 비면 닫음
+pub fn join(room: u32, id: u32) -> Result<(WorldInterface, Option<WorldInterface>), RoomError> {
+    let mut rooms = ROOMS.lock().unwrap();
+    let target = rooms.rooms.iter().position(|r| r.id == room).ok_or(RoomError::NotFound)?;
+    if rooms.rooms[target].players.contains(&id) {
+        return Ok((rooms.rooms[target].world.clone(), None));
+    }
+    if rooms.rooms[target].players.len() >= rooms.rooms[target].capacity {
+        return Err(RoomError::Full);
+    }
+
+    let mut previous = None;
+    for room in rooms.rooms.iter_mut() {
+        if let Some(idx) = room.players.iter().position(|&player| player == id) {
+            room.players.swap_remove(idx);
+            previous = Some((room.id, room.world.clone()));
+        }
+    }
+    rooms.rooms[target].players.push(id);
+    let world = rooms.rooms[target].world.clone();
+
+    let previous = previous.map(|(room, world)| {
+        rooms.close_if_empty(room);
+        world
+    });
+    Ok((world, previous))
+}
+
+/// `room`의 채팅을 받음. 방을 옮기면 새 방에서 다시 받아야 함
+pub fn subscribe(room: u32) -> Option<broadcast::Receiver<ServerMessage>> {
+    ROOMS.lock().unwrap().rooms
+        .iter()
+        .find(|r| r.id == room)
+        .map(|room| room.chat.subscribe())
+}
+
+/// `room`에 있는 모든 연결에 채팅을 보냄 (보낸 연결 포함)
+pub fn broadcast(room: u32, message: ServerMessage) {
+    if let Some(room) = ROOMS.lock().unwrap().rooms.iter().find(|r| r.id == room) {
+        // 받는 연결이 없으면 실패하지만 버려도 됨
+        let _ = room.chat.send(message);
+    }
+}
+
+/// 모든 방의 플레이어와 그 방의 월드
+pub fn players() -> Vec<(u32, WorldInterface)> {
+    ROOMS.lock().unwrap().rooms
+        .iter()
+        .flat_map(|room| room.players.iter().map(|&id| (id, room.world.clone())))
+        .collect()
+}
+
+pub fn world_of(room: u32) -> Option<WorldInterface> {
+    ROOMS.lock().unwrap().rooms
+        .iter()
+        .find(|r| r.id == room)
+        .map(|room| room.world.clone())
+}
+
+/// 플레이어 `id`가 있는 방과 그 방의 월드
+pub fn room_of(id: u32) -> Option<(u32, WorldInterface)> {
+    ROOMS.lock().unwrap().rooms
+        .iter()
+        .find(|room| room.players.contains(&id))
+        .map(|room| (room.id, room.world.clone()))
+}
+
+/// 플레이어 `id`의 상태를 기록하고 있던 방의 월드와 방에서 제거. 방이 비면 닫음
+pub async fn remove_player(id: u32) {
+    let Some((room, world)) = room_of(id) else {
+        return;
+    };
+    tcp_server::record_player(id, &world);
+    world.remove_player(id).await;
+
+    let mut rooms = ROOMS.lock().unwrap();
+    for room in rooms.rooms.iter_mut() {
+        room.players.retain(|&player| player != id);
+    }
+    rooms.close_if_empty(room);
+}
+
+/// `room`이 로비가 아니고 비었으면 닫음 (예: 아무도 들어오지 않은 매치 방)
+pub fn close_if_empty(room: u32) {
+    ROOMS.lock().unwrap().close_if_empty(room);
+}
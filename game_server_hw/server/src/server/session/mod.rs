@@ -0,0 +1,238 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::store;
+
+
+/// 연결이 끊긴 플레이어를 남겨두는 시간.
+/// 이 안에 같은 토큰으로 `resume`하면 새 플레이어 대신 그 플레이어를 이어서 사용함.
+pub const RESUME_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 이름의 최대 글자 수. 허용하는 글자는 영문, 숫자, `_`, `-`
+pub const MAX_NAME_LEN: usize = 12;
+
+/// 플레이어에게 주는 색. 다른 세션이 쓰지 않는 첫 번째 색이며, 모두 쓰고 있으면 id로 고름
+pub const PLAYER_COLORS: [&str; 8] = [
+    "#e6194b", "#3cb44b", "#ffe119", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6",
+];
+
+
+struct Session {
+    id: u32,
+    token: u64,
+    /// 연결이 끊긴 시각. 연결되어 있으면 `None`
+    detached: Option<Instant>,
+    /// 위치를 UDP로 요청함. 이때는 TCP로 위치를 보내지 않음
+    udp: bool,
+    /// 겹치지 않는 이름. 끊긴 뒤 `resume`을 기다리는 동안에도 유지함
+    name: Option<String>,
+    /// 이름의 저장된 상태를 찾는 열쇠 (`store`). 클라이언트가 `name`에 붙여 보내지 않으면 `token`
+    key: u64,
+    /// `PLAYER_COLORS` 중 하나. 세션이 끝날 때까지 바뀌지 않음
+    color: &'static str,
+}
+
+static SESSIONS: Mutex<Vec<Session>> = Mutex::new(Vec::new());
+
+
+/// 새 연결의 세션을 만들고 토큰을 반환
+pub fn open(id: u32) -> u64 {
+    let token = RandomState::new().build_hasher().finish();
+
+    let mut sessions = SESSIONS.lock().unwrap();
+    sessions.retain(|session| session.id != id);
+    let color = PLAYER_COLORS.iter()
+        .find(|color| !sessions.iter().any(|session| session.color == **color))
+        .unwrap_or(&PLAYER_COLORS[id as usize % PLAYER_COLORS.len()]);
+    sessions.push(Session { id, token, detached: None, udp: false, name: None, key: token, color });
+
+    token
+}
+
+/// 끊긴 세션 `id`의 토큰이 맞으면 다시 연결된 것으로 표시하고 `true`
+pub fn resume(id: u32, token: u64) -> bool {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let Some(session) = sessions.iter_mut().find(|session| session.id == id) else {
+        return false;
+    };
+    if session.token != token || session.detached.is_none() {
+        return false;
+    }
+
+    session.detached = None;
+    true
+}
+
+/// 연결되어 있는 세션 `id`의 토큰이 맞으면 UDP를 사용하는 것으로 표시하고 `true`
+pub fn verify_udp(id: u32, token: u64) -> bool {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let Some(session) = sessions.iter_mut()
+        .find(|session| session.id == id && session.token == token && session.detached.is_none())
+    else {
+        return false;
+    };
+
+    session.udp = true;
+    true
+}
+
+pub fn uses_udp(id: u32) -> bool {
+    SESSIONS.lock().unwrap()
+        .iter()
+        .any(|session| session.id == id && session.udp)
+}
+
+/// 허용하지 않는 글자를 지우고, 다른 세션과 겹치거나 (대소문자 무시) 다른 열쇠로 저장된 이름이면
+/// 뒤에 숫자를 붙여서 등록. 남는 글자가 없으면 `player<id>`. `key`가 있으면 세션의 열쇠도 바꿈.
+/// 등록한 이름을 반환
+pub fn set_name(id: u32, requested: &str, key: Option<u64>) -> String {
+    let mut base = requested.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .take(MAX_NAME_LEN)
+        .collect::<String>();
+    if base.is_empty() {
+        base = format!("player{}", id);
+    }
+
+    let mut sessions = SESSIONS.lock().unwrap();
+    let Some(idx) = sessions.iter().position(|session| session.id == id) else {
+        return base;
+    };
+    if let Some(key) = key {
+        sessions[idx].key = key;
+    }
+    let own_key = sessions[idx].key;
+    let taken = |name: &str| store::is_reserved(name, own_key) || sessions.iter()
+        .any(|session| session.id != id && session.name.as_deref().is_some_and(|other| other.eq_ignore_ascii_case(name)));
+
+    let mut name = base.clone();
+    let mut n = 2;
+    while taken(&name) {
+        let suffix = n.to_string();
+        let prefix = base.chars().take(MAX_NAME_LEN - suffix.len()).collect::<String>();
+        name = format!("{}{}", prefix, suffix);
+        n += 1;
+    }
+
+    sessions[idx].name = Some(name.clone());
+    name
+}
+
+pub fn name(id: u32) -> Option<String> {
+    SESSIONS.lock().unwrap()
+        .iter()
+        .find(|session| session.id == id)
+        .and_then(|session| session.name.clone())
+}
+
+/// 이름이 있는 세션의 이름과 열쇠
+pub fn saved_name(id: u32) -> Option<(String, u64)> {
+    SESSIONS.lock().unwrap()
+        .iter()
+        .find(|session| session.id == id)
+        .and_then(|session| Some((session.name.clone()?, session.key)))
+}
+
+/// `ids` 중 이름이 있는 플레이어의 id와 이름 (id 순서)
+pub fn names(ids: &[u32]) -> Vec<(u32, String)> {
+    let mut names = SESSIONS.lock().unwrap()
+        .iter()
+        .filter(|session| ids.contains(&session.id))
+        .filter_map(|session| Some((session.id, session.name.clone()?)))
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// `ids` 중 세션이 있는 플레이어의 id와 색 (id 순서)
+pub fn colors(ids: &[u32]) -> Vec<(u32, String)> {
+    let mut colors = SESSIONS.lock().unwrap()
+        .iter()
+        .filter(|session| ids.contains(&session.id))
+        .map(|session| (session.id, session.color.to_string()))
+        .collect::<Vec<_>>();
+    colors.sort();
+    colors
+}
+
+/// 연결이 끊긴 것으로 표시하고 그 시각을 반환
+pub fn detach(id: u32) -> Instant {
+    let now = Instant::now();
+    let mut sessions = SESSIONS.lock().unwrap();
+    if let Some(session) = sessions.iter_mut().find(|session| session.id == id) {
+        session.detached = Some(now);
+    }
+
+    now
+}
+
+/// `detached`에 끊긴 뒤로 다시 연결되지 않았으면 세션을 지우고 `true`
+pub fn expire(id: u32, detached: Instant) -> bool {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let Some(idx) = sessions.iter().position(|session| session.id == id && session.detached == Some(detached)) else {
+        return false;
+    };
+
+    sessions.swap_remove(idx);
+    true
+}
+
+pub fn close(id: u32) {
+    SESSIONS.lock().unwrap().retain(|session| session.id != id);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 세션은 모든 테스트가 같이 쓰므로 테스트마다 다른 id와 이름을 씀
+
+    #[test]
+    fn test_resume() {
+        let token = open(9101);
+        // 연결되어 있는 동안에는 이어받을 수 없음
+        assert!(!resume(9101, token));
+
+        let detached = detach(9101);
+        assert!(!resume(9101, token ^ 1));
+        assert!(!resume(9102, token));
+        assert!(resume(9101, token));
+        // 다시 연결되었으므로 지우지 않음
+        assert!(!expire(9101, detached));
+
+        let detached = detach(9101);
+        assert!(expire(9101, detached));
+        assert!(!resume(9101, token));
+    }
+
+    #[test]
+    fn test_set_name() {
+        for id in 9201..=9204 {
+            open(id);
+        }
+
+        assert_eq!(set_name(9201, "Zed!", None), "Zed");
+        assert_eq!(name(9201).as_deref(), Some("Zed"));
+        // 다른 세션과 겹치면 (대소문자 무시) 숫자를 붙임
+        assert_eq!(set_name(9202, "zed", None), "zed2");
+        assert_eq!(set_name(9203, "ZED", None), "ZED3");
+        // 자기 이름과는 겹치지 않음
+        assert_eq!(set_name(9201, "zed", None), "zed");
+
+        // 숫자를 붙여도 `MAX_NAME_LEN`을 넘지 않음
+        assert_eq!(set_name(9203, "verylongname_x", None), "verylongname");
+        assert_eq!(set_name(9204, "verylongname", None), "verylongnam2");
+        assert_eq!(set_name(9204, "  ", None), "player9204");
+        assert_eq!(names(&[9204, 9201]), [(9201, "zed".to_string()), (9204, "player9204".to_string())]);
+
+        for id in 9201..=9204 {
+            close(id);
+        }
+        assert_eq!(name(9201), None);
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::Mutex,
+    time::Duration,
+};
+
+
+/// 플레이어 상태를 저장할 파일 경로를 정하는 환경변수. 없으면 저장하지 않음
+pub const STATE_ENV: &str = "GAMESERVER_STATE";
+
+/// 바뀐 상태를 파일에 쓰는 간격. 서버가 갑자기 끝나도 이보다 오래된 상태만 남음
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+
+/// 서버를 다시 시작해도 남는 플레이어 상태. 버틴 시간은 저장하지 않음.  
+/// 상태는 플레이어가 정한 이름과 처음 저장할 때의 열쇠 (`name`의 `key`, 없으면 세션 토큰)로 찾음.
+/// 다른 열쇠로는 그 상태를 이어받을 수 없고, 그 이름도 다른 플레이어에게 주지 않음
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerState {
+    pub x: i32,
+    pub y: i32,
+    pub captures: u32,
+    pub items: u32,
+}
+
+struct Store {
+    /// `open`하기 전에는 `None`이며 아무것도 기억하지 않음
+    path: Option<PathBuf>,
+    /// 소문자 이름마다 열쇠와 마지막 상태. 이름이 없는 플레이어는 저장하지 않음
+    players: BTreeMap<String, (u64, PlayerState)>,
+    /// 마지막으로 파일에 쓴 뒤 바뀜
+    dirty: bool,
+}
+
+static STORE: Mutex<Store> = Mutex::new(Store {
+    path: None,
+    players: BTreeMap::new(),
+    dirty: false,
+});
+
+
+/// `path`에 저장된 상태를 읽고 이후 `save`할 때 그 파일에 씀. 파일이 없으면 빈 상태로 시작.
+/// 읽은 플레이어 수를 반환하며, 형식이 잘못된 줄은 건너뜀
+pub fn open(path: PathBuf) -> std::io::Result<usize> {
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    // <name> <key> <x> <y> <captures> <items>. 열쇠가 없는 이전 형식의 줄은 누구 것인지 모르므로 버림
+    let players = text.lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [name, key, x, y, captures, items] = fields[..] else {
+                return None;
+            };
+            Some((name.to_ascii_lowercase(), (key.parse().ok()?, PlayerState {
+                x: x.parse().ok()?,
+                y: y.parse().ok()?,
+                captures: captures.parse().ok()?,
+                items: items.parse().ok()?,
+            })))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut store = STORE.lock().unwrap();
+    let count = players.len();
+    store.path = Some(path);
+    store.players = players;
+    store.dirty = false;
+    Ok(count)
+}
+
+/// `key`로 저장한, 이름이 `name`인 (대소문자 무시) 플레이어의 마지막 상태
+pub fn load(name: &str, key: u64) -> Option<PlayerState> {
+    STORE.lock().unwrap().players.get(&name.to_ascii_lowercase())
+        .filter(|(owner, _)| *owner == key)
+        .map(|(_, state)| *state)
+}
+
+/// `name`이 `key`가 아닌 열쇠로 저장된 이름인지
+pub fn is_reserved(name: &str, key: u64) -> bool {
+    STORE.lock().unwrap().players.get(&name.to_ascii_lowercase())
+        .is_some_and(|(owner, _)| *owner != key)
+}
+
+/// 다른 열쇠로 저장된 이름이면 무시함
+pub fn record(name: &str, key: u64, state: PlayerState) {
+    let mut store = STORE.lock().unwrap();
+    if store.path.is_none() {
+        return;
+    }
+
+    let name = name.to_ascii_lowercase();
+    if store.players.get(&name).is_some_and(|(owner, _)| *owner != key) {
+        return;
+    }
+    if store.players.insert(name, (key, state)) != Some((key, state)) {
+        store.dirty = true;
+    }
+}
+
+/// 바뀐 것이 있으면 파일에 씀. 쓰는 도중에 서버가 끝나도 이전 파일이 남도록 다른 파일에 쓰고 바꿈
+pub fn save() -> std::io::Result<()> {
+    let (path, text) = {
+        let mut store = STORE.lock().unwrap();
+        let Some(path) = store.path.clone().filter(|_| store.dirty) else {
+            return Ok(());
+        };
+        store.dirty = false;
+
+        let text = store.players.iter()
+            .map(|(name, (key, PlayerState { x, y, captures, items }))| format!("{} {} {} {} {} {}\n", name, key, x, y, captures, items))
+            .collect::<String>();
+        (path, text)
+    };
+
+    let temp = path.with_extension("tmp");
+    let written = std::fs::write(&temp, text).and_then(|_| std::fs::rename(&temp, &path));
+    if written.is_err() {
+        // 다음 `save`에서 다시 시도
+        STORE.lock().unwrap().dirty = true;
+    }
+    written
+}
+
+/// `save`를 blocking 스레드에서 실행. 파일을 쓰는 동안 tokio 작업 스레드를 막지 않음
+pub async fn save_async() -> std::io::Result<()> {
+    tokio::task::spawn_blocking(save).await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::session;
+
+    #[test]
+    fn test_open_and_save() {
+        let path = std::env::temp_dir().join(format!("gameserver-state-{}.txt", std::process::id()));
+        let text = "\
+keeper 7 1 2 3 4
+Ghost 8 5 6 0 1
+legacy 1 2 3 4
+broken 7 x 2 3 4
+";
+        std::fs::write(&path, text).unwrap();
+        assert_eq!(open(path.clone()).unwrap(), 2);
+
+        let keeper = PlayerState { x: 1, y: 2, captures: 3, items: 4 };
+        assert_eq!(load("KEEPER", 7), Some(keeper));
+        assert_eq!(load("ghost", 8), Some(PlayerState { x: 5, y: 6, captures: 0, items: 1 }));
+        // 열쇠가 다르면 이어받을 수 없고, 열쇠가 없던 줄은 버림
+        assert_eq!(load("keeper", 8), None);
+        assert_eq!(load("legacy", 1), None);
+        assert!(is_reserved("keeper", 8));
+        assert!(!is_reserved("keeper", 7));
+        assert!(!is_reserved("nobody", 7));
+
+        // 다른 열쇠로 저장된 이름은 다른 세션에 주지 않음
+        session::open(9301);
+        assert_eq!(session::set_name(9301, "ghost", Some(1)), "ghost2");
+        assert_eq!(session::set_name(9301, "ghost", Some(8)), "ghost");
+        session::close(9301);
+
+        let moved = PlayerState { x: 0, y: 7, captures: 4, items: 4 };
+        record("keeper", 8, PlayerState { x: 0, y: 0, captures: 0, items: 0 });
+        record("Keeper", 7, moved);
+        record("newbie", 9, keeper);
+        save().unwrap();
+
+        assert_eq!(open(path.clone()).unwrap(), 3);
+        assert_eq!(load("keeper", 7), Some(moved));
+        assert_eq!(load("newbie", 9), Some(keeper));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
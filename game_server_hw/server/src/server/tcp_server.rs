@@ -1,31 +1,137 @@
-use tokio::net::{TcpListener, TcpStream};
-use std::sync::Mutex;
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+use network::{ClientMessage, GameMap, ServerMessage, UpdateKind};
 
 use super::{
     world::*,
+    admin,
     client::Client,
+    config::ServerConfig,
+    matchmaking,
+    metrics,
+    rate_limit::{self, RateLimiter, Verdict},
+    replay,
+    room,
+    logging,
+    session,
+    store,
 };
 
 
 
-pub async fn run_server(ip: &str, port: u16) {
-    let addr = format!("{}:{}", ip, port);
-    let tcp_listener = TcpListener::bind(addr.clone()).await
+pub async fn run_server(config: ServerConfig) {
+    let addr = format!("{}:{}", get_addr::resolve_host(&config.host), config.port);
+    let tcp_listener = TcpListener::bind(addr).await
         .expect("Failed to bind tcp listener");
 
-    println!("Tcp server - listening on: {}", tcp_listener.local_addr().unwrap());
+    serve(tcp_listener, config).await;
+}
+
+/// 이미 연 `tcp_listener`로 서버를 실행 (예: 테스트에서 `127.0.0.1:0`). UDP는 같은 주소에 엶.
+/// `config`의 주소는 쓰지 않음
+pub async fn serve(tcp_listener: TcpListener, config: ServerConfig) {
+    let addr = tcp_listener.local_addr().expect("Failed to get listener address");
+    log::info!(target: logging::NET, "Tcp server - listening on: {}", tcp_listener.local_addr().unwrap());
+
+    log::info!(target: logging::SERVER, "Interest radius: {} cells of {}", config.interest_radius, CELL_SIZE);
+    log::info!(target: logging::SERVER, "NPCs per room: {}", config.npcs);
+    log::info!(target: logging::SERVER, "Capture: {}", config.capture);
+    log::info!(target: logging::SERVER, "Tick rate: {}/s", config.tick_rate);
 
-    let mut world = World::new();
+    MAX_PLAYERS.store(config.max_players.clamp(1, MAX_CLIENTS), Ordering::Relaxed);
+    log::info!(target: logging::SERVER, "Max players: {}", max_players());
+
+    matchmaking::set_match_size(config.match_size);
+    log::info!(target: logging::SERVER, "Match size: {}", matchmaking::match_size());
+
+    rate_limit::set_limits(rate_limit::Limits::from_env());
+    log::info!(target: logging::SERVER, "Rate limits: {}", rate_limit::limits());
+
+    let map = match &config.map {
+        Some(path) => match std::fs::read_to_string(path).map_err(|e| e.to_string())
+            .and_then(|text| GameMap::parse(&text).map_err(|e| e.to_string()))
+        {
+            Ok(map) => map,
+            Err(e) => {
+                log::error!(target: logging::SERVER, "Failed to load map {}: {}", path.display(), e);
+                return;
+            }
+        },
+        None => default_map(),
+    };
+    log::info!(target: logging::SERVER, "Map: {}x{}", map.width, map.height);
+
+    match std::env::var(store::STATE_ENV) {
+        Ok(path) => match store::open(path.clone().into()) {
+            Ok(count) => {
+                log::info!(target: logging::SERVER, "Player state: {} ({} players)", path, count);
+                tokio::spawn(save_player_states());
+            }
+            Err(e) => {
+                log::error!(target: logging::SERVER, "Failed to load player state {}: {}", path, e);
+                return;
+            }
+        },
+        Err(_) => log::info!(target: logging::SERVER, "Player state: not saved"),
+    }
+
+    match std::env::var(replay::REPLAY_DIR_ENV) {
+        Ok(dir) => match std::fs::create_dir_all(&dir) {
+            Ok(()) => {
+                log::info!(target: logging::SERVER, "Match replays: {}", dir);
+                replay::set_dir(dir.into());
+            }
+            Err(e) => log::error!(target: logging::SERVER, "Failed to create replay directory {}: {}", dir, e),
+        },
+        Err(_) => log::info!(target: logging::SERVER, "Match replays: not recorded"),
+    }
+
+    let mut world = World::with_map(map, config.interest_radius);
+    world.set_npc_tick(config.tick_interval());
+    world.spawn_npcs(config.npcs);
+    world.set_capture(config.capture);
+    room::open_lobby(&world, max_players());
 
     {
-        tokio::spawn(wait_for_players(tcp_listener, (&world).into()));
+        tokio::spawn(wait_for_players(tcp_listener, world.interface()));
+    }
+
+    // TCP와 같은 포트에서 UDP update 요청을 받음
+    match UdpSocket::bind(addr).await {
+        Ok(socket) => {
+            log::info!(target: logging::NET, "Udp server - listening on: {}", socket.local_addr().unwrap());
+            tokio::spawn(serve_datagrams(socket));
+        },
+        Err(e) => log::error!(target: logging::NET, "Failed to bind udp socket: {}", e),
+    }
+
+    if let Ok(addr) = std::env::var(metrics::METRICS_ENV) {
+        match TcpListener::bind(&addr).await {
+            Ok(listener) => {
+                log::info!(target: logging::NET, "Metrics - listening on: http://{}/metrics", listener.local_addr().unwrap());
+                tokio::spawn(metrics::serve(listener));
+            }
+            Err(e) => log::error!(target: logging::NET, "Failed to bind metrics listener {}: {}", addr, e),
+        }
     }
 
+    tokio::spawn(admin::run_console());
+    tokio::spawn(shutdown_on_interrupt());
+
     world.run_message_loop().await; 
 }
 
 
-const MAX_CLIENTS: usize =  10000;
+/// `max_players`로 정할 수 있는 최대 연결 수
+pub const MAX_CLIENTS: usize =  10000;
+/// 설정의 `max_players`. 자리 번호 (연결 id)는 이보다 작음
+static MAX_PLAYERS: AtomicUsize = AtomicUsize::new(MAX_CLIENTS);
 /// World를 직접 읽으면 최신 데이터가 아닐 가능성이 있다.  
 /// World에 Mutex, RwLock등을 걸면 클라이언트가 읽는데 병목이 생길 수 있다.  
 /// 따라서 클라이언트 개수만 세기 위해 따로 분리.  
@@ -34,50 +140,162 @@ static CLIENT_SLOTS: Mutex<[Option<()>; MAX_CLIENTS]> = Mutex::new([None; MAX_CL
 
 
 /// Listens for incoming connections
-async fn wait_for_players(listener: TcpListener, world: WorldPointer) {
+async fn wait_for_players(listener: TcpListener, world: WorldInterface) {
     loop {
-        // println!("Waiting for connection...");
         match listener.accept().await {
-            Ok((stream, _addr)) => {
+            Ok((stream, addr)) => {
                 let mut slots = CLIENT_SLOTS.lock().unwrap();
                 let mut accepted = false;
 
-                for id in 0..MAX_CLIENTS {
+                for id in 0..max_players() {
                     if slots[id].is_none() {
                         slots[id] = Some(());
-                        // println!("Accepted connection from: {}", addr);
+                        log::debug!(target: logging::NET, "Accepted connection {} from {}", id, addr);
                         accepted = true;
-                        tokio::spawn(handle_connection(id as u32, stream, world));
+                        tokio::spawn(handle_connection(id as u32, stream, world.clone()));
                         break;
                     }
                 }
                 if !accepted {
-                    // println!("Connection from {} refused; server full", addr);
+                    log::warn!(target: logging::NET, "Connection from {} refused; server full", addr);
                 }
             },
-            Err(_) => {
-                // eprintln!("Failed to accept connection; err = {:?}", e);
+            Err(e) => {
+                log::warn!(target: logging::NET, "Failed to accept connection: {}", e);
             }
         }
     }
 }
 
 
-async fn handle_connection(id: u32, stream: TcpStream, world: WorldPointer) {
-    let mut client = Client::new(id, stream, WorldInterface::new(world));
+async fn handle_connection(id: u32, stream: TcpStream, world: WorldInterface) {
+    let mut client = Client::new(id, stream, world);
 
     {
         let slots = CLIENT_SLOTS.lock().unwrap();
-        println!("num clients: {}", slots.iter().filter(|x| x.is_some()).count());
+        log::info!(target: logging::NET, "num clients: {}", slots.iter().filter(|x| x.is_some()).count());
     }
     
     client.handle_connection().await;
+    let id = client.id();
 
-    {
-        let mut slots = CLIENT_SLOTS.lock().unwrap();
-        slots[id as usize] = None;
-        println!("Connection {} closed", id);
+    // 직접 종료하지 않았으면 다시 접속할 수 있도록 자리와 플레이어를 잠시 남겨둠.
+    // 그 사이에 다른 연결이 이어서 사용하면 그 연결이 나중에 정리함
+    if !client.disconnected() {
+        let detached = session::detach(id);
+        tokio::time::sleep(session::RESUME_TIMEOUT).await;
+        // 세션이 지워지면 이름을 알 수 없으므로 먼저 기록
+        if let Some((_, world)) = room::room_of(id) {
+            record_player(id, &world);
+        }
+        if !session::expire(id, detached) {
+            return;
+        }
+        room::remove_player(id).await;
+    }
+
+    release_slot(id);
+}
 
-        println!("num clients: {}", slots.iter().filter(|x| x.is_some()).count());
+/// UDP로 `update`를 요청하는 세션의 일련번호와 제한
+struct DatagramClient {
+    token: u64,
+    seq: u32,
+    limiter: RateLimiter,
+}
+
+/// UDP로 받은 `update` 요청에 UDP로 관심 영역 안의 전체 상태를 보냄.
+/// 잃어버리거나 순서가 바뀐 응답은 클라이언트가 일련번호로 걸러내므로 delta를 쓰지 않음
+async fn serve_datagrams(socket: UdpSocket) {
+    // 자리 id마다 하나. 토큰이 바뀌면 자리를 새 세션이 쓰는 것이므로 처음부터 셈
+    let mut clients: HashMap<u32, DatagramClient> = HashMap::new();
+    let mut buf = [0; 1024];
+
+    loop {
+        let Ok((n, addr)) = socket.recv_from(&mut buf).await else {
+            continue;
+        };
+        metrics::record_bytes_received(n);
+        metrics::record_message_received();
+        let msg = String::from_utf8_lossy(&buf[..n]);
+        let Some(message @ ClientMessage::DatagramUpdate { id, token }) = ClientMessage::decode(msg.trim_end()) else {
+            continue;
+        };
+        if !session::verify_udp(id, token) {
+            continue;
+        }
+        let fresh = || DatagramClient { token, seq: 0, limiter: RateLimiter::new() };
+        let client = clients.entry(id).or_insert_with(fresh);
+        if client.token != token {
+            *client = fresh();
+        }
+        // UDP로는 연결을 끊을 수 없으므로 넘는 요청은 버리기만 함
+        if client.limiter.check(&message, std::time::Instant::now()) != Verdict::Allow {
+            continue;
+        }
+
+        client.seq = client.seq.wrapping_add(1);
+        let Some((_, world)) = room::room_of(id) else {
+            continue;
+        };
+        let objects = world.objects_near(id);
+        let message = ServerMessage::Update { kind: UpdateKind::Rotation, seq: Some(client.seq), objects }.encode();
+        metrics::record_sent(id, message.len(), message.len());
+        if let Err(e) = socket.send_to(message.as_bytes(), addr).await {
+            log::debug!(target: logging::NET, "Failed to send datagram to {}: {}", addr, e);
+        }
+    }
+}
+
+/// Ctrl+C (SIGINT)를 받으면 클라이언트에 알리고 종료
+async fn shutdown_on_interrupt() {
+    match tokio::signal::ctrl_c().await {
+        Ok(()) => admin::shutdown().await,
+        Err(e) => log::error!(target: logging::SERVER, "Failed to listen for interrupt: {}", e),
     }
 }
+
+/// `SAVE_INTERVAL`마다 이름이 있는 플레이어의 상태를 기록하고 파일에 씀
+async fn save_player_states() {
+    let mut interval = tokio::time::interval(store::SAVE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+        for (id, world) in room::players() {
+            record_player(id, &world);
+        }
+        if let Err(e) = store::save_async().await {
+            log::error!(target: logging::SERVER, "Failed to save player state: {}", e);
+        }
+    }
+}
+
+/// 이름이 있으면 `world`에서의 플레이어 `id` 상태를 기록 (파일에는 다음 `save`에 씀)
+pub fn record_player(id: u32, world: &WorldInterface) {
+    let Some((name, key)) = session::saved_name(id) else {
+        return;
+    };
+    if let Some(state) = world.player_state(id) {
+        store::record(&name, key, state);
+    }
+}
+
+/// 동시에 받는 최대 연결 수
+pub fn max_players() -> usize {
+    MAX_PLAYERS.load(Ordering::Relaxed)
+}
+
+/// 자리를 쓰고 있는 연결 수. 끊긴 채 `resume`을 기다리는 플레이어도 포함
+pub fn client_count() -> usize {
+    CLIENT_SLOTS.lock().unwrap().iter().filter(|x| x.is_some()).count()
+}
+
+/// 연결 `id`의 자리를 비움
+pub fn release_slot(id: u32) {
+    let mut slots = CLIENT_SLOTS.lock().unwrap();
+    slots[id as usize] = None;
+    log::info!(target: logging::NET, "Connection {} closed", id);
+
+    log::info!(target: logging::NET, "num clients: {}", slots.iter().filter(|x| x.is_some()).count());
+}
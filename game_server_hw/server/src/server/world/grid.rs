@@ -0,0 +1,70 @@
+//! 보드 칸을 `cell_size`칸씩 묶은 격자. 격자 칸마다 그 안의 오브젝트 id를 모아서
+//! "(x, y) 근처의 오브젝트"를 전체 오브젝트를 훑지 않고 찾음.
+
+use std::collections::HashMap;
+
+
+/// 격자 칸 좌표
+pub type Cell = (i32, i32);
+
+
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: i32,
+    cells: HashMap<Cell, Vec<u32>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: i32) -> Self {
+        Self { cell_size: cell_size.max(1), cells: HashMap::new() }
+    }
+
+    /// 보드 칸 `(x, y)`가 들어있는 격자 칸
+    pub fn cell_of(&self, (x, y): (i32, i32)) -> Cell {
+        (x.div_euclid(self.cell_size), y.div_euclid(self.cell_size))
+    }
+
+    pub fn insert(&mut self, id: u32, tile: (i32, i32)) {
+        self.cells.entry(self.cell_of(tile)).or_default().push(id);
+    }
+
+    pub fn remove(&mut self, id: u32, tile: (i32, i32)) {
+        let cell = self.cell_of(tile);
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.retain(|&other| other != id);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// 같은 격자 칸 안에서 움직이면 아무것도 하지 않음
+    pub fn relocate(&mut self, id: u32, from: (i32, i32), to: (i32, i32)) {
+        if self.cell_of(from) != self.cell_of(to) {
+            self.remove(id, from);
+            self.insert(id, to);
+        }
+    }
+
+    /// `center` 격자 칸에서 가로세로 `radius` 격자 칸 안에 있는 id
+    pub fn in_cells(&self, center: Cell, radius: i32) -> impl Iterator<Item = u32> + '_ {
+        let (cx, cy) = center;
+        (cx - radius..=cx + radius)
+            .flat_map(move |x| (cy - radius..=cy + radius).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+
+    /// 보드 칸 `tile`에서 가로세로 `range`칸 안을 덮는 격자 칸들의 id.
+    /// 범위 밖의 오브젝트도 섞여 있으므로 정확한 거리는 호출한 쪽에서 확인
+    pub fn near(&self, (x, y): (i32, i32), range: i32) -> impl Iterator<Item = u32> + '_ {
+        let (min_x, min_y) = self.cell_of((x - range, y - range));
+        let (max_x, max_y) = self.cell_of((x + range, y + range));
+        (min_x..=max_x)
+            .flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
@@ -1,149 +1,883 @@
-use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{broadcast, mpsc, watch},
+    time::MissedTickBehavior,
+};
+use network::{facing_yaw, EntityKind, GameMap, ObjectState, ScoreEntry, ServerMessage};
 
+use super::{logging, metrics, store::PlayerState};
 
-struct Player {
+mod grid;
+use grid::SpatialGrid;
+
+
+/// 맵 파일 경로를 정하는 환경변수. 없으면 `DEFAULT_MAP`
+pub const MAP_ENV: &str = "GAMESERVER_MAP";
+
+/// 맵 파일이 없을 때의 8x8 보드 (`GameMap::parse` 형식).
+/// 플레이어는 `S` 칸에 생기고, 다른 오브젝트가 있으면 가장 가까운 빈 칸에 생김
+pub const DEFAULT_MAP: &str = "\
+........
+.o....*.
+........
+...S....
+........
+........
+.*....o.
+........
+";
+
+/// 관심 영역 격자의 한 칸의 크기 (보드 칸 단위)
+pub const CELL_SIZE: i32 = 2;
+
+/// 기본 관심 반경 (격자 칸 단위). 8x8 보드에서는 모든 플레이어가 보임
+pub const DEFAULT_INTEREST_RADIUS: i32 = 2;
+
+/// 관심 반경을 바꾸는 환경변수
+pub const INTEREST_RADIUS_ENV: &str = "GAMESERVER_INTEREST_RADIUS";
+
+/// 플레이어가 아닌 오브젝트의 첫 id. 플레이어 id(연결 자리 번호)와 겹치지 않음
+pub const FIRST_ENTITY_ID: u32 = 1 << 20;
+
+/// NPC 수를 바꾸는 환경변수
+pub const NPCS_ENV: &str = "GAMESERVER_NPCS";
+pub const DEFAULT_NPCS: usize = 2;
+
+/// NPC가 한 칸씩 움직이는 간격
+pub const NPC_TICK: Duration = Duration::from_millis(500);
+
+/// NPC는 이 거리 (보드 칸 단위, 가로세로 중 먼 쪽) 안의 가장 가까운 플레이어를 쫓고, 없으면 돌아다님
+pub const NPC_CHASE_RANGE: i32 = 3;
+
+/// `1`이나 `true`이면 다른 플레이어나 NPC가 있는 칸으로 움직여 잡을 수 있음.
+/// 아니면 다른 오브젝트가 있는 칸으로는 움직이지 못함
+pub const CAPTURE_ENV: &str = "GAMESERVER_CAPTURE";
+
+/// `move_to`로 목적지를 정한 플레이어가 한 칸씩 움직이는 간격
+pub const PATH_STEP_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 이 간격마다 보드에 아이템이 `MAX_ITEMS`개보다 적으면 아무 빈 칸에 하나 놓음
+pub const ITEM_SPAWN_INTERVAL: Duration = Duration::from_secs(5);
+pub const MAX_ITEMS: usize = 4;
+
+/// 다른 플레이어나 NPC를 한 번 잡을 때의 점수
+pub const CAPTURE_POINTS: u32 = 10;
+/// 아이템 하나를 주울 때의 점수
+pub const ITEM_POINTS: u32 = 5;
+/// 잡히지 않고 이 시간을 버틸 때마다 1점
+pub const SURVIVAL_POINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 방의 연결들에 보내는 채팅과 이벤트를 쌓아두는 개수. 넘치면 느린 연결은 오래된 것을 놓침
+const EVENT_BUFFER: usize = 64;
+
+
+/// `DEFAULT_MAP`
+pub fn default_map() -> GameMap {
+    GameMap::parse(DEFAULT_MAP).expect("default map is valid")
+}
+
+
+#[derive(Clone)]
+struct Entity {
+    kind: EntityKind,
     x: i32,
     y: i32,
+    /// 마지막으로 움직인 방향 (y축 회전, 도). 처음에는 0 (+y 쪽)
+    yaw: f32,
+}
+
+impl Entity {
+    fn new(kind: EntityKind, x: i32, y: i32) -> Self {
+        Self { kind, x, y, yaw: 0.0 }
+    }
+
+    fn state(&self, id: u32) -> ObjectState {
+        ObjectState { id, x: self.x, y: 0, z: self.y, yaw: Some(self.yaw) }
+    }
 }
 
+/// 플레이어 한 명의 점수. 방을 옮기면 새로 시작함
+#[derive(Clone)]
+struct Score {
+    captures: u32,
+    items: u32,
+    /// 마지막으로 잡힌 (또는 들어온) 시각
+    alive_since: Instant,
+}
 
-pub type WorldPointer = usize;
+impl Score {
+    fn new() -> Self {
+        Self { captures: 0, items: 0, alive_since: Instant::now() }
+    }
+
+    fn survival(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.alive_since)
+    }
+
+    fn points(&self, now: Instant) -> u32 {
+        let survival = self.survival(now).as_secs() / SURVIVAL_POINT_INTERVAL.as_secs();
+        self.captures * CAPTURE_POINTS + self.items * ITEM_POINTS + survival as u32
+    }
+}
+
+/// 아이템 외의 오브젝트는 한 칸에 하나만 있을 수 있음
+fn occupies(kind: EntityKind) -> bool {
+    kind != EntityKind::Item
+}
 
 
 pub struct World {
-    players: HashMap<u32, Player>,
+    /// 보드의 크기와 벽. 월드가 끝날 때까지 바뀌지 않음
+    map: Arc<GameMap>,
+    /// 플레이어와 플레이어가 아닌 오브젝트
+    entities: HashMap<u32, Entity>,
+    /// `CELL_SIZE` 격자 칸마다 그 안에 있는 오브젝트 id. 관심 영역, 아이템, NPC 추적에 씀
+    grid: SpatialGrid,
+    /// 보드 칸마다 그 칸을 차지한 오브젝트 id (`occupies`)
+    occupied: HashMap<(i32, i32), u32>,
+    /// 플레이어의 점수
+    scores: HashMap<u32, Score>,
+    /// `move_to`로 정한 플레이어의 목적지. 도착하거나 길이 막히거나 직접 움직이면 지움
+    destinations: HashMap<u32, (i32, i32)>,
+    next_entity_id: u32,
+    capture: bool,
+    /// 방에 있는 연결들에 보내는 채팅과 잡기 이벤트. 방의 `subscribe`로 받음
+    events: broadcast::Sender<ServerMessage>,
+    /// NPC의 움직임과 위치에 쓰는 xorshift 상태
+    rng: u64,
+    /// 플레이어의 칸에서 이 반경 (격자 칸 단위) 안의 오브젝트만 그 클라이언트에 보냄
+    interest_radius: i32,
+    /// NPC를 움직이는 간격 (설정의 `tick_rate`)
+    npc_tick: Duration,
     sender: mpsc::Sender<String>, 
     receiver: mpsc::Receiver<String>,
+    /// 메세지를 처리할 때마다 공개하는 상태. `WorldInterface`가 읽음
+    snapshot: watch::Sender<Arc<Snapshot>>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
     pub fn new() -> Self {
+        Self::with_interest_radius(DEFAULT_INTEREST_RADIUS)
+    }
+
+    pub fn with_interest_radius(interest_radius: i32) -> Self {
+        Self::with_map(default_map(), interest_radius)
+    }
+
+    /// `map`의 보드를 쓰고 맵에 있는 오브젝트를 놓은 월드
+    pub fn with_map(map: GameMap, interest_radius: i32) -> Self {
         let (sender, receiver) = mpsc::channel(128);
-        Self {
-            players: HashMap::new(),
+        let entities = map.entities.clone();
+        let mut world = Self {
+            map: Arc::new(map),
+            entities: HashMap::new(),
+            grid: SpatialGrid::new(CELL_SIZE),
+            occupied: HashMap::new(),
+            scores: HashMap::new(),
+            destinations: HashMap::new(),
+            next_entity_id: FIRST_ENTITY_ID,
+            capture: false,
+            events: broadcast::channel(EVENT_BUFFER).0,
+            // xorshift는 0에서 벗어나지 못함
+            rng: RandomState::new().build_hasher().finish() | 1,
+            interest_radius,
+            npc_tick: NPC_TICK,
             sender,
             receiver,
+            snapshot: watch::channel(Arc::new(Snapshot {
+                entities: HashMap::new(),
+                grid: SpatialGrid::new(CELL_SIZE),
+                scores: HashMap::new(),
+                interest_radius,
+            })).0,
+        };
+        for (kind, x, y) in entities {
+            world.spawn_entity(kind, x, y);
         }
+        world.publish();
+        world
     }
 
+    /// 메세지를 처리하고 `npc_tick`마다 NPC를 움직이고, `PATH_STEP_INTERVAL`마다 목적지로 가는 플레이어를 움직이고,
+    /// `ITEM_SPAWN_INTERVAL`마다 아이템을 놓음. 바뀐 상태는 그때마다 `publish`함.
+    /// `close` 메세지를 받으면 끝남
     pub async fn run_message_loop(&mut self) {
+        let mut npc_tick = tokio::time::interval(self.npc_tick);
+        npc_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut path_tick = tokio::time::interval(PATH_STEP_INTERVAL);
+        path_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut item_tick = tokio::time::interval(ITEM_SPAWN_INTERVAL);
+        item_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        self.publish();
+
         loop {
-            match self.receiver.recv().await {
-                Some(msg) => {
-                    // println!("channel received: {}", msg);
-
-                    let msg = msg.split_whitespace()
-                        .collect::<Vec<&str>>();
-                
-                    match msg[0] {
-                        "add" => {
-                            let id = msg[1].parse::<u32>().unwrap();
-                            self.add_player(id);
-                        },
-                        
-                        "move" => {
-                            let id = msg[1].parse::<u32>().unwrap();
-                            let x = msg[2].parse::<i32>().unwrap();
-                            let y = msg[3].parse::<i32>().unwrap();
-                            self.move_player(id, x, y);
-                        },
-                
-                        "remove" => {
-                            let id = msg[1].parse::<u32>().unwrap();
-                            self.remove_player(id);
-                        },
-                
-                        _ => {}
-                    }
+            let msg = tokio::select! {
+                msg = self.receiver.recv() => msg,
+                _ = npc_tick.tick() => {
+                    let started = Instant::now();
+                    self.update_npcs();
+                    log::trace!(target: logging::WORLD, "NPC tick took {:?}", started.elapsed());
+                    metrics::record_tick(started.elapsed());
+                    self.publish();
+                    continue;
+                },
+                _ = path_tick.tick(), if !self.destinations.is_empty() => {
+                    let started = Instant::now();
+                    self.step_destinations();
+                    log::trace!(target: logging::WORLD, "Path tick took {:?}", started.elapsed());
+                    metrics::record_tick(started.elapsed());
+                    self.publish();
+                    continue;
+                },
+                _ = item_tick.tick() => {
+                    let started = Instant::now();
+                    self.spawn_item();
+                    log::trace!(target: logging::WORLD, "Item tick took {:?}", started.elapsed());
+                    metrics::record_tick(started.elapsed());
+                    self.publish();
+                    continue;
                 },
-                None => {
-                    // println!("channel closed");
+            };
+            let Some(msg) = msg else {
+                break;
+            };
+            // 쌓인 메세지를 모두 처리한 뒤 한 번만 공개
+            let mut open = self.handle_message(&msg);
+            while open {
+                let Ok(msg) = self.receiver.try_recv() else {
                     break;
-                }
+                };
+                open = self.handle_message(&msg);
+            }
+            self.publish();
+            if !open {
+                break;
             }
         }
+        log::debug!(target: logging::WORLD, "Message loop closed");
     }
 
+    /// `WorldInterface`가 보낸 메세지 하나를 처리. `close`이면 `false`
+    fn handle_message(&mut self, msg: &str) -> bool {
+        log::trace!(target: logging::WORLD, "Received: {}", msg);
+
+        let msg = msg.split_whitespace()
+            .collect::<Vec<&str>>();
+
+        match msg[0] {
+            "add" => {
+                let id = msg[1].parse::<u32>().unwrap();
+                self.add_player(id);
+            },
+
+            // 직접 움직이면 목적지로 가던 길은 멈춤
+            "move" => {
+                let id = msg[1].parse::<u32>().unwrap();
+                let x = msg[2].parse::<i32>().unwrap();
+                let y = msg[3].parse::<i32>().unwrap();
+                self.destinations.remove(&id);
+                self.move_player(id, x, y);
+            },
+
+            "move_to" => {
+                let id = msg[1].parse::<u32>().unwrap();
+                let x = msg[2].parse::<i32>().unwrap();
+                let y = msg[3].parse::<i32>().unwrap();
+                self.set_destination(id, (x, y));
+            },
+
+            "remove" => {
+                let id = msg[1].parse::<u32>().unwrap();
+                self.remove_player(id);
+            },
+
+            "place" => {
+                let id = msg[1].parse::<u32>().unwrap();
+                let x = msg[2].parse::<i32>().unwrap();
+                let y = msg[3].parse::<i32>().unwrap();
+                self.place_player(id, (x, y));
+            },
+
+            "restore" => {
+                let id = msg[1].parse::<u32>().unwrap();
+                let state = PlayerState {
+                    x: msg[2].parse().unwrap(),
+                    y: msg[3].parse().unwrap(),
+                    captures: msg[4].parse().unwrap(),
+                    items: msg[5].parse().unwrap(),
+                };
+                self.restore_player(id, state);
+            },
+
+            "close" => return false,
+
+            _ => {}
+        }
+        true
+    }
+
+
+    pub fn map(&self) -> &GameMap {
+        &self.map
+    }
+
+    pub fn interest_radius(&self) -> i32 {
+        self.interest_radius
+    }
+
+    pub fn capture(&self) -> bool {
+        self.capture
+    }
+
+    pub fn npc_tick(&self) -> Duration {
+        self.npc_tick
+    }
+
+    pub fn set_npc_tick(&mut self, interval: Duration) {
+        self.npc_tick = interval;
+    }
+
+    pub fn set_capture(&mut self, capture: bool) {
+        self.capture = capture;
+    }
+
+    pub fn events(&self) -> broadcast::Sender<ServerMessage> {
+        self.events.clone()
+    }
 
     pub fn add_player(&mut self, id: u32) {
-        self.players.insert(id, Player { x: 3, y: 3 });
+        self.remove_player(id);
+        let (x, y) = self.free_tile_near(self.map.spawn);
+        self.insert(id, Entity::new(EntityKind::Player, x, y));
+        self.scores.insert(id, Score::new());
     }
 
-    pub fn move_player(&mut self, id: u32, x: i32, y: i32) {
-        // println!("Move {}: ({}, {})", id, x, y);
+    /// 저장해둔 점수를 돌려주고 저장해둔 칸으로 옮김 (`place_player`)
+    pub fn restore_player(&mut self, id: u32, state: PlayerState) {
+        let Some(score) = self.scores.get_mut(&id) else {
+            return;
+        };
+        score.captures = state.captures;
+        score.items = state.items;
+        self.place_player(id, (state.x, state.y));
+    }
 
-        if let Some(player) = self.players.get_mut(&id) {
-            player.x += x;
-            player.y += y;
+    /// `tile`이나 (차지되어 있으면) 가장 가까운 빈 칸으로 옮김. 보드 밖의 칸이면 옮기지 않음
+    pub fn place_player(&mut self, id: u32, tile: (i32, i32)) {
+        let current = self.entities.get(&id).map(|entity| (entity.x, entity.y));
+        if self.map.contains(tile.0, tile.1) && current.is_some_and(|current| current != tile) {
+            let tile = self.free_tile_near(tile);
+            self.relocate(id, tile);
+        }
+    }
+
+    /// 플레이어가 아닌 오브젝트를 놓고 id를 반환
+    pub fn spawn_entity(&mut self, kind: EntityKind, x: i32, y: i32) -> u32 {
+        let id = self.next_entity_id;
+        self.next_entity_id += 1;
+        self.insert(id, Entity::new(kind, x, y));
+        id
+    }
+
+    fn insert(&mut self, id: u32, entity: Entity) {
+        self.grid.insert(id, (entity.x, entity.y));
+        if occupies(entity.kind) {
+            self.occupied.insert((entity.x, entity.y), id);
+        }
+        self.entities.insert(id, entity);
+    }
+
+    /// 벽이 아니고 다른 오브젝트가 차지하지 않은 보드 안의 칸
+    fn is_free(&self, (x, y): (i32, i32)) -> bool {
+        self.map.contains(x, y) && !self.map.is_wall(x, y) && !self.occupied.contains_key(&(x, y))
+    }
+
+    /// `tile`에서 가장 가까운 (가로세로 중 먼 쪽 기준) 빈 칸. 보드가 가득 찼으면 `tile`
+    fn free_tile_near(&self, tile: (i32, i32)) -> (i32, i32) {
+        (0..self.map.width.max(self.map.height))
+            .flat_map(|r| {
+                (tile.0 - r..=tile.0 + r)
+                    .flat_map(move |x| (tile.1 - r..=tile.1 + r).map(move |y| (x, y)))
+                    .filter(move |&(x, y)| (x - tile.0).abs().max((y - tile.1).abs()) == r)
+            })
+            .find(|&tile| self.is_free(tile))
+            .unwrap_or(tile)
+    }
+
+    pub fn kind_of(&self, id: u32) -> Option<EntityKind> {
+        self.entities.get(&id).map(|entity| entity.kind)
+    }
 
-            if player.x < 0 {
-                player.x = 0;
+    /// NPC `count`명을 보드의 아무 칸에 놓음
+    pub fn spawn_npcs(&mut self, count: usize) {
+        for _ in 0..count {
+            let (x, y) = self.random_free_tile();
+            self.spawn_entity(EntityKind::Npc, x, y);
+        }
+    }
+
+    /// 아이템이 `MAX_ITEMS`개보다 적으면 오브젝트도 아이템도 없는 아무 칸에 하나 놓음.
+    /// 클라이언트는 아이템이 보이게 될 때 `spawn`으로 알게 됨
+    pub fn spawn_item(&mut self) -> Option<u32> {
+        let items = self.entities.values()
+            .filter(|entity| entity.kind == EntityKind::Item)
+            .count();
+        if items >= MAX_ITEMS {
+            return None;
+        }
+
+        // 빈 칸을 몇 번 골라보고, 모두 아이템이 있으면 다음 간격에 다시 시도
+        for _ in 0..self.map.width.max(self.map.height) {
+            let (x, y) = self.random_free_tile();
+            if self.is_free((x, y)) && self.item_at((x, y)).is_none() {
+                return Some(self.spawn_entity(EntityKind::Item, x, y));
             }
-            if player.y < 0 {
-                player.y = 0;
+        }
+        None
+    }
+
+    pub fn npc_count(&self) -> usize {
+        self.entities.values()
+            .filter(|entity| entity.kind == EntityKind::Npc)
+            .count()
+    }
+
+    /// 각 NPC가 `NPC_CHASE_RANGE` 안의 가장 가까운 플레이어 쪽으로, 없으면 아무 쪽으로 한 칸 움직임
+    pub fn update_npcs(&mut self) {
+        let npcs = self.entities.iter()
+            .filter(|(_, entity)| entity.kind == EntityKind::Npc)
+            .map(|(&id, entity)| (id, entity.x, entity.y))
+            .collect::<Vec<_>>();
+
+        for (id, x, y) in npcs {
+            let nearest = self.grid.near((x, y), NPC_CHASE_RANGE)
+                .filter_map(|id| self.entities.get(&id))
+                .filter(|entity| entity.kind == EntityKind::Player)
+                .map(|entity| (entity.x - x, entity.y - y))
+                .filter(|(dx, dy)| dx.abs().max(dy.abs()) <= NPC_CHASE_RANGE)
+                .min_by_key(|(dx, dy)| dx.abs().max(dy.abs()));
+
+            let (dx, dy) = match nearest {
+                Some((dx, dy)) => (dx.signum(), dy.signum()),
+                None => (self.random(3) - 1, self.random(3) - 1),
+            };
+            self.move_player(id, dx, dy);
+        }
+    }
+
+    /// 플레이어가 `tile`까지 길을 따라 `PATH_STEP_INTERVAL`마다 한 칸씩 움직이게 함.
+    /// 보드 밖이나 벽이면 가던 길만 멈춤
+    pub fn set_destination(&mut self, id: u32, tile: (i32, i32)) {
+        self.destinations.remove(&id);
+        if self.kind_of(id) == Some(EntityKind::Player) && self.map.contains(tile.0, tile.1) && !self.map.is_wall(tile.0, tile.1) {
+            self.destinations.insert(id, tile);
+        }
+    }
+
+    /// 목적지가 있는 플레이어를 한 칸씩 움직임. 다른 오브젝트는 피해서 돌아가고,
+    /// 도착했거나 갈 수 없으면 (목적지를 다른 오브젝트가 차지해서 들어가지 못하는 경우도) 목적지를 지움
+    pub fn step_destinations(&mut self) {
+        let destinations = self.destinations.iter()
+            .map(|(&id, &tile)| (id, tile))
+            .collect::<Vec<_>>();
+
+        for (id, to) in destinations {
+            let Some(from) = self.entities.get(&id).map(|entity| (entity.x, entity.y)) else {
+                self.destinations.remove(&id);
+                continue;
+            };
+            let step = self.map.next_step(from, to, |tile| self.occupied.contains_key(&tile));
+            let Some((dx, dy)) = step else {
+                self.destinations.remove(&id);
+                continue;
+            };
+
+            self.move_player(id, dx, dy);
+            let now = self.entities.get(&id).map(|entity| (entity.x, entity.y));
+            if now == Some(from) || now == Some(to) {
+                self.destinations.remove(&id);
             }
-            if player.x > 7 {
-                player.x = 7;
+        }
+    }
+
+    fn random_free_tile(&mut self) -> (i32, i32) {
+        let tile = (self.random(self.map.width as u32), self.random(self.map.height as u32));
+        self.free_tile_near(tile)
+    }
+
+    /// `0..n` 중 하나
+    fn random(&mut self, n: u32) -> i32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng % n as u64) as i32
+    }
+
+    /// 보드 밖이나 벽으로는 나가지 못함. 다른 오브젝트가 있는 칸으로는 움직이지 못하지만,
+    /// `capture`이면 그 칸의 플레이어나 (NPC끼리가 아니면) NPC를 잡고 들어감.
+    /// 플레이어가 아이템이 있는 칸에 들어가면 아이템을 주움. 움직이면 그 방향을 바라봄
+    pub fn move_player(&mut self, id: u32, x: i32, y: i32) {
+        let Some(entity) = self.entities.get(&id) else {
+            return;
+        };
+        let kind = entity.kind;
+        let from = (entity.x, entity.y);
+        let to = ((from.0 + x).clamp(0, self.map.width - 1), (from.1 + y).clamp(0, self.map.height - 1));
+        if to == from || self.map.is_wall(to.0, to.1) {
+            return;
+        }
+        let yaw = facing_yaw(to.0 - from.0, to.1 - from.1);
+
+        if let Some(&other) = self.occupied.get(&to) {
+            if !self.can_capture(kind, other) {
+                return;
             }
-            if player.y > 7 {
-                player.y = 7;
+            self.captured(id, other);
+        }
+        self.relocate(id, to);
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.yaw = yaw;
+        }
+
+        if kind == EntityKind::Player {
+            if let Some(item) = self.item_at(to) {
+                self.remove_player(item);
+                if let Some(score) = self.scores.get_mut(&id) {
+                    score.items += 1;
+                }
+                let _ = self.events.send(ServerMessage::Pickup { player: id, item });
             }
         }
     }
 
+    fn item_at(&self, (x, y): (i32, i32)) -> Option<u32> {
+        self.grid.near((x, y), 0)
+            .find(|id| self.entities.get(id).is_some_and(|entity| {
+                entity.kind == EntityKind::Item && (entity.x, entity.y) == (x, y)
+            }))
+    }
+
+    fn can_capture(&self, kind: EntityKind, target: u32) -> bool {
+        let Some(target) = self.kind_of(target) else {
+            return false;
+        };
+        self.capture && matches!(
+            (kind, target),
+            (EntityKind::Player | EntityKind::Npc, EntityKind::Player) | (EntityKind::Player, EntityKind::Npc)
+        )
+    }
+
+    /// 잡힌 플레이어는 처음 생기는 칸 근처로, NPC는 아무 빈 칸으로 옮기고 방에 알림.
+    /// 잡은 플레이어는 점수를 얻고, 잡힌 플레이어는 버틴 시간이 처음부터 다시 시작함
+    fn captured(&mut self, by: u32, target: u32) {
+        if let Some(score) = self.scores.get_mut(&by) {
+            score.captures += 1;
+        }
+        if let Some(score) = self.scores.get_mut(&target) {
+            score.alive_since = Instant::now();
+        }
+
+        self.destinations.remove(&target);
+        let tile = match self.kind_of(target) {
+            Some(EntityKind::Npc) => self.random_free_tile(),
+            _ => self.free_tile_near(self.map.spawn),
+        };
+        self.relocate(target, tile);
+
+        // 받는 연결이 없으면 실패하지만 버려도 됨
+        let _ = self.events.send(ServerMessage::Capture { by, target });
+    }
+
+    fn relocate(&mut self, id: u32, (x, y): (i32, i32)) {
+        let Some(entity) = self.entities.get_mut(&id) else {
+            return;
+        };
+        let from = (entity.x, entity.y);
+        entity.x = x;
+        entity.y = y;
+
+        if occupies(entity.kind) {
+            if self.occupied.get(&from) == Some(&id) {
+                self.occupied.remove(&from);
+            }
+            self.occupied.insert((x, y), id);
+        }
+
+        self.grid.relocate(id, from, (x, y));
+    }
+
     pub fn remove_player(&mut self, id: u32) {
-        self.players.remove(&id);
+        self.scores.remove(&id);
+        self.destinations.remove(&id);
+        if let Some(player) = self.entities.remove(&id) {
+            self.grid.remove(id, (player.x, player.y));
+            if self.occupied.get(&(player.x, player.y)) == Some(&id) {
+                self.occupied.remove(&(player.x, player.y));
+            }
+        }
+    }
+
+    /// `WorldInterface`가 읽는 상태
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            entities: self.entities.clone(),
+            grid: self.grid.clone(),
+            scores: self.scores.clone(),
+            interest_radius: self.interest_radius,
+        }
     }
 
-    pub fn update_message(&self) -> String {
-        let objects = self.players.iter()
-            .map(|(id, player)| format!("{} {} {}", id, player.x, player.y))
-            .collect::<Vec<String>>();
-        
-        format!("update {} {}", objects.len(), objects.join(" "))
+    /// 지금 상태를 `WorldInterface`에 공개
+    fn publish(&self) {
+        self.snapshot.send_replace(Arc::new(self.snapshot()));
     }
+
+    /// 다른 태스크에서 이 월드를 쓰는 핸들
+    pub fn interface(&self) -> WorldInterface {
+        WorldInterface {
+            map: self.map.clone(),
+            sender: self.sender.clone(),
+            snapshot: self.snapshot.subscribe(),
+        }
+    }
+}
+
+
+
+/// 월드가 마지막으로 공개한 상태. 메세지 루프가 바꾸는 도중의 상태는 보이지 않음
+pub struct Snapshot {
+    entities: HashMap<u32, Entity>,
+    grid: SpatialGrid,
+    scores: HashMap<u32, Score>,
+    interest_radius: i32,
 }
 
-impl Into<WorldPointer> for &World {
-    fn into(self) -> WorldPointer {
-        self as *const World as WorldPointer
+impl Snapshot {
+    pub fn player_state(&self, id: u32) -> Option<PlayerState> {
+        let entity = self.entities.get(&id)?;
+        let score = self.scores.get(&id)?;
+        Some(PlayerState { x: entity.x, y: entity.y, captures: score.captures, items: score.items })
+    }
+
+    pub fn kind_of(&self, id: u32) -> Option<EntityKind> {
+        self.entities.get(&id).map(|entity| entity.kind)
+    }
+
+    pub fn objects(&self) -> Vec<ObjectState> {
+        self.entities.iter()
+            .map(|(&id, entity)| entity.state(id))
+            .collect()
+    }
+
+    /// 이 월드에 있는 플레이어의 점수 (점수가 높은 순서). 이름은 `name`으로 정함
+    pub fn scoreboard(&self, name: impl Fn(u32) -> String) -> Vec<ScoreEntry> {
+        let now = Instant::now();
+        let mut entries = self.scores.iter()
+            .map(|(&id, score)| ScoreEntry {
+                id,
+                name: name(id),
+                score: score.points(now),
+                captures: score.captures,
+                items: score.items,
+                survival: score.survival(now).as_secs() as u32,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| (std::cmp::Reverse(entry.score), entry.id));
+        entries
+    }
+
+    /// 플레이어 `id`의 칸에서 `interest_radius` 안에 있는 오브젝트.
+    /// 없는 플레이어면 아무것도 보이지 않음
+    pub fn objects_near(&self, id: u32) -> Vec<ObjectState> {
+        let Some(player) = self.entities.get(&id) else {
+            return Vec::new();
+        };
+        let center = self.grid.cell_of((player.x, player.y));
+
+        self.grid.in_cells(center, self.interest_radius)
+            .filter_map(|id| self.entities.get(&id).map(|entity| entity.state(id)))
+            .collect()
     }
 }
 
 
 
 /// Mutex를 적용하면 read할때도 lock을 걸어야 하기 때문에 사용하지 않음.
-/// `Arc`를 사용해서 여러 스레드에서 **read**가능하도록 하고,
+/// **read**는 `World`가 메세지를 처리할 때마다 `watch`로 공개하는 `Snapshot`을 읽고,
 /// **write**이 필요한 경우는 `WorldInterface`에서 `mpsc`를 통해 `World`로 메세지를 보내서 처리.
+#[derive(Clone)]
 pub struct WorldInterface {
-    world: WorldPointer,
+    /// 월드가 끝날 때까지 바뀌지 않으므로 따로 가짐
+    map: Arc<GameMap>,
     sender: mpsc::Sender<String>,
+    snapshot: watch::Receiver<Arc<Snapshot>>,
 }
 
 impl WorldInterface {
-    pub fn new(world: WorldPointer) -> Self {
-        let ptr = world as *const World ;
-
-        Self { 
-            world: world as WorldPointer, 
-            sender: unsafe { &*ptr }.sender.clone(),
-        }
+    /// 닫힌 월드에 보낸 메세지는 버림 (방을 닫을 때는 플레이어가 없음)
+    async fn send(&self, msg: String) {
+        let _ = self.sender.send(msg).await;
     }
 
     pub async fn add_player(&self, id: u32) {
-        self.sender.send(format!("add {}", id)).await.unwrap();
+        self.send(format!("add {}", id)).await;
     }
 
     pub async fn move_player(&self, id: u32, x: i32, y: i32) {
-        self.sender.send(format!("move {} {} {}", id, x, y)).await.unwrap();
+        self.send(format!("move {} {} {}", id, x, y)).await;
+    }
+
+    pub async fn move_to(&self, id: u32, (x, y): (i32, i32)) {
+        self.send(format!("move_to {} {} {}", id, x, y)).await;
     }
 
     pub async fn remove_player(&self, id: u32) {
-        self.sender.send(format!("remove {}", id)).await.unwrap();
+        self.send(format!("remove {}", id)).await;
+    }
+
+    pub async fn place_player(&self, id: u32, (x, y): (i32, i32)) {
+        self.send(format!("place {} {} {}", id, x, y)).await;
+    }
+
+    pub async fn restore_player(&self, id: u32, state: PlayerState) {
+        let PlayerState { x, y, captures, items } = state;
+        self.send(format!("restore {} {} {} {} {}", id, x, y, captures, items)).await;
+    }
+
+    /// 메세지 루프를 끝냄. 월드는 루프를 돌리던 태스크와 함께 사라짐
+    pub async fn close(&self) {
+        self.send("close".to_string()).await;
+    }
+
+    /// 월드가 마지막으로 공개한 상태. 보낸 메세지는 월드가 처리한 뒤에 보임
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot.borrow().clone()
     }
 
-    pub fn update_message(&self) -> String {
-        unsafe { &*(self.world as *const World) }.update_message()
+    pub fn player_state(&self, id: u32) -> Option<PlayerState> {
+        self.snapshot().player_state(id)
+    }
+
+    pub fn objects(&self) -> Vec<ObjectState> {
+        self.snapshot().objects()
+    }
+
+    pub fn objects_near(&self, id: u32) -> Vec<ObjectState> {
+        self.snapshot().objects_near(id)
+    }
+
+    pub fn map(&self) -> &GameMap {
+        &self.map
+    }
+
+    pub fn kind_of(&self, id: u32) -> Option<EntityKind> {
+        self.snapshot().kind_of(id)
+    }
+
+    pub fn scoreboard(&self, name: impl Fn(u32) -> String) -> Vec<ScoreEntry> {
+        self.snapshot().scoreboard(name)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "\
+S.*#
+.o..
+....
+";
+
+    fn position(world: &World, id: u32) -> (i32, i32) {
+        let entity = &world.entities[&id];
+        (entity.x, entity.y)
+    }
+
+    #[test]
+    fn test_move_player() {
+        let mut world = World::with_map(GameMap::parse(MAP).unwrap(), DEFAULT_INTEREST_RADIUS);
+        let mut events = world.events().subscribe();
+        world.add_player(1);
+        world.add_player(2);
+        // 처음 생기는 칸이 차지되어 있으면 가장 가까운 빈 칸
+        assert_eq!(position(&world, 1), (0, 0));
+        assert_eq!(position(&world, 2), (0, 1));
+
+        // 아이템이 있는 칸에 들어가면 주움
+        world.move_player(1, 1, 0);
+        world.move_player(1, 1, 0);
+        assert_eq!(position(&world, 1), (2, 0));
+        assert_eq!(world.snapshot().player_state(1).unwrap().items, 1);
+        let Ok(ServerMessage::Pickup { player: 1, item }) = events.try_recv() else {
+            panic!("no pickup event");
+        };
+        assert_eq!(world.kind_of(item), None);
+
+        // 벽, 보드 밖과 장애물로는 나가지 못함
+        world.move_player(1, 1, 0);
+        world.move_player(1, 0, -1);
+        assert_eq!(position(&world, 1), (2, 0));
+        world.move_player(2, 1, 0);
+        assert_eq!(position(&world, 2), (0, 1));
+
+        // 잡기를 켜지 않으면 다른 플레이어가 있는 칸으로 들어가지 못함
+        world.move_player(2, 0, -1);
+        world.move_player(2, 1, 0);
+        world.move_player(2, 1, 0);
+        assert_eq!(position(&world, 2), (1, 0));
+        assert_eq!(position(&world, 1), (2, 0));
+        assert_eq!(world.occupied[&(2, 0)], 1);
+    }
+
+    #[test]
+    fn test_capture() {
+        let mut world = World::with_map(GameMap::parse(MAP).unwrap(), DEFAULT_INTEREST_RADIUS);
+        let mut events = world.events().subscribe();
+        world.set_capture(true);
+        world.add_player(1);
+        world.add_player(2);
+        world.move_player(1, 1, 0);
+        world.move_player(2, 0, -1);
+
+        // 잡힌 플레이어는 처음 생기는 칸 근처로 옮겨지고, 잡은 플레이어가 점수를 얻음
+        world.move_player(2, 1, 0);
+        assert_eq!(position(&world, 2), (1, 0));
+        assert_eq!(position(&world, 1), (0, 1));
+        assert_eq!(world.snapshot().player_state(2).unwrap().captures, 1);
+        assert_eq!(events.try_recv(), Ok(ServerMessage::Capture { by: 2, target: 1 }));
+
+        // NPC도 플레이어를 잡음
+        let npc = world.spawn_entity(EntityKind::Npc, 0, 2);
+        world.move_player(npc, 0, -1);
+        assert_eq!(position(&world, npc), (0, 1));
+        assert_eq!(position(&world, 1), (0, 0));
+        assert_eq!(events.try_recv(), Ok(ServerMessage::Capture { by: npc, target: 1 }));
+
+        // NPC끼리는 잡지 못함
+        let other = world.spawn_entity(EntityKind::Npc, 3, 2);
+        let blocked = world.spawn_entity(EntityKind::Npc, 2, 2);
+        world.move_player(blocked, 1, 0);
+        assert_eq!(position(&world, blocked), (2, 2));
+        assert_eq!(position(&world, other), (3, 2));
+
+        // 잡힌 NPC는 다른 빈 칸으로 옮겨짐
+        world.move_player(1, 0, 1);
+        assert_eq!(position(&world, 1), (0, 1));
+        assert_ne!(position(&world, npc), (0, 1));
+        assert_eq!(world.occupied[&position(&world, npc)], npc);
+        assert_eq!(world.snapshot().player_state(1).unwrap().captures, 1);
     }
 }